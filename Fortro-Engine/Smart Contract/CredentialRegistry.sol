@@ -15,6 +15,8 @@ contract CredentialRegistry is AccessControl {
     }
 
     mapping(bytes32 => Credential) private _credentials;
+    // Credentials temporarily suspended, which can be reinstated unlike revoked ones
+    mapping(bytes32 => bool) private _suspended;
 
     event CredentialRegistered(
         bytes32 indexed credentialId,
@@ -30,6 +32,20 @@ contract CredentialRegistry is AccessControl {
         uint256 timestamp
     );
 
+    event CredentialSuspended(
+        bytes32 indexed credentialId,
+        string indexed did,
+        address indexed suspendedBy,
+        uint256 timestamp
+    );
+
+    event CredentialReinstated(
+        bytes32 indexed credentialId,
+        string indexed did,
+        address indexed reinstatedBy,
+        uint256 timestamp
+    );
+
     function _generateCredentialId(string memory did, string memory credentialHash)
     internal pure returns (bytes32) {
         return keccak256(abi.encodePacked(did, credentialHash));
@@ -76,16 +92,61 @@ contract CredentialRegistry is AccessControl {
         _credentials[credentialId].isRevoked = true;
         _credentials[credentialId].revokedAt = block.timestamp;
         _credentials[credentialId].revokedBy = msg.sender;
+        delete _suspended[credentialId];
 
         emit CredentialRevoked(credentialId, did, msg.sender, block.timestamp);
         return true;
     }
 
+    function suspendCredential(string memory did, string memory credentialHash)
+    public onlyRole(ISSUER_ROLE) returns (bool) {
+        bytes32 credentialId = _generateCredentialId(did, credentialHash);
+        require(_credentials[credentialId].isRegistered, "Not registered");
+        require(!_credentials[credentialId].isRevoked, "Already revoked");
+        require(!_suspended[credentialId], "Already suspended");
+
+        // Only the issuer or verifier can suspend
+        require(
+            _credentials[credentialId].registeredBy == msg.sender ||
+            hasRole(VERIFIER_ROLE, msg.sender),
+            "Unauthorized"
+        );
+
+        _suspended[credentialId] = true;
+
+        emit CredentialSuspended(credentialId, did, msg.sender, block.timestamp);
+        return true;
+    }
+
+    function reinstateCredential(string memory did, string memory credentialHash)
+    public onlyRole(ISSUER_ROLE) returns (bool) {
+        bytes32 credentialId = _generateCredentialId(did, credentialHash);
+        require(_suspended[credentialId], "Not suspended");
+
+        // Only the issuer or verifier can reinstate
+        require(
+            _credentials[credentialId].registeredBy == msg.sender ||
+            hasRole(VERIFIER_ROLE, msg.sender),
+            "Unauthorized"
+        );
+
+        _suspended[credentialId] = false;
+
+        emit CredentialReinstated(credentialId, did, msg.sender, block.timestamp);
+        return true;
+    }
+
     function isCredentialValid(string memory did, string memory credentialHash)
     public view returns (bool) {
         bytes32 credentialId = _generateCredentialId(did, credentialHash);
         return _credentials[credentialId].isRegistered &&
-            !_credentials[credentialId].isRevoked;
+            !_credentials[credentialId].isRevoked &&
+            !_suspended[credentialId];
+    }
+
+    function isCredentialSuspended(string memory did, string memory credentialHash)
+    public view returns (bool) {
+        return _suspended[_generateCredentialId(did, credentialHash)];
     }
 
     function getCredentialInfo(string memory did, string memory credentialHash)
@@ -11,7 +11,11 @@ interface ISSIRegistry {
     // Credential functions
     function registerCredential(string memory did, string memory credentialHash, string memory metadataURI) external returns (bytes32);
     function revokeCredential(string memory did, string memory credentialHash) external returns (bool);
+    function suspendCredential(string memory did, string memory credentialHash) external returns (bool);
+    function reinstateCredential(string memory did, string memory credentialHash) external returns (bool);
     function isCredentialValid(string memory did, string memory credentialHash) external view returns (bool);
+    function isCredentialSuspended(string memory did, string memory credentialHash) external view returns (bool);
+    function getCredentialInfo(string memory did, string memory credentialHash) external view returns (bool isRegistered, bool isRevoked, uint256 registeredAt, uint256 revokedAt, address registeredBy, address revokedBy, string memory metadataURI);
 
     // Schema functions
     function registerSchema(string memory schemaId, string memory schemaURI) external returns (bool);
@@ -0,0 +1,103 @@
+// Benchmark of the range proofs behind predicate proofs
+// Compares one 64-bit Bulletproof per predicate with a single aggregated proof
+// over all predicates, in proof size, proving time and verification time.
+//
+// Run with `cargo run --release --example range_proof_bench [iterations]`
+
+use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
+use curve25519_dalek_ng::{ristretto::CompressedRistretto, scalar::Scalar};
+use merlin::Transcript;
+use rand::{thread_rng, Rng};
+use std::time::{Duration, Instant};
+
+const RANGE_BITS: usize = 64;
+const PREDICATE_COUNTS: [usize; 5] = [1, 2, 4, 8, 16];
+
+// Measurements of one way of proving a set of predicates
+struct Measurement {
+    size: usize,
+    prove: Duration,
+    verify: Duration,
+}
+
+// Random values and blindings, one per predicate
+fn openings(count: usize) -> (Vec<u64>, Vec<Scalar>) {
+    let mut rng = thread_rng();
+    let values = (0..count).map(|_| rng.gen()).collect();
+    let blindings = (0..count).map(|_| Scalar::random(&mut rng)).collect();
+    (values, blindings)
+}
+
+// One single-value proof per predicate, verified one by one
+fn individual(values: &[u64], blindings: &[Scalar]) -> Result<Measurement, Box<dyn std::error::Error>> {
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(RANGE_BITS, 1);
+
+    let start = Instant::now();
+    let mut proofs: Vec<(RangeProof, CompressedRistretto)> = Vec::new();
+    for (value, blinding) in values.iter().zip(blindings) {
+        let mut transcript = Transcript::new(b"range-proof-bench");
+        proofs.push(RangeProof::prove_single(&bp_gens, &pc_gens, &mut transcript, *value, blinding, RANGE_BITS)?);
+    }
+    let prove = start.elapsed();
+
+    let start = Instant::now();
+    for (proof, commitment) in &proofs {
+        let mut transcript = Transcript::new(b"range-proof-bench");
+        proof.verify_single(&bp_gens, &pc_gens, &mut transcript, commitment, RANGE_BITS)?;
+    }
+    let verify = start.elapsed();
+
+    let size = proofs.iter().map(|(proof, _)| proof.to_bytes().len()).sum();
+    Ok(Measurement { size, prove, verify })
+}
+
+// One aggregated proof over all predicates, with a party capacity of their number
+fn aggregated(values: &[u64], blindings: &[Scalar]) -> Result<Measurement, Box<dyn std::error::Error>> {
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(RANGE_BITS, values.len());
+
+    let start = Instant::now();
+    let mut transcript = Transcript::new(b"range-proof-bench");
+    let (proof, commitments) = RangeProof::prove_multiple(&bp_gens, &pc_gens, &mut transcript, values, blindings, RANGE_BITS)?;
+    let prove = start.elapsed();
+
+    let start = Instant::now();
+    let mut transcript = Transcript::new(b"range-proof-bench");
+    proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &commitments, RANGE_BITS)?;
+    let verify = start.elapsed();
+
+    Ok(Measurement { size: proof.to_bytes().len(), prove, verify })
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let iterations: u32 = std::env::args().nth(1).map(|arg| arg.parse()).transpose()?.unwrap_or(10);
+
+    println!("Range proofs for predicate proofs, {}-bit, mean of {} runs", RANGE_BITS, iterations);
+    println!("=============================================================");
+    println!(
+        "{:>10} | {:>10} {:>12} {:>12} | {:>10} {:>12} {:>12}",
+        "predicates", "size (B)", "prove (ms)", "verify (ms)", "agg. (B)", "prove (ms)", "verify (ms)"
+    );
+
+    for count in PREDICATE_COUNTS {
+        let mut totals = [(0, Duration::ZERO, Duration::ZERO); 2];
+        for _ in 0..iterations {
+            let (values, blindings) = openings(count);
+            for (total, measurement) in totals.iter_mut().zip([individual(&values, &blindings)?, aggregated(&values, &blindings)?]) {
+                total.0 = measurement.size;
+                total.1 += measurement.prove;
+                total.2 += measurement.verify;
+            }
+        }
+
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0 / iterations as f64;
+        let [(size, prove, verify), (aggregated_size, aggregated_prove, aggregated_verify)] = totals;
+        println!(
+            "{:>10} | {:>10} {:>12.2} {:>12.2} | {:>10} {:>12.2} {:>12.2}",
+            count, size, ms(prove), ms(verify), aggregated_size, ms(aggregated_prove), ms(aggregated_verify)
+        );
+    }
+
+    Ok(())
+}
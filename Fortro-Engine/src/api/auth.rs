@@ -1,24 +1,32 @@
 use axum::{
-    extract::{Json, State},
+    extract::{Extension, Json, State},
     http::StatusCode,
+    middleware::from_fn_with_state,
     routing::post,
     Router,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+use crate::api::middleware::require_auth;
 use crate::error::AppError;
 use crate::services::AppState;
-use crate::services::auth::{RegisterRequest, LoginRequest, GenerateDIDRequest};
+use crate::services::auth::{AuthContext, RegisterRequest, LoginRequest, GenerateDIDRequest, RefreshRequest};
 
 /// Create auth routes
-pub fn routes() -> Router<AppState> {
+pub fn routes(state: AppState) -> Router<AppState> {
+    let protected = Router::new()
+        .route("/logout", post(logout))
+        .route_layer(from_fn_with_state(state, require_auth));
+
     Router::new()
         .route("/register", post(register))
         .route("/login", post(login))
+        .route("/refresh", post(refresh))
         .route("/challenge", post(generate_challenge))
         .route("/verify-challenge", post(verify_challenge))
         .route("/generate-did", post(generate_did))
+        .merge(protected)
 }
 
 /// Register request handler
@@ -53,7 +61,49 @@ async fn login(
             "success": true,
             "message": "Login successful",
             "user": auth_response.user,
-            "token": auth_response.token,
+            "token": auth_response.tokens.access_token,
+            "refresh_token": auth_response.tokens.refresh_token,
+            "token_type": auth_response.tokens.token_type,
+            "expires_in": auth_response.tokens.expires_in,
+        })),
+    ))
+}
+
+/// Refresh token handler
+async fn refresh(
+    State(state): State<AppState>,
+    Json(request): Json<RefreshRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    let auth_service = state.auth_service();
+    let auth_response = auth_service.refresh(request).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "success": true,
+            "message": "Session refreshed",
+            "user": auth_response.user,
+            "token": auth_response.tokens.access_token,
+            "refresh_token": auth_response.tokens.refresh_token,
+            "token_type": auth_response.tokens.token_type,
+            "expires_in": auth_response.tokens.expires_in,
+        })),
+    ))
+}
+
+/// Logout handler
+async fn logout(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    let auth_service = state.auth_service();
+    auth_service.logout(&auth).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "success": true,
+            "message": "Logged out successfully",
         })),
     ))
 }
@@ -103,7 +153,7 @@ async fn verify_challenge(
     Json(request): Json<VerifyChallengeRequest>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
     let auth_service = state.auth_service();
-    let (user, token) = auth_service.verify_challenge(
+    let (user, tokens) = auth_service.verify_challenge(
         &request.did,
         &request.challenge,
         &request.signature,
@@ -115,7 +165,10 @@ async fn verify_challenge(
             "success": true,
             "message": "Challenge verified successfully",
             "user": user,
-            "token": token,
+            "token": tokens.access_token,
+            "refresh_token": tokens.refresh_token,
+            "token_type": tokens.token_type,
+            "expires_in": tokens.expires_in,
         })),
     ))
 }
@@ -0,0 +1,176 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use crate::api::middleware::require_auth;
+use crate::error::AppError;
+use crate::resolver::DID_JSON_MEDIA_TYPE;
+use crate::services::auth::AuthContext;
+use crate::services::ethr::EthrChangeRequest;
+use crate::services::lifecycle::DeactivateDidRequest;
+use crate::services::AppState;
+use crate::utils::did;
+
+/// Media type of a full DID resolution result
+const DID_RESOLUTION_MEDIA_TYPE: &str = "application/ld+json;profile=\"https://w3id.org/did-resolution\"";
+
+/// Create DID resolution routes
+pub fn routes(state: AppState) -> Router<AppState> {
+    // Relaying did:ethr changes spends the engine's gas, so it needs a session
+    let protected = Router::new()
+        .route("/ethr/changes/hash", post(ethr_change_hash))
+        .route("/ethr/changes", post(submit_ethr_change))
+        .route("/:did/deactivate", post(deactivate_did))
+        .route_layer(from_fn_with_state(state, require_auth));
+
+    Router::new()
+        .route("/:did", get(resolve_did))
+        .merge(protected)
+}
+
+/// Deactivate DID handler; a DID can be deactivated by itself or by the controller of its issuer
+async fn deactivate_did(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(did): Path<String>,
+    request: Option<Json<DeactivateDidRequest>>,
+) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    if !did::validate_did(&did) {
+        return Err(AppError::ValidationError(format!("Invalid DID: {}", did)));
+    }
+    if !state.issuer_service().is_controlled_by(&did, &auth.did).await? {
+        return Err(AppError::AccessDeniedError(format!("DID {} cannot deactivate {}", auth.did, did)));
+    }
+
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    let deactivation = state.lifecycle_service().deactivate(&did, &auth.did, request).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "success": true,
+            "message": "DID deactivated",
+            "deactivation": deactivation,
+        })),
+    ))
+}
+
+/// did:ethr change hash handler; returns the hash the identity owner signs
+async fn ethr_change_hash(
+    State(state): State<AppState>,
+    Json(request): Json<EthrChangeRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    let hash = state.ethr_did_service().change_hash(&request.did, &request.change).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "success": true,
+            "hash": hash,
+        })),
+    ))
+}
+
+/// Relay an owner-signed did:ethr change to the DID Registry
+async fn submit_ethr_change(
+    State(state): State<AppState>,
+    Json(request): Json<EthrChangeRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    let signature = request.signature.as_deref()
+        .ok_or_else(|| AppError::ValidationError("signature is required".to_string()))?;
+
+    let tx_hash = state.ethr_did_service()
+        .submit_signed_change(&request.did, &request.change, signature)
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "success": true,
+            "message": "DID Registry change submitted",
+            "transaction_hash": tx_hash,
+        })),
+    ))
+}
+
+/// Create did:web hosting routes, served at the domain root
+pub fn web_routes() -> Router<AppState> {
+    Router::new()
+        .route("/.well-known/did.json", get(domain_did_document))
+        .route("/issuers/:slug/did.json", get(issuer_did_document))
+}
+
+/// Resolve DID handler.
+///
+/// Responds with the bare DID document, or with the full resolution result,
+/// including `didDocumentMetadata`, when the client accepts the DID resolution profile.
+async fn resolve_did(
+    State(state): State<AppState>,
+    Path(did): Path<String>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let resolved = state.did_resolver().resolve_with_metadata(&did).await?;
+
+    let wants_resolution = headers.get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("did-resolution"));
+
+    if wants_resolution {
+        return Ok((
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, DID_RESOLUTION_MEDIA_TYPE)],
+            Json(serde_json::to_value(resolved)?),
+        ));
+    }
+
+    // Without metadata a deactivated document would look usable, so it is not served bare
+    if resolved.metadata.deactivated {
+        return Err(AppError::NotFoundError(format!("DID {} is deactivated", did)));
+    }
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, DID_JSON_MEDIA_TYPE)],
+        Json(serde_json::to_value(resolved.document)?),
+    ))
+}
+
+/// Serve the DID document of the domain's root did:web issuer
+async fn domain_did_document(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    hosted_did_document(&state, &[]).await
+}
+
+/// Serve the DID document of a path-based did:web issuer
+async fn issuer_did_document(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    hosted_did_document(&state, &["issuers", &slug]).await
+}
+
+/// Serve the DID document of a did:web DID on the engine's domain
+async fn hosted_did_document(state: &AppState, path: &[&str]) -> Result<impl IntoResponse, AppError> {
+    let domain = state.config.did_web.domain.as_deref()
+        .ok_or_else(|| AppError::NotFoundError("did:web hosting is not configured".to_string()))?;
+
+    // Deactivated did:web DIDs stop being served, which is how did:web signals deactivation
+    let did = did::did_web(domain, path);
+    let resolved = state.did_resolver().resolve_with_metadata(&did).await?;
+    if resolved.metadata.deactivated {
+        return Err(AppError::NotFoundError(format!("DID {} is deactivated", did)));
+    }
+    let document = resolved.document;
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, DID_JSON_MEDIA_TYPE)],
+        Json(document),
+    ))
+}
@@ -1,26 +1,33 @@
 use axum::{
-    extract::{Json, Path, State, Query},
+    extract::{rejection::JsonRejection, Extension, Json, Path, State, Query},
     http::StatusCode,
+    middleware::from_fn_with_state,
     routing::{get, post, put, delete},
     Router,
 };
 use serde::{Deserialize};
 use serde_json::json;
 use std::collections::HashMap;
+use crate::api::middleware::{require_auth, require_issuer};
 use crate::error::AppError;
-use crate::services::AppState;
+use crate::services::auth::AuthContext;
+use crate::services::{AppState, RevokeCredentialRequest};
 use crate::services::issuer::{
     CreateIssuerRequest, CreateSchemaRequest, IssueCredentialRequest, 
     CreateCredentialTemplateRequest,
 };
 
 /// Create issuer routes
-pub fn routes() -> Router<AppState> {
-    Router::new()
-        // Issuer management
+pub fn routes(state: AppState) -> Router<AppState> {
+    // Any authenticated user may register an issuer it controls
+    let registration = Router::new()
         .route("/", post(create_issuer))
-        .route("/:did", get(get_issuer))
+        .route_layer(from_fn_with_state(state.clone(), require_auth));
+
+    let protected = Router::new()
+        // Issuer management
         .route("/:did/update", put(update_issuer))
+        .route("/:did/keys/rotate", post(rotate_issuer_key))
 
         // Schema management
         .route("/:did/schemas", post(create_schema))
@@ -43,6 +50,8 @@ pub fn routes() -> Router<AppState> {
         .route("/:did/credentials", get(list_issued_credentials))
         .route("/:did/credentials/:credential_id", get(get_issued_credential))
         .route("/:did/credentials/:credential_id/revoke", post(revoke_credential))
+        .route("/:did/credentials/:credential_id/suspend", post(suspend_credential))
+        .route("/:did/credentials/:credential_id/unsuspend", post(unsuspend_credential))
 
         // Credential requests from users
         .route("/:did/requests", get(list_credential_requests))
@@ -60,15 +69,24 @@ pub fn routes() -> Router<AppState> {
 
         // Dashboard statistics
         .route("/:did/statistics", get(get_issuer_statistics))
+        .route_layer(from_fn_with_state(state.clone(), require_issuer))
+        .route_layer(from_fn_with_state(state, require_auth));
+
+    Router::new()
+        .route("/:did", get(get_issuer))
+        .route("/:did/keys", get(list_issuer_keys))
+        .merge(registration)
+        .merge(protected)
 }
 
 /// Create issuer handler
 async fn create_issuer(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Json(request): Json<CreateIssuerRequest>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
     let issuer_service = state.issuer_service();
-    let issuer = issuer_service.create_issuer(request).await?;
+    let issuer = issuer_service.create_issuer(&auth.did, request).await?;
 
     Ok((
         StatusCode::CREATED,
@@ -116,6 +134,41 @@ async fn update_issuer(
     ))
 }
 
+/// Rotate issuer key handler
+async fn rotate_issuer_key(
+    State(state): State<AppState>,
+    Path(did): Path<String>,
+) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    let issuer_service = state.issuer_service();
+    let key = issuer_service.rotate_issuer_key(&did).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "success": true,
+            "message": "Issuer key rotated successfully",
+            "key": key,
+        })),
+    ))
+}
+
+/// List issuer keys handler
+async fn list_issuer_keys(
+    State(state): State<AppState>,
+    Path(did): Path<String>,
+) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    let issuer_service = state.issuer_service();
+    let keys = issuer_service.list_issuer_keys(&did).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "success": true,
+            "keys": keys,
+        })),
+    ))
+}
+
 /// Create schema handler
 async fn create_schema(
     State(state): State<AppState>,
@@ -362,10 +415,18 @@ async fn get_issued_credential(
 /// Revoke credential handler
 async fn revoke_credential(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Path((did, credential_id)): Path<(String, String)>,
+    request: Result<Json<RevokeCredentialRequest>, JsonRejection>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    // The body is optional; without one the credential is revoked for an unspecified reason
+    let request = match request {
+        Ok(Json(request)) => request,
+        Err(JsonRejection::MissingJsonContentType(_)) => RevokeCredentialRequest::default(),
+        Err(e) => return Err(AppError::ValidationError(e.body_text())),
+    };
     let credential_service = state.credential_service();
-    let credential = credential_service.revoke_credential(&did, &credential_id).await?;
+    let (credential, revocation) = credential_service.revoke_credential(&did, &credential_id, &auth.did, request).await?;
 
     Ok((
         StatusCode::OK,
@@ -373,6 +434,43 @@ async fn revoke_credential(
             "success": true,
             "message": "Credential revoked successfully",
             "credential": credential,
+            "revocation": revocation,
+        })),
+    ))
+}
+
+/// Suspend credential handler
+async fn suspend_credential(
+    State(state): State<AppState>,
+    Path((did, credential_id)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    let credential_service = state.credential_service();
+    let credential = credential_service.suspend_credential(&did, &credential_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "success": true,
+            "message": "Credential suspended successfully",
+            "credential": credential,
+        })),
+    ))
+}
+
+/// Unsuspend credential handler
+async fn unsuspend_credential(
+    State(state): State<AppState>,
+    Path((did, credential_id)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    let credential_service = state.credential_service();
+    let credential = credential_service.unsuspend_credential(&did, &credential_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "success": true,
+            "message": "Credential reinstated successfully",
+            "credential": credential,
         })),
     ))
 }
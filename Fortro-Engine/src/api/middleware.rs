@@ -0,0 +1,102 @@
+use axum::{
+    extract::{RawPathParams, State},
+    http::{header, Request},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::error::AppError;
+use crate::models::UserRole;
+use crate::services::auth::AuthContext;
+use crate::services::AppState;
+
+/// Authenticate the `Authorization: Bearer` token and store the caller's context
+pub async fn require_auth<B>(
+    State(state): State<AppState>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, AppError> {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| AppError::AuthError("Missing or malformed bearer token".to_string()))?;
+
+    let context = state.auth_service().authenticate(token).await?;
+    request.extensions_mut().insert(context);
+
+    Ok(next.run(request).await)
+}
+
+/// Only let the wallet's owner access `/:did` wallet routes
+pub async fn require_wallet_owner<B>(
+    params: RawPathParams,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, AppError> {
+    let context = auth_context(&request)?;
+
+    if let Some(did) = path_did(&params) {
+        context.ensure_did(&did)?;
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Only let issuers act on `/:did` issuer routes they control
+pub async fn require_issuer<B>(
+    State(state): State<AppState>,
+    params: RawPathParams,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, AppError> {
+    let context = auth_context(&request)?;
+    context.require_role(&UserRole::Issuer)?;
+
+    if let Some(did) = path_did(&params) {
+        if !state.issuer_service().is_controlled_by(&did, &context.did).await? {
+            return Err(AppError::AccessDeniedError(format!(
+                "DID {} does not control issuer {}",
+                context.did, did
+            )));
+        }
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Only let verifiers use the verifier routes
+pub async fn require_verifier<B>(
+    params: RawPathParams,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, AppError> {
+    let context = auth_context(&request)?;
+    context.require_role(&UserRole::Verifier)?;
+
+    if let Some(did) = path_did(&params) {
+        context.ensure_did(&did)?;
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Get the context stored by `require_auth`
+fn auth_context<B>(request: &Request<B>) -> Result<AuthContext, AppError> {
+    request
+        .extensions()
+        .get::<AuthContext>()
+        .cloned()
+        .ok_or_else(|| AppError::AuthError("Request is not authenticated".to_string()))
+}
+
+/// Get the `did` path parameter, if the route has one
+fn path_did(params: &RawPathParams) -> Option<String> {
+    params
+        .iter()
+        .find(|(key, _)| *key == "did")
+        .map(|(_, value)| value.to_string())
+}
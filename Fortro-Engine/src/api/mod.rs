@@ -1,20 +1,25 @@
 pub mod auth;
+pub mod dids;
 pub mod health;
 pub mod wallet;
 pub mod issuer;
 pub mod verifier;
 pub mod qr;
+pub mod status_lists;
+pub mod middleware;
 
 use axum::Router;
 use crate::services::AppState;
 
 /// Create all API routes
-pub fn routes() -> Router<AppState> {
+pub fn routes(state: AppState) -> Router<AppState> {
     Router::new()
-        .nest("/auth", auth::routes())
-        .nest("/wallet", wallet::routes())
-        .nest("/issuer", issuer::routes())
-        .nest("/verifier", verifier::routes())
+        .nest("/auth", auth::routes(state.clone()))
+        .nest("/dids", dids::routes(state.clone()))
+        .nest("/wallet", wallet::routes(state.clone()))
+        .nest("/issuer", issuer::routes(state.clone()))
+        .nest("/verifier", verifier::routes(state.clone()))
+        .nest("/status-lists", status_lists::routes())
         .nest("/health", health::health_check())
-        .nest("/qr", qr::routes())
+        .nest("/qr", qr::routes(state))
 }
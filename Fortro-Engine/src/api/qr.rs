@@ -1,6 +1,7 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Extension, Path, State},
     http::StatusCode,
+    middleware::from_fn_with_state,
     response::IntoResponse,
     routing::{get, post},
     Json, Router,
@@ -8,7 +9,9 @@ use axum::{
 use serde::{Deserialize};
 use serde_json::{json};
 
+use crate::api::middleware::require_auth;
 use crate::error::AppError;
+use crate::services::auth::AuthContext;
 use crate::services::AppState;
 
 /// Request models
@@ -28,11 +31,15 @@ pub struct PresentationRequestRequest {
 }
 
 /// QR code routes
-pub fn routes() -> Router<AppState> {
-    Router::new()
-        .route("/resolve/:short_id", get(resolve_short_url))
+pub fn routes(state: AppState) -> Router<AppState> {
+    let protected = Router::new()
         .route("/credential-offer", post(generate_credential_offer_short_url))
         .route("/presentation-request", post(generate_presentation_request_short_url))
+        .route_layer(from_fn_with_state(state, require_auth));
+
+    Router::new()
+        .route("/resolve/:short_id", get(resolve_short_url))
+        .merge(protected)
 }
 
 /// Resolve a short URL to QR code content
@@ -49,8 +56,16 @@ async fn resolve_short_url(
 /// Generate a short URL for a credential offer
 async fn generate_credential_offer_short_url(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Json(request): Json<CredentialOfferRequest>,
 ) -> Result<impl IntoResponse, AppError> {
+    if !state.issuer_service().is_controlled_by(&request.issuer_did, &auth.did).await? {
+        return Err(AppError::AccessDeniedError(format!(
+            "DID {} does not control issuer {}",
+            auth.did, request.issuer_did
+        )));
+    }
+
     let qr_service = state.qr_service();
     let short_id = qr_service.generate_credential_offer_short_url(
         &request.issuer_did,
@@ -72,8 +87,11 @@ async fn generate_credential_offer_short_url(
 /// Generate a short URL for a presentation request
 async fn generate_presentation_request_short_url(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Json(request): Json<PresentationRequestRequest>,
 ) -> Result<impl IntoResponse, AppError> {
+    auth.ensure_did(&request.verifier_did)?;
+
     let qr_service = state.qr_service();
     let short_id = qr_service.generate_presentation_request_short_url(
         &request.verifier_did,
@@ -0,0 +1,32 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use crate::error::AppError;
+use crate::services::AppState;
+
+/// Media type of a credential secured as a JWT
+const VC_JWT_MEDIA_TYPE: &str = "application/vc+jwt";
+
+/// Create status list routes; lists are public so any verifier can check credentials against them
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/:id", get(get_status_list))
+}
+
+/// Serve the latest signed status list credential
+async fn get_status_list(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let credential = state.status_list_service().published(&id).await?;
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, VC_JWT_MEDIA_TYPE)],
+        credential,
+    ))
+}
@@ -1,6 +1,7 @@
 use axum::{
-    extract::{Json, Path, State, Query},
+    extract::{Extension, Json, Path, State, Query},
     http::StatusCode,
+    middleware::from_fn_with_state,
     routing::{get, post, put},
     Router,
 };
@@ -8,13 +9,15 @@ use serde::{Deserialize};
 use serde_json::json;
 use std::collections::HashMap;
 
+use crate::api::middleware::{require_auth, require_verifier};
 use crate::error::AppError;
 use crate::models::{PresentationStatus, CredentialRequirement, AccessLevel, ExpirationPolicy};
+use crate::services::auth::AuthContext;
 use crate::services::AppState;
 use crate::services::verifier::{CreatePresentationRequestRequest, VerifyPresentationRequest};
 
 /// Create verifier routes
-pub fn routes() -> Router<AppState> {
+pub fn routes(state: AppState) -> Router<AppState> {
     Router::new()
         // Presentation requests
         .route("/requests", post(create_presentation_request))
@@ -36,13 +39,18 @@ pub fn routes() -> Router<AppState> {
         
         // Statistics
         .route("/:did/statistics", get(get_verifier_statistics))
+        .route_layer(from_fn_with_state(state.clone(), require_verifier))
+        .route_layer(from_fn_with_state(state, require_auth))
 }
 
 /// Create presentation request handler
 async fn create_presentation_request(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Json(request): Json<CreatePresentationRequestRequest>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    auth.ensure_did(&request.verifier_did)?;
+
     let verifier_service = state.verifier_service();
     let response = verifier_service.create_presentation_request(request).await?;
 
@@ -60,11 +68,13 @@ async fn create_presentation_request(
 /// Get presentation request handler
 async fn get_presentation_request(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Path(id): Path<String>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
     let presentation_service = state.presentation_service();
     let request = presentation_service.get_presentation_by_id(&id).await?
         .ok_or_else(|| AppError::NotFoundError(format!("Presentation request with ID {} not found", id)))?;
+    auth.ensure_did(&request.verifier_did)?;
 
     Ok((
         StatusCode::OK,
@@ -78,11 +88,13 @@ async fn get_presentation_request(
 /// List presentations handler
 async fn list_presentations(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Query(params): Query<HashMap<String, String>>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
     let verifier_did = params.get("verifier_did")
         .ok_or_else(|| AppError::ValidationError("verifier_did parameter is required".to_string()))?;
-    
+    auth.ensure_did(verifier_did)?;
+
     let verifier_service = state.verifier_service();
     let presentations = verifier_service.get_presentations_by_verifier(verifier_did).await?;
 
@@ -98,11 +110,13 @@ async fn list_presentations(
 /// Get presentation handler
 async fn get_presentation(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Path(id): Path<String>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
     let verifier_service = state.verifier_service();
     let presentation = verifier_service.get_presentation_by_id(&id).await?
         .ok_or_else(|| AppError::NotFoundError(format!("Presentation with ID {} not found", id)))?;
+    auth.ensure_did(&presentation.verifier_did)?;
 
     Ok((
         StatusCode::OK,
@@ -116,8 +130,11 @@ async fn get_presentation(
 /// Verify presentation handler
 async fn verify_presentation(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Json(request): Json<VerifyPresentationRequest>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    auth.ensure_did(&request.verifier_did)?;
+
     let verifier_service = state.verifier_service();
     let result = verifier_service.verify_presentation(request).await?;
 
@@ -140,9 +157,12 @@ pub struct UpdatePresentationStatusRequest {
 /// Update presentation status handler
 async fn update_presentation_status(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Path(id): Path<String>,
     Json(request): Json<UpdatePresentationStatusRequest>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    auth.ensure_did(&request.verifier_did)?;
+
     let verifier_service = state.verifier_service();
     let success = verifier_service.update_presentation_status(&id, &request.verifier_did, request.status).await?;
 
@@ -158,11 +178,13 @@ async fn update_presentation_status(
 /// List consents handler
 async fn list_consents(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Query(params): Query<HashMap<String, String>>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
     let verifier_did = params.get("verifier_did")
         .ok_or_else(|| AppError::ValidationError("verifier_did parameter is required".to_string()))?;
-    
+    auth.ensure_did(verifier_did)?;
+
     let verifier_service = state.verifier_service();
     let consents = verifier_service.get_consents_for_verifier(verifier_did).await?;
 
@@ -190,8 +212,11 @@ pub struct RequestConsentRequest {
 /// Request consent handler
 async fn request_consent(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Json(request): Json<RequestConsentRequest>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    auth.ensure_did(&request.verifier_did)?;
+
     let verifier_service = state.verifier_service();
     let consent = verifier_service.request_consent(
         &request.verifier_did,
@@ -224,8 +249,11 @@ pub struct CheckConsentRequest {
 /// Check consent handler
 async fn check_consent(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Json(request): Json<CheckConsentRequest>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    auth.ensure_did(&request.verifier_did)?;
+
     let verifier_service = state.verifier_service();
     let has_consent = verifier_service.check_consent(
         &request.verifier_did,
@@ -256,8 +284,11 @@ pub struct GeneratePresentationRequestQrRequest {
 /// Generate presentation request QR code handler
 async fn generate_presentation_request_qr(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Json(request): Json<GeneratePresentationRequestQrRequest>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    auth.ensure_did(&request.verifier_did)?;
+
     let verifier_service = state.verifier_service();
     let qr_data = verifier_service.generate_presentation_request_qr(
         &request.verifier_did,
@@ -1,12 +1,14 @@
 use axum::{
     extract::{Json, Path, State},
     http::StatusCode,
-    routing::{get, post, delete},
+    middleware::from_fn_with_state,
+    routing::{get, post, put, delete},
     Router,
 };
 use serde::{Deserialize};
 use serde_json::json;
 
+use crate::api::middleware::{require_auth, require_wallet_owner};
 use crate::error::AppError;
 use crate::services::AppState;
 use crate::services::wallet::{
@@ -14,10 +16,15 @@ use crate::services::wallet::{
 };
 
 /// Create wallet routes
-pub fn routes() -> Router<AppState> {
-    Router::new()
+pub fn routes(state: AppState) -> Router<AppState> {
+    // Wallet creation and restore are how a holder gets a DID in the first place
+    let public = Router::new()
         .route("/", post(create_wallet))
+        .route("/restore", post(restore_wallet));
+
+    let protected = Router::new()
         .route("/:did", get(get_wallet))
+        .route("/:did/key-agreement", put(set_key_agreement_key))
         .route("/:did/credentials", get(get_credentials))
         .route("/:did/credentials/import", post(import_credential))
         .route("/:did/credentials/:credential_id", get(get_credential))
@@ -29,8 +36,11 @@ pub fn routes() -> Router<AppState> {
         .route("/:did/consents/:consent_id/revoke", post(revoke_consent))
         .route("/:did/statistics", get(get_statistics))
         .route("/:did/backup", post(backup_wallet))
-        .route("/restore", post(restore_wallet))
         .route("/scan-qr", post(scan_qr_code))
+        .route_layer(from_fn_with_state(state.clone(), require_wallet_owner))
+        .route_layer(from_fn_with_state(state, require_auth));
+
+    public.merge(protected)
 }
 
 /// Scan QR code request
@@ -39,6 +49,13 @@ pub struct ScanQrCodeRequest {
     pub qr_data: String,
 }
 
+/// Key agreement key request
+#[derive(Debug, Deserialize)]
+pub struct SetKeyAgreementKeyRequest {
+    /// Base58 Kyber768 public key
+    pub public_key: String,
+}
+
 /// Backup wallet request
 #[derive(Debug, Deserialize)]
 pub struct BackupWalletRequest {
@@ -70,6 +87,25 @@ async fn create_wallet(
     ))
 }
 
+/// Publish key agreement key handler
+async fn set_key_agreement_key(
+    State(state): State<AppState>,
+    Path(did): Path<String>,
+    Json(request): Json<SetKeyAgreementKeyRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
+    let wallet_service = state.wallet_service();
+    let key_id = wallet_service.set_key_agreement_key(&did, &request.public_key).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "success": true,
+            "message": "Key agreement key published successfully",
+            "key_id": key_id,
+        })),
+    ))
+}
+
 /// Get wallet handler
 async fn get_wallet(
     State(state): State<AppState>,
@@ -117,12 +153,14 @@ async fn get_credential(
     if credential.owner_did != did {
         return Err(AppError::AccessDeniedError("You can only access your own credentials".to_string()));
     }
+    let revocation = credential_service.credential_revocation(&credential).await?;
 
     Ok((
         StatusCode::OK,
         Json(json!({
             "success": true,
             "credential": credential,
+            "revocation": revocation,
         })),
     ))
 }
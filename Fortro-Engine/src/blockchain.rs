@@ -1,13 +1,16 @@
 use ethers::{
     prelude::{
-        abigen, Address, ContractFactory, LocalWallet, Provider, SignerMiddleware, TransactionRequest,
-        U256,
+        abigen, Address, ContractCall, ContractFactory, LocalWallet, Provider, SignerMiddleware,
+        TransactionRequest, U256,
     },
     providers::{Http, Middleware},
-    types::{TransactionReceipt, H256},
-    abi::parse_abi,
+    types::{Signature, TransactionReceipt, H256},
+    abi::{encode_packed, parse_abi, Token},
     core::types::Bytes,
+    utils::keccak256,
 };
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use std::sync::Arc;
 
 use crate::error::AppError;
@@ -19,7 +22,11 @@ abigen!(
     r#"[
         function registerCredential(string did, string credentialHash, string metadataURI) external returns (bytes32)
         function revokeCredential(string did, string credentialHash) external returns (bool)
+        function suspendCredential(string did, string credentialHash) external returns (bool)
+        function reinstateCredential(string did, string credentialHash) external returns (bool)
         function isCredentialValid(string did, string credentialHash) external view returns (bool)
+        function isCredentialSuspended(string did, string credentialHash) external view returns (bool)
+        function getCredentialInfo(string did, string credentialHash) external view returns (bool isRegistered, bool isRevoked, uint256 registeredAt, uint256 revokedAt, address registeredBy, address revokedBy, string metadataURI)
         function registerSchema(string schemaId, string schemaURI) external returns (bool)
         function getSchemaURI(string schemaId) external view returns (string)
         function isSchemaRegistered(string schemaId) external view returns (bool)
@@ -32,10 +39,49 @@ abigen!(
     ]"#
 );
 
+// Generate bindings for the ERC-1056 DID Registry behind did:ethr (DIDRegistry.sol)
+abigen!(
+    DIDRegistry,
+    r#"[
+        function identityOwner(address identity) external view returns (address)
+        function changed(address identity) external view returns (uint256)
+        function nonce(address identity) external view returns (uint256)
+        function validDelegate(address identity, bytes32 delegateType, address delegate) external view returns (bool)
+        function changeOwner(address identity, address newOwner) external
+        function changeOwnerSigned(address identity, uint8 sigV, bytes32 sigR, bytes32 sigS, address newOwner) external
+        function addDelegate(address identity, bytes32 delegateType, address delegate, uint256 validity) external
+        function addDelegateSigned(address identity, uint8 sigV, bytes32 sigR, bytes32 sigS, bytes32 delegateType, address delegate, uint256 validity) external
+        function setAttribute(address identity, bytes32 name, bytes value, uint256 validity) external
+        function setAttributeSigned(address identity, uint8 sigV, bytes32 sigR, bytes32 sigS, bytes32 name, bytes value, uint256 validity) external
+        event DIDOwnerChanged(address indexed identity, address owner, uint256 previousChange)
+        event DIDDelegateChanged(address indexed identity, bytes32 delegateType, address delegate, uint256 validTo, uint256 previousChange)
+        event DIDAttributeChanged(address indexed identity, bytes32 name, bytes value, uint256 validTo, uint256 previousChange)
+    ]"#
+);
+
+type SignerClient = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+/// DID Registry attribute under which the engine wallet anchors DID deactivations
+const DEACTIVATION_ATTRIBUTE: &str = "fortro/did/deactivated";
+
+/// Validity of a deactivation record, 100 years in seconds; the registry adds it to
+/// the block timestamp, so it must leave that sum well inside a `u64`
+const DEACTIVATION_VALIDITY: u64 = 100 * 365 * 24 * 60 * 60;
+
+/// DID Registry change an identity owner can authorize off-chain for a `*Signed` call
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DidRegistryChange {
+    ChangeOwner { new_owner: Address },
+    AddDelegate { delegate_type: String, delegate: Address, validity: u64 },
+    SetAttribute { name: String, value: Bytes, validity: u64 },
+}
+
 /// Ethereum client for interacting with the blockchain
 pub struct EthereumClient {
-    provider: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    provider: Arc<SignerClient>,
     registry_address: Option<Address>,
+    did_registry_address: Option<Address>,
 }
 
 impl EthereumClient {
@@ -86,6 +132,7 @@ impl EthereumClient {
         Ok(Self {
             provider,
             registry_address: None,
+            did_registry_address: None,
         })
     }
 
@@ -114,6 +161,17 @@ impl EthereumClient {
         Ok(self)
     }
 
+    /// Set the DID Registry contract address used by did:ethr
+    pub fn with_did_registry_address(mut self, address: &str) -> Result<Self, AppError> {
+        self.did_registry_address = Some(
+            address
+                .parse::<Address>()
+                .map_err(|e| AppError::BlockchainError(format!("Invalid address: {}", e)))?,
+        );
+
+        Ok(self)
+    }
+
     /// Deploy the SSI Registry contract
     pub async fn deploy_registry(&mut self) -> Result<Address, AppError> {
 
@@ -164,9 +222,9 @@ impl EthereumClient {
     /// Register a credential on the blockchain
     /// metadata_uri is typically an IPFS URI/hash for the credential metadata
     pub async fn register_credential(&self, did: &str, credential_hash: &str, metadata_uri: &str) -> Result<H256, AppError> {
-            // Enforce did:alyra usage across blockchain interactions
+            // Reject malformed DIDs across blockchain interactions
             if !did::validate_did(did) { 
-                return Err(AppError::ValidationError("Invalid DID: only did:alyra is supported".to_string()));
+                return Err(AppError::ValidationError("Invalid DID".to_string()));
             }
         let registry = self.get_registry()?;
 
@@ -193,7 +251,7 @@ impl EthereumClient {
     /// Revoke a credential on the blockchain
     pub async fn revoke_credential(&self, did: &str, credential_hash: &str) -> Result<H256, AppError> {
             if !did::validate_did(did) {
-                return Err(AppError::ValidationError("Invalid DID: only did:alyra is supported".to_string()));
+                return Err(AppError::ValidationError("Invalid DID".to_string()));
             }
         let registry = self.get_registry()?;
 
@@ -217,10 +275,64 @@ impl EthereumClient {
         Ok(tx_hash)
     }
 
+    /// Suspend a credential on the blockchain, until it is reinstated
+    pub async fn suspend_credential(&self, did: &str, credential_hash: &str) -> Result<H256, AppError> {
+            if !did::validate_did(did) {
+                return Err(AppError::ValidationError("Invalid DID".to_string()));
+            }
+        let registry = self.get_registry()?;
+
+        let pending_tx = registry
+            .suspend_credential(did.to_string(), credential_hash.to_string());
+
+        let tx = pending_tx
+            .send()
+            .await
+            .map_err(|e| AppError::BlockchainError(format!("Failed to suspend credential: {}", e)))?;
+
+        let receipt = tx
+            .await
+            .map_err(|e| AppError::BlockchainError(format!("Failed to get transaction receipt: {}", e)))?
+            .ok_or_else(|| AppError::BlockchainError("Transaction not found".to_string()))?;
+
+        let tx_hash = receipt.transaction_hash;
+
+        tracing::info!("Suspended credential for DID {} with hash {}", did, credential_hash);
+
+        Ok(tx_hash)
+    }
+
+    /// Reinstate a suspended credential on the blockchain
+    pub async fn reinstate_credential(&self, did: &str, credential_hash: &str) -> Result<H256, AppError> {
+            if !did::validate_did(did) {
+                return Err(AppError::ValidationError("Invalid DID".to_string()));
+            }
+        let registry = self.get_registry()?;
+
+        let pending_tx = registry
+            .reinstate_credential(did.to_string(), credential_hash.to_string());
+
+        let tx = pending_tx
+            .send()
+            .await
+            .map_err(|e| AppError::BlockchainError(format!("Failed to reinstate credential: {}", e)))?;
+
+        let receipt = tx
+            .await
+            .map_err(|e| AppError::BlockchainError(format!("Failed to get transaction receipt: {}", e)))?
+            .ok_or_else(|| AppError::BlockchainError("Transaction not found".to_string()))?;
+
+        let tx_hash = receipt.transaction_hash;
+
+        tracing::info!("Reinstated credential for DID {} with hash {}", did, credential_hash);
+
+        Ok(tx_hash)
+    }
+
     /// Check if a credential is registered/valid on the blockchain
     pub async fn is_credential_registered(&self, did: &str, credential_hash: &str) -> Result<bool, AppError> {
             if !did::validate_did(did) {
-                return Err(AppError::ValidationError("Invalid DID: only did:alyra is supported".to_string()));
+                return Err(AppError::ValidationError("Invalid DID".to_string()));
             }
         let registry = self.get_registry()?;
 
@@ -233,10 +345,53 @@ impl EthereumClient {
         Ok(result)
     }
 
-    /// Check if a credential is revoked on the blockchain (compatibility wrapper)
+    /// Check if a credential is suspended on the blockchain
+    pub async fn is_credential_suspended(&self, did: &str, credential_hash: &str) -> Result<bool, AppError> {
+            if !did::validate_did(did) {
+                return Err(AppError::ValidationError("Invalid DID".to_string()));
+            }
+        let registry = self.get_registry()?;
+
+        let result = registry
+            .is_credential_suspended(did.to_string(), credential_hash.to_string())
+            .call()
+            .await
+            .map_err(|e| AppError::BlockchainError(format!("Failed to check credential suspension: {}", e)))?;
+
+        Ok(result)
+    }
+
+    /// Get the block time a credential was registered at, `None` if it never was
+    pub async fn credential_registered_at(&self, did: &str, credential_hash: &str) -> Result<Option<DateTime<Utc>>, AppError> {
+            if !did::validate_did(did) {
+                return Err(AppError::ValidationError("Invalid DID".to_string()));
+            }
+        let registry = self.get_registry()?;
+
+        let (is_registered, _, registered_at, _, _, _, _) = registry
+            .get_credential_info(did.to_string(), credential_hash.to_string())
+            .call()
+            .await
+            .map_err(|e| AppError::BlockchainError(format!("Failed to get credential info: {}", e)))?;
+
+        if !is_registered {
+            return Ok(None);
+        }
+        Ok(DateTime::from_timestamp(registered_at.low_u64() as i64, 0))
+    }
+
+    /// Check if a credential is revoked on the blockchain; unlike an invalid one, it is neither
+    /// unregistered nor merely suspended
     pub async fn is_credential_revoked(&self, did: &str, credential_hash: &str) -> Result<bool, AppError> {
-        let is_valid = self.is_credential_registered(did, credential_hash).await?;
-        Ok(!is_valid)
+        let registry = self.get_registry()?;
+
+        let (_, is_revoked, _, _, _, _, _) = registry
+            .get_credential_info(did.to_string(), credential_hash.to_string())
+            .call()
+            .await
+            .map_err(|e| AppError::BlockchainError(format!("Failed to get credential info: {}", e)))?;
+
+        Ok(is_revoked)
     }
 
     /// Register a schema on the blockchain
@@ -336,4 +491,253 @@ impl EthereumClient {
 
         Ok(receipt)
     }
+
+    /// Get the DID Registry contract instance
+    fn get_did_registry(&self) -> Result<DIDRegistry<SignerClient>, AppError> {
+        let address = self.did_registry_address
+            .ok_or_else(|| AppError::BlockchainError("DID registry address not set".to_string()))?;
+
+        Ok(DIDRegistry::new(address, self.provider.clone()))
+    }
+
+    /// Get the identity address of a did:ethr DID, checking it is on the connected chain
+    pub async fn did_ethr_identity(&self, did: &str) -> Result<(u64, Address), AppError> {
+        let (chain_id, identity) = did::parse_did_ethr(did)?;
+
+        let connected_chain_id = self.get_chain_id().await?;
+        if chain_id != connected_chain_id {
+            return Err(AppError::ValidationError(format!(
+                "{} is on chain {}, but the engine is connected to chain {}",
+                did, chain_id, connected_chain_id
+            )));
+        }
+
+        Ok((chain_id, identity))
+    }
+
+    /// Get the current owner of a did:ethr identity; an identity owns itself until changed
+    pub async fn identity_owner(&self, identity: Address) -> Result<Address, AppError> {
+        self.get_did_registry()?
+            .identity_owner(identity)
+            .call()
+            .await
+            .map_err(|e| AppError::BlockchainError(format!("Failed to get identity owner: {}", e)))
+    }
+
+    /// Get the registry events of a did:ethr identity, oldest first.
+    ///
+    /// Each event points to the block of the identity's previous change, so the
+    /// history is walked back from the latest change one block at a time.
+    pub async fn did_registry_events(&self, identity: Address) -> Result<Vec<DIDRegistryEvents>, AppError> {
+        let registry = self.get_did_registry()?;
+
+        let mut block = registry
+            .changed(identity)
+            .call()
+            .await
+            .map_err(|e| AppError::BlockchainError(format!("Failed to get last change block: {}", e)))?
+            .as_u64();
+
+        let mut events = Vec::new();
+        while block > 0 {
+            let logs = registry
+                .events()
+                .from_block(block)
+                .to_block(block)
+                .topic1(H256::from(identity))
+                .query()
+                .await
+                .map_err(|e| AppError::BlockchainError(format!("Failed to get DID registry events: {}", e)))?;
+
+            // Newest first; only step to strictly earlier blocks so the walk always ends
+            let mut previous = 0;
+            for event in logs.into_iter().rev() {
+                let previous_change = match &event {
+                    DIDRegistryEvents::DidownerChangedFilter(e) => e.previous_change,
+                    DIDRegistryEvents::DiddelegateChangedFilter(e) => e.previous_change,
+                    DIDRegistryEvents::DidattributeChangedFilter(e) => e.previous_change,
+                }
+                .as_u64();
+                if previous_change < block {
+                    previous = previous_change;
+                }
+                events.push(event);
+            }
+            block = previous;
+        }
+
+        events.reverse();
+        Ok(events)
+    }
+
+    /// Hash the identity owner signs, without a message prefix, to authorize a `*Signed` call
+    pub async fn did_change_hash(&self, identity: Address, change: &DidRegistryChange) -> Result<H256, AppError> {
+        let registry = self.get_did_registry()?;
+
+        // The nonce belongs to the owner, not the identity
+        let owner = self.identity_owner(identity).await?;
+        let nonce = registry
+            .nonce(owner)
+            .call()
+            .await
+            .map_err(|e| AppError::BlockchainError(format!("Failed to get owner nonce: {}", e)))?;
+
+        let mut tokens = vec![
+            Token::FixedBytes(vec![0x19]),
+            Token::FixedBytes(vec![0x00]),
+            Token::Address(registry.address()),
+            uint256(nonce),
+            Token::Address(identity),
+        ];
+        match change {
+            DidRegistryChange::ChangeOwner { new_owner } => {
+                tokens.push(Token::String("changeOwner".to_string()));
+                tokens.push(Token::Address(*new_owner));
+            }
+            DidRegistryChange::AddDelegate { delegate_type, delegate, validity } => {
+                tokens.push(Token::String("addDelegate".to_string()));
+                tokens.push(Token::FixedBytes(bytes32(delegate_type)?.to_vec()));
+                tokens.push(Token::Address(*delegate));
+                tokens.push(uint256(U256::from(*validity)));
+            }
+            DidRegistryChange::SetAttribute { name, value, validity } => {
+                tokens.push(Token::String("setAttribute".to_string()));
+                tokens.push(Token::FixedBytes(bytes32(name)?.to_vec()));
+                tokens.push(Token::Bytes(value.to_vec()));
+                tokens.push(uint256(U256::from(*validity)));
+            }
+        }
+
+        let packed = encode_packed(&tokens)
+            .map_err(|e| AppError::BlockchainError(format!("Failed to encode DID registry change: {}", e)))?;
+
+        Ok(H256::from(keccak256(packed)))
+    }
+
+    /// Transfer a did:ethr identity to a new owner, sent by the current owner
+    pub async fn change_owner(&self, identity: Address, new_owner: Address) -> Result<H256, AppError> {
+        let call = self.get_did_registry()?.change_owner(identity, new_owner);
+        let tx_hash = send_and_confirm(call, "change DID owner").await?;
+
+        tracing::info!("Changed owner of {:?} to {:?}", identity, new_owner);
+        Ok(tx_hash)
+    }
+
+    /// Transfer a did:ethr identity to a new owner, authorized by the owner's signature
+    pub async fn change_owner_signed(&self, identity: Address, signature: &Signature, new_owner: Address) -> Result<H256, AppError> {
+        let (v, r, s) = split_signature(signature);
+        let call = self.get_did_registry()?.change_owner_signed(identity, v, r, s, new_owner);
+        let tx_hash = send_and_confirm(call, "change DID owner").await?;
+
+        tracing::info!("Changed owner of {:?} to {:?} with a signed change", identity, new_owner);
+        Ok(tx_hash)
+    }
+
+    /// Add a delegate (`veriKey` or `sigAuth`) valid for `validity` seconds, sent by the owner
+    pub async fn add_delegate(&self, identity: Address, delegate_type: &str, delegate: Address, validity: u64) -> Result<H256, AppError> {
+        let call = self.get_did_registry()?
+            .add_delegate(identity, bytes32(delegate_type)?, delegate, U256::from(validity));
+        let tx_hash = send_and_confirm(call, "add DID delegate").await?;
+
+        tracing::info!("Added {} delegate {:?} to {:?}", delegate_type, delegate, identity);
+        Ok(tx_hash)
+    }
+
+    /// Add a delegate, authorized by the owner's signature
+    pub async fn add_delegate_signed(
+        &self,
+        identity: Address,
+        signature: &Signature,
+        delegate_type: &str,
+        delegate: Address,
+        validity: u64,
+    ) -> Result<H256, AppError> {
+        let (v, r, s) = split_signature(signature);
+        let call = self.get_did_registry()?
+            .add_delegate_signed(identity, v, r, s, bytes32(delegate_type)?, delegate, U256::from(validity));
+        let tx_hash = send_and_confirm(call, "add DID delegate").await?;
+
+        tracing::info!("Added {} delegate {:?} to {:?} with a signed change", delegate_type, delegate, identity);
+        Ok(tx_hash)
+    }
+
+    /// Publish a DID attribute such as `did/pub/Ed25519/veriKey/hex` or `did/svc/<type>`, sent by the owner
+    pub async fn set_attribute(&self, identity: Address, name: &str, value: &[u8], validity: u64) -> Result<H256, AppError> {
+        let call = self.get_did_registry()?
+            .set_attribute(identity, bytes32(name)?, Bytes::from(value.to_vec()), U256::from(validity));
+        let tx_hash = send_and_confirm(call, "set DID attribute").await?;
+
+        tracing::info!("Set attribute {} of {:?}", name, identity);
+        Ok(tx_hash)
+    }
+
+    /// Publish a DID attribute, authorized by the owner's signature
+    pub async fn set_attribute_signed(
+        &self,
+        identity: Address,
+        signature: &Signature,
+        name: &str,
+        value: &[u8],
+        validity: u64,
+    ) -> Result<H256, AppError> {
+        let (v, r, s) = split_signature(signature);
+        let call = self.get_did_registry()?
+            .set_attribute_signed(identity, v, r, s, bytes32(name)?, Bytes::from(value.to_vec()), U256::from(validity));
+        let tx_hash = send_and_confirm(call, "set DID attribute").await?;
+
+        tracing::info!("Set attribute {} of {:?} with a signed change", name, identity);
+        Ok(tx_hash)
+    }
+
+    /// Record a DID's deactivation as a `fortro/did/deactivated` attribute of the engine wallet's own identity.
+    ///
+    /// The wallet owns its identity, so no other signature is needed; the event
+    /// timestamps the deactivation publicly.
+    pub async fn anchor_did_deactivation(&self, did: &str) -> Result<H256, AppError> {
+        self.set_attribute(self.provider.address(), DEACTIVATION_ATTRIBUTE, did.as_bytes(), DEACTIVATION_VALIDITY).await
+    }
+}
+
+/// Send a contract transaction and wait for its receipt
+async fn send_and_confirm(call: ContractCall<SignerClient, ()>, action: &str) -> Result<H256, AppError> {
+    let tx = call
+        .send()
+        .await
+        .map_err(|e| AppError::BlockchainError(format!("Failed to {}: {}", action, e)))?;
+
+    let receipt = tx
+        .await
+        .map_err(|e| AppError::BlockchainError(format!("Failed to get transaction receipt: {}", e)))?
+        .ok_or_else(|| AppError::BlockchainError("Transaction not found".to_string()))?;
+
+    Ok(receipt.transaction_hash)
+}
+
+/// Right-pad a DID Registry name or delegate type to a `bytes32`
+fn bytes32(value: &str) -> Result<[u8; 32], AppError> {
+    if value.len() > 32 {
+        return Err(AppError::ValidationError(format!("'{}' is longer than 32 bytes", value)));
+    }
+    let mut bytes = [0u8; 32];
+    bytes[..value.len()].copy_from_slice(value.as_bytes());
+    Ok(bytes)
+}
+
+/// Packed `uint256` token; ethers packs `Token::Uint` in as few bytes as the value needs
+fn uint256(value: U256) -> Token {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    Token::FixedBytes(bytes.to_vec())
+}
+
+/// Split a signature into the `v`, `r` and `s` arguments `ecrecover` expects
+fn split_signature(signature: &Signature) -> (u8, [u8; 32], [u8; 32]) {
+    let mut r = [0u8; 32];
+    let mut s = [0u8; 32];
+    signature.r.to_big_endian(&mut r);
+    signature.s.to_big_endian(&mut s);
+
+    // Accept both 0/1 and 27/28 recovery IDs
+    let v = if signature.v < 27 { signature.v + 27 } else { signature.v };
+    (v as u8, r, s)
 }
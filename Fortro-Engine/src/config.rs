@@ -1,44 +1,212 @@
 use serde::Deserialize;
 use std::env;
 use crate::error::AppError;
+use crate::utils::jwt::HmacAlgorithm;
+
+/// Storage backend used for persistence
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum StorageBackend {
+    #[serde(rename = "mongodb")]
+    MongoDb,
+    #[serde(rename = "memory")]
+    Memory,
+}
+
+impl std::str::FromStr for StorageBackend {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "mongodb" | "mongo" => Ok(StorageBackend::MongoDb),
+            "memory" | "in-memory" => Ok(StorageBackend::Memory),
+            other => Err(AppError::ConfigError(format!(
+                "STORAGE_BACKEND must be 'mongodb' or 'memory', got '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Where issuer signing keys are kept
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum KeyStoreBackend {
+    /// Password-encrypted keystore file read by the engine itself
+    #[serde(rename = "file")]
+    File,
+    /// Separate signing-service process reached over a local Unix socket
+    #[serde(rename = "socket")]
+    Socket,
+}
+
+impl std::str::FromStr for KeyStoreBackend {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "file" => Ok(KeyStoreBackend::File),
+            "socket" => Ok(KeyStoreBackend::Socket),
+            other => Err(AppError::ConfigError(format!(
+                "KEYSTORE_BACKEND must be 'file' or 'socket', got '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Key store configuration, shared by the engine and the signing service
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyStoreConfig {
+    pub backend: KeyStoreBackend,
+    pub path: String,
+    pub socket_path: String,
+    pub encryption_secret: Option<String>,
+}
+
+impl KeyStoreConfig {
+    pub fn from_env() -> Result<Self, AppError> {
+        let backend: KeyStoreBackend = env::var("KEYSTORE_BACKEND")
+            .unwrap_or_else(|_| "file".to_string())
+            .parse()?;
+        let encryption_secret = env::var("KEY_ENCRYPTION_SECRET").ok().filter(|s| !s.is_empty());
+        if backend == KeyStoreBackend::File && encryption_secret.is_none() {
+            return Err(AppError::ConfigError("KEY_ENCRYPTION_SECRET must be set".to_string()));
+        }
+
+        Ok(Self {
+            backend,
+            path: env::var("KEYSTORE_PATH").unwrap_or_else(|_| "keystore.enc".to_string()),
+            socket_path: env::var("KEYSTORE_SOCKET").unwrap_or_else(|_| "/tmp/fortro-signer.sock".to_string()),
+            encryption_secret,
+        })
+    }
+}
+
+/// did:web hosting and resolution settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct DidWebConfig {
+    /// Host (and optional port) under which the engine serves did:web documents
+    pub domain: Option<String>,
+    /// DID allowed to claim the root `did:web:<domain>` issuer
+    pub root_controller: Option<String>,
+    /// How long resolved external documents are cached, in seconds
+    pub cache_ttl: u64,
+    /// Resolve external did:web DIDs over plain HTTP; for local development only
+    pub allow_http: bool,
+}
+
+impl DidWebConfig {
+    pub fn from_env() -> Result<Self, AppError> {
+        Ok(Self {
+            domain: env::var("DID_WEB_DOMAIN").ok()
+                .map(|s| s.trim().trim_end_matches('/').to_ascii_lowercase())
+                .filter(|s| !s.is_empty()),
+            root_controller: env::var("DID_WEB_ROOT_CONTROLLER").ok().filter(|s| !s.trim().is_empty()),
+            cache_ttl: env::var("DID_WEB_CACHE_TTL")
+                .unwrap_or_else(|_| "300".to_string()) // Default: 5 minutes
+                .parse()
+                .map_err(|_| AppError::ConfigError("DID_WEB_CACHE_TTL must be a valid number".to_string()))?,
+            allow_http: env::var("DID_WEB_ALLOW_HTTP")
+                .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+        })
+    }
+}
+
+/// Bitstring Status List publication and caching settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct StatusListConfig {
+    /// Public base URL of the engine, under which status list credentials are served
+    pub base_url: String,
+    /// How long fetched status lists are cached, in seconds
+    pub cache_ttl: u64,
+    /// Register the hash of every published status list in the SSIRegistry contract
+    pub anchor: bool,
+}
+
+impl StatusListConfig {
+    pub fn from_env(port: u16) -> Result<Self, AppError> {
+        Ok(Self {
+            base_url: env::var("STATUS_LIST_BASE_URL").ok()
+                .map(|s| s.trim().trim_end_matches('/').to_string())
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| format!("http://localhost:{}", port)),
+            cache_ttl: env::var("STATUS_LIST_CACHE_TTL")
+                .unwrap_or_else(|_| "300".to_string()) // Default: 5 minutes
+                .parse()
+                .map_err(|_| AppError::ConfigError("STATUS_LIST_CACHE_TTL must be a valid number".to_string()))?,
+            anchor: env::var("STATUS_LIST_ANCHOR")
+                .map(|s| matches!(s.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+        })
+    }
+}
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
-    pub mongodb_uri: String,
+    pub storage_backend: StorageBackend,
+    pub mongodb_uri: Option<String>,
     pub ipfs_api_url: String,
     pub ethereum_rpc_url: String,
     pub port: u16,
     pub jwt_expiration: u64,
+    pub jwt_refresh_expiration: u64,
+    pub jwt_algorithm: HmacAlgorithm,
     pub issuer_did: String,
     pub issuer_private_key: String,
+    pub keystore: KeyStoreConfig,
+    pub did_web: DidWebConfig,
+    pub status_list: StatusListConfig,
     pub jwt_secret: String,
     pub cors_allowed_origins: Option<Vec<String>>,
     pub registry_address: Option<String>,
+    pub did_registry_address: Option<String>,
 }
 
 impl Config {
     pub fn from_env() -> Result<Self, AppError> {
+        let storage_backend: StorageBackend = env::var("STORAGE_BACKEND")
+            .unwrap_or_else(|_| "mongodb".to_string())
+            .parse()?;
+        let mongodb_uri = env::var("MONGODB_URI").ok().filter(|s| !s.trim().is_empty());
+        if storage_backend == StorageBackend::MongoDb && mongodb_uri.is_none() {
+            return Err(AppError::ConfigError("MONGODB_URI must be set".to_string()));
+        }
+
+        let port = env::var("PORT")
+            .unwrap_or_else(|_| "3000".to_string())
+            .parse()
+            .map_err(|_| AppError::ConfigError("PORT must be a valid number".to_string()))?;
+
         Ok(Self {
-            mongodb_uri: env::var("MONGODB_URI")
-                .map_err(|_| AppError::ConfigError("MONGODB_URI must be set".to_string()))?,
+            storage_backend,
+            mongodb_uri,
             ipfs_api_url: env::var("IPFS_API_URL")
                 .map_err(|_| AppError::ConfigError("IPFS_API_URL must be set".to_string()))?,
             ethereum_rpc_url: env::var("ETHEREUM_RPC_URL")
                 .map_err(|_| AppError::ConfigError("ETHEREUM_RPC_URL must be set".to_string()))?,
-            port: env::var("PORT")
-                .unwrap_or_else(|_| "3000".to_string())
-                .parse()
-                .map_err(|_| AppError::ConfigError("PORT must be a valid number".to_string()))?,
+            port,
             jwt_secret: env::var("JWT_SECRET")
-                .map_err(|_| AppError::ConfigError("JWT_SECRET must be set".to_string()))?,
+                .ok()
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| AppError::ConfigError("JWT_SECRET must be set".to_string()))?,
             jwt_expiration: env::var("JWT_EXPIRATION")
                 .unwrap_or_else(|_| "86400".to_string()) // Default: 24 hours
                 .parse()
                 .map_err(|_| AppError::ConfigError("JWT_EXPIRATION must be a valid number".to_string()))?,
+            jwt_refresh_expiration: env::var("JWT_REFRESH_EXPIRATION")
+                .unwrap_or_else(|_| "2592000".to_string()) // Default: 30 days
+                .parse()
+                .map_err(|_| AppError::ConfigError("JWT_REFRESH_EXPIRATION must be a valid number".to_string()))?,
+            jwt_algorithm: env::var("JWT_ALGORITHM")
+                .unwrap_or_else(|_| "HS256".to_string())
+                .parse()?,
             issuer_did: env::var("ISSUER_DID")
                 .map_err(|_| AppError::ConfigError("ISSUER_DID must be set".to_string()))?,
             issuer_private_key: env::var("ISSUER_PRIVATE_KEY")
                 .map_err(|_| AppError::ConfigError("ISSUER_PRIVATE_KEY must be set".to_string()))?,
+            keystore: KeyStoreConfig::from_env()?,
+            did_web: DidWebConfig::from_env()?,
+            status_list: StatusListConfig::from_env(port)?,
             cors_allowed_origins: env::var("CORS_ALLOWED_ORIGINS").ok().map(|s| {
                 s.split(',')
                     .map(|o| o.trim())
@@ -47,6 +215,7 @@ impl Config {
                     .collect::<Vec<_>>()
             }).filter(|v| !v.is_empty()),
             registry_address: env::var("REGISTRY_ADDRESS").ok().filter(|s| !s.trim().is_empty()),
+            did_registry_address: env::var("DID_REGISTRY_ADDRESS").ok().filter(|s| !s.trim().is_empty()),
         })
     }
 }
\ No newline at end of file
@@ -0,0 +1,662 @@
+use async_trait::async_trait;
+use mongodb::{
+    bson::{oid::ObjectId, Bson, Document},
+    options::FindOptions,
+};
+use regex::RegexBuilder;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use super::Storage;
+use crate::error::AppError;
+
+/// In-memory storage backend.
+///
+/// Documents are kept as BSON so the services can keep using the same filters
+/// they send to MongoDB. The supported query subset is what the services use:
+/// field equality (including dotted paths and array membership), `$and`, `$or`,
+/// `$ne`, `$in`, `$nin`, `$exists`, `$lt`/`$lte`/`$gt`/`$gte` and `$regex`, plus
+/// the `$set`, `$unset`, `$inc` and `$push` update operators.
+#[derive(Default)]
+pub struct MemoryStorage {
+    collections: RwLock<HashMap<String, Vec<Document>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read<R>(&self, collection: &str, f: impl FnOnce(&[Document]) -> R) -> Result<R, AppError> {
+        let collections = self
+            .collections
+            .read()
+            .map_err(|_| AppError::DatabaseError("In-memory storage lock poisoned".to_string()))?;
+        Ok(f(collections.get(collection).map(Vec::as_slice).unwrap_or(&[])))
+    }
+
+    fn write<R>(&self, collection: &str, f: impl FnOnce(&mut Vec<Document>) -> Result<R, AppError>) -> Result<R, AppError> {
+        let mut collections = self
+            .collections
+            .write()
+            .map_err(|_| AppError::DatabaseError("In-memory storage lock poisoned".to_string()))?;
+        f(collections.entry(collection.to_string()).or_default())
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn find_document(&self, collection: &str, filter: Document) -> Result<Option<Document>, AppError> {
+        let mut found = None;
+        self.read(collection, |documents| {
+            for document in documents {
+                match matches(document, &filter) {
+                    Ok(true) => {
+                        found = Some(Ok(document.clone()));
+                        break;
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        found = Some(Err(e));
+                        break;
+                    }
+                }
+            }
+        })?;
+        found.transpose()
+    }
+
+    async fn find_documents(
+        &self,
+        collection: &str,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<Document>, AppError> {
+        let mut results = self.read(collection, |documents| {
+            documents
+                .iter()
+                .filter_map(|document| match matches(document, &filter) {
+                    Ok(true) => Some(Ok(document.clone())),
+                    Ok(false) => None,
+                    Err(e) => Some(Err(e)),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })??;
+
+        if let Some(options) = options {
+            if let Some(sort) = &options.sort {
+                results.sort_by(|a, b| compare_documents(a, b, sort));
+            }
+            if let Some(skip) = options.skip {
+                results = results.into_iter().skip(skip as usize).collect();
+            }
+            if let Some(limit) = options.limit {
+                results.truncate(limit.unsigned_abs() as usize);
+            }
+        }
+
+        Ok(results)
+    }
+
+    async fn insert_document(&self, collection: &str, mut document: Document) -> Result<String, AppError> {
+        let id = match document.get("_id") {
+            Some(Bson::ObjectId(id)) => id.to_hex(),
+            Some(other) => other.to_string(),
+            None => {
+                let id = ObjectId::new();
+                document.insert("_id", id);
+                id.to_hex()
+            }
+        };
+
+        self.write(collection, |documents| {
+            documents.push(document);
+            Ok(id)
+        })
+    }
+
+    async fn insert_document_if_absent(
+        &self,
+        collection: &str,
+        filter: Document,
+        mut document: Document,
+    ) -> Result<bool, AppError> {
+        // Checking and inserting under one write lock makes this atomic
+        self.write(collection, |documents| {
+            for existing in documents.iter() {
+                if matches(existing, &filter)? {
+                    return Ok(false);
+                }
+            }
+            if !document.contains_key("_id") {
+                document.insert("_id", ObjectId::new());
+            }
+            documents.push(document);
+            Ok(true)
+        })
+    }
+
+    async fn replace_document(
+        &self,
+        collection: &str,
+        filter: Document,
+        mut document: Document,
+        upsert: bool,
+    ) -> Result<bool, AppError> {
+        self.write(collection, |documents| {
+            for existing in documents.iter_mut() {
+                if matches(existing, &filter)? {
+                    if let Some(id) = existing.get("_id").cloned() {
+                        document.insert("_id", id);
+                    }
+                    *existing = document;
+                    return Ok(true);
+                }
+            }
+
+            if upsert {
+                if !document.contains_key("_id") {
+                    document.insert("_id", ObjectId::new());
+                }
+                documents.push(document);
+                return Ok(true);
+            }
+
+            Ok(false)
+        })
+    }
+
+    async fn update_document(&self, collection: &str, filter: Document, update: Document) -> Result<bool, AppError> {
+        self.write(collection, |documents| {
+            for existing in documents.iter_mut() {
+                if matches(existing, &filter)? {
+                    let before = existing.clone();
+                    apply_update(existing, &update)?;
+                    return Ok(*existing != before);
+                }
+            }
+            Ok(false)
+        })
+    }
+
+    async fn delete_document(&self, collection: &str, filter: Document) -> Result<bool, AppError> {
+        self.write(collection, |documents| {
+            for index in 0..documents.len() {
+                if matches(&documents[index], &filter)? {
+                    documents.remove(index);
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        })
+    }
+
+    async fn delete_documents(&self, collection: &str, filter: Document) -> Result<u64, AppError> {
+        self.write(collection, |documents| {
+            let matched = documents
+                .iter()
+                .map(|document| matches(document, &filter))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut flags = matched.iter();
+            documents.retain(|_| !flags.next().copied().unwrap_or(false));
+            Ok(matched.into_iter().filter(|m| *m).count() as u64)
+        })
+    }
+
+    async fn count_documents(&self, collection: &str, filter: Document) -> Result<u64, AppError> {
+        self.read(collection, |documents| {
+            documents.iter().try_fold(0u64, |count, document| {
+                Ok(count + matches(document, &filter)? as u64)
+            })
+        })?
+    }
+}
+
+/// Check whether a document matches a MongoDB-style filter
+fn matches(document: &Document, filter: &Document) -> Result<bool, AppError> {
+    for (key, condition) in filter {
+        let matched = match key.as_str() {
+            "$and" => logical_operands(condition)?
+                .iter()
+                .map(|sub| matches(document, sub))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .all(|m| m),
+            "$or" => logical_operands(condition)?
+                .iter()
+                .map(|sub| matches(document, sub))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .any(|m| m),
+            "$nor" => !logical_operands(condition)?
+                .iter()
+                .map(|sub| matches(document, sub))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .any(|m| m),
+            _ => matches_field(&lookup(document, key), condition)?,
+        };
+
+        if !matched {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+fn logical_operands(condition: &Bson) -> Result<Vec<Document>, AppError> {
+    match condition {
+        Bson::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                Bson::Document(document) => Ok(document.clone()),
+                _ => Err(AppError::DatabaseError("Logical operator expects an array of documents".to_string())),
+            })
+            .collect(),
+        _ => Err(AppError::DatabaseError("Logical operator expects an array".to_string())),
+    }
+}
+
+/// Evaluate a condition against the values found at a field path
+fn matches_field(values: &[&Bson], condition: &Bson) -> Result<bool, AppError> {
+    let operators = match condition {
+        Bson::Document(document) if document.keys().next().is_some_and(|k| k.starts_with('$')) => document,
+        // Like MongoDB, `null` also matches a missing field
+        Bson::Null if values.is_empty() => return Ok(true),
+        _ => return Ok(values.iter().any(|value| value_equals(value, condition))),
+    };
+
+    // A missing field counts as `null` for `$eq` and `$ne`
+    let missing_is_null = values.is_empty();
+    for (operator, operand) in operators {
+        let matched = match operator.as_str() {
+            "$eq" if missing_is_null => *operand == Bson::Null,
+            "$ne" if missing_is_null => *operand != Bson::Null,
+            "$eq" => values.iter().any(|value| value_equals(value, operand)),
+            "$ne" => !values.iter().any(|value| value_equals(value, operand)),
+            "$in" => in_list(values, operand)?,
+            "$nin" => !in_list(values, operand)?,
+            "$exists" => {
+                let exists = !values.is_empty();
+                exists == matches!(operand, Bson::Boolean(true))
+            }
+            "$gt" => values.iter().any(|value| compare_bson(value, operand) == Some(Ordering::Greater)),
+            "$gte" => values
+                .iter()
+                .any(|value| matches!(compare_bson(value, operand), Some(Ordering::Greater | Ordering::Equal))),
+            "$lt" => values.iter().any(|value| compare_bson(value, operand) == Some(Ordering::Less)),
+            "$lte" => values
+                .iter()
+                .any(|value| matches!(compare_bson(value, operand), Some(Ordering::Less | Ordering::Equal))),
+            "$regex" => {
+                let pattern = operand
+                    .as_str()
+                    .ok_or_else(|| AppError::DatabaseError("$regex expects a string".to_string()))?;
+                let case_insensitive = operators
+                    .get_str("$options")
+                    .map(|options| options.contains('i'))
+                    .unwrap_or(false);
+                let regex = RegexBuilder::new(pattern)
+                    .case_insensitive(case_insensitive)
+                    .build()
+                    .map_err(|e| AppError::DatabaseError(format!("Invalid regex: {}", e)))?;
+                values.iter().any(|value| value.as_str().is_some_and(|s| regex.is_match(s)))
+            }
+            "$options" => true,
+            other => {
+                return Err(AppError::DatabaseError(format!(
+                    "Unsupported query operator for in-memory storage: {}",
+                    other
+                )))
+            }
+        };
+
+        if !matched {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+fn in_list(values: &[&Bson], operand: &Bson) -> Result<bool, AppError> {
+    let candidates = operand
+        .as_array()
+        .ok_or_else(|| AppError::DatabaseError("$in/$nin expects an array".to_string()))?;
+    Ok(candidates
+        .iter()
+        .any(|candidate| values.iter().any(|value| value_equals(value, candidate))))
+}
+
+/// Equality with MongoDB's array semantics: an array field matches a scalar it contains
+fn value_equals(value: &Bson, expected: &Bson) -> bool {
+    if compare_bson(value, expected) == Some(Ordering::Equal) || value == expected {
+        return true;
+    }
+    match value {
+        Bson::Array(items) => items.iter().any(|item| item == expected),
+        _ => false,
+    }
+}
+
+/// Resolve a dotted field path, descending into arrays of sub-documents
+fn lookup<'a>(document: &'a Document, path: &str) -> Vec<&'a Bson> {
+    let mut current: Vec<&Bson> = Vec::new();
+    let mut segments = path.split('.');
+
+    if let Some(first) = segments.next() {
+        if let Some(value) = document.get(first) {
+            current.push(value);
+        }
+    }
+
+    for segment in segments {
+        let mut next = Vec::new();
+        for value in current {
+            match value {
+                Bson::Document(sub) => next.extend(sub.get(segment)),
+                Bson::Array(items) => {
+                    if let Ok(index) = segment.parse::<usize>() {
+                        next.extend(items.get(index));
+                    } else {
+                        next.extend(items.iter().filter_map(|item| match item {
+                            Bson::Document(sub) => sub.get(segment),
+                            _ => None,
+                        }));
+                    }
+                }
+                _ => {}
+            }
+        }
+        current = next;
+    }
+
+    current
+}
+
+fn numeric(value: &Bson) -> Option<f64> {
+    match value {
+        Bson::Int32(v) => Some(*v as f64),
+        Bson::Int64(v) => Some(*v as f64),
+        Bson::Double(v) => Some(*v),
+        _ => None,
+    }
+}
+
+fn compare_bson(a: &Bson, b: &Bson) -> Option<Ordering> {
+    if let (Some(x), Some(y)) = (numeric(a), numeric(b)) {
+        return x.partial_cmp(&y);
+    }
+    match (a, b) {
+        (Bson::String(x), Bson::String(y)) => Some(x.cmp(y)),
+        (Bson::DateTime(x), Bson::DateTime(y)) => Some(x.cmp(y)),
+        (Bson::Boolean(x), Bson::Boolean(y)) => Some(x.cmp(y)),
+        (Bson::ObjectId(x), Bson::ObjectId(y)) => Some(x.cmp(y)),
+        (Bson::Null, Bson::Null) => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+fn compare_documents(a: &Document, b: &Document, sort: &Document) -> Ordering {
+    for (key, direction) in sort {
+        let left = lookup(a, key).into_iter().next();
+        let right = lookup(b, key).into_iter().next();
+        let ordering = match (left, right) {
+            (Some(x), Some(y)) => compare_bson(x, y).unwrap_or(Ordering::Equal),
+            (Some(_), None) => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (None, None) => Ordering::Equal,
+        };
+        let ordering = if numeric(direction).unwrap_or(1.0) < 0.0 {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Apply a MongoDB-style update document
+fn apply_update(document: &mut Document, update: &Document) -> Result<(), AppError> {
+    for (operator, fields) in update {
+        let fields = fields
+            .as_document()
+            .ok_or_else(|| AppError::DatabaseError(format!("{} expects a document", operator)))?;
+
+        for (path, value) in fields {
+            match operator.as_str() {
+                "$set" => set_path(document, path, value.clone()),
+                "$unset" => {
+                    unset_path(document, path);
+                }
+                "$inc" => {
+                    let current = get_path(document, path).cloned().unwrap_or(Bson::Int64(0));
+                    let incremented = match (&current, value) {
+                        (Bson::Int32(a), Bson::Int32(b)) => Bson::Int32(a + b),
+                        (Bson::Int64(a), Bson::Int64(b)) => Bson::Int64(a + b),
+                        (Bson::Int32(a), Bson::Int64(b)) | (Bson::Int64(b), Bson::Int32(a)) => Bson::Int64(*a as i64 + b),
+                        _ => Bson::Double(
+                            numeric(&current)
+                                .zip(numeric(value))
+                                .map(|(a, b)| a + b)
+                                .ok_or_else(|| AppError::DatabaseError("$inc expects numeric values".to_string()))?,
+                        ),
+                    };
+                    set_path(document, path, incremented);
+                }
+                "$push" => {
+                    let mut items = match get_path(document, path) {
+                        Some(Bson::Array(items)) => items.clone(),
+                        Some(_) => return Err(AppError::DatabaseError(format!("$push target {} is not an array", path))),
+                        None => Vec::new(),
+                    };
+                    items.push(value.clone());
+                    set_path(document, path, Bson::Array(items));
+                }
+                other => {
+                    return Err(AppError::DatabaseError(format!(
+                        "Unsupported update operator for in-memory storage: {}",
+                        other
+                    )))
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn get_path<'a>(document: &'a Document, path: &str) -> Option<&'a Bson> {
+    match path.split_once('.') {
+        Some((head, rest)) => document.get_document(head).ok().and_then(|sub| get_path(sub, rest)),
+        None => document.get(path),
+    }
+}
+
+fn set_path(document: &mut Document, path: &str, value: Bson) {
+    match path.split_once('.') {
+        Some((head, rest)) => {
+            if !matches!(document.get(head), Some(Bson::Document(_))) {
+                document.insert(head, Document::new());
+            }
+            if let Ok(sub) = document.get_document_mut(head) {
+                set_path(sub, rest, value);
+            }
+        }
+        None => {
+            document.insert(path, value);
+        }
+    }
+}
+
+fn unset_path(document: &mut Document, path: &str) -> Option<Bson> {
+    match path.split_once('.') {
+        Some((head, rest)) => document.get_document_mut(head).ok().and_then(|sub| unset_path(sub, rest)),
+        None => document.remove(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    async fn storage_with(documents: Vec<Document>) -> MemoryStorage {
+        let storage = MemoryStorage::new();
+        for document in documents {
+            storage.insert_document("items", document).await.unwrap();
+        }
+        storage
+    }
+
+    async fn names(storage: &MemoryStorage, filter: Document) -> Vec<String> {
+        storage
+            .find_documents("items", filter, None)
+            .await
+            .unwrap()
+            .iter()
+            .map(|document| document.get_str("name").unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn null_matches_missing_and_null_fields() {
+        let storage = storage_with(vec![
+            doc! { "name": "missing" },
+            doc! { "name": "null", "retired_at": Bson::Null },
+            doc! { "name": "set", "retired_at": 1_700_000_000i64 },
+        ])
+        .await;
+
+        assert_eq!(names(&storage, doc! { "retired_at": Bson::Null }).await, ["missing", "null"]);
+        assert_eq!(names(&storage, doc! { "retired_at": { "$exists": true } }).await, ["null", "set"]);
+        assert_eq!(names(&storage, doc! { "retired_at": { "$ne": Bson::Null } }).await, ["set"]);
+    }
+
+    #[tokio::test]
+    async fn comparison_operators_compare_across_numeric_types() {
+        let storage = storage_with(vec![
+            doc! { "name": "a", "expires_at": 10i32 },
+            doc! { "name": "b", "expires_at": 20i64 },
+            doc! { "name": "c", "expires_at": 30.5 },
+            doc! { "name": "d", "expires_at": "not a number" },
+        ])
+        .await;
+
+        assert_eq!(names(&storage, doc! { "expires_at": { "$lt": 20i64 } }).await, ["a"]);
+        assert_eq!(names(&storage, doc! { "expires_at": { "$lte": 20i32 } }).await, ["a", "b"]);
+        assert_eq!(names(&storage, doc! { "expires_at": { "$gt": 15, "$lt": 31 } }).await, ["b", "c"]);
+        assert_eq!(names(&storage, doc! { "expires_at": { "$gte": 30.5 } }).await, ["c"]);
+    }
+
+    #[tokio::test]
+    async fn equality_follows_dotted_paths_and_arrays() {
+        let storage = storage_with(vec![
+            doc! { "name": "a", "roles": ["issuer", "verifier"], "profile": { "country": "FR" } },
+            doc! { "name": "b", "roles": ["holder"], "profile": { "country": "DE" } },
+        ])
+        .await;
+
+        assert_eq!(names(&storage, doc! { "roles": "verifier" }).await, ["a"]);
+        assert_eq!(names(&storage, doc! { "profile.country": "DE" }).await, ["b"]);
+        assert_eq!(names(&storage, doc! { "roles": { "$in": ["holder", "admin"] } }).await, ["b"]);
+        assert_eq!(names(&storage, doc! { "$or": [{ "name": "a" }, { "profile.country": "DE" }] }).await, ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn set_and_unset_update_nested_fields() {
+        let storage = storage_with(vec![doc! { "name": "a", "secret": "x" }]).await;
+
+        let updated = storage
+            .update_document(
+                "items",
+                doc! { "name": "a" },
+                doc! { "$set": { "status.state": "revoked" }, "$unset": { "secret": "" } },
+            )
+            .await
+            .unwrap();
+        assert!(updated);
+
+        let document = storage.find_document("items", doc! { "name": "a" }).await.unwrap().unwrap();
+        assert_eq!(document.get_document("status").unwrap().get_str("state").unwrap(), "revoked");
+        assert!(!document.contains_key("secret"));
+    }
+
+    #[tokio::test]
+    async fn inc_keeps_integer_types_and_starts_from_zero() {
+        let storage = storage_with(vec![doc! { "name": "a", "small": 1i32, "large": 1i64 }]).await;
+
+        storage
+            .update_document(
+                "items",
+                doc! { "name": "a" },
+                doc! { "$inc": { "small": 2i32, "large": 2i32, "missing": 5i64, "nested.count": 1i64 } },
+            )
+            .await
+            .unwrap();
+
+        let document = storage.find_document("items", doc! { "name": "a" }).await.unwrap().unwrap();
+        assert_eq!(document.get("small"), Some(&Bson::Int32(3)));
+        assert_eq!(document.get("large"), Some(&Bson::Int64(3)));
+        assert_eq!(document.get("missing"), Some(&Bson::Int64(5)));
+        assert_eq!(document.get_document("nested").unwrap().get("count"), Some(&Bson::Int64(1)));
+    }
+
+    #[tokio::test]
+    async fn conditional_update_only_applies_once() {
+        // The compare-and-swap pattern the services use for single-use and versioned records
+        let storage = storage_with(vec![doc! { "name": "list", "version": 1i64, "next_index": 0i64 }]).await;
+        let reserve = || {
+            storage.update_document(
+                "items",
+                doc! { "name": "list", "version": 1i64, "next_index": { "$lt": 1i64 } },
+                doc! { "$inc": { "next_index": 1i64, "version": 1i64 } },
+            )
+        };
+
+        assert!(reserve().await.unwrap());
+        assert!(!reserve().await.unwrap());
+
+        let document = storage.find_document("items", doc! { "name": "list" }).await.unwrap().unwrap();
+        assert_eq!(document.get("version"), Some(&Bson::Int64(2)));
+        assert_eq!(document.get("next_index"), Some(&Bson::Int64(1)));
+    }
+
+    #[tokio::test]
+    async fn insert_if_absent_only_inserts_once() {
+        let storage = MemoryStorage::new();
+        let insert = || storage.insert_document_if_absent("items", doc! { "jti": "a" }, doc! { "jti": "a" });
+
+        assert!(insert().await.unwrap());
+        assert!(!insert().await.unwrap());
+        assert_eq!(storage.count_documents("items", doc! {}).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn update_that_changes_nothing_reports_no_modification() {
+        let storage = storage_with(vec![doc! { "name": "a", "used": true }]).await;
+
+        let updated = storage
+            .update_document("items", doc! { "name": "a" }, doc! { "$set": { "used": true } })
+            .await
+            .unwrap();
+        assert!(!updated);
+    }
+
+    #[tokio::test]
+    async fn unsupported_operators_are_rejected() {
+        let storage = storage_with(vec![doc! { "name": "a", "count": 1 }]).await;
+
+        assert!(storage.find_document("items", doc! { "count": { "$mod": [2, 1] } }).await.is_err());
+        assert!(storage
+            .update_document("items", doc! { "name": "a" }, doc! { "$mul": { "count": 2 } })
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,386 @@
+mod memory;
+mod mongo;
+
+use async_trait::async_trait;
+use mongodb::{
+    bson::{self, doc, Document},
+    options::FindOptions,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+
+use crate::config::{Config, StorageBackend};
+use crate::error::AppError;
+use crate::models::{Credential, CredentialRequest, User, Presentation, ConsentRecord, ShortUrlQrCode};
+
+pub use memory::MemoryStorage;
+pub use mongo::MongoStorage;
+
+/// Persistence operations used by the services.
+///
+/// The typed methods have default implementations written against the generic
+/// document operations, so a backend only has to provide the latter. Backends
+/// with native support (MongoDB) override them to keep their own queries.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    // Generic document operations
+    async fn find_document(&self, collection: &str, filter: Document) -> Result<Option<Document>, AppError>;
+
+    async fn find_documents(
+        &self,
+        collection: &str,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<Document>, AppError>;
+
+    async fn insert_document(&self, collection: &str, document: Document) -> Result<String, AppError>;
+
+    /// Insert a document unless one already matches `filter`, and report whether it was inserted.
+    /// Of several concurrent calls with the same filter, only one inserts.
+    async fn insert_document_if_absent(
+        &self,
+        collection: &str,
+        filter: Document,
+        document: Document,
+    ) -> Result<bool, AppError>;
+
+    /// Replace the first document matching `filter`, inserting it when `upsert` is set
+    async fn replace_document(
+        &self,
+        collection: &str,
+        filter: Document,
+        document: Document,
+        upsert: bool,
+    ) -> Result<bool, AppError>;
+
+    async fn update_document(&self, collection: &str, filter: Document, update: Document) -> Result<bool, AppError>;
+
+    async fn delete_document(&self, collection: &str, filter: Document) -> Result<bool, AppError>;
+
+    async fn delete_documents(&self, collection: &str, filter: Document) -> Result<u64, AppError>;
+
+    async fn count_documents(&self, collection: &str, filter: Document) -> Result<u64, AppError>;
+
+    // User operations
+    async fn find_user_by_did(&self, did: &str) -> Result<Option<User>, AppError> {
+        find_typed(self, "users", doc! { "did": did }).await
+    }
+
+    async fn create_user(&self, user: &User) -> Result<(), AppError> {
+        self.insert_document("users", bson::to_document(user)?).await?;
+        Ok(())
+    }
+
+    async fn update_user(&self, user: &User) -> Result<(), AppError> {
+        self.replace_document("users", doc! { "did": &user.did }, bson::to_document(user)?, false).await?;
+        Ok(())
+    }
+
+    // Credential operations
+    async fn find_credentials_by_owner(&self, owner_did: &str) -> Result<Vec<Credential>, AppError> {
+        find_many_typed(self, "credentials", doc! { "owner_did": owner_did }, newest_first()).await
+    }
+
+    async fn find_credential_by_id(&self, id: &str) -> Result<Option<Credential>, AppError> {
+        find_typed(self, "credentials", doc! { "id": id }).await
+    }
+
+    async fn save_credential(&self, credential: &Credential) -> Result<(), AppError> {
+        self.replace_document("credentials", doc! { "id": &credential.id }, bson::to_document(credential)?, true).await?;
+        Ok(())
+    }
+
+    async fn delete_credential(&self, id: &str, owner_did: &str) -> Result<bool, AppError> {
+        self.delete_document("credentials", doc! { "id": id, "owner_did": owner_did }).await
+    }
+
+    // Credential request operations
+    async fn find_credential_requests_by_issuer(&self, issuer_did: &str) -> Result<Vec<CredentialRequest>, AppError> {
+        find_many_typed(self, "credential_requests", doc! { "issuer_did": issuer_did }, newest_first()).await
+    }
+
+    async fn find_credential_requests_by_user(&self, user_did: &str) -> Result<Vec<CredentialRequest>, AppError> {
+        find_many_typed(self, "credential_requests", doc! { "user_did": user_did }, newest_first()).await
+    }
+
+    async fn save_credential_request(&self, request: &CredentialRequest) -> Result<(), AppError> {
+        self.replace_document("credential_requests", doc! { "id": &request.id }, bson::to_document(request)?, true).await?;
+        Ok(())
+    }
+
+    async fn update_credential_request_status(&self, id: &str, status: &str) -> Result<bool, AppError> {
+        self.update_document("credential_requests", doc! { "id": id }, doc! { "$set": { "status": status } }).await
+    }
+
+    // Presentation operations
+    async fn save_presentation(&self, presentation: &Presentation) -> Result<(), AppError> {
+        self.replace_document("presentations", doc! { "id": &presentation.id }, bson::to_document(presentation)?, true).await?;
+        Ok(())
+    }
+
+    async fn find_presentations_by_verifier(&self, verifier_did: &str) -> Result<Vec<Presentation>, AppError> {
+        find_many_typed(self, "presentations", doc! { "verifier_did": verifier_did }, newest_first()).await
+    }
+
+    async fn find_presentations_by_prover(&self, prover_did: &str) -> Result<Vec<Presentation>, AppError> {
+        find_many_typed(self, "presentations", doc! { "prover_did": prover_did }, newest_first()).await
+    }
+
+    // Consent record operations
+    async fn save_consent_record(&self, record: &ConsentRecord) -> Result<(), AppError> {
+        self.replace_document("consent_records", doc! { "id": &record.id }, bson::to_document(record)?, true).await?;
+        Ok(())
+    }
+
+    async fn find_consent_records_by_user(&self, user_did: &str) -> Result<Vec<ConsentRecord>, AppError> {
+        find_many_typed(self, "consent_records", doc! { "user_did": user_did }, newest_first()).await
+    }
+
+    async fn revoke_consent(&self, id: &str, user_did: &str) -> Result<bool, AppError> {
+        let update = doc! { "$set": { "revoked": true, "revoked_at": bson::to_bson(&chrono::Utc::now())? } };
+        self.update_document("consent_records", doc! { "id": id, "user_did": user_did }, update).await
+    }
+
+    // Short URL QR code operations
+    async fn save_short_url_qr_code(&self, qr_code: &ShortUrlQrCode) -> Result<(), AppError> {
+        self.replace_document("short_url_qr_codes", doc! { "id": &qr_code.id }, bson::to_document(qr_code)?, true).await?;
+        Ok(())
+    }
+
+    async fn find_short_url_qr_code_by_short_id(&self, short_id: &str) -> Result<Option<ShortUrlQrCode>, AppError> {
+        find_typed(self, "short_url_qr_codes", doc! { "short_id": short_id }).await
+    }
+
+    async fn find_short_url_qr_codes_by_issuer_verifier(&self, did: &str) -> Result<Vec<ShortUrlQrCode>, AppError> {
+        find_many_typed(self, "short_url_qr_codes", doc! { "issuer_verifier_did": did }, newest_first()).await
+    }
+
+    async fn delete_short_url_qr_code(&self, short_id: &str, did: &str) -> Result<bool, AppError> {
+        self.delete_document("short_url_qr_codes", doc! { "short_id": short_id, "issuer_verifier_did": did }).await
+    }
+}
+
+/// Sort order used by the list queries
+fn newest_first() -> FindOptions {
+    FindOptions::builder().sort(doc! { "created_at": -1 }).build()
+}
+
+async fn find_typed<S, T>(storage: &S, collection: &str, filter: Document) -> Result<Option<T>, AppError>
+where
+    S: Storage + ?Sized,
+    T: DeserializeOwned,
+{
+    storage
+        .find_document(collection, filter)
+        .await?
+        .map(|document| bson::from_document(document).map_err(AppError::from))
+        .transpose()
+}
+
+async fn find_many_typed<S, T>(
+    storage: &S,
+    collection: &str,
+    filter: Document,
+    options: FindOptions,
+) -> Result<Vec<T>, AppError>
+where
+    S: Storage + ?Sized,
+    T: DeserializeOwned,
+{
+    storage
+        .find_documents(collection, filter, Some(options))
+        .await?
+        .into_iter()
+        .map(|document| bson::from_document(document).map_err(AppError::from))
+        .collect()
+}
+
+/// Handle to the configured storage backend
+#[derive(Clone)]
+pub struct Database {
+    storage: Arc<dyn Storage>,
+}
+
+impl Database {
+    /// Connect to the storage backend selected in the configuration
+    pub async fn connect(config: &Config) -> Result<Self, AppError> {
+        match config.storage_backend {
+            StorageBackend::MongoDb => {
+                let uri = config.mongodb_uri.as_deref().ok_or_else(|| {
+                    AppError::ConfigError("MONGODB_URI must be set when STORAGE_BACKEND=mongodb".to_string())
+                })?;
+                Ok(Self::new(MongoStorage::connect(uri).await?))
+            }
+            StorageBackend::Memory => {
+                tracing::warn!("Using in-memory storage. All data will be lost when the process exits.");
+                Ok(Self::new(MemoryStorage::new()))
+            }
+        }
+    }
+
+    /// Wrap an existing storage backend
+    pub fn new<S: Storage + 'static>(storage: S) -> Self {
+        Self { storage: Arc::new(storage) }
+    }
+
+    // User methods
+    pub async fn find_user_by_did(&self, did: &str) -> Result<Option<User>, AppError> {
+        self.storage.find_user_by_did(did).await
+    }
+
+    pub async fn create_user(&self, user: &User) -> Result<(), AppError> {
+        self.storage.create_user(user).await
+    }
+
+    pub async fn update_user(&self, user: &User) -> Result<(), AppError> {
+        self.storage.update_user(user).await
+    }
+
+    // Credential methods
+    pub async fn find_credentials_by_owner(&self, owner_did: &str) -> Result<Vec<Credential>, AppError> {
+        self.storage.find_credentials_by_owner(owner_did).await
+    }
+
+    pub async fn find_credential_by_id(&self, id: &str) -> Result<Option<Credential>, AppError> {
+        self.storage.find_credential_by_id(id).await
+    }
+
+    pub async fn get_credential_by_id(&self, id: &str) -> Result<Option<Credential>, AppError> {
+        self.find_credential_by_id(id).await
+    }
+
+    pub async fn save_credential(&self, credential: &Credential) -> Result<(), AppError> {
+        self.storage.save_credential(credential).await
+    }
+
+    pub async fn delete_credential(&self, id: &str, owner_did: &str) -> Result<bool, AppError> {
+        self.storage.delete_credential(id, owner_did).await
+    }
+
+    // Credential request methods
+    pub async fn find_credential_requests_by_issuer(&self, issuer_did: &str) -> Result<Vec<CredentialRequest>, AppError> {
+        self.storage.find_credential_requests_by_issuer(issuer_did).await
+    }
+
+    pub async fn find_credential_requests_by_user(&self, user_did: &str) -> Result<Vec<CredentialRequest>, AppError> {
+        self.storage.find_credential_requests_by_user(user_did).await
+    }
+
+    pub async fn save_credential_request(&self, request: &CredentialRequest) -> Result<(), AppError> {
+        self.storage.save_credential_request(request).await
+    }
+
+    pub async fn update_credential_request_status(&self, id: &str, status: &str) -> Result<bool, AppError> {
+        self.storage.update_credential_request_status(id, status).await
+    }
+
+    // Presentation methods
+    pub async fn save_presentation(&self, presentation: &Presentation) -> Result<(), AppError> {
+        self.storage.save_presentation(presentation).await
+    }
+
+    pub async fn find_presentations_by_verifier(&self, verifier_did: &str) -> Result<Vec<Presentation>, AppError> {
+        self.storage.find_presentations_by_verifier(verifier_did).await
+    }
+
+    pub async fn find_presentations_by_prover(&self, prover_did: &str) -> Result<Vec<Presentation>, AppError> {
+        self.storage.find_presentations_by_prover(prover_did).await
+    }
+
+    // Consent record methods
+    pub async fn save_consent_record(&self, record: &ConsentRecord) -> Result<(), AppError> {
+        self.storage.save_consent_record(record).await
+    }
+
+    pub async fn find_consent_records_by_user(&self, user_did: &str) -> Result<Vec<ConsentRecord>, AppError> {
+        self.storage.find_consent_records_by_user(user_did).await
+    }
+
+    pub async fn revoke_consent(&self, id: &str, user_did: &str) -> Result<bool, AppError> {
+        self.storage.revoke_consent(id, user_did).await
+    }
+
+    // Short URL QR code methods
+    pub async fn save_short_url_qr_code(&self, qr_code: &ShortUrlQrCode) -> Result<(), AppError> {
+        self.storage.save_short_url_qr_code(qr_code).await
+    }
+
+    pub async fn find_short_url_qr_code_by_short_id(&self, short_id: &str) -> Result<Option<ShortUrlQrCode>, AppError> {
+        self.storage.find_short_url_qr_code_by_short_id(short_id).await
+    }
+
+    pub async fn find_short_url_qr_codes_by_issuer_verifier(&self, did: &str) -> Result<Vec<ShortUrlQrCode>, AppError> {
+        self.storage.find_short_url_qr_codes_by_issuer_verifier(did).await
+    }
+
+    pub async fn delete_short_url_qr_code(&self, short_id: &str, did: &str) -> Result<bool, AppError> {
+        self.storage.delete_short_url_qr_code(short_id, did).await
+    }
+
+    // Generic methods for any collection
+    pub async fn find_one<T>(&self, collection_name: &str, filter: Document) -> Result<Option<T>, AppError>
+    where
+        T: DeserializeOwned + Unpin + Send + Sync,
+    {
+        find_typed(self.storage.as_ref(), collection_name, filter).await
+    }
+
+    pub async fn find_many<T>(&self, collection_name: &str, filter: Document) -> Result<Vec<T>, AppError>
+    where
+        T: DeserializeOwned + Unpin + Send + Sync,
+    {
+        self.find_many_with_options(collection_name, filter, FindOptions::default()).await
+    }
+
+    pub async fn find_many_with_options<T>(
+        &self,
+        collection_name: &str,
+        filter: Document,
+        options: FindOptions,
+    ) -> Result<Vec<T>, AppError>
+    where
+        T: DeserializeOwned + Unpin + Send + Sync,
+    {
+        find_many_typed(self.storage.as_ref(), collection_name, filter, options).await
+    }
+
+    pub async fn insert_one<T>(&self, collection_name: &str, document: &T) -> Result<String, AppError>
+    where
+        T: Serialize,
+    {
+        self.storage
+            .insert_document(collection_name, bson::to_document(document)?)
+            .await
+    }
+
+    /// Insert a document unless one already matches `filter`; returns whether it was inserted
+    pub async fn insert_one_if_absent<T>(&self, collection_name: &str, filter: Document, document: &T) -> Result<bool, AppError>
+    where
+        T: Serialize,
+    {
+        self.storage
+            .insert_document_if_absent(collection_name, filter, bson::to_document(document)?)
+            .await
+    }
+
+    pub async fn update_one(
+        &self,
+        collection_name: &str,
+        filter: Document,
+        update: Document,
+    ) -> Result<bool, AppError> {
+        self.storage.update_document(collection_name, filter, update).await
+    }
+
+    pub async fn delete_one(&self, collection_name: &str, filter: Document) -> Result<bool, AppError> {
+        self.storage.delete_document(collection_name, filter).await
+    }
+
+    pub async fn delete_many(&self, collection_name: &str, filter: Document) -> Result<u64, AppError> {
+        self.storage.delete_documents(collection_name, filter).await
+    }
+
+    pub async fn count_documents(&self, collection_name: &str, filter: Document) -> Result<u64, AppError> {
+        self.storage.count_documents(collection_name, filter).await
+    }
+}
@@ -0,0 +1,340 @@
+use async_trait::async_trait;
+use mongodb::{
+    bson::{doc, Document},
+    error::{ErrorKind, WriteFailure},
+    options::{ClientOptions, FindOptions, IndexOptions, ReplaceOptions},
+    Client, Collection, Database as MongoDatabase, IndexModel,
+};
+use futures::TryStreamExt;
+use uuid::Uuid;
+
+use super::{newest_first, Storage};
+use crate::error::AppError;
+use crate::models::{Credential, CredentialRequest, User, Presentation, ConsentRecord, ShortUrlQrCode};
+
+/// Collections and fields whose values must be unique
+const UNIQUE_KEYS: [(&str, &str); 2] = [("revoked_tokens", "jti"), ("issuer_keys", "key_id")];
+
+/// MongoDB storage backend
+#[derive(Debug, Clone)]
+pub struct MongoStorage {
+    client: Client,
+    db: MongoDatabase,
+}
+
+impl MongoStorage {
+    pub async fn connect(uri: &str) -> Result<Self, AppError> {
+        let client_options = ClientOptions::parse(uri).await?;
+        let client = Client::with_options(client_options)?;
+        let db = client.database("ssi_wallet");
+
+        // Ping the database to check the connection
+        client
+            .database("admin")
+            .run_command(doc! {"ping": 1})
+            .await?;
+
+        tracing::info!("Connected to MongoDB");
+        let storage = Self { client, db };
+        storage.create_unique_indexes().await;
+        Ok(storage)
+    }
+
+    /// Unique keys that `insert_document_if_absent` relies on to stay atomic across concurrent upserts
+    async fn create_unique_indexes(&self) {
+        for (collection, key) in UNIQUE_KEYS {
+            let index = IndexModel::builder()
+                .keys(doc! { key: 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build();
+            if let Err(e) = self.db.collection::<Document>(collection).create_index(index).await {
+                tracing::warn!("Failed to create unique index on {}.{}: {}", collection, key, e);
+            }
+        }
+    }
+
+    fn users(&self) -> Collection<User> {
+        self.db.collection("users")
+    }
+
+    fn credentials(&self) -> Collection<Credential> {
+        self.db.collection("credentials")
+    }
+
+    fn credential_requests(&self) -> Collection<CredentialRequest> {
+        self.db.collection("credential_requests")
+    }
+
+    fn presentations(&self) -> Collection<Presentation> {
+        self.db.collection("presentations")
+    }
+
+    fn consent_records(&self) -> Collection<ConsentRecord> {
+        self.db.collection("consent_records")
+    }
+
+    fn short_url_qr_codes(&self) -> Collection<ShortUrlQrCode> {
+        self.db.collection("short_url_qr_codes")
+    }
+
+    fn upsert() -> ReplaceOptions {
+        ReplaceOptions::builder().upsert(true).build()
+    }
+}
+
+#[async_trait]
+impl Storage for MongoStorage {
+    // Generic methods for any collection
+    async fn find_document(&self, collection: &str, filter: Document) -> Result<Option<Document>, AppError> {
+        self.db
+            .collection::<Document>(collection)
+            .find_one(filter)
+            .await
+            .map_err(|e| e.into())
+    }
+
+    async fn find_documents(
+        &self,
+        collection: &str,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<Document>, AppError> {
+        let cursor = self.db
+            .collection::<Document>(collection)
+            .find(filter)
+            .with_options(options)
+            .await?;
+
+        cursor.try_collect().await.map_err(|e| e.into())
+    }
+
+    async fn insert_document(&self, collection: &str, document: Document) -> Result<String, AppError> {
+        let result = self.db
+            .collection::<Document>(collection)
+            .insert_one(document)
+            .await?;
+
+        Ok(result
+            .inserted_id
+            .as_object_id()
+            .map(|id| id.to_hex())
+            .unwrap_or_else(|| Uuid::new_v4().to_string()))
+    }
+
+    async fn insert_document_if_absent(
+        &self,
+        collection: &str,
+        filter: Document,
+        document: Document,
+    ) -> Result<bool, AppError> {
+        let result = self.db
+            .collection::<Document>(collection)
+            .update_one(filter, doc! { "$setOnInsert": document })
+            .upsert(true)
+            .await;
+
+        match result {
+            Ok(result) => Ok(result.upserted_id.is_some()),
+            // A concurrent upsert won the race for the unique key
+            Err(e) if matches!(&*e.kind, ErrorKind::Write(WriteFailure::WriteError(error)) if error.code == 11000) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn replace_document(
+        &self,
+        collection: &str,
+        filter: Document,
+        document: Document,
+        upsert: bool,
+    ) -> Result<bool, AppError> {
+        let options = ReplaceOptions::builder().upsert(upsert).build();
+        let result = self.db
+            .collection::<Document>(collection)
+            .replace_one(filter, document)
+            .with_options(options)
+            .await?;
+
+        Ok(result.matched_count > 0 || result.upserted_id.is_some())
+    }
+
+    async fn update_document(&self, collection: &str, filter: Document, update: Document) -> Result<bool, AppError> {
+        let result = self.db
+            .collection::<Document>(collection)
+            .update_one(filter, update)
+            .await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn delete_document(&self, collection: &str, filter: Document) -> Result<bool, AppError> {
+        let result = self.db
+            .collection::<Document>(collection)
+            .delete_one(filter)
+            .await?;
+
+        Ok(result.deleted_count > 0)
+    }
+
+    async fn delete_documents(&self, collection: &str, filter: Document) -> Result<u64, AppError> {
+        let result = self.db
+            .collection::<Document>(collection)
+            .delete_many(filter)
+            .await?;
+
+        Ok(result.deleted_count)
+    }
+
+    async fn count_documents(&self, collection: &str, filter: Document) -> Result<u64, AppError> {
+        self.db
+            .collection::<Document>(collection)
+            .count_documents(filter)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to count documents in {}: {}", collection, e)))
+    }
+
+    // User collection methods
+    async fn find_user_by_did(&self, did: &str) -> Result<Option<User>, AppError> {
+        let filter = doc! { "did": did };
+        self.users().find_one(filter).await.map_err(|e| e.into())
+    }
+
+    async fn create_user(&self, user: &User) -> Result<(), AppError> {
+        self.users().insert_one(user).await?;
+        Ok(())
+    }
+
+    async fn update_user(&self, user: &User) -> Result<(), AppError> {
+        let filter = doc! { "did": &user.did };
+        self.users().replace_one(filter, user).await?;
+        Ok(())
+    }
+
+    // Credential collection methods
+    async fn find_credentials_by_owner(&self, owner_did: &str) -> Result<Vec<Credential>, AppError> {
+        let filter = doc! { "owner_did": owner_did };
+
+        let cursor = self.credentials().find(filter).with_options(newest_first()).await?;
+        let credentials = cursor.try_collect().await?;
+
+        Ok(credentials)
+    }
+
+    async fn find_credential_by_id(&self, id: &str) -> Result<Option<Credential>, AppError> {
+        let filter = doc! { "id": id };
+        self.credentials().find_one(filter).await.map_err(|e| e.into())
+    }
+
+    async fn save_credential(&self, credential: &Credential) -> Result<(), AppError> {
+        let filter = doc! { "id": &credential.id };
+        self.credentials().replace_one(filter, credential).with_options(Self::upsert()).await?;
+        Ok(())
+    }
+
+    async fn delete_credential(&self, id: &str, owner_did: &str) -> Result<bool, AppError> {
+        let filter = doc! { "id": id, "owner_did": owner_did };
+        let result = self.credentials().delete_one(filter).await?;
+        Ok(result.deleted_count > 0)
+    }
+
+    // Credential request collection methods
+    async fn find_credential_requests_by_issuer(&self, issuer_did: &str) -> Result<Vec<CredentialRequest>, AppError> {
+        let filter = doc! { "issuer_did": issuer_did };
+
+        let cursor = self.credential_requests().find(filter).with_options(newest_first()).await?;
+        let requests = cursor.try_collect().await?;
+
+        Ok(requests)
+    }
+
+    async fn find_credential_requests_by_user(&self, user_did: &str) -> Result<Vec<CredentialRequest>, AppError> {
+        let filter = doc! { "user_did": user_did };
+
+        let cursor = self.credential_requests().find(filter).with_options(newest_first()).await?;
+        let requests = cursor.try_collect().await?;
+
+        Ok(requests)
+    }
+
+    async fn save_credential_request(&self, request: &CredentialRequest) -> Result<(), AppError> {
+        let filter = doc! { "id": &request.id };
+        self.credential_requests().replace_one(filter, request).with_options(Self::upsert()).await?;
+        Ok(())
+    }
+
+    async fn update_credential_request_status(&self, id: &str, status: &str) -> Result<bool, AppError> {
+        let filter = doc! { "id": id };
+        let update = doc! { "$set": { "status": status } };
+
+        let result = self.credential_requests().update_one(filter, update).await?;
+        Ok(result.modified_count > 0)
+    }
+
+    // Presentation collection methods
+    async fn save_presentation(&self, presentation: &Presentation) -> Result<(), AppError> {
+        let filter = doc! { "id": &presentation.id };
+        self.presentations().replace_one(filter, presentation).with_options(Self::upsert()).await?;
+        Ok(())
+    }
+
+    async fn find_presentations_by_verifier(&self, verifier_did: &str) -> Result<Vec<Presentation>, AppError> {
+        let filter = doc! { "verifier_did": verifier_did };
+
+        let cursor = self.presentations().find(filter).with_options(newest_first()).await?;
+        let presentations = cursor.try_collect().await?;
+
+        Ok(presentations)
+    }
+
+    async fn find_presentations_by_prover(&self, prover_did: &str) -> Result<Vec<Presentation>, AppError> {
+        let filter = doc! { "prover_did": prover_did };
+
+        let cursor = self.presentations().find(filter).with_options(newest_first()).await?;
+        let presentations = cursor.try_collect().await?;
+
+        Ok(presentations)
+    }
+
+    // Consent records collection methods
+    async fn save_consent_record(&self, record: &ConsentRecord) -> Result<(), AppError> {
+        let filter = doc! { "id": &record.id };
+        self.consent_records().replace_one(filter, record).with_options(Self::upsert()).await?;
+        Ok(())
+    }
+
+    async fn find_consent_records_by_user(&self, user_did: &str) -> Result<Vec<ConsentRecord>, AppError> {
+        let filter = doc! { "user_did": user_did };
+
+        let cursor = self.consent_records().find(filter).with_options(newest_first()).await?;
+        let records = cursor.try_collect().await?;
+
+        Ok(records)
+    }
+
+    // Short URL QR codes collection methods
+    async fn save_short_url_qr_code(&self, qr_code: &ShortUrlQrCode) -> Result<(), AppError> {
+        let filter = doc! { "id": &qr_code.id };
+        self.short_url_qr_codes().replace_one(filter, qr_code).with_options(Self::upsert()).await?;
+        Ok(())
+    }
+
+    async fn find_short_url_qr_code_by_short_id(&self, short_id: &str) -> Result<Option<ShortUrlQrCode>, AppError> {
+        let filter = doc! { "short_id": short_id };
+        self.short_url_qr_codes().find_one(filter).await.map_err(|e| e.into())
+    }
+
+    async fn find_short_url_qr_codes_by_issuer_verifier(&self, did: &str) -> Result<Vec<ShortUrlQrCode>, AppError> {
+        let filter = doc! { "issuer_verifier_did": did };
+
+        let cursor = self.short_url_qr_codes().find(filter).with_options(newest_first()).await?;
+        let qr_codes = cursor.try_collect().await?;
+
+        Ok(qr_codes)
+    }
+
+    async fn delete_short_url_qr_code(&self, short_id: &str, did: &str) -> Result<bool, AppError> {
+        let filter = doc! { "short_id": short_id, "issuer_verifier_did": did };
+        let result = self.short_url_qr_codes().delete_one(filter).await?;
+        Ok(result.deleted_count > 0)
+    }
+}
@@ -96,3 +96,8 @@ impl From<mongodb::bson::ser::Error> for AppError {
     }
 }
 
+impl From<mongodb::bson::de::Error> for AppError {
+    fn from(err: mongodb::bson::de::Error) -> Self {
+        AppError::DatabaseError(err.to_string())
+    }
+}
@@ -0,0 +1,148 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+use super::KeyStore;
+use crate::error::AppError;
+use crate::utils::{bbs, crypto};
+
+const DILITHIUM2: &str = "Dilithium2";
+
+/// Key pair as kept inside the encrypted file
+#[derive(Serialize, Deserialize)]
+struct StoredKey {
+    algorithm: String,
+    public_key_base58: String,
+    private_key_base58: String,
+}
+
+/// Keystore file encrypted with `crypto::encrypt_with_password`
+pub struct FileKeyStore {
+    path: PathBuf,
+    password: String,
+    keys: Mutex<HashMap<String, StoredKey>>,
+}
+
+impl FileKeyStore {
+    /// Open the keystore file, or start an empty one if it does not exist yet
+    pub async fn open(path: impl AsRef<Path>, password: &str) -> Result<Self, AppError> {
+        let path = path.as_ref().to_path_buf();
+
+        let keys = match tokio::fs::read(&path).await {
+            Ok(encrypted) => {
+                let plaintext = crypto::decrypt_with_password(&encrypted, password).map_err(|_| {
+                    AppError::ConfigError(format!(
+                        "Failed to decrypt keystore {}; check KEY_ENCRYPTION_SECRET",
+                        path.display()
+                    ))
+                })?;
+                serde_json::from_slice(&plaintext)?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path,
+            password: password.to_string(),
+            keys: Mutex::new(keys),
+        })
+    }
+
+    /// Encrypt and write all keys, replacing the file atomically
+    async fn persist(&self, keys: &HashMap<String, StoredKey>) -> Result<(), AppError> {
+        let plaintext = serde_json::to_vec(keys)?;
+        let encrypted = crypto::encrypt_with_password(&plaintext, &self.password)?;
+
+        let tmp_path = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, encrypted).await?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            tokio::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600)).await?;
+        }
+
+        tokio::fs::rename(&tmp_path, &self.path).await?;
+        Ok(())
+    }
+
+    /// Store a new key pair under `kid` and write the file
+    async fn insert(&self, kid: &str, algorithm: &str, public_key: &[u8], private_key: &[u8]) -> Result<(), AppError> {
+        let mut keys = self.keys.lock().await;
+        if keys.contains_key(kid) {
+            return Err(AppError::ValidationError(format!("Key {} already exists", kid)));
+        }
+
+        keys.insert(kid.to_string(), StoredKey {
+            algorithm: algorithm.to_string(),
+            public_key_base58: bs58::encode(public_key).into_string(),
+            private_key_base58: bs58::encode(private_key).into_string(),
+        });
+
+        // Keep memory and disk in step if the write fails
+        if let Err(e) = self.persist(&keys).await {
+            keys.remove(kid);
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Private key stored under `kid`, which must be a key of `algorithm`
+    async fn private_key(&self, kid: &str, algorithm: &str) -> Result<Vec<u8>, AppError> {
+        let keys = self.keys.lock().await;
+        let key = keys.get(kid)
+            .ok_or_else(|| AppError::NotFoundError(format!("Key {} not found in keystore", kid)))?;
+        if key.algorithm != algorithm {
+            return Err(AppError::ValidationError(format!("Key {} is a {} key, not {}", kid, key.algorithm, algorithm)));
+        }
+
+        bs58::decode(&key.private_key_base58)
+            .into_vec()
+            .map_err(|e| AppError::InternalError(format!("Corrupt private key {}: {}", kid, e)))
+    }
+}
+
+#[async_trait]
+impl KeyStore for FileKeyStore {
+    async fn generate_key(&self, kid: &str) -> Result<Vec<u8>, AppError> {
+        let (public_key, private_key) = crypto::generate_dilithium_keypair()?;
+        self.insert(kid, DILITHIUM2, &public_key, &private_key).await?;
+        Ok(public_key)
+    }
+
+    async fn import_key(&self, kid: &str, public_key: &[u8], private_key: &[u8]) -> Result<(), AppError> {
+        self.insert(kid, DILITHIUM2, public_key, private_key).await
+    }
+
+    async fn sign(&self, kid: &str, data: &[u8]) -> Result<Vec<u8>, AppError> {
+        let private_key = self.private_key(kid, DILITHIUM2).await?;
+        Ok(crypto::dilithium_sign(data, &private_key)?)
+    }
+
+    async fn generate_bbs_key(&self, kid: &str) -> Result<Vec<u8>, AppError> {
+        let private_key = bbs::generate_secret_key()?;
+        let public_key = bbs::sk_to_pk(&private_key)?;
+        self.insert(kid, bbs::ALGORITHM, &public_key, &private_key).await?;
+        Ok(public_key)
+    }
+
+    async fn bbs_sign(&self, kid: &str, header: &[u8], messages: &[Vec<u8>]) -> Result<Vec<u8>, AppError> {
+        let private_key = self.private_key(kid, bbs::ALGORITHM).await?;
+        let public_key = bbs::sk_to_pk(&private_key)?;
+        bbs::sign(&private_key, &public_key, header, messages)
+    }
+
+    async fn public_key(&self, kid: &str) -> Result<Vec<u8>, AppError> {
+        let keys = self.keys.lock().await;
+        let key = keys.get(kid)
+            .ok_or_else(|| AppError::NotFoundError(format!("Key {} not found in keystore", kid)))?;
+
+        bs58::decode(&key.public_key_base58)
+            .into_vec()
+            .map_err(|e| AppError::InternalError(format!("Corrupt public key {}: {}", kid, e)))
+    }
+}
@@ -0,0 +1,54 @@
+mod file;
+mod socket;
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::config::{KeyStoreBackend, KeyStoreConfig};
+use crate::error::AppError;
+
+pub use file::FileKeyStore;
+pub use socket::{serve, SocketKeyStore};
+
+/// Holder of issuer signing keys.
+///
+/// Keys are addressed by an opaque key ID chosen by the caller. Private keys
+/// never leave the store: callers only get signatures and public keys back.
+#[async_trait]
+pub trait KeyStore: Send + Sync {
+    /// Generate a Dilithium key pair under `kid` and return its public key
+    async fn generate_key(&self, kid: &str) -> Result<Vec<u8>, AppError>;
+
+    /// Store an existing Dilithium key pair under `kid`
+    async fn import_key(&self, kid: &str, public_key: &[u8], private_key: &[u8]) -> Result<(), AppError>;
+
+    /// Sign bytes with the Dilithium private key stored under `kid`
+    async fn sign(&self, kid: &str, data: &[u8]) -> Result<Vec<u8>, AppError>;
+
+    /// Generate a BBS key pair under `kid` and return its public key
+    async fn generate_bbs_key(&self, kid: &str) -> Result<Vec<u8>, AppError>;
+
+    /// Sign a header and a list of messages with the BBS private key stored under `kid`
+    async fn bbs_sign(&self, kid: &str, header: &[u8], messages: &[Vec<u8>]) -> Result<Vec<u8>, AppError>;
+
+    /// Get the public key stored under `kid`
+    async fn public_key(&self, kid: &str) -> Result<Vec<u8>, AppError>;
+}
+
+/// Open the key store selected in the configuration
+pub async fn open(config: &KeyStoreConfig) -> Result<Arc<dyn KeyStore>, AppError> {
+    match config.backend {
+        KeyStoreBackend::File => {
+            let secret = config.encryption_secret.as_deref().ok_or_else(|| {
+                AppError::ConfigError("KEY_ENCRYPTION_SECRET must be set".to_string())
+            })?;
+            let store = FileKeyStore::open(&config.path, secret).await?;
+            tracing::info!("Using keystore file {}", config.path);
+            Ok(Arc::new(store))
+        }
+        KeyStoreBackend::Socket => {
+            tracing::info!("Using signing service at {}", config.socket_path);
+            Ok(Arc::new(SocketKeyStore::new(&config.socket_path)))
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::KeyStore;
+use crate::error::AppError;
+
+/// Request sent to the signing service, one JSON line per connection
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op")]
+enum SignerRequest {
+    #[serde(rename = "generate_key")]
+    GenerateKey { kid: String },
+    #[serde(rename = "import_key")]
+    ImportKey { kid: String, public_key: String, private_key: String },
+    #[serde(rename = "sign")]
+    Sign { kid: String, data: String },
+    #[serde(rename = "public_key")]
+    PublicKey { kid: String },
+    #[serde(rename = "generate_bbs_key")]
+    GenerateBbsKey { kid: String },
+    #[serde(rename = "bbs_sign")]
+    BbsSign { kid: String, header: String, messages: Vec<String> },
+}
+
+/// Response from the signing service; binary values are base64 encoded
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SignerResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Client for a signing-service process listening on a local Unix socket
+pub struct SocketKeyStore {
+    socket_path: String,
+}
+
+impl SocketKeyStore {
+    pub fn new(socket_path: &str) -> Self {
+        Self {
+            socket_path: socket_path.to_string(),
+        }
+    }
+
+    #[cfg(unix)]
+    async fn request(&self, request: &SignerRequest) -> Result<SignerResponse, AppError> {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+        use tokio::net::UnixStream;
+
+        let stream = UnixStream::connect(&self.socket_path).await.map_err(|e| {
+            AppError::InternalError(format!("Failed to reach signing service at {}: {}", self.socket_path, e))
+        })?;
+        let (reader, mut writer) = stream.into_split();
+
+        let mut line = serde_json::to_string(request)?;
+        line.push('\n');
+        writer.write_all(line.as_bytes()).await?;
+        writer.shutdown().await?;
+
+        let mut response = String::new();
+        BufReader::new(reader).read_line(&mut response).await?;
+
+        let response: SignerResponse = serde_json::from_str(&response)
+            .map_err(|e| AppError::InternalError(format!("Invalid response from signing service: {}", e)))?;
+
+        match response.error {
+            Some(error) => Err(AppError::InternalError(format!("Signing service error: {}", error))),
+            None => Ok(response),
+        }
+    }
+
+    #[cfg(not(unix))]
+    async fn request(&self, _request: &SignerRequest) -> Result<SignerResponse, AppError> {
+        Err(AppError::ConfigError("The socket keystore requires Unix domain sockets".to_string()))
+    }
+}
+
+#[async_trait]
+impl KeyStore for SocketKeyStore {
+    async fn generate_key(&self, kid: &str) -> Result<Vec<u8>, AppError> {
+        let response = self.request(&SignerRequest::GenerateKey { kid: kid.to_string() }).await?;
+        decode_field(response.public_key, "public_key")
+    }
+
+    async fn import_key(&self, kid: &str, public_key: &[u8], private_key: &[u8]) -> Result<(), AppError> {
+        self.request(&SignerRequest::ImportKey {
+            kid: kid.to_string(),
+            public_key: general_purpose::STANDARD.encode(public_key),
+            private_key: general_purpose::STANDARD.encode(private_key),
+        }).await?;
+        Ok(())
+    }
+
+    async fn sign(&self, kid: &str, data: &[u8]) -> Result<Vec<u8>, AppError> {
+        let response = self.request(&SignerRequest::Sign {
+            kid: kid.to_string(),
+            data: general_purpose::STANDARD.encode(data),
+        }).await?;
+        decode_field(response.signature, "signature")
+    }
+
+    async fn public_key(&self, kid: &str) -> Result<Vec<u8>, AppError> {
+        let response = self.request(&SignerRequest::PublicKey { kid: kid.to_string() }).await?;
+        decode_field(response.public_key, "public_key")
+    }
+
+    async fn generate_bbs_key(&self, kid: &str) -> Result<Vec<u8>, AppError> {
+        let response = self.request(&SignerRequest::GenerateBbsKey { kid: kid.to_string() }).await?;
+        decode_field(response.public_key, "public_key")
+    }
+
+    async fn bbs_sign(&self, kid: &str, header: &[u8], messages: &[Vec<u8>]) -> Result<Vec<u8>, AppError> {
+        let response = self.request(&SignerRequest::BbsSign {
+            kid: kid.to_string(),
+            header: general_purpose::STANDARD.encode(header),
+            messages: messages.iter().map(|message| general_purpose::STANDARD.encode(message)).collect(),
+        }).await?;
+        decode_field(response.signature, "signature")
+    }
+}
+
+fn decode_field(value: Option<String>, name: &str) -> Result<Vec<u8>, AppError> {
+    let value = value
+        .ok_or_else(|| AppError::InternalError(format!("Signing service response is missing {}", name)))?;
+    general_purpose::STANDARD.decode(value)
+        .map_err(|e| AppError::InternalError(format!("Signing service returned invalid {}: {}", name, e)))
+}
+
+/// Run the signing service: answer key store requests on a Unix socket until the process exits
+#[cfg(unix)]
+pub async fn serve(store: Arc<dyn KeyStore>, socket_path: &str) -> Result<(), AppError> {
+    use std::os::unix::fs::PermissionsExt;
+    use tokio::net::UnixListener;
+
+    // Replace a socket left behind by a previous run
+    if tokio::fs::metadata(socket_path).await.is_ok() {
+        tokio::fs::remove_file(socket_path).await?;
+    }
+
+    let listener = UnixListener::bind(socket_path)?;
+    // Only the engine's user may talk to the signer
+    tokio::fs::set_permissions(socket_path, std::fs::Permissions::from_mode(0o600)).await?;
+    tracing::info!("Signing service listening on {}", socket_path);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let store = store.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(store, stream).await {
+                tracing::warn!("Signing service connection failed: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn serve(_store: Arc<dyn KeyStore>, _socket_path: &str) -> Result<(), AppError> {
+    Err(AppError::ConfigError("The signing service requires Unix domain sockets".to_string()))
+}
+
+#[cfg(unix)]
+async fn handle_connection(store: Arc<dyn KeyStore>, stream: tokio::net::UnixStream) -> Result<(), AppError> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+
+    let response = match serde_json::from_str::<SignerRequest>(&line) {
+        Ok(request) => answer(store.as_ref(), request).await.unwrap_or_else(|e| SignerResponse {
+            error: Some(e.to_string()),
+            ..Default::default()
+        }),
+        Err(e) => SignerResponse {
+            error: Some(format!("Invalid request: {}", e)),
+            ..Default::default()
+        },
+    };
+
+    let mut output = serde_json::to_string(&response)?;
+    output.push('\n');
+    writer.write_all(output.as_bytes()).await?;
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn answer(store: &dyn KeyStore, request: SignerRequest) -> Result<SignerResponse, AppError> {
+    let response = match request {
+        SignerRequest::GenerateKey { kid } => SignerResponse {
+            public_key: Some(general_purpose::STANDARD.encode(store.generate_key(&kid).await?)),
+            ..Default::default()
+        },
+        SignerRequest::ImportKey { kid, public_key, private_key } => {
+            let decode = |value: &str| general_purpose::STANDARD.decode(value)
+                .map_err(|e| AppError::ValidationError(format!("Invalid key encoding: {}", e)));
+            store.import_key(&kid, &decode(&public_key)?, &decode(&private_key)?).await?;
+            SignerResponse::default()
+        }
+        SignerRequest::Sign { kid, data } => {
+            let data = general_purpose::STANDARD.decode(data)
+                .map_err(|e| AppError::ValidationError(format!("Invalid data encoding: {}", e)))?;
+            SignerResponse {
+                signature: Some(general_purpose::STANDARD.encode(store.sign(&kid, &data).await?)),
+                ..Default::default()
+            }
+        }
+        SignerRequest::PublicKey { kid } => SignerResponse {
+            public_key: Some(general_purpose::STANDARD.encode(store.public_key(&kid).await?)),
+            ..Default::default()
+        },
+        SignerRequest::GenerateBbsKey { kid } => SignerResponse {
+            public_key: Some(general_purpose::STANDARD.encode(store.generate_bbs_key(&kid).await?)),
+            ..Default::default()
+        },
+        SignerRequest::BbsSign { kid, header, messages } => {
+            let decode = |value: &str| general_purpose::STANDARD.decode(value)
+                .map_err(|e| AppError::ValidationError(format!("Invalid data encoding: {}", e)));
+            let header = decode(&header)?;
+            let messages = messages.iter().map(|message| decode(message)).collect::<Result<Vec<_>, _>>()?;
+            SignerResponse {
+                signature: Some(general_purpose::STANDARD.encode(store.bbs_sign(&kid, &header, &messages).await?)),
+                ..Default::default()
+            }
+        }
+    };
+
+    Ok(response)
+}
@@ -3,7 +3,9 @@ mod config;
 mod db;
 mod blockchain;
 mod ipfs;
+mod keystore;
 mod models;
+mod resolver;
 mod services;
 mod utils;
 mod error;
@@ -30,11 +32,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    // `fortro-engine signing-service` runs the key store behind a local socket instead of the API
+    if std::env::args().nth(1).as_deref() == Some("signing-service") {
+        let keystore_config = config::KeyStoreConfig::from_env()?;
+        let secret = keystore_config.encryption_secret.as_deref()
+            .ok_or_else(|| error::AppError::ConfigError("KEY_ENCRYPTION_SECRET must be set".to_string()))?;
+        let store = keystore::FileKeyStore::open(&keystore_config.path, secret).await?;
+        keystore::serve(std::sync::Arc::new(store), &keystore_config.socket_path).await?;
+        return Ok(());
+    }
+
     // Initialize configuration
     let config = config::Config::from_env()?;
 
+    // Open the issuer key store
+    let keystore = keystore::open(&config.keystore).await?;
+
     // Initialize database connection
-    let db = db::Database::connect(&config.mongodb_uri).await?;
+    let db = db::Database::connect(&config).await?;
 
     // Initialize IPFS client
     let ipfs_client = ipfs::IpfsClient::new(&config.ipfs_api_url)?;
@@ -59,12 +74,29 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         tracing::warn!("REGISTRY_ADDRESS not set. On-chain features that require the SSIRegistry will not work until configured.");
     }
 
+    // Initialize did:web client for external DID documents
+    let did_web = resolver::DidWebClient::new(&config.did_web)?;
+
+    // Initialize the cache of status lists checked by verifiers
+    let status_lists = services::StatusListCache::new(&config.status_list)?;
+
+    // Configure the DID Registry used to resolve did:ethr DIDs
+    if let Some(addr) = &config.did_registry_address {
+        eth_client = eth_client.with_did_registry_address(addr)?;
+    } else {
+        tracing::warn!("DID_REGISTRY_ADDRESS not set. did:ethr DIDs cannot be resolved until configured.");
+    }
+
     // Build application state
-    let state = services::AppState::new(db, ipfs_client, eth_client);
+    let state = services::AppState::new(config.clone(), db, ipfs_client, eth_client, keystore, did_web, status_lists);
+
+    // Move issuer keys kept in the database by earlier versions into the key store
+    state.key_service().migrate_legacy_keys(config.keystore.encryption_secret.as_deref()).await?;
 
     // Build our application with routes
     let app = Router::new()
-        .nest("/api", api::routes())
+        .nest("/api", api::routes(state.clone()))
+        .merge(api::dids::web_routes())
         .route("/api/test", axum::routing::get(|| async { "OK" }))
         // Add middleware
         .layer(TraceLayer::new_for_http())
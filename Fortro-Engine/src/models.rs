@@ -12,6 +12,11 @@ pub struct User {
     pub name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub email: Option<String>,
+    #[serde(default = "default_roles")]
+    pub roles: Vec<UserRole>,
+    /// Base58 Kyber768 public key the holder published for key agreement
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kyber_public_key: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -24,10 +29,30 @@ impl User {
             public_key,
             name: None,
             email: None,
+            roles: default_roles(),
+            kyber_public_key: None,
             created_at: now,
             updated_at: now,
         }
     }
+
+    pub fn has_role(&self, role: &UserRole) -> bool {
+        self.roles.contains(role)
+    }
+}
+
+fn default_roles() -> Vec<UserRole> {
+    vec![UserRole::Holder]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum UserRole {
+    #[serde(rename = "holder")]
+    Holder,
+    #[serde(rename = "issuer")]
+    Issuer,
+    #[serde(rename = "verifier")]
+    Verifier,
 }
 
 // Credential model
@@ -46,6 +71,12 @@ pub struct Credential {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub expires_at: Option<DateTime<Utc>>,
+    /// Blinding factors opening the issuer-signed commitments to numeric attributes, by attribute name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub commitment_blindings: HashMap<String, String>,
+    /// Bits of the issuer's status lists that hold the credential's status
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub status_entries: Vec<StatusListEntry>,
 }
 
 impl Credential {
@@ -72,20 +103,121 @@ impl Credential {
             created_at: now,
             updated_at: now,
             expires_at: None,
+            commitment_blindings: HashMap::new(),
+            status_entries: Vec::new(),
         }
     }
 }
 
+/// Purpose of a status list: what a set bit means
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum StatusPurpose {
+    #[serde(rename = "revocation")]
+    Revocation,
+    #[serde(rename = "suspension")]
+    Suspension,
+}
+
+impl StatusPurpose {
+    /// `statusPurpose` value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatusPurpose::Revocation => "revocation",
+            StatusPurpose::Suspension => "suspension",
+        }
+    }
+}
+
+/// Position of a credential's status in one of its issuer's status lists
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusListEntry {
+    pub list_id: String,
+    pub purpose: StatusPurpose,
+    pub index: i64,
+}
+
+/// Bitstring Status List kept by an issuer for one purpose
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusList {
+    pub id: String,
+    pub issuer_did: String,
+    pub purpose: StatusPurpose,
+    /// URL the signed list is served at, the `statusListCredential` of its entries
+    pub url: String,
+    /// GZIP-compressed bitstring, multibase base64url
+    pub encoded_list: String,
+    /// Next index handed to a credential
+    pub next_index: i64,
+    /// Incremented on every change of the bits
+    pub version: i64,
+    /// Latest signed status list credential
+    pub credential: Option<String>,
+    /// IPFS CID of the latest published list
+    pub ipfs_cid: Option<String>,
+    /// Transaction anchoring the hash of the latest list
+    pub anchor_tx: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum CredentialStatus {
     #[serde(rename = "active")]
     Active,
     #[serde(rename = "revoked")]
     Revoked,
+    #[serde(rename = "suspended")]
+    Suspended,
     #[serde(rename = "expired")]
     Expired,
 }
 
+/// Reason code of a revocation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum RevocationReason {
+    #[default]
+    #[serde(rename = "unspecified")]
+    Unspecified,
+    /// The holder's or issuer's key was compromised
+    #[serde(rename = "key_compromise")]
+    KeyCompromise,
+    /// A new credential replaces this one
+    #[serde(rename = "superseded")]
+    Superseded,
+    /// The subject's affiliation with the issuer changed
+    #[serde(rename = "affiliation_changed")]
+    AffiliationChanged,
+    /// The subject lost the privilege the credential attests
+    #[serde(rename = "privilege_withdrawn")]
+    PrivilegeWithdrawn,
+    /// The issuer stopped the activity the credential was issued for
+    #[serde(rename = "cessation_of_operation")]
+    CessationOfOperation,
+    /// The credential was issued in error
+    #[serde(rename = "issued_in_error")]
+    IssuedInError,
+}
+
+/// Audit record of a credential's revocation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevocationRecord {
+    pub id: String,
+    pub credential_id: String,
+    /// Registry hash of the secured credential, which finds the record from any copy of it
+    pub credential_hash: String,
+    pub issuer_did: String,
+    pub reason_code: RevocationReason,
+    /// Free-text explanation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// DID of the user who revoked the credential
+    pub revoked_by: String,
+    pub revoked_at: DateTime<Utc>,
+    /// SSIRegistry transaction revoking the credential; unknown when a retry found the
+    /// credential already revoked on chain
+    pub tx_hash: Option<String>,
+}
+
 // Credential Request model
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CredentialRequest {
@@ -100,6 +232,22 @@ pub struct CredentialRequest {
     pub updated_at: DateTime<Utc>,
     pub processed_at: Option<DateTime<Utc>>,
     pub credential_id: Option<String>,
+    /// Commitment to the requester's link secret, for the credential to be bound to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_secret_commitment: Option<LinkSecretCommitment>,
+}
+
+/// A holder's commitment to their link secret, sent along a credential request.
+///
+/// The wallet commits to its secret with a fresh blinding, so the commitments
+/// of two credentials cannot be linked, and proves it can open the commitment.
+/// The issuer signs the commitment without ever learning the secret.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkSecretCommitment {
+    /// Compressed Ristretto point, base64url
+    pub commitment: String,
+    /// Schnorr proof of knowledge of the secret and blinding, bound to the issuer and subject
+    pub proof: Vec<u8>,
 }
 
 impl CredentialRequest {
@@ -123,6 +271,7 @@ impl CredentialRequest {
             updated_at: now,
             processed_at: None,
             credential_id: None,
+            link_secret_commitment: None,
         }
     }
 }
@@ -203,6 +352,9 @@ pub struct PresentationRequest {
     pub expires_at: Option<DateTime<Utc>>,
     pub schema_ids: Vec<String>,
     pub recipient_did: Option<String>,
+    /// Set when a presentation answering the request was verified; a request is answered once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consumed_at: Option<DateTime<Utc>>,
 }
 
 impl PresentationRequest {
@@ -225,6 +377,7 @@ impl PresentationRequest {
             expires_at,
             schema_ids: vec![],
             recipient_did: None,
+            consumed_at: None,
         }
     }
 
@@ -239,13 +392,42 @@ pub struct CredentialRequirement {
     pub issuer_did: Option<String>,
     pub required_attributes: Vec<String>,
     pub predicate: Option<Predicate>,
+    /// Data elements requested from an mdoc, as in an ISO 18013-5 `ItemsRequest`
+    #[serde(default)]
+    pub mdoc: Option<MdocItemsRequest>,
+}
+
+/// mdoc data elements requested by a verifier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MdocItemsRequest {
+    pub doc_type: String,
+    /// Requested elements per namespace, each with whether the verifier intends to retain it
+    pub name_spaces: HashMap<String, HashMap<String, bool>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Predicate {
     pub attribute: String,
     pub predicate_type: PredicateType,
+    /// A number, a date for date attributes, or the list of values of `in` and `not_in`;
+    /// unused when `relative` is set
+    #[serde(default)]
     pub value: serde_json::Value,
+    /// Compare a date attribute with the verification date moved by this offset instead,
+    /// e.g. `<=` with `{"years": -18}` for "at least 18 years old"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relative: Option<DateOffset>,
+}
+
+/// Offset from the verification date; negative offsets are in the past
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DateOffset {
+    #[serde(default)]
+    pub years: i32,
+    #[serde(default)]
+    pub months: i32,
+    #[serde(default)]
+    pub days: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -262,6 +444,25 @@ pub enum PredicateType {
     Equal,
     #[serde(rename = "!=")]
     NotEqual,
+    #[serde(rename = "in")]
+    In,
+    #[serde(rename = "not_in")]
+    NotIn,
+}
+
+impl PredicateType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PredicateType::GreaterThanOrEqual => ">=",
+            PredicateType::LessThanOrEqual => "<=",
+            PredicateType::GreaterThan => ">",
+            PredicateType::LessThan => "<",
+            PredicateType::Equal => "==",
+            PredicateType::NotEqual => "!=",
+            PredicateType::In => "in",
+            PredicateType::NotIn => "not_in",
+        }
+    }
 }
 
 // Credential Offer model
@@ -467,10 +668,37 @@ pub struct Schema {
     pub version: String,
     pub issuer_did: String,
     pub attributes: Vec<SchemaAttribute>,
+    /// Data model version of credentials issued against this schema
+    #[serde(default)]
+    pub vc_version: VcVersion,
+    /// ISO 18013-5 document type and namespace; credentials of mdoc schemas are issued as mdocs
+    #[serde(default)]
+    pub mdoc: Option<MdocProfile>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// ISO 18013-5 document type of a schema and the namespace holding its attributes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MdocProfile {
+    /// e.g. `org.iso.18013.5.1.mDL`
+    pub doc_type: String,
+    /// e.g. `org.iso.18013.5.1`
+    pub namespace: String,
+}
+
+/// W3C Verifiable Credentials Data Model version
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum VcVersion {
+    /// `vc` claim of a JWT, with `issuanceDate`/`expirationDate`
+    #[default]
+    #[serde(rename = "1.1")]
+    V1_1,
+    /// `vc+jwt` whose payload is the credential, with `validFrom`/`validUntil`
+    #[serde(rename = "2.0")]
+    V2_0,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchemaAttribute {
     pub name: String,
@@ -495,6 +723,98 @@ pub enum AttributeDataType {
     Array,
 }
 
+// Issuer signing key model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuerKey {
+    pub did: String,
+    pub key_id: String,
+    pub algorithm: String,
+    pub public_key_base58: String,
+    /// Key ID of the private key inside the key store
+    pub key_handle: String,
+    /// Position in the DID's key history; `key_id` ends in `#pq-keys-<version>`,
+    /// or `#bbs-keys-<version>` for BBS keys
+    #[serde(default = "default_key_version")]
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    /// Unix time at which a newer key replaced this one
+    #[serde(default)]
+    pub retired_at: Option<i64>,
+}
+
+fn default_key_version() -> u32 {
+    1
+}
+
+impl IssuerKey {
+    /// Whether the key was the issuer's signing key at `issued_at` (unix seconds), a trusted
+    /// issuance time such as the credential's on-chain registration.
+    /// The rotation second itself counts for both keys, since block times have no finer resolution.
+    pub fn was_active_at(&self, issued_at: i64) -> bool {
+        issued_at >= self.created_at.timestamp()
+            && self.retired_at.is_none_or(|retired_at| issued_at <= retired_at)
+    }
+}
+
+// DID lifecycle model
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum DidState {
+    #[serde(rename = "active")]
+    Active,
+    #[serde(rename = "deactivated")]
+    Deactivated,
+}
+
+/// Deactivation record of a DID; DIDs without one are active. Deactivation is permanent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DidDeactivation {
+    pub did: String,
+    /// DID of the session that deactivated it: the DID itself or its controller
+    pub deactivated_by: String,
+    pub reason: Option<String>,
+    pub deactivated_at: DateTime<Utc>,
+    /// Transaction that anchored the deactivation on chain, if requested
+    pub anchor_tx_hash: Option<String>,
+}
+
+// Authentication challenge model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthChallenge {
+    pub id: String,
+    pub did: String,
+    pub challenge: String,
+    pub created_at: DateTime<Utc>,
+    /// Unix timestamp, kept numeric so storage backends can range-query it
+    pub expires_at: i64,
+    pub consumed: bool,
+    pub consumed_at: Option<DateTime<Utc>>,
+}
+
+impl AuthChallenge {
+    pub fn new(did: String, challenge: String, expires_at: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            did,
+            challenge,
+            created_at: Utc::now(),
+            expires_at: expires_at.timestamp(),
+            consumed: false,
+            consumed_at: None,
+        }
+    }
+}
+
+// Revoked session token model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevokedToken {
+    /// `jti` of a single token, or `sid` of a whole session
+    pub jti: String,
+    pub did: String,
+    /// Unix timestamp after which the token is expired anyway and the record can be dropped
+    pub expires_at: i64,
+    pub revoked_at: DateTime<Utc>,
+}
+
 // API Request/Response models
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
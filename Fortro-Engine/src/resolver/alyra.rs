@@ -0,0 +1,74 @@
+use async_trait::async_trait;
+use crystals_dilithium::dilithium2::PUBLICKEYBYTES;
+use std::sync::Arc;
+
+use super::hosted::{add_issuer_profile, hosted_document};
+use super::{DidDocument, DidResolver, ResolvedDid, VerificationMethod};
+use crate::db::Database;
+use crate::error::AppError;
+use crate::services::KeyService;
+
+const METHOD_PREFIX: &str = "did:alyra:";
+
+/// Resolver for `did:alyra`, whose method-specific ID is the base58 Dilithium2 public key.
+///
+/// The key in the DID is always `#pq-keys-1`. For issuers managed by this
+/// engine, rotated keys and profile services come from the engine's records;
+/// a Kyber key the holder published is listed as `#kem-keys-1`.
+pub struct AlyraResolver {
+    db: Arc<Database>,
+    key_service: KeyService,
+}
+
+impl AlyraResolver {
+    pub fn new(db: Arc<Database>, key_service: KeyService) -> Self {
+        Self { db, key_service }
+    }
+}
+
+#[async_trait]
+impl DidResolver for AlyraResolver {
+    async fn resolve_with_metadata(&self, did: &str) -> Result<ResolvedDid, AppError> {
+        // Step 1: Decode the public key embedded in the DID
+        let public_key_base58 = did.strip_prefix(METHOD_PREFIX)
+            .ok_or_else(|| AppError::ValidationError(format!("Not a did:alyra DID: {}", did)))?;
+        let public_key = bs58::decode(public_key_base58).into_vec()
+            .ok()
+            .filter(|key| key.len() == PUBLICKEYBYTES)
+            .ok_or_else(|| AppError::ValidationError(format!("Invalid did:alyra DID: {}", did)))?;
+
+        // Step 2: Prefer the engine's key history for issuers it manages
+        let mut document = match hosted_document(&self.db, &self.key_service, did).await? {
+            Some(document) => {
+                // The first key must be the one the DID was derived from
+                let first_key = document.verification_method.first().map(|m| m.public_key_multibase.as_str());
+                if first_key != Some(&format!("z{}", public_key_base58)) {
+                    return Err(AppError::InternalError(format!("Key history of {} does not start with its DID key", did)));
+                }
+                document
+            }
+            // Step 3: Otherwise the DID key is the only signing key
+            None => {
+                let mut document = DidDocument::new(did);
+                let method = VerificationMethod::dilithium(format!("{}#pq-keys-1", did), did, &public_key);
+                document.authentication.push(method.id.clone());
+                document.assertion_method.push(method.id.clone());
+                document.verification_method.push(method);
+
+                add_issuer_profile(&self.db, &mut document).await?;
+                document
+            }
+        };
+
+        // Step 4: Add the Kyber key the holder published for key agreement, if any
+        if let Some(kyber_public_key) = self.db.find_user_by_did(did).await?.and_then(|user| user.kyber_public_key) {
+            let key_bytes = bs58::decode(&kyber_public_key).into_vec()
+                .map_err(|e| AppError::InternalError(format!("Corrupt Kyber key of {}: {}", did, e)))?;
+            let method = VerificationMethod::kyber(format!("{}#kem-keys-1", did), did, &key_bytes);
+            document.key_agreement.push(method.id.clone());
+            document.verification_method.push(method);
+        }
+
+        Ok(ResolvedDid::active(document))
+    }
+}
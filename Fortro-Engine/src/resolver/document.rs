@@ -0,0 +1,231 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::utils::bbs;
+use crate::utils::did::{KeyType, PublicKey};
+
+/// JSON-LD context of a DID Core document
+pub const DID_CONTEXT: &str = "https://www.w3.org/ns/did/v1";
+
+/// Media type of a DID document in plain JSON
+pub const DID_JSON_MEDIA_TYPE: &str = "application/did+json";
+
+/// Verification method type of the engine's Dilithium2 keys
+pub const DILITHIUM_KEY_TYPE: &str = "Dilithium2VerificationKey2024";
+
+/// Verification method type of multicodec-prefixed keys such as did:key keys
+pub const MULTIKEY_TYPE: &str = "Multikey";
+
+/// Older verification method type of Ed25519 keys, still served by many resolvers
+pub const ED25519_KEY_TYPE: &str = "Ed25519VerificationKey2020";
+
+/// Verification method type of Ethereum accounts that sign with recoverable secp256k1 signatures
+pub const SECP256K1_RECOVERY_TYPE: &str = "EcdsaSecp256k1RecoveryMethod2020";
+
+/// Verification method type of the Kyber768 keys holders publish for key agreement
+pub const KYBER_KEY_TYPE: &str = "Kyber768KeyAgreementKey2024";
+
+/// W3C DID Core document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidDocument {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub controller: Vec<String>,
+    #[serde(default)]
+    pub verification_method: Vec<VerificationMethod>,
+    #[serde(default)]
+    pub authentication: Vec<String>,
+    #[serde(default)]
+    pub assertion_method: Vec<String>,
+    #[serde(default)]
+    pub key_agreement: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub service: Vec<Service>,
+}
+
+/// DID document metadata of a resolution result
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentMetadata {
+    /// Set once the DID has been deactivated; its document must no longer be used
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deactivated: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<DateTime<Utc>>,
+}
+
+/// DID document together with its metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedDid {
+    #[serde(rename = "didDocument")]
+    pub document: DidDocument,
+    #[serde(rename = "didDocumentMetadata")]
+    pub metadata: DocumentMetadata,
+}
+
+/// Public key entry of a DID document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationMethod {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub method_type: String,
+    pub controller: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub public_key_multibase: String,
+    /// CAIP-10 account of an on-chain key, e.g. `eip155:8453:0x…`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blockchain_account_id: Option<String>,
+    /// Signatures made after this time must not be accepted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<DateTime<Utc>>,
+}
+
+/// Service endpoint entry of a DID document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Service {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub service_type: String,
+    pub service_endpoint: String,
+}
+
+impl DidDocument {
+    /// Create an empty document for a DID
+    pub fn new(did: &str) -> Self {
+        Self {
+            context: vec![DID_CONTEXT.to_string()],
+            id: did.to_string(),
+            controller: Vec::new(),
+            verification_method: Vec::new(),
+            authentication: Vec::new(),
+            assertion_method: Vec::new(),
+            key_agreement: Vec::new(),
+            service: Vec::new(),
+        }
+    }
+
+    /// Find a verification method by its DID URL
+    pub fn verification_method(&self, id: &str) -> Option<&VerificationMethod> {
+        self.verification_method.iter().find(|method| method.id == id)
+    }
+
+    /// Find a verification method the document authorizes to sign assertions or authentication proofs
+    pub fn signing_key(&self, id: &str) -> Option<&VerificationMethod> {
+        let authorized = self.assertion_method.iter()
+            .chain(self.authentication.iter())
+            .any(|reference| reference == id);
+        if !authorized {
+            return None;
+        }
+        self.verification_method(id)
+    }
+}
+
+impl ResolvedDid {
+    /// Resolution result of an active DID
+    pub fn active(document: DidDocument) -> Self {
+        Self { document, metadata: DocumentMetadata::default() }
+    }
+
+    /// Resolution result of a deactivated DID
+    pub fn deactivated(document: DidDocument, updated: Option<DateTime<Utc>>) -> Self {
+        Self { document, metadata: DocumentMetadata { deactivated: true, updated } }
+    }
+}
+
+impl VerificationMethod {
+    /// Create a Dilithium2 verification method from raw public key bytes
+    pub fn dilithium(id: String, controller: &str, public_key: &[u8]) -> Self {
+        Self {
+            id,
+            method_type: DILITHIUM_KEY_TYPE.to_string(),
+            controller: controller.to_string(),
+            // Multibase base58btc
+            public_key_multibase: format!("z{}", bs58::encode(public_key).into_string()),
+            blockchain_account_id: None,
+            expires: None,
+        }
+    }
+
+    /// Create a Kyber768 key agreement method from raw public key bytes
+    pub fn kyber(id: String, controller: &str, public_key: &[u8]) -> Self {
+        Self {
+            id,
+            method_type: KYBER_KEY_TYPE.to_string(),
+            controller: controller.to_string(),
+            public_key_multibase: format!("z{}", bs58::encode(public_key).into_string()),
+            blockchain_account_id: None,
+            expires: None,
+        }
+    }
+
+    /// Create a Multikey verification method
+    pub fn multikey(id: String, controller: &str, public_key: &PublicKey) -> Result<Self, AppError> {
+        Ok(Self {
+            id,
+            method_type: MULTIKEY_TYPE.to_string(),
+            controller: controller.to_string(),
+            public_key_multibase: public_key.to_multikey()?,
+            blockchain_account_id: None,
+            expires: None,
+        })
+    }
+
+    /// Create a Multikey verification method for a BLS12-381 G2 BBS public key
+    pub fn bbs(id: String, controller: &str, public_key: &[u8]) -> Self {
+        Self {
+            id,
+            method_type: MULTIKEY_TYPE.to_string(),
+            controller: controller.to_string(),
+            public_key_multibase: bbs::public_key_to_multikey(public_key),
+            blockchain_account_id: None,
+            expires: None,
+        }
+    }
+
+    /// Create a verification method for an Ethereum account, identified by its chain and address
+    pub fn ethereum_account(id: String, controller: &str, chain_id: u64, address: &str) -> Self {
+        Self {
+            id,
+            method_type: SECP256K1_RECOVERY_TYPE.to_string(),
+            controller: controller.to_string(),
+            public_key_multibase: String::new(),
+            blockchain_account_id: Some(format!("eip155:{}:{}", chain_id, address)),
+            expires: None,
+        }
+    }
+
+    /// Decode the public key and its type
+    pub fn public_key(&self) -> Result<PublicKey, AppError> {
+        match self.method_type.as_str() {
+            DILITHIUM_KEY_TYPE => {
+                let encoded = self.public_key_multibase.strip_prefix('z').ok_or_else(|| {
+                    AppError::SsiError(format!("Unsupported multibase encoding in {}", self.id))
+                })?;
+
+                let bytes = bs58::decode(encoded)
+                    .into_vec()
+                    .map_err(|e| AppError::SsiError(format!("Failed to decode public key of {}: {}", self.id, e)))?;
+
+                Ok(PublicKey { key_type: KeyType::Dilithium2, bytes })
+            }
+            MULTIKEY_TYPE | ED25519_KEY_TYPE => PublicKey::from_multikey(&self.public_key_multibase),
+            other => Err(AppError::SsiError(format!("Unsupported verification method type {} in {}", other, self.id))),
+        }
+    }
+
+    /// Decode the BBS public key of a BLS12-381 G2 Multikey
+    pub fn bbs_public_key(&self) -> Result<Vec<u8>, AppError> {
+        if self.method_type != MULTIKEY_TYPE {
+            return Err(AppError::SsiError(format!("{} is not a BBS key", self.id)));
+        }
+        bbs::public_key_from_multikey(&self.public_key_multibase)
+    }
+}
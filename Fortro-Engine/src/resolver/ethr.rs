@@ -0,0 +1,235 @@
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::Utc;
+use ethers::types::{Address, U256};
+use ethers::utils::to_checksum;
+use std::sync::Arc;
+
+use super::{DidDocument, DidResolver, ResolvedDid, Service, VerificationMethod};
+use crate::blockchain::{DIDRegistryEvents, EthereumClient};
+use crate::error::AppError;
+use crate::utils::did::{KeyType, PublicKey};
+
+/// JSON-LD context of `EcdsaSecp256k1RecoveryMethod2020` verification methods
+const SECP256K1_RECOVERY_CONTEXT: &str = "https://w3id.org/security/suites/secp256k1recovery-2020/v2";
+
+/// Resolver for `did:ethr`, whose documents are rebuilt from the events of the DID Registry contract.
+///
+/// The owner becomes `#controller`. Live `veriKey`/`sigAuth` delegates and
+/// `did/pub/Ed25519/…` attributes become `#delegate-N` keys, and `did/svc/…`
+/// attributes become `#service-N` services, numbered in event order.
+pub struct EthrResolver {
+    blockchain: Arc<EthereumClient>,
+}
+
+/// Key or service contributed by a registry event
+enum Entry {
+    Method { method: VerificationMethod, authentication: bool },
+    Service(Service),
+}
+
+impl EthrResolver {
+    pub fn new(blockchain: Arc<EthereumClient>) -> Self {
+        Self { blockchain }
+    }
+}
+
+#[async_trait]
+impl DidResolver for EthrResolver {
+    async fn resolve_with_metadata(&self, did: &str) -> Result<ResolvedDid, AppError> {
+        // Step 1: Parse the DID and check it lives on the chain this engine is connected to
+        let (chain_id, identity) = self.blockchain.did_ethr_identity(did).await?;
+
+        // Step 2: Read the current owner and the identity's change history
+        let owner = self.blockchain.identity_owner(identity).await?;
+        let events = self.blockchain.did_registry_events(identity).await?;
+
+        // Step 3: Replay the history; later events replace or remove earlier entries
+        let now = U256::from(Utc::now().timestamp().max(0));
+        let entries = replay(did, chain_id, events, now);
+
+        // Step 4: Assemble the document; handing an identity to the zero address deactivates it
+        let mut document = DidDocument::new(did);
+        if owner == Address::zero() {
+            return Ok(ResolvedDid::deactivated(document, None));
+        }
+        document.context.push(SECP256K1_RECOVERY_CONTEXT.to_string());
+
+        let controller = VerificationMethod::ethereum_account(
+            format!("{}#controller", did),
+            did,
+            chain_id,
+            &to_checksum(&owner, None),
+        );
+        document.authentication.push(controller.id.clone());
+        document.assertion_method.push(controller.id.clone());
+        document.verification_method.push(controller);
+
+        for (_, entry) in entries {
+            match entry {
+                Entry::Method { method, authentication } => {
+                    if authentication {
+                        document.authentication.push(method.id.clone());
+                    }
+                    document.assertion_method.push(method.id.clone());
+                    document.verification_method.push(method);
+                }
+                Entry::Service(service) => document.service.push(service),
+            }
+        }
+
+        Ok(ResolvedDid::active(document))
+    }
+}
+
+/// Replay a DID's registry events into its live keys and services, in event order.
+///
+/// `validTo` is compared as a `uint256`: the registry adds the caller's validity to
+/// the block timestamp, so it can exceed any `u64`.
+fn replay(did: &str, chain_id: u64, events: Vec<DIDRegistryEvents>, now: U256) -> Vec<(String, Entry)> {
+    let mut delegate_count = 0;
+    let mut service_count = 0;
+    let mut entries: Vec<(String, Entry)> = Vec::new();
+
+    for event in events {
+        let (key, valid_to, entry) = match event {
+            DIDRegistryEvents::DiddelegateChangedFilter(e) => {
+                delegate_count += 1;
+                let delegate_type = bytes32_to_string(&e.delegate_type);
+                let key = format!("{}-{:?}", delegate_type, e.delegate);
+                let entry = match delegate_type.as_str() {
+                    "veriKey" | "sigAuth" => Some(Entry::Method {
+                        method: VerificationMethod::ethereum_account(
+                            format!("{}#delegate-{}", did, delegate_count),
+                            did,
+                            chain_id,
+                            &to_checksum(&e.delegate, None),
+                        ),
+                        authentication: delegate_type == "sigAuth",
+                    }),
+                    _ => None,
+                };
+                (key, e.valid_to, entry)
+            }
+            DIDRegistryEvents::DidattributeChangedFilter(e) => {
+                let name = bytes32_to_string(&e.name);
+                let key = format!("{}-{}", name, hex::encode(&e.value));
+                let entry = if let Some(service_type) = name.strip_prefix("did/svc/") {
+                    service_count += 1;
+                    Some(Entry::Service(Service {
+                        id: format!("{}#service-{}", did, service_count),
+                        service_type: service_type.to_string(),
+                        service_endpoint: String::from_utf8_lossy(&e.value).into_owned(),
+                    }))
+                } else if name.starts_with("did/pub/") {
+                    delegate_count += 1;
+                    public_key_entry(did, delegate_count, &name, &e.value)
+                } else {
+                    None
+                };
+                (key, e.valid_to, entry)
+            }
+            // The current owner is read from the contract directly
+            DIDRegistryEvents::DidownerChangedFilter(_) => continue,
+        };
+
+        let position = entries.iter().position(|(existing, _)| *existing == key);
+        match (valid_to >= now, entry, position) {
+            (true, Some(entry), Some(position)) => entries[position].1 = entry,
+            (true, Some(entry), None) => entries.push((key, entry)),
+            (false, _, Some(position)) => {
+                entries.remove(position);
+            }
+            _ => {}
+        }
+    }
+
+    entries
+}
+
+/// Build a key from a `did/pub/<algorithm>/<purpose>[/<encoding>]` attribute.
+///
+/// Only Ed25519 signing keys are kept, as the engine cannot verify with the others.
+fn public_key_entry(did: &str, index: u32, name: &str, value: &[u8]) -> Option<Entry> {
+    let mut parts = name.split('/').skip(2);
+    let (algorithm, purpose, encoding) = (parts.next()?, parts.next()?, parts.next().unwrap_or("hex"));
+    if algorithm != "Ed25519" || !matches!(purpose, "veriKey" | "sigAuth") {
+        return None;
+    }
+
+    // `hex` values are the raw key; the other encodings are text
+    let bytes = match encoding {
+        "hex" => value.to_vec(),
+        "base64" => STANDARD.decode(value).ok()?,
+        "base58" => bs58::decode(value).into_vec().ok()?,
+        _ => return None,
+    };
+
+    let public_key = PublicKey { key_type: KeyType::Ed25519, bytes };
+    let method = VerificationMethod::multikey(format!("{}#delegate-{}", did, index), did, &public_key).ok()?;
+    method.public_key().ok()?;
+
+    Some(Entry::Method { method, authentication: purpose == "sigAuth" })
+}
+
+/// Read a right-padded `bytes32` name as a string
+fn bytes32_to_string(bytes: &[u8; 32]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::{DiddelegateChangedFilter, DidattributeChangedFilter};
+
+    const DID: &str = "did:ethr:0x2105:0x1111111111111111111111111111111111111111";
+
+    fn name(value: &str) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        bytes[..value.len()].copy_from_slice(value.as_bytes());
+        bytes
+    }
+
+    fn delegate(valid_to: U256) -> DIDRegistryEvents {
+        DIDRegistryEvents::DiddelegateChangedFilter(DiddelegateChangedFilter {
+            identity: Address::repeat_byte(0x11),
+            delegate_type: name("veriKey"),
+            delegate: Address::repeat_byte(0x33),
+            valid_to,
+            previous_change: U256::zero(),
+        })
+    }
+
+    fn service(valid_to: U256) -> DIDRegistryEvents {
+        DIDRegistryEvents::DidattributeChangedFilter(DidattributeChangedFilter {
+            identity: Address::repeat_byte(0x11),
+            name: name("did/svc/LinkedDomains"),
+            value: b"https://example.com".to_vec().into(),
+            valid_to,
+            previous_change: U256::zero(),
+        })
+    }
+
+    #[test]
+    fn keeps_entries_valid_beyond_u64() {
+        let now = U256::from(1_700_000_000u64);
+        let valid_to = U256::from(u64::MAX) + now;
+
+        let entries = replay(DID, 0x2105, vec![delegate(valid_to), service(U256::MAX)], now);
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(entries[0].1, Entry::Method { authentication: false, .. }));
+        assert!(matches!(&entries[1].1, Entry::Service(s) if s.service_endpoint == "https://example.com"));
+    }
+
+    #[test]
+    fn drops_expired_and_revoked_entries() {
+        let now = U256::from(1_700_000_000u64);
+
+        let entries = replay(DID, 0x2105, vec![delegate(U256::MAX), service(now - 1)], now);
+        assert_eq!(entries.len(), 1);
+
+        let entries = replay(DID, 0x2105, vec![delegate(U256::MAX), delegate(U256::zero())], now);
+        assert!(entries.is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::doc;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::{DidDocument, Service, VerificationMethod};
+use crate::db::Database;
+use crate::error::AppError;
+use crate::services::KeyService;
+use crate::utils::bbs;
+
+/// Build the DID document of an issuer whose keys this engine manages.
+///
+/// Returns `None` when the engine holds no keys for the DID.
+pub async fn hosted_document(
+    db: &Database,
+    key_service: &KeyService,
+    did: &str,
+) -> Result<Option<DidDocument>, AppError> {
+    // Step 1: Collect the key history, oldest first, with the BBS key after the Dilithium keys
+    let mut keys = key_service.key_history(did).await?;
+    if keys.is_empty() {
+        return Ok(None);
+    }
+    keys.reverse();
+    keys.sort_by_key(|key| key.algorithm == bbs::ALGORITHM);
+
+    let mut document = DidDocument::new(did);
+
+    // Step 2: List every key, marking retired ones with their retirement time
+    for key in &keys {
+        let key_bytes = bs58::decode(&key.public_key_base58).into_vec()
+            .map_err(|e| AppError::InternalError(format!("Corrupt public key {}: {}", key.key_id, e)))?;
+        // BBS keys only sign credentials, never authentication proofs
+        if key.algorithm == bbs::ALGORITHM {
+            let method = VerificationMethod::bbs(key.key_id.clone(), did, &key_bytes);
+            document.assertion_method.push(method.id.clone());
+            document.verification_method.push(method);
+            continue;
+        }
+
+        let mut method = VerificationMethod::dilithium(key.key_id.clone(), did, &key_bytes);
+        method.expires = key.retired_at.and_then(|ts| DateTime::<Utc>::from_timestamp(ts, 0));
+
+        // Retired keys stay listed so credentials they signed keep verifying
+        document.assertion_method.push(method.id.clone());
+        if key.retired_at.is_none() {
+            document.authentication.push(method.id.clone());
+        }
+        document.verification_method.push(method);
+    }
+
+    // Step 3: Describe the issuer behind the DID, if there is one
+    add_issuer_profile(db, &mut document).await?;
+
+    Ok(Some(document))
+}
+
+/// Add the issuer profile's controller and website to the document
+pub async fn add_issuer_profile(db: &Database, document: &mut DidDocument) -> Result<(), AppError> {
+    let issuer = match db
+        .find_one::<HashMap<String, Value>>("issuers", doc! { "id": &document.id })
+        .await?
+    {
+        Some(issuer) => issuer,
+        None => return Ok(()),
+    };
+
+    if let Some(controller) = issuer.get("controller_did").and_then(|v| v.as_str()) {
+        if controller != document.id {
+            document.controller = vec![document.id.clone(), controller.to_string()];
+        }
+    }
+
+    if let Some(website) = issuer.get("website").and_then(|v| v.as_str()).filter(|w| !w.is_empty()) {
+        document.service.push(Service {
+            id: format!("{}#website", document.id),
+            service_type: "LinkedDomains".to_string(),
+            service_endpoint: website.to_string(),
+        });
+    }
+
+    Ok(())
+}
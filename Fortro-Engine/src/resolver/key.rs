@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+
+use super::{DidDocument, DidResolver, ResolvedDid, VerificationMethod};
+use crate::error::AppError;
+use crate::utils::did;
+
+/// Resolver for `did:key`, whose document is derived entirely from the key in the DID
+pub struct KeyResolver;
+
+#[async_trait]
+impl DidResolver for KeyResolver {
+    async fn resolve_with_metadata(&self, did: &str) -> Result<ResolvedDid, AppError> {
+        let public_key = did::did_key_public_key(did)
+            .map_err(|e| AppError::ValidationError(format!("Invalid did:key DID {}: {}", did, e)))?;
+
+        let mut document = DidDocument::new(did);
+        let method = VerificationMethod::multikey(did::default_key_id(did)?, did, &public_key)?;
+
+        document.authentication.push(method.id.clone());
+        document.assertion_method.push(method.id.clone());
+        document.verification_method.push(method);
+
+        Ok(ResolvedDid::active(document))
+    }
+}
@@ -0,0 +1,99 @@
+mod alyra;
+mod document;
+mod ethr;
+mod hosted;
+mod key;
+mod web;
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::AppError;
+use crate::services::DidLifecycleService;
+
+pub use alyra::AlyraResolver;
+pub use ethr::EthrResolver;
+pub use key::KeyResolver;
+pub use web::{DidWebClient, WebResolver};
+pub use document::{DidDocument, ResolvedDid, Service, VerificationMethod, DID_JSON_MEDIA_TYPE};
+
+/// Resolves a DID to its DID document
+#[async_trait]
+pub trait DidResolver: Send + Sync {
+    /// Resolve a DID to its current DID document and document metadata
+    async fn resolve_with_metadata(&self, did: &str) -> Result<ResolvedDid, AppError>;
+
+    /// Resolve a DID to a DID document that may be used, refusing deactivated DIDs
+    async fn resolve(&self, did: &str) -> Result<DidDocument, AppError> {
+        let resolved = self.resolve_with_metadata(did).await?;
+        if resolved.metadata.deactivated {
+            return Err(AppError::SsiError(format!("DID {} is deactivated", did)));
+        }
+        Ok(resolved.document)
+    }
+}
+
+/// Resolver that hands each DID to the resolver registered for its method
+#[derive(Clone, Default)]
+pub struct DidResolvers {
+    methods: HashMap<String, Arc<dyn DidResolver>>,
+    lifecycle: Option<DidLifecycleService>,
+}
+
+impl DidResolvers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the resolver for a DID method, e.g. `alyra` for `did:alyra:…`
+    pub fn with_method(mut self, method: &str, resolver: impl DidResolver + 'static) -> Self {
+        self.methods.insert(method.to_string(), Arc::new(resolver));
+        self
+    }
+
+    /// Mark DIDs deactivated through the engine as deactivated, whatever their method
+    pub fn with_lifecycle(mut self, lifecycle: DidLifecycleService) -> Self {
+        self.lifecycle = Some(lifecycle);
+        self
+    }
+}
+
+#[async_trait]
+impl DidResolver for DidResolvers {
+    async fn resolve_with_metadata(&self, did: &str) -> Result<ResolvedDid, AppError> {
+        let method = did_method(did)
+            .ok_or_else(|| AppError::ValidationError(format!("Invalid DID: {}", did)))?;
+
+        let resolver = self.methods.get(method)
+            .ok_or_else(|| AppError::ValidationError(format!("Unsupported DID method: {}", method)))?;
+
+        let resolved = resolver.resolve_with_metadata(did).await?;
+
+        // Deactivations recorded by the engine override the method's own state
+        if let Some(lifecycle) = &self.lifecycle {
+            if let Some(deactivation) = lifecycle.deactivation(did).await? {
+                return Ok(ResolvedDid::deactivated(resolved.document, Some(deactivation.deactivated_at)));
+            }
+        }
+
+        Ok(resolved)
+    }
+}
+
+/// Method name of a DID, e.g. `alyra` for `did:alyra:…`
+pub fn did_method(did: &str) -> Option<&str> {
+    let mut parts = did.splitn(3, ':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("did"), Some(method), Some(id)) if !method.is_empty() && !id.is_empty() => Some(method),
+        _ => None,
+    }
+}
+
+/// Split a DID URL into its DID and fragment
+pub fn split_did_url(did_url: &str) -> (&str, Option<&str>) {
+    match did_url.split_once('#') {
+        Some((did, fragment)) => (did, Some(fragment)),
+        None => (did_url, None),
+    }
+}
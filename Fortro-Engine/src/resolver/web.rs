@@ -0,0 +1,370 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::document::DID_CONTEXT;
+use super::hosted::hosted_document;
+use super::{DidDocument, DidResolver, ResolvedDid, Service, VerificationMethod};
+use crate::config::DidWebConfig;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::services::KeyService;
+use crate::utils::did::{self, PublicKey};
+
+/// Largest DID document accepted from a remote host
+const MAX_DOCUMENT_BYTES: usize = 256 * 1024;
+
+/// Timeout of a remote DID document request
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Fetches and caches the DID documents of external did:web DIDs.
+///
+/// Shared across requests so cached documents outlive a single resolver.
+pub struct DidWebClient {
+    http: reqwest::Client,
+    allow_http: bool,
+    ttl: Duration,
+    cache: Mutex<HashMap<String, (Instant, DidDocument)>>,
+}
+
+impl DidWebClient {
+    pub fn new(config: &DidWebConfig) -> Result<Self, AppError> {
+        let http = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            // did:web documents must be served at their own URL
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| AppError::ConfigError(format!("Failed to build did:web HTTP client: {}", e)))?;
+
+        Ok(Self {
+            http,
+            allow_http: config.allow_http,
+            ttl: Duration::from_secs(config.cache_ttl),
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Resolve an external did:web DID, serving from the cache while fresh
+    pub async fn resolve(&self, did: &str) -> Result<DidDocument, AppError> {
+        if let Some(document) = self.cached(did) {
+            return Ok(document);
+        }
+
+        let document = self.fetch(did).await?;
+
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < self.ttl);
+        cache.insert(did.to_string(), (Instant::now(), document.clone()));
+
+        Ok(document)
+    }
+
+    /// Cached document of a DID, if still fresh
+    fn cached(&self, did: &str) -> Option<DidDocument> {
+        let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.get(did)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < self.ttl)
+            .map(|(_, document)| document.clone())
+    }
+
+    /// Download and parse the DID document of a did:web DID
+    async fn fetch(&self, did: &str) -> Result<DidDocument, AppError> {
+        // Step 1: Map the DID to its document URL
+        let url = did::did_web_url(did, self.allow_http)?;
+
+        // Step 2: Download the document, refusing oversized bodies
+        let mut response = self.http.get(&url)
+            .header(reqwest::header::ACCEPT, "application/did+json, application/json")
+            .send()
+            .await
+            .map_err(|e| AppError::SsiError(format!("Failed to fetch {}: {}", url, e)))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(AppError::NotFoundError(format!("DID not found: {}", did)));
+        }
+        if !response.status().is_success() {
+            return Err(AppError::SsiError(format!("Fetching {} returned {}", url, response.status())));
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await
+            .map_err(|e| AppError::SsiError(format!("Failed to read {}: {}", url, e)))?
+        {
+            body.extend_from_slice(&chunk);
+            if body.len() > MAX_DOCUMENT_BYTES {
+                return Err(AppError::SsiError(format!("DID document at {} is too large", url)));
+            }
+        }
+
+        // Step 3: Parse the document and check it describes the requested DID
+        let value: Value = serde_json::from_slice(&body)
+            .map_err(|e| AppError::SsiError(format!("Invalid DID document at {}: {}", url, e)))?;
+
+        parse_document(did, &value)
+    }
+}
+
+/// Resolver for `did:web`.
+///
+/// DIDs on the engine's own domain are answered from its issuer records;
+/// all others are fetched over HTTPS.
+pub struct WebResolver {
+    db: Arc<Database>,
+    key_service: KeyService,
+    domain: Option<String>,
+    client: Arc<DidWebClient>,
+}
+
+impl WebResolver {
+    pub fn new(db: Arc<Database>, key_service: KeyService, domain: Option<String>, client: Arc<DidWebClient>) -> Self {
+        Self { db, key_service, domain, client }
+    }
+
+    /// Whether the DID lives on the domain this engine serves
+    fn is_hosted(&self, did: &str) -> bool {
+        match &self.domain {
+            Some(domain) => {
+                let root = did::did_web(domain, &[]);
+                did == root || did.starts_with(&format!("{}:", root))
+            }
+            None => false,
+        }
+    }
+}
+
+#[async_trait]
+impl DidResolver for WebResolver {
+    async fn resolve_with_metadata(&self, did: &str) -> Result<ResolvedDid, AppError> {
+        did::did_web_url(did, false)?;
+
+        if self.is_hosted(did) {
+            return hosted_document(&self.db, &self.key_service, did).await?
+                .map(ResolvedDid::active)
+                .ok_or_else(|| AppError::NotFoundError(format!("DID not found: {}", did)));
+        }
+
+        self.client.resolve(did).await.map(ResolvedDid::active)
+    }
+}
+
+/// Convert a fetched DID document into the engine's document model.
+///
+/// Relative DID URLs are made absolute, embedded verification methods are
+/// moved into `verificationMethod`, and JWK keys are re-encoded as Multikey.
+/// Keys and services the engine cannot use are left out.
+fn parse_document(did: &str, value: &Value) -> Result<DidDocument, AppError> {
+    let id = value.get("id").and_then(|v| v.as_str())
+        .ok_or_else(|| AppError::SsiError(format!("DID document of {} has no id", did)))?;
+    if id != did {
+        return Err(AppError::SsiError(format!("DID document id {} does not match {}", id, did)));
+    }
+
+    let absolute = |reference: &str| {
+        if reference.starts_with('#') { format!("{}{}", did, reference) } else { reference.to_string() }
+    };
+
+    let mut document = DidDocument::new(did);
+    document.context = string_list(value.get("@context"));
+    if document.context.is_empty() {
+        document.context.push(DID_CONTEXT.to_string());
+    }
+    document.controller = string_list(value.get("controller"));
+
+    for entry in value.get("verificationMethod").and_then(|v| v.as_array()).into_iter().flatten() {
+        if let Some(method) = parse_method(did, entry, &absolute) {
+            document.verification_method.push(method);
+        }
+    }
+
+    for (name, relationship) in [
+        ("authentication", &mut document.authentication),
+        ("assertionMethod", &mut document.assertion_method),
+        ("keyAgreement", &mut document.key_agreement),
+    ] {
+        for entry in value.get(name).and_then(|v| v.as_array()).into_iter().flatten() {
+            match entry {
+                Value::String(reference) => relationship.push(absolute(reference)),
+                Value::Object(_) => {
+                    if let Some(method) = parse_method(did, entry, &absolute) {
+                        relationship.push(method.id.clone());
+                        document.verification_method.push(method);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for entry in value.get("service").and_then(|v| v.as_array()).into_iter().flatten() {
+        let service = (
+            entry.get("id").and_then(|v| v.as_str()),
+            entry.get("type").and_then(|v| v.as_str()),
+            entry.get("serviceEndpoint").and_then(|v| v.as_str()),
+        );
+        if let (Some(id), Some(service_type), Some(endpoint)) = service {
+            document.service.push(Service {
+                id: absolute(id),
+                service_type: service_type.to_string(),
+                service_endpoint: endpoint.to_string(),
+            });
+        }
+    }
+
+    Ok(document)
+}
+
+/// Parse a verification method, converting `publicKeyJwk` keys to Multikey
+fn parse_method(did: &str, entry: &Value, absolute: &impl Fn(&str) -> String) -> Option<VerificationMethod> {
+    let id = absolute(entry.get("id")?.as_str()?);
+    let controller = entry.get("controller").and_then(|v| v.as_str()).unwrap_or(did);
+
+    if let Some(jwk) = entry.get("publicKeyJwk") {
+        let public_key = PublicKey::from_jwk(jwk).ok()?;
+        return VerificationMethod::multikey(id, controller, &public_key).ok();
+    }
+
+    let mut method: VerificationMethod = serde_json::from_value(entry.clone()).ok()?;
+    method.id = id;
+    if method.public_key().is_err() {
+        method.bbs_public_key().ok()?;
+    }
+    Some(method)
+}
+
+/// Read a JSON string or array of strings as a list
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::String(s)) => vec![s.clone()],
+        Some(Value::Array(items)) => items.iter().filter_map(|v| v.as_str().map(str::to_string)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+    use serde_json::json;
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Ed25519 public key of RFC 8037, appendix A.2
+    const ED25519_X: &str = "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo";
+
+    /// Serve DID documents from a local listener, returning the DID of its root and the request count
+    fn serve() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let did = format!("did:web:127.0.0.1%3A{}", listener.local_addr().unwrap().port());
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let root = {
+            let did = did.clone();
+            move |State(requests): State<Arc<AtomicUsize>>| async move {
+                requests.fetch_add(1, Ordering::SeqCst);
+                Json(json!({
+                    "@context": [DID_CONTEXT],
+                    "id": did,
+                    "verificationMethod": [{
+                        "id": "#key-1",
+                        "type": "JsonWebKey2020",
+                        "publicKeyJwk": { "kty": "OKP", "crv": "Ed25519", "x": ED25519_X },
+                    }],
+                    "authentication": ["#key-1"],
+                    "assertionMethod": ["#key-1"],
+                    "service": [{ "id": "#site", "type": "LinkedDomains", "serviceEndpoint": "https://example.com" }],
+                }))
+            }
+        };
+        let user = {
+            let did = format!("{}:users:alice", did);
+            move |State(requests): State<Arc<AtomicUsize>>| async move {
+                requests.fetch_add(1, Ordering::SeqCst);
+                Json(json!({ "id": did }))
+            }
+        };
+        let impostor = |State(requests): State<Arc<AtomicUsize>>| async move {
+            requests.fetch_add(1, Ordering::SeqCst);
+            Json(json!({ "id": "did:web:example.com" }))
+        };
+
+        let app = Router::new()
+            .route("/.well-known/did.json", get(root))
+            .route("/users/alice/did.json", get(user))
+            .route("/users/mallory/did.json", get(impostor))
+            .route("/moved/did.json", get(|| async { (StatusCode::FOUND, [("location", "/.well-known/did.json")]) }))
+            .with_state(requests.clone());
+        let server = axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service());
+        tokio::spawn(server);
+
+        (did, requests)
+    }
+
+    fn client(cache_ttl: u64) -> DidWebClient {
+        DidWebClient::new(&DidWebConfig {
+            domain: None,
+            root_controller: None,
+            cache_ttl,
+            allow_http: true,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn maps_dids_to_document_urls() {
+        assert_eq!(did::did_web_url("did:web:example.com", false).unwrap(), "https://example.com/.well-known/did.json");
+        assert_eq!(
+            did::did_web_url("did:web:example.com:users:alice", false).unwrap(),
+            "https://example.com/users/alice/did.json"
+        );
+        assert_eq!(did::did_web_url("did:web:localhost%3A8443", true).unwrap(), "http://localhost:8443/.well-known/did.json");
+
+        for invalid in ["did:web:", "did:web:example.com:..:admin", "did:web:example.com::alice", "did:web:exa/mple.com", "did:web:host%3Aport"] {
+            assert!(did::did_web_url(invalid, false).is_err(), "{} should be rejected", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn resolves_documents_from_their_host() {
+        let (did, _) = serve();
+        let document = client(300).resolve(&did).await.unwrap();
+
+        let key_id = format!("{}#key-1", did);
+        assert_eq!(document.authentication, vec![key_id.clone()]);
+        assert_eq!(document.assertion_method, vec![key_id.clone()]);
+        let method = document.signing_key(&key_id).unwrap();
+        assert_eq!(method.method_type, "Multikey");
+        assert_eq!(method.public_key().unwrap().key_type, did::KeyType::Ed25519);
+        assert_eq!(document.service[0].id, format!("{}#site", did));
+
+        let user = client(300).resolve(&format!("{}:users:alice", did)).await.unwrap();
+        assert_eq!(user.id, format!("{}:users:alice", did));
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_mismatched_and_redirected_documents() {
+        let (did, _) = serve();
+        let client = client(300);
+
+        let missing = client.resolve(&format!("{}:users:bob", did)).await;
+        assert!(matches!(missing, Err(AppError::NotFoundError(_))));
+        assert!(client.resolve(&format!("{}:users:mallory", did)).await.is_err());
+        assert!(client.resolve(&format!("{}:moved", did)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn caches_documents_until_they_expire() {
+        let (did, requests) = serve();
+
+        let cached = client(300);
+        cached.resolve(&did).await.unwrap();
+        cached.resolve(&did).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let uncached = client(0);
+        uncached.resolve(&did).await.unwrap();
+        uncached.resolve(&did).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+}
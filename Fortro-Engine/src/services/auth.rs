@@ -1,42 +1,32 @@
 use crate::db::Database;
 use crate::error::AppError;
-use crate::models::User;
+use crate::models::{AuthChallenge, User, UserRole};
+use crate::services::lifecycle::DidLifecycleService;
+use crate::services::session::{SessionClaims, SessionService, SessionTokens, TokenUse};
 use crate::utils::crypto;
 use crate::utils::did::{self, DidKeyPair};
-use crate::utils::jwt::{self, JwtClaims, JwtHeader};
 use chrono::{Duration, Utc};
+use mongodb::bson::{self, doc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::sync::Arc;
-use uuid::Uuid;
+
+const AUTH_CHALLENGES: &str = "auth_challenges";
 
 /// Authentication service
 pub struct AuthService {
     db: Arc<Database>,
+    session_service: SessionService,
+    lifecycle: DidLifecycleService,
 }
 
 impl AuthService {
-    pub(crate) async fn verify_challenge(&self, did: &String, challenge: &String, signature: &String) -> Result<(User, String), AppError> {
-        // Get the user
-        let user = self.db.find_user_by_did(did).await?
-            .ok_or_else(|| AppError::AuthError(format!("User with DID {} not found", did)))?;
+    pub(crate) async fn verify_challenge(&self, did: &str, challenge: &str, signature: &str) -> Result<(User, SessionTokens), AppError> {
+        let user = self.answer_challenge(did, challenge, signature).await?;
 
-        // Verify the signature
-        let is_valid = did::verify(
-            challenge.as_bytes(),
-            &base64::decode(signature)
-                .map_err(|e| AppError::AuthError(format!("Invalid signature: {}", e)))?,
-            &user.public_key,
-        )?;
+        // Start a session
+        let tokens = self.session_service.create_session(&user)?;
 
-        if !is_valid {
-            return Err(AppError::AuthError("Invalid signature".to_string()));
-        }
-
-        // Generate a JWT token
-        let token = self.generate_token(&user)?;
-
-        Ok((user, token))
+        Ok((user, tokens))
     }
 }
 
@@ -55,15 +45,61 @@ pub struct RegisterRequest {
     pub public_key: String,
     pub name: Option<String>,
     pub email: Option<String>,
+    /// Additional roles requested at registration (issuer roles are granted by creating an issuer)
+    #[serde(default)]
+    pub roles: Vec<UserRole>,
+}
+
+/// Refresh request
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
 }
 
 /// Authentication response
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
-    pub token: String,
+    pub tokens: SessionTokens,
     pub user: User,
 }
 
+/// Identity of an authenticated caller, stored in the request extensions
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    pub did: String,
+    pub roles: Vec<UserRole>,
+    pub session: SessionClaims,
+}
+
+impl AuthContext {
+    /// Check whether the caller has a role
+    pub fn has_role(&self, role: &UserRole) -> bool {
+        self.roles.contains(role)
+    }
+
+    /// Reject the call unless the caller has a role
+    pub fn require_role(&self, role: &UserRole) -> Result<(), AppError> {
+        if !self.has_role(role) {
+            return Err(AppError::AccessDeniedError(format!(
+                "DID {} does not have the {:?} role",
+                self.did, role
+            )));
+        }
+        Ok(())
+    }
+
+    /// Reject the call unless it is made by the given DID
+    pub fn ensure_did(&self, did: &str) -> Result<(), AppError> {
+        if self.did != did {
+            return Err(AppError::AccessDeniedError(format!(
+                "Authenticated DID {} cannot act on behalf of {}",
+                self.did, did
+            )));
+        }
+        Ok(())
+    }
+}
+
 /// Challenge response
 #[derive(Debug, Serialize)]
 pub struct ChallengeResponse {
@@ -73,8 +109,8 @@ pub struct ChallengeResponse {
 
 impl AuthService {
     /// Create a new authentication service
-    pub fn new(db: Arc<Database>) -> Self {
-        Self { db }
+    pub fn new(db: Arc<Database>, session_service: SessionService, lifecycle: DidLifecycleService) -> Self {
+        Self { db, session_service, lifecycle }
     }
 
     /// Generate a challenge for authentication
@@ -84,20 +120,87 @@ impl AuthService {
         if user.is_none() {
             return Err(AppError::AuthError(format!("User with DID {} not found", did)));
         }
+        self.ensure_active(did).await?;
+
+        // Drop challenges that can no longer be answered
+        self.purge_expired_challenges().await?;
 
-        // Generate a random challenge
+        // Generate a random challenge and remember who it was issued to
         let challenge = crypto::generate_secure_string(32);
         let expires_at = Utc::now() + Duration::minutes(5);
 
+        let record = AuthChallenge::new(did.to_string(), challenge.clone(), expires_at);
+        self.db.insert_one(AUTH_CHALLENGES, &record).await?;
+
         Ok(ChallengeResponse { challenge, expires_at })
     }
 
+    /// Delete expired challenges
+    pub async fn purge_expired_challenges(&self) -> Result<u64, AppError> {
+        self.db
+            .delete_many(AUTH_CHALLENGES, doc! { "expires_at": { "$lt": Utc::now().timestamp() } })
+            .await
+    }
+
+    /// Check a signed challenge and consume it so it cannot be replayed
+    async fn answer_challenge(&self, did: &str, challenge: &str, signature: &str) -> Result<User, AppError> {
+        // Get the user
+        let user = self.db.find_user_by_did(did).await?
+            .ok_or_else(|| AppError::AuthError(format!("User with DID {} not found", did)))?;
+        self.ensure_active(did).await?;
+
+        // The challenge must have been issued to this DID, and still be open
+        let open_challenge = doc! {
+            "did": did,
+            "challenge": challenge,
+            "consumed": false,
+            "expires_at": { "$gte": Utc::now().timestamp() },
+        };
+
+        let issued = self.db.count_documents(AUTH_CHALLENGES, open_challenge.clone()).await?;
+        if issued == 0 {
+            return Err(AppError::AuthError("Challenge is unknown, expired or already used".to_string()));
+        }
+
+        // Verify the signature
+        let is_valid = did::verify_did_signature(
+            &user.did,
+            &user.public_key,
+            challenge.as_bytes(),
+            &base64::decode(signature)
+                .map_err(|e| AppError::AuthError(format!("Invalid signature: {}", e)))?,
+        )?;
+
+        if !is_valid {
+            return Err(AppError::AuthError("Invalid signature".to_string()));
+        }
+
+        // Consume the challenge; only one concurrent answer can win this update
+        let consumed = self.db
+            .update_one(
+                AUTH_CHALLENGES,
+                open_challenge,
+                doc! { "$set": { "consumed": true, "consumed_at": bson::to_bson(&Utc::now())? } },
+            )
+            .await?;
+
+        if !consumed {
+            return Err(AppError::AuthError("Challenge is unknown, expired or already used".to_string()));
+        }
+
+        Ok(user)
+    }
+
     /// Register a new user
     pub async fn register(&self, request: RegisterRequest) -> Result<User, AppError> {
         // Check if the DID is valid
         if !did::validate_did(&request.did) {
             return Err(AppError::ValidationError("Invalid DID".to_string()));
         }
+        // Login checks signatures against the DID's own key, which only these methods carry
+        if !request.did.starts_with("did:alyra:") && !request.did.starts_with("did:key:") {
+            return Err(AppError::ValidationError("Only did:alyra and did:key DIDs can register".to_string()));
+        }
 
         // Check if the user already exists
         let existing_user = self.db.find_user_by_did(&request.did).await?;
@@ -108,11 +211,22 @@ impl AuthService {
             )));
         }
 
+        // A did:key DID is its own public key
+        let public_key = match did::did_key_public_key(&request.did) {
+            Ok(key) => bs58::encode(key.bytes).into_string(),
+            Err(_) => request.public_key,
+        };
+
         // Create a new user
-        let mut user = User::new(request.did, request.public_key);
+        let mut user = User::new(request.did, public_key);
         user.name = request.name;
         user.email = request.email;
 
+        // Holders and verifiers can self-register; the issuer role is granted by creating an issuer
+        if request.roles.contains(&UserRole::Verifier) {
+            user.roles.push(UserRole::Verifier);
+        }
+
         // Save the user to the database
         self.db.create_user(&user).await?;
 
@@ -121,72 +235,71 @@ impl AuthService {
 
     /// Login a user
     pub async fn login(&self, request: LoginRequest) -> Result<AuthResponse, AppError> {
-        // Get the user
-        let user = self.db.find_user_by_did(&request.did).await?
-            .ok_or_else(|| AppError::AuthError(format!("User with DID {} not found", request.did)))?;
+        let user = self.answer_challenge(&request.did, &request.challenge, &request.signature).await?;
 
-        // Verify the signature
-        let is_valid = did::verify(
-            request.challenge.as_bytes(),
-            &base64::decode(&request.signature)
-                .map_err(|e| AppError::AuthError(format!("Invalid signature: {}", e)))?,
-            &user.public_key,
-        )?;
+        // Start a session
+        let tokens = self.session_service.create_session(&user)?;
 
-        if !is_valid {
-            return Err(AppError::AuthError("Invalid signature".to_string()));
-        }
+        Ok(AuthResponse { tokens, user })
+    }
+
+    /// Exchange a refresh token for a new token pair in the same session
+    pub async fn refresh(&self, request: RefreshRequest) -> Result<AuthResponse, AppError> {
+        let session = self.session_service.rotate(&request.refresh_token).await?;
+        self.ensure_active(&session.did).await?;
+
+        let user = self.db.find_user_by_did(&session.did).await?
+            .ok_or_else(|| AppError::AuthError(format!("User with DID {} not found", session.did)))?;
 
-        // Generate a JWT token
-        let token = self.generate_token(&user)?;
+        let tokens = self.session_service.resume_session(&user, &session.session_id)?;
 
-        Ok(AuthResponse { token, user })
+        Ok(AuthResponse { tokens, user })
     }
 
-    /// Generate a JWT token for a user
-    pub fn generate_token(&self, user: &User) -> Result<String, AppError> {
-        let now = Utc::now();
-        let expires_at = now + Duration::hours(24);
+    /// Log out, revoking the access token and its session's refresh tokens
+    pub async fn logout(&self, context: &AuthContext) -> Result<(), AppError> {
+        self.session_service.end_session(&context.session).await
+    }
 
-        let header = JwtHeader {
-            alg: "HS256".to_string(),
-            typ: "JWT".to_string(),
-            kid: format!("{}#auth", user.did),
-        };
+    /// Get a user from an access token
+    pub async fn get_user_from_token(&self, token: &str) -> Result<(User, SessionClaims), AppError> {
+        let session = self.session_service.verify(token, TokenUse::Access).await?;
 
-        let mut claims = JwtClaims {
-            iss: "ssi-wallet".to_string(),
-            sub: Some(user.did.clone()),
-            aud: None,
-            exp: Some(expires_at.timestamp()),
-            nbf: Some(now.timestamp()),
-            iat: now.timestamp(),
-            jti: Uuid::new_v4().to_string(),
-            additional_claims: HashMap::new(),
-        };
+        let user = self.db.find_user_by_did(&session.did).await?
+            .ok_or_else(|| AppError::AuthError(format!("User with DID {} not found", session.did)))?;
 
-        claims.additional_claims.insert("name".to_string(), serde_json::to_value(user.name.clone()).unwrap());
-        claims.additional_claims.insert("email".to_string(), serde_json::to_value(user.email.clone()).unwrap());
+        Ok((user, session))
+    }
 
-        // In a real implementation, we would use a proper signing key
-        // For this example, we'll use a dummy key
-        let private_key = "dummy_key";
-        jwt::create_jwt(&header, &claims, private_key)
+    /// Authenticate a bearer token and build the caller's context
+    pub async fn authenticate(&self, token: &str) -> Result<AuthContext, AppError> {
+        let (user, session) = self.get_user_from_token(token).await?;
+        // Sessions opened before a deactivation stop working with it
+        self.ensure_active(&user.did).await?;
+
+        Ok(AuthContext {
+            did: user.did,
+            roles: user.roles,
+            session,
+        })
     }
 
-    /// Verify a JWT token
-    pub fn verify_token(&self, token: &str) -> Result<JwtClaims, AppError> {
-        let (_, claims) = jwt::verify_pq_jwt(token)?;
-        Ok(claims)
+    /// Refuse authentication as a deactivated DID
+    async fn ensure_active(&self, did: &str) -> Result<(), AppError> {
+        self.lifecycle.ensure_active(did).await
+            .map_err(|_| AppError::AuthError(format!("DID {} is deactivated", did)))
     }
 
-    /// Get a user from a JWT token
-    pub async fn get_user_from_token(&self, token: &str) -> Result<User, AppError> {
-        let claims = self.verify_token(token)?;
-        let did = claims.sub.ok_or_else(|| AppError::AuthError("Token missing subject".to_string()))?;
+    /// Grant a role to a user
+    pub async fn grant_role(&self, did: &str, role: UserRole) -> Result<User, AppError> {
+        let mut user = self.db.find_user_by_did(did).await?
+            .ok_or_else(|| AppError::NotFoundError(format!("User with DID {} not found", did)))?;
 
-        let user = self.db.find_user_by_did(&did).await?
-            .ok_or_else(|| AppError::AuthError(format!("User with DID {} not found", did)))?;
+        if !user.has_role(&role) {
+            user.roles.push(role);
+            user.updated_at = Utc::now();
+            self.db.update_user(&user).await?;
+        }
 
         Ok(user)
     }
@@ -200,11 +313,11 @@ impl AuthService {
     pub async fn create_did_document(&self, request: GenerateDIDRequest) -> Result<DidKeyPair, AppError> {
         // If a private key is provided, use it to generate the DID
         if let Some(private_key) = request.private_key {
-            return did::did_from_private_key(&private_key);
+            return did::did_key_from_private_key(request.key_type, &private_key);
         }
 
-        // Otherwise, generate a new DID
-        did::generate_did()
+        // Otherwise, generate a new DID: did:alyra for Dilithium2, did:key for the others
+        did::generate_did_key(request.key_type)
     }
 }
 
@@ -212,4 +325,7 @@ impl AuthService {
 #[derive(Debug, Deserialize)]
 pub struct GenerateDIDRequest {
     pub private_key: Option<String>,
+    /// `dilithium2` (default, did:alyra), `ed25519` or `p256` (did:key)
+    #[serde(default)]
+    pub key_type: did::KeyType,
 }
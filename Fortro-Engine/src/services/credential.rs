@@ -2,8 +2,16 @@ use crate::blockchain::EthereumClient;
 use crate::db::Database;
 use crate::error::AppError;
 use crate::ipfs::IpfsClient;
-use crate::models::{Credential, CredentialStatus};
-use crate::utils::{crypto, did, jwt, zk_proofs};
+use crate::models::{Credential, CredentialStatus, IssuerKey, LinkSecretCommitment, RevocationReason, RevocationRecord, Schema, StatusPurpose, VcVersion};
+use crate::resolver::DidResolver;
+use crate::services::key::KeyService;
+use crate::services::lifecycle::DidLifecycleService;
+use crate::services::status_list::StatusListService;
+use crate::utils::bbs_credential;
+use crate::utils::data_integrity::{self, Cryptosuite};
+use crate::utils::mdoc;
+use crate::utils::sd_jwt::{self, SdJwt};
+use crate::utils::{crypto, did, jcs, jwt, link_secret, status_list, zk_proofs};
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -11,11 +19,24 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// `credentialSchema` type of schemas whose hash is registered in the SSIRegistry contract
+const SCHEMA_TYPE: &str = "SSIRegistrySchema";
+
+/// `credentialStatus` type of credentials whose JWT hash is registered in the SSIRegistry contract
+const STATUS_TYPE: &str = "SSIRegistryStatus";
+
+/// Collection of the revocation records of issued credentials
+const REVOCATIONS: &str = "revocations";
+
 /// Credential service
 pub struct CredentialService {
     db: Arc<Database>,
     ipfs: Arc<IpfsClient>,
     blockchain: Arc<EthereumClient>,
+    key_service: KeyService,
+    resolver: Arc<dyn DidResolver>,
+    lifecycle: DidLifecycleService,
+    status_lists: StatusListService,
 }
 
 /// Issue credential request
@@ -26,18 +47,40 @@ pub struct IssueCredentialRequest {
     pub subject_did: String,
     pub attributes: HashMap<String, Value>,
     pub expiration_date: Option<DateTime<Utc>>,
+    /// Data model version; defaults to the schema's
+    #[serde(default)]
+    pub vc_version: Option<VcVersion>,
+    /// Secure the credential with an embedded Data Integrity proof instead of as a JWT
+    #[serde(default)]
+    pub cryptosuite: Option<Cryptosuite>,
+    /// Issue an SD-JWT VC whose attributes the holder can disclose one by one
+    #[serde(default)]
+    pub selective_disclosure: bool,
+    /// Sign a VCDM 2.0 credential with BBS, from which the holder derives unlinkable proofs
+    #[serde(default)]
+    pub bbs: bool,
+    /// Commitment to the holder's link secret, made by their wallet, to bind the credential to
+    #[serde(default)]
+    pub link_secret_commitment: Option<LinkSecretCommitment>,
 }
 
 /// Verify credential request
 #[derive(Debug, Deserialize)]
 pub struct VerifyCredentialRequest {
+    /// Credential JWT, SD-JWT or base64url mdoc, or a Data Integrity credential serialized as JSON
+    #[serde(default)]
     pub credential_jwt: String,
+    /// Data Integrity credential, as an alternative to `credential_jwt`
+    #[serde(default)]
+    pub credential: Option<Value>,
 }
 
 /// Revoke credential request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct RevokeCredentialRequest {
-    pub credential_id: String,
+    #[serde(default)]
+    pub reason_code: RevocationReason,
+    /// Free-text explanation, shown to the holder and to verifiers
     pub reason: Option<String>,
 }
 
@@ -45,7 +88,11 @@ pub struct RevokeCredentialRequest {
 #[derive(Debug, Serialize)]
 pub struct CredentialResponse {
     pub credential: Credential,
+    /// Secured credential: the JWT, the SD-JWT with all its disclosures, the base64url `IssuerSigned` of an mdoc, or the JCS serialization of a Data Integrity credential
     pub jwt: String,
+    /// Data Integrity credential, when one was issued
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document: Option<Value>,
 }
 
 /// Verification result
@@ -60,6 +107,10 @@ pub struct VerificationResult {
     pub expiration_date: Option<DateTime<Utc>>,
     pub is_expired: bool,
     pub is_revoked: bool,
+    pub is_suspended: bool,
+    /// Why the credential was revoked, when its issuer revoked it on this engine
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revocation: Option<RevocationRecord>,
 }
 
 impl CredentialService {
@@ -68,55 +119,200 @@ impl CredentialService {
         db: Arc<Database>,
         ipfs: Arc<IpfsClient>,
         blockchain: Arc<EthereumClient>,
+        key_service: KeyService,
+        resolver: Arc<dyn DidResolver>,
+        lifecycle: DidLifecycleService,
+        status_lists: StatusListService,
     ) -> Self {
         Self {
             db,
             ipfs,
             blockchain,
+            key_service,
+            resolver,
+            lifecycle,
+            status_lists,
         }
     }
 
-    /// Issue a new credential (simplified version for API)
+    /// Issue a new credential signed with the issuer's managed key
     pub async fn issue_credential(
         &self,
         issuer_did: &str,
         request: IssueCredentialRequest,
     ) -> Result<CredentialResponse, AppError> {
-        // In a real implementation, we would retrieve the issuer's private key from a secure storage
-        // For now, we'll use a dummy private key for demonstration purposes
-        let issuer_private_key = "dummy_private_key";
+        // Look up the issuer's managed signing key
+        let issuer_key = self.key_service.signing_key(issuer_did).await?;
 
         // Call the full implementation
-        self.issue_credential_with_key(issuer_did, issuer_private_key, request).await
+        self.issue_credential_with_key(issuer_did, &issuer_key, request).await
     }
 
     /// Issue a new credential (full implementation with private key)
     pub async fn issue_credential_with_key(
         &self,
         issuer_did: &str,
-        issuer_private_key: &str,
+        issuer_key: &IssuerKey,
         request: IssueCredentialRequest,
     ) -> Result<CredentialResponse, AppError> {
-        // Enforce issuer DID uses did:alyra
-        if !did::validate_did(issuer_did) {
-            return Err(AppError::ValidationError("Invalid issuer DID: only did:alyra is supported".to_string()));
+        // Enforce issuer DID uses did:alyra or did:web, the methods of engine-managed issuers
+        let managed_method = issuer_did.starts_with("did:alyra:") || issuer_did.starts_with("did:web:");
+        if !managed_method || !did::validate_did(issuer_did) {
+            return Err(AppError::ValidationError("Invalid issuer DID: only did:alyra and did:web are supported".to_string()));
         }
         // Validate the subject DID
         if !did::validate_did(&request.subject_did) {
             return Err(AppError::ValidationError("Invalid subject DID".to_string()));
         }
+        if issuer_key.did != issuer_did {
+            return Err(AppError::ValidationError(format!("Key {} does not belong to issuer {}", issuer_key.key_id, issuer_did)));
+        }
+        // Deactivated DIDs can neither issue nor receive credentials
+        self.lifecycle.ensure_active(issuer_did).await?;
+        self.lifecycle.ensure_active(&request.subject_did).await?;
 
-        // Create a credential JWT
-        let jwt = jwt::create_pq_credential_jwt(
-            issuer_did,
-            &request.subject_did,
-            json!(request.attributes),
-            issuer_private_key.as_bytes(),
-            "dummy_public_key".as_bytes(),
-            request.expiration_date.map(|date| (date - Utc::now()).num_seconds()),
-        )?;
-
-        // Create a credential object
+        // Pick the data model version: the request's, else the schema's; BBS credentials are always VCDM 2.0
+        let schema = self.db.find_one::<Schema>("schemas", mongodb::bson::doc! { "id": &request.schema_id }).await?;
+        let version = match (request.bbs, request.vc_version) {
+            (true, Some(VcVersion::V1_1)) => {
+                return Err(AppError::ValidationError("BBS credentials are only issued as VCDM 2.0".to_string()));
+            }
+            (true, _) => VcVersion::V2_0,
+            (false, version) => version
+                .or_else(|| schema.as_ref().map(|schema| schema.vc_version))
+                .unwrap_or_default(),
+        };
+        let mdoc_profile = schema.as_ref().and_then(|schema| schema.mdoc.clone());
+
+        let mut draft = jwt::CredentialDraft {
+            id: Uuid::new_v4().to_string(),
+            version,
+            credential_type: request.credential_type.clone(),
+            subject_did: request.subject_did.clone(),
+            attributes: request.attributes.clone(),
+            schema: None,
+            status: None,
+            expiration_seconds: request.expiration_date.map(|date| (date - Utc::now()).num_seconds()),
+            commitments: None,
+            link_secret_commitment: None,
+        };
+
+        // Sign commitments to numeric attributes, for the holder's predicate proofs;
+        // BBS credentials and mdocs disclose attributes selectively instead
+        let commitments = if request.bbs || mdoc_profile.is_some() {
+            HashMap::new()
+        } else {
+            zk_proofs::commit_attributes(&request.attributes)
+        };
+        if !commitments.is_empty() {
+            let signed: HashMap<&String, &String> = commitments.iter()
+                .map(|(name, commitment)| (name, &commitment.commitment))
+                .collect();
+            draft.commitments = Some(json!(signed));
+        }
+
+        // Bind the credential to the holder's link secret through the commitment their wallet sent,
+        // once the wallet has proven it can open it
+        if let Some(commitment) = &request.link_secret_commitment {
+            if request.bbs || mdoc_profile.is_some() {
+                return Err(AppError::ValidationError(
+                    "BBS credentials and mdocs cannot be bound to a link secret".to_string(),
+                ));
+            }
+            link_secret::verify_commitment(commitment, issuer_did, &request.subject_did)?;
+            draft.link_secret_commitment = Some(commitment.commitment.clone());
+        }
+
+        // VCDM 2.0 credentials link their schema and where their status is kept
+        if version == VcVersion::V2_0 {
+            let schema = schema.ok_or_else(|| {
+                AppError::NotFoundError(format!("Schema with ID {} not found", request.schema_id))
+            })?;
+            draft.schema = Some(json!({ "id": schema.id, "type": SCHEMA_TYPE }));
+            draft.status = Some(self.registry_status().await?);
+        }
+
+        // Reserve the credential's bits in the issuer's status lists. BBS proofs would be
+        // linkable by their index, and mdocs do not carry a credentialStatus.
+        let status_entries = if request.bbs || mdoc_profile.is_some() {
+            Vec::new()
+        } else {
+            self.status_lists.allocate(issuer_did).await?
+        };
+        if !status_entries.is_empty() {
+            draft.status = Some(self.status_lists.credential_status(&status_entries).await?);
+        }
+
+        // Secure the credential as a JWT, an SD-JWT, an mdoc, or with an embedded proof
+        let credential_id = draft.id.clone();
+        if request.bbs && (mdoc_profile.is_some() || request.cryptosuite.is_some() || request.selective_disclosure) {
+            return Err(AppError::ValidationError(
+                "BBS credentials cannot also be mdocs, SD-JWT VCs or carry another Data Integrity proof".to_string(),
+            ));
+        }
+        let (jwt, document) = match (mdoc_profile, request.cryptosuite, request.selective_disclosure) {
+            (Some(_), Some(_), _) | (Some(_), None, true) => {
+                return Err(AppError::ValidationError(
+                    "Credentials of mdoc schemas are signed with COSE and are already selectively disclosable".to_string(),
+                ));
+            }
+            (Some(profile), None, false) => {
+                let draft = mdoc::MdocDraft {
+                    doc_type: profile.doc_type,
+                    namespace: profile.namespace,
+                    elements: request.attributes.clone(),
+                    holder_did: request.subject_did.clone(),
+                    valid_until: request.expiration_date,
+                };
+                let issuer_signed = mdoc::create_issuer_signed_with_store(
+                    &issuer_key.key_id,
+                    &draft,
+                    self.key_service.keystore(),
+                    &issuer_key.key_handle,
+                ).await?;
+                (issuer_signed, None)
+            }
+            (None, Some(_), true) => {
+                return Err(AppError::ValidationError("An SD-JWT VC cannot also carry a Data Integrity proof".to_string()));
+            }
+            (None, Some(cryptosuite), false) => {
+                let document = self.create_data_integrity_credential(issuer_did, issuer_key, &draft, cryptosuite).await?;
+                (jcs::canonicalize(&document)?, Some(document))
+            }
+            (None, None, true) => {
+                let sd_jwt = sd_jwt::create_sd_jwt_vc_with_store(
+                    issuer_did,
+                    &issuer_key.key_id,
+                    &draft,
+                    self.key_service.keystore(),
+                    &issuer_key.key_handle,
+                ).await?;
+                (sd_jwt, None)
+            }
+            (None, None, false) if request.bbs => {
+                let bbs_key = self.key_service.bbs_key(issuer_did).await?;
+                let document = bbs_credential::create_credential_with_store(
+                    issuer_did,
+                    &bbs_key.key_id,
+                    &draft,
+                    self.key_service.keystore(),
+                    &bbs_key.key_handle,
+                ).await?;
+                (jcs::canonicalize(&document)?, Some(document))
+            }
+            (None, None, false) => {
+                let jwt = jwt::create_pq_credential_jwt_with_store(
+                    issuer_did,
+                    &issuer_key.key_id,
+                    draft,
+                    self.key_service.keystore(),
+                    &issuer_key.key_handle,
+                ).await?;
+                (jwt, None)
+            }
+        };
+
+        // Create a credential object, under the same ID as the signed credential
         let mut credential = Credential::new(
             issuer_did.to_string(),
             request.subject_did.clone(),
@@ -126,9 +322,17 @@ impl CredentialService {
             jwt.clone(),
         );
 
+        credential.id = credential_id;
+
         // Set expiration date if provided
         credential.expires_at = request.expiration_date;
 
+        // Keep the blinding factors opening the signed commitments
+        credential.commitment_blindings = commitments.into_iter()
+            .map(|(name, commitment)| (name, commitment.blinding))
+            .collect();
+        credential.status_entries = status_entries;
+
         // Store sensitive data in IPFS
         let encryption_key = crypto::generate_key();
         let ipfs_hash = self
@@ -139,7 +343,7 @@ impl CredentialService {
         credential.ipfs_hash = Some(ipfs_hash.clone());
 
         // Store credential hash on blockchain
-        let credential_hash = crypto::hash_to_hex(jwt.as_bytes());
+        let credential_hash = registry_hash(&jwt)?;
         let tx_hash = self
             .blockchain
             .register_credential(issuer_did, &credential_hash, &ipfs_hash)
@@ -153,9 +357,54 @@ impl CredentialService {
         Ok(CredentialResponse {
             credential,
             jwt,
+            document,
         })
     }
 
+    /// Sign a credential with an embedded Data Integrity proof made with the issuer key
+    async fn create_data_integrity_credential(
+        &self,
+        issuer_did: &str,
+        issuer_key: &IssuerKey,
+        draft: &jwt::CredentialDraft,
+        cryptosuite: Cryptosuite,
+    ) -> Result<Value, AppError> {
+        // Managed keys are Dilithium2, so they can only sign the Dilithium cryptosuite
+        if issuer_key.algorithm != format!("{:?}", cryptosuite.key_type()) {
+            return Err(AppError::ValidationError(format!(
+                "Key {} is a {} key and cannot sign {} proofs",
+                issuer_key.key_id, issuer_key.algorithm, cryptosuite.as_str()
+            )));
+        }
+
+        let mut document = jwt::credential_document(issuer_did, draft, Utc::now())?;
+
+        // The VCDM 1.1 context does not define DataIntegrityProof
+        if draft.version == VcVersion::V1_1 {
+            if let Some(contexts) = document["@context"].as_array_mut() {
+                contexts.push(json!(data_integrity::DATA_INTEGRITY_CONTEXT));
+            }
+        }
+
+        let (proof_config, hash_data) = data_integrity::prepare_proof(&document, cryptosuite, &issuer_key.key_id, Utc::now())?;
+        let signature = self.key_service.keystore().sign(&issuer_key.key_handle, &hash_data).await?;
+
+        data_integrity::attach_proof(document, proof_config, &signature)
+    }
+
+    /// `credentialStatus` entry naming the on-chain registry where the credential's JWT hash is registered
+    async fn registry_status(&self) -> Result<Value, AppError> {
+        let registry = self.blockchain.registry_address_str()
+            .ok_or_else(|| AppError::BlockchainError("Registry address not set".to_string()))?;
+        let chain_id = self.blockchain.get_chain_id().await?;
+
+        Ok(json!({
+            "id": format!("eip155:{}:{}", chain_id, registry),
+            "type": STATUS_TYPE,
+            "statusPurpose": "revocation",
+        }))
+    }
+
     /// Verify a credential
     pub async fn verify_credential(
         &self,
@@ -164,8 +413,17 @@ impl CredentialService {
         let mut errors = Vec::new();
         let mut is_valid = true;
 
-        // Extract the credential from the JWT
-        let credential_data = match jwt::extract_credential(&request.credential_jwt) {
+        let secured = match &request.credential {
+            Some(document) => jcs::canonicalize(document)?,
+            None => request.credential_jwt,
+        };
+        let is_data_integrity = data_integrity::is_secured_document(&secured);
+        let is_sd_jwt = !is_data_integrity && SdJwt::is_sd_jwt(&secured);
+        let is_mdoc = mdoc::is_mdoc(&secured);
+        let mut is_derived_bbs = false;
+
+        // Extract the credential from the JWT or the secured document
+        let credential_data = match extract_credential(&secured) {
             Ok(data) => data,
             Err(e) => {
                 errors.push(format!("Failed to extract credential: {}", e));
@@ -179,6 +437,8 @@ impl CredentialService {
                     expiration_date: None,
                     is_expired: false,
                     is_revoked: false,
+                    is_suspended: false,
+                    revocation: None,
                 });
             }
         };
@@ -186,6 +446,7 @@ impl CredentialService {
         // Extract required fields
         let issuer_did = credential_data["issuer"]
             .as_str()
+            .or_else(|| credential_data["issuer"]["id"].as_str())
             .unwrap_or("")
             .to_string();
         let subject_did = credential_data["credentialSubject"]["id"]
@@ -199,8 +460,11 @@ impl CredentialService {
             .unwrap_or("VerifiableCredential")
             .to_string();
 
+        // VCDM 2.0 renamed the validity dates
         let issuance_date = match DateTime::parse_from_rfc3339(
-            credential_data["issuanceDate"].as_str().unwrap_or(""),
+            credential_data["validFrom"].as_str()
+                .or_else(|| credential_data["issuanceDate"].as_str())
+                .unwrap_or(""),
         ) {
             Ok(date) => date.with_timezone(&Utc),
             Err(_) => {
@@ -210,8 +474,9 @@ impl CredentialService {
             }
         };
 
-        let expiration_date = credential_data["expirationDate"]
+        let expiration_date = credential_data["validUntil"]
             .as_str()
+            .or_else(|| credential_data["expirationDate"].as_str())
             .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
             .map(|date| date.with_timezone(&Utc));
 
@@ -226,35 +491,164 @@ impl CredentialService {
             is_valid = false;
         }
 
-        // Verify the JWT signature
-        match jwt::verify_pq_jwt(&request.credential_jwt) {
-            Ok(_) => {}
-            Err(e) => {
-                errors.push(format!("JWT signature verification failed: {}", e));
-                is_valid = false;
+        // Issuer keys are checked at the credential's on-chain registration time, never at
+        // a signing time the credential asserts itself
+        let signed_at = if is_data_integrity && bbs_credential::is_bbs_document(&credential_data) {
+            None
+        } else {
+            match self.registered_at(&issuer_did, &secured).await {
+                Ok(signed_at) => signed_at,
+                Err(e) => {
+                    errors.push(format!("Failed to get the on-chain registration time: {}", e));
+                    is_valid = false;
+                    None
+                }
+            }
+        };
+
+        if is_data_integrity && bbs_credential::is_bbs_document(&credential_data) {
+            // Verify the BBS signature, or the holder's proof derived from it
+            match bbs_credential::verify(&credential_data, self.resolver.as_ref(), None).await {
+                Ok(proof) => {
+                    is_derived_bbs = proof.derived;
+                    // The BBS key is not rotated, so only its owner is checked
+                    let (key_did, _) = crate::resolver::split_did_url(&proof.verification_method);
+                    if key_did != issuer_did {
+                        errors.push(format!("Signing key check failed: {} is not a key of issuer {}", proof.verification_method, issuer_did));
+                        is_valid = false;
+                    }
+                }
+                Err(e) => {
+                    errors.push(format!("BBS proof verification failed: {}", e));
+                    is_valid = false;
+                }
+            }
+        } else if is_data_integrity {
+            // Verify the embedded proof
+            match data_integrity::verify_proof(&credential_data, self.resolver.as_ref(), signed_at).await {
+                Ok(proof) => {
+                    if let Err(e) = self.check_signing_key(&issuer_did, &proof.verification_method, signed_at).await {
+                        errors.push(format!("Signing key check failed: {}", e));
+                        is_valid = false;
+                    }
+                }
+                Err(e) => {
+                    errors.push(format!("Proof verification failed: {}", e));
+                    is_valid = false;
+                }
+            }
+        } else if is_sd_jwt {
+            // Verify the issuer signature, the disclosures and any key binding
+            match sd_jwt::verify(&secured, self.resolver.as_ref(), signed_at, None).await {
+                Ok(verified) => {
+                    if let Err(e) = self.check_signing_key(&issuer_did, &verified.header.kid, signed_at).await {
+                        errors.push(format!("Signing key check failed: {}", e));
+                        is_valid = false;
+                    }
+                }
+                Err(e) => {
+                    errors.push(format!("SD-JWT verification failed: {}", e));
+                    is_valid = false;
+                }
+            }
+        } else if is_mdoc {
+            // Verify the MSO signature, the element digests and the validity period
+            match mdoc::verify(&secured, self.resolver.as_ref(), signed_at, None).await {
+                Ok(verified) => {
+                    if let Err(e) = self.check_signing_key(&issuer_did, &verified.issuer_key_id, signed_at).await {
+                        errors.push(format!("Signing key check failed: {}", e));
+                        is_valid = false;
+                    }
+                }
+                Err(e) => {
+                    errors.push(format!("mdoc verification failed: {}", e));
+                    is_valid = false;
+                }
+            }
+        } else {
+            // Verify the JWT signature
+            match jwt::verify_did_jwt_signed_at(&secured, self.resolver.as_ref(), signed_at).await {
+                Ok((header, claims)) => {
+                    // Check the signing key against the issuer's key history
+                    let checked = if claims.iss != issuer_did {
+                        Err(AppError::SsiError(format!("JWT issuer {} does not match credential issuer {}", claims.iss, issuer_did)))
+                    } else {
+                        self.check_signing_key(&issuer_did, &header.kid, signed_at).await
+                    };
+                    if let Err(e) = checked {
+                        errors.push(format!("Signing key check failed: {}", e));
+                        is_valid = false;
+                    }
+                }
+                Err(e) => {
+                    errors.push(format!("JWT signature verification failed: {}", e));
+                    is_valid = false;
+                }
             }
         }
 
-        // Check if the credential is revoked on the blockchain
-        let credential_hash = crypto::hash_to_hex(request.credential_jwt.as_bytes());
-        let is_valid_on_chain = match self
-            .blockchain
-            .is_credential_registered(&issuer_did, &credential_hash)
-            .await
-        {
-            Ok(valid) => valid,
+        // Check the credential's bits in its issuer's status lists; credentials without
+        // status list entries are checked in the on-chain registry instead. A derived BBS
+        // proof cannot be traced back to the registered credential by design.
+        let mut is_revoked = false;
+        let mut is_suspended = false;
+        match status_list::entries(&credential_data) {
+            Ok(entries) if !entries.is_empty() => {
+                for entry in entries {
+                    match self.check_status_entry(&issuer_did, &entry).await {
+                        Ok(true) if entry.purpose == StatusPurpose::Revocation => is_revoked = true,
+                        Ok(true) => is_suspended = true,
+                        Ok(false) => {}
+                        Err(e) => {
+                            errors.push(format!("Failed to check {} status: {}", entry.purpose.as_str(), e));
+                            is_valid = false;
+                        }
+                    }
+                }
+            }
+            Ok(_) if is_derived_bbs => {}
+            Ok(_) => {
+                let credential_hash = registry_hash(&secured)?;
+                match self
+                    .blockchain
+                    .is_credential_registered(&issuer_did, &credential_hash)
+                    .await
+                {
+                    Ok(true) => {}
+                    // An invalid credential is either suspended or revoked for good
+                    Ok(false) => match self.blockchain.is_credential_suspended(&issuer_did, &credential_hash).await {
+                        Ok(suspended) => {
+                            is_suspended = suspended;
+                            is_revoked = !suspended;
+                        }
+                        Err(e) => {
+                            errors.push(format!("Failed to check on-chain suspension: {}", e));
+                            is_valid = false;
+                        }
+                    },
+                    Err(e) => {
+                        errors.push(format!("Failed to check on-chain validity: {}", e));
+                        is_valid = false;
+                    }
+                }
+            }
             Err(e) => {
-                errors.push(format!("Failed to check on-chain validity: {}", e));
+                errors.push(format!("Invalid credential status: {}", e));
                 is_valid = false;
-                false
             }
-        };
-
-        let is_revoked = !is_valid_on_chain;
+        }
 
+        let mut revocation = None;
         if is_revoked {
             errors.push("Credential is revoked".to_string());
             is_valid = false;
+            if let Ok(credential_hash) = registry_hash(&secured) {
+                revocation = self.find_revocation(&credential_hash).await?;
+            }
+        }
+        if is_suspended {
+            errors.push("Credential is suspended".to_string());
+            is_valid = false;
         }
 
         Ok(VerificationResult {
@@ -267,15 +661,76 @@ impl CredentialService {
             expiration_date,
             is_expired,
             is_revoked,
+            is_suspended,
+            revocation,
         })
     }
 
+    /// Check that a credential was signed by its stated issuer, with a key the issuer held at signing time.
+    ///
+    /// `signed_at` is a trusted signing time; without one the key must be the current one.
+    /// The key history check only applies to issuers whose keys this engine
+    /// manages; for other issuers the resolved DID document is all there is.
+    async fn check_signing_key(
+        &self,
+        issuer_did: &str,
+        key_id: &str,
+        signed_at: Option<DateTime<Utc>>,
+    ) -> Result<(), AppError> {
+        let (key_did, _) = crate::resolver::split_did_url(key_id);
+        if key_did != issuer_did {
+            return Err(AppError::SsiError(format!("{} is not a key of issuer {}", key_id, issuer_did)));
+        }
+
+        if !self.key_service.manages(issuer_did).await? {
+            return Ok(());
+        }
+
+        let key = self.key_service.find_key(key_id).await?
+            .filter(|key| key.did == issuer_did)
+            .ok_or_else(|| AppError::SsiError(format!("{} is not a key of issuer {}", key_id, issuer_did)))?;
+
+        if !key.was_active_at(signed_at.unwrap_or_else(Utc::now).timestamp()) {
+            return Err(AppError::SsiError(format!("{} was not the issuer's signing key at issuance time", key.key_id)));
+        }
+
+        Ok(())
+    }
+
+    /// Time a credential was registered on chain by its issuer, `None` if it never was.
+    ///
+    /// This is the trusted issuance time the issuer's signing key is checked at.
+    pub async fn registered_at(&self, issuer_did: &str, secured: &str) -> Result<Option<DateTime<Utc>>, AppError> {
+        self.blockchain.credential_registered_at(issuer_did, &registry_hash(secured)?).await
+    }
+
+    /// Sign an issuer's status lists again, after a key rotation
+    pub async fn republish_status_lists(&self, issuer_did: &str) -> Result<(), AppError> {
+        self.status_lists.republish(issuer_did).await
+    }
+
+    /// Whether a credential's bit is set in one of its issuer's status lists
+    async fn check_status_entry(&self, issuer_did: &str, entry: &status_list::Entry) -> Result<bool, AppError> {
+        let list = self.status_lists.fetch(&entry.list_url).await?;
+        if list.issuer_did != issuer_did {
+            return Err(AppError::SsiError(format!("Status list {} is not issued by {}", entry.list_url, issuer_did)));
+        }
+        if list.purpose != entry.purpose {
+            return Err(AppError::SsiError(format!("Status list {} is not a {} list", entry.list_url, entry.purpose.as_str())));
+        }
+        self.check_signing_key(issuer_did, &list.key_id, list.signed_at).await?;
+
+        status_list::bit(&list.bits, entry.index)
+    }
+
     /// Revoke a credential (simplified version for API)
     pub async fn revoke_credential(
         &self,
         issuer_did: &str,
         credential_id: &str,
-    ) -> Result<Credential, AppError> {
+        revoked_by: &str,
+        request: RevokeCredentialRequest,
+    ) -> Result<(Credential, RevocationRecord), AppError> {
         // Get the credential
         let credential = self
             .get_credential_by_id(credential_id)
@@ -301,15 +756,8 @@ impl CredentialService {
             ));
         }
 
-        // In a real implementation, we would retrieve the issuer's private key from a secure storage
-        // For now, we'll use a dummy private key for demonstration purposes
-        let issuer_private_key = "dummy_private_key";
-
         // Call the full implementation
-        let request = RevokeCredentialRequest {
-            credential_id: credential_id.to_string(),
-            reason: None,
-        };
+        let revocation = self.revoke_credential_with_key(issuer_did, credential_id, revoked_by, request).await?;
 
         // Get the updated credential
         let updated_credential = self
@@ -322,24 +770,26 @@ impl CredentialService {
                 ))
             })?;
 
-        Ok(updated_credential)
+        Ok((updated_credential, revocation))
     }
 
     /// Revoke a credential (full implementation with private key)
     pub async fn revoke_credential_with_key(
         &self,
         issuer_did: &str,
+        credential_id: &str,
+        revoked_by: &str,
         request: RevokeCredentialRequest,
-    ) -> Result<bool, AppError> {
+    ) -> Result<RevocationRecord, AppError> {
         // Get the credential
         let credential = self
             .db
-            .find_credential_by_id(&request.credential_id)
+            .find_credential_by_id(credential_id)
             .await?
             .ok_or_else(|| {
                 AppError::NotFoundError(format!(
                     "Credential with ID {} not found",
-                    request.credential_id
+                    credential_id
                 ))
             })?;
 
@@ -350,20 +800,141 @@ impl CredentialService {
             ));
         }
 
-        // Revoke the credential on the blockchain
-        let credential_hash = crypto::hash_to_hex(credential.jwt.as_bytes());
-        self.blockchain
-            .revoke_credential(issuer_did, &credential_hash)
-            .await?;
+        // Revoke the credential on the blockchain, unless an interrupted attempt already did:
+        // the registry refuses to revoke twice, and a retry must still finish the steps below
+        let credential_hash = registry_hash(&credential.jwt)?;
+        let tx_hash = if self.blockchain.is_credential_revoked(issuer_did, &credential_hash).await? {
+            None
+        } else {
+            let tx_hash = self.blockchain
+                .revoke_credential(issuer_did, &credential_hash)
+                .await?;
+            Some(format!("{:?}", tx_hash))
+        };
+
+        // Set its bit in the issuer's revocation list; revocation supersedes a suspension
+        self.status_lists.set_status(&credential, StatusPurpose::Revocation, true).await?;
+        if credential.status == CredentialStatus::Suspended {
+            self.status_lists.set_status(&credential, StatusPurpose::Suspension, false).await?;
+        }
 
-        // Update the credential status in the database
-        let mut updated_credential = credential.clone();
+        // Record who revoked the credential, when and why, unless an interrupted attempt already did
+        let revocation = match self.find_revocation(&credential_hash).await? {
+            Some(revocation) => revocation,
+            None => {
+                let revocation = RevocationRecord {
+                    id: Uuid::new_v4().to_string(),
+                    credential_id: credential.id.clone(),
+                    credential_hash,
+                    issuer_did: issuer_did.to_string(),
+                    reason_code: request.reason_code,
+                    reason: request.reason.filter(|reason| !reason.trim().is_empty()),
+                    revoked_by: revoked_by.to_string(),
+                    revoked_at: Utc::now(),
+                    tx_hash,
+                };
+                self.db.insert_one(REVOCATIONS, &revocation).await?;
+                revocation
+            }
+        };
+
+        // Mark the credential revoked last, so a failed step above can be retried
+        let mut updated_credential = credential;
         updated_credential.status = CredentialStatus::Revoked;
         updated_credential.updated_at = Utc::now();
 
         self.db.save_credential(&updated_credential).await?;
 
-        Ok(true)
+        Ok(revocation)
+    }
+
+    /// Revocation record of a credential, found by its registry hash
+    pub async fn find_revocation(&self, credential_hash: &str) -> Result<Option<RevocationRecord>, AppError> {
+        self.db.find_one(REVOCATIONS, mongodb::bson::doc! { "credential_hash": credential_hash }).await
+    }
+
+    /// Revocation record of a stored credential, also found for a holder's imported copy
+    pub async fn credential_revocation(&self, credential: &Credential) -> Result<Option<RevocationRecord>, AppError> {
+        match registry_hash(&credential.jwt) {
+            Ok(credential_hash) => self.find_revocation(&credential_hash).await,
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Suspend a credential until its issuer reinstates it
+    pub async fn suspend_credential(
+        &self,
+        issuer_did: &str,
+        credential_id: &str,
+    ) -> Result<Credential, AppError> {
+        self.set_suspended(issuer_did, credential_id, true).await
+    }
+
+    /// Reinstate a suspended credential
+    pub async fn unsuspend_credential(
+        &self,
+        issuer_did: &str,
+        credential_id: &str,
+    ) -> Result<Credential, AppError> {
+        self.set_suspended(issuer_did, credential_id, false).await
+    }
+
+    /// Suspend or reinstate a credential on the blockchain, in its issuer's suspension list and in the database
+    async fn set_suspended(
+        &self,
+        issuer_did: &str,
+        credential_id: &str,
+        suspended: bool,
+    ) -> Result<Credential, AppError> {
+        // Step 1: Get the credential and check the issuer
+        let credential = self
+            .get_credential_by_id(credential_id)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFoundError(format!(
+                    "Credential with ID {} not found",
+                    credential_id
+                ))
+            })?;
+
+        if credential.issuer_did != issuer_did {
+            return Err(AppError::AccessDeniedError(
+                "Only the issuer can suspend or reinstate a credential".to_string(),
+            ));
+        }
+
+        // Step 2: Only active credentials can be suspended, and only suspended ones reinstated
+        let (expected, updated) = if suspended {
+            (CredentialStatus::Active, CredentialStatus::Suspended)
+        } else {
+            (CredentialStatus::Suspended, CredentialStatus::Active)
+        };
+        if credential.status != expected {
+            return Err(AppError::ValidationError(if suspended {
+                "Only an active credential can be suspended".to_string()
+            } else {
+                "Credential is not suspended".to_string()
+            }));
+        }
+
+        // Step 3: Update the on-chain registry
+        let credential_hash = registry_hash(&credential.jwt)?;
+        if suspended {
+            self.blockchain.suspend_credential(issuer_did, &credential_hash).await?;
+        } else {
+            self.blockchain.reinstate_credential(issuer_did, &credential_hash).await?;
+        }
+
+        // Step 4: Set or clear its bit in the issuer's suspension list
+        self.status_lists.set_status(&credential, StatusPurpose::Suspension, suspended).await?;
+
+        // Step 5: Update the credential status in the database
+        let mut updated_credential = credential;
+        updated_credential.status = updated;
+        updated_credential.updated_at = Utc::now();
+        self.db.save_credential(&updated_credential).await?;
+
+        Ok(updated_credential)
     }
 
     /// Get a credential by ID
@@ -388,6 +959,7 @@ impl CredentialService {
             let status_enum = match status.as_str() {
                 "active" => CredentialStatus::Active,
                 "revoked" => CredentialStatus::Revoked,
+                "suspended" => CredentialStatus::Suspended,
                 "expired" => CredentialStatus::Expired,
                 _ => return Err(AppError::ValidationError(format!("Invalid status: {}", status))),
             };
@@ -415,12 +987,12 @@ impl CredentialService {
         self.db.find_many("credentials", filter).await
     }
 
-    /// Create a selective disclosure proof for a credential
+    /// Create an SD-JWT revealing only some attributes of an SD-JWT VC, without key binding
     pub async fn create_selective_disclosure(
         &self,
         credential_id: &str,
         disclosed_attributes: &[String],
-    ) -> Result<HashMap<String, Value>, AppError> {
+    ) -> Result<SdJwt, AppError> {
         // Get the credential
         let credential = self
             .db
@@ -433,8 +1005,45 @@ impl CredentialService {
                 ))
             })?;
 
-        // Create a selective disclosure proof
-        zk_proofs::create_selective_disclosure(&credential.credential_data, disclosed_attributes)
+        if !SdJwt::is_sd_jwt(&credential.jwt) {
+            return Err(AppError::ValidationError(format!(
+                "Credential {} is not an SD-JWT VC and cannot be disclosed selectively",
+                credential_id
+            )));
+        }
+
+        SdJwt::parse(&credential.jwt)?.select(disclosed_attributes)
+    }
+
+    /// Derive an unlinkable proof from a BBS credential that discloses only some of its attributes.
+    ///
+    /// `verifier` and `nonce` bind the proof to one presentation.
+    pub async fn derive_bbs_proof(
+        &self,
+        credential_id: &str,
+        disclosed_attributes: &[String],
+        verifier: Option<&str>,
+        nonce: Option<&str>,
+    ) -> Result<Value, AppError> {
+        let credential = self
+            .db
+            .find_credential_by_id(credential_id)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFoundError(format!(
+                    "Credential with ID {} not found",
+                    credential_id
+                ))
+            })?;
+
+        let document = bbs_credential::parse(&credential.jwt).ok_or_else(|| {
+            AppError::ValidationError(format!(
+                "Credential {} is not a BBS credential and cannot derive proofs",
+                credential_id
+            ))
+        })?;
+
+        bbs_credential::derive_proof(&document, disclosed_attributes, verifier, nonce, self.resolver.as_ref()).await
     }
 
     /// Create a predicate proof for a credential attribute
@@ -457,48 +1066,92 @@ impl CredentialService {
                 ))
             })?;
 
-        // Get the attribute value
-        let attribute_value = credential
-            .credential_data
-            .get(attribute_name)
-            .ok_or_else(|| {
-                AppError::ValidationError(format!(
-                    "Attribute {} not found in credential",
-                    attribute_name
-                ))
-            })?;
-
-        // Convert the attribute value to a number
-        let attribute_number = match attribute_value {
-            Value::Number(n) => n.as_u64().ok_or_else(|| {
-                AppError::ValidationError(format!(
-                    "Attribute {} is not a positive number",
-                    attribute_name
-                ))
-            })?,
-            Value::String(s) => s.parse::<u64>().map_err(|_| {
-                AppError::ValidationError(format!(
-                    "Attribute {} cannot be converted to a number",
-                    attribute_name
-                ))
-            })?,
-            _ => {
-                return Err(AppError::ValidationError(format!(
-                    "Attribute {} is not a number or string",
-                    attribute_name
-                )))
-            }
-        };
+        let (attribute_value, commitment) = committed_attribute(&credential, attribute_name)?;
+        let attribute_number = zk_proofs::attribute_number(attribute_value).ok_or_else(|| {
+            AppError::ValidationError(format!(
+                "Attribute {} is not a positive number",
+                attribute_name
+            ))
+        })?;
 
         // Create a predicate proof
         zk_proofs::create_predicate_proof(
             attribute_name,
             attribute_number,
+            &commitment,
             predicate_type,
             predicate_value,
         )
     }
 
+    /// Create one aggregated range proof for several comparison predicates,
+    /// each given as credential ID, attribute name, predicate type and value
+    pub async fn create_aggregated_predicate_proof(
+        &self,
+        predicates: &[(&str, &str, &str, i64)],
+    ) -> Result<zk_proofs::AggregatedPredicateProof, AppError> {
+        // Open the signed commitment of each predicate's attribute
+        let mut openings = Vec::new();
+        for (credential_id, attribute_name, _, _) in predicates {
+            let credential = self
+                .db
+                .find_credential_by_id(credential_id)
+                .await?
+                .ok_or_else(|| {
+                    AppError::NotFoundError(format!(
+                        "Credential with ID {} not found",
+                        credential_id
+                    ))
+                })?;
+
+            let (attribute_value, commitment) = committed_attribute(&credential, attribute_name)?;
+            let attribute_number = zk_proofs::attribute_number(attribute_value).ok_or_else(|| {
+                AppError::ValidationError(format!(
+                    "Attribute {} is not a positive number",
+                    attribute_name
+                ))
+            })?;
+            openings.push((attribute_number, commitment));
+        }
+
+        let statements: Vec<zk_proofs::PredicateStatement> = predicates.iter().zip(&openings)
+            .map(|((_, attribute_name, predicate_type, predicate_value), (attribute_value, commitment))| {
+                zk_proofs::PredicateStatement {
+                    attribute_name,
+                    attribute_value: *attribute_value,
+                    commitment,
+                    predicate_type,
+                    predicate_value: *predicate_value,
+                }
+            })
+            .collect();
+        zk_proofs::create_aggregated_predicate_proof(&statements)
+    }
+
+    /// Create a proof that a credential attribute is (`in`) or is not (`not_in`) one of a set of values
+    pub async fn create_set_membership_proof(
+        &self,
+        credential_id: &str,
+        attribute_name: &str,
+        predicate_type: &str,
+        set: &[Value],
+    ) -> Result<zk_proofs::PredicateProof, AppError> {
+        // Get the credential
+        let credential = self
+            .db
+            .find_credential_by_id(credential_id)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFoundError(format!(
+                    "Credential with ID {} not found",
+                    credential_id
+                ))
+            })?;
+
+        let (attribute_value, commitment) = committed_attribute(&credential, attribute_name)?;
+        zk_proofs::create_set_membership_proof(attribute_name, attribute_value, &commitment, predicate_type, set)
+    }
+
     /// Delete a credential
     pub async fn delete_credential(&self, owner_did: &str, credential_id: &str) -> Result<bool, AppError> {
         // Get the credential
@@ -524,3 +1177,60 @@ impl CredentialService {
         self.db.delete_credential(credential_id, owner_did).await
     }
 }
+
+/// Value of a credential attribute, with the commitment the issuer signed for it and its opening
+fn committed_attribute<'a>(
+    credential: &'a Credential,
+    attribute_name: &str,
+) -> Result<(&'a Value, zk_proofs::AttributeCommitment), AppError> {
+    let attribute_value = credential.credential_data.get(attribute_name).ok_or_else(|| {
+        AppError::ValidationError(format!("Attribute {} not found in credential", attribute_name))
+    })?;
+
+    let signed_commitment = extract_credential(&credential.jwt)?[zk_proofs::COMMITMENTS_PROPERTY][attribute_name]
+        .as_str()
+        .map(str::to_string);
+    match (signed_commitment, credential.commitment_blindings.get(attribute_name)) {
+        (Some(commitment), Some(blinding)) => Ok((attribute_value, zk_proofs::AttributeCommitment {
+            commitment,
+            blinding: blinding.clone(),
+        })),
+        _ => Err(AppError::ValidationError(format!(
+            "Credential {} has no signed commitment to attribute {}",
+            credential.id, attribute_name
+        ))),
+    }
+}
+
+/// Extract the credential from a secured credential without verifying it: a JWT, an SD-JWT, an mdoc, or a Data Integrity credential
+pub fn extract_credential(secured: &str) -> Result<Value, AppError> {
+    if data_integrity::is_secured_document(secured) {
+        return Ok(serde_json::from_str(secured)?);
+    }
+    if SdJwt::is_sd_jwt(secured) {
+        return sd_jwt::extract_credential(secured);
+    }
+    if mdoc::is_mdoc(secured) {
+        return mdoc::extract_credential(secured);
+    }
+    jwt::extract_credential(secured)
+}
+
+/// Hash under which a secured credential is registered on chain.
+///
+/// Data Integrity credentials are hashed in their JCS form, SD-JWTs by their
+/// issuer-signed JWT and mdocs by their MSO, so every presentation of a
+/// credential maps to the same hash.
+pub fn registry_hash(secured: &str) -> Result<String, AppError> {
+    if data_integrity::is_secured_document(secured) {
+        let document: Value = serde_json::from_str(secured)?;
+        return Ok(crypto::hash_to_hex(jcs::canonicalize(&document)?.as_bytes()));
+    }
+    if SdJwt::is_sd_jwt(secured) {
+        return Ok(crypto::hash_to_hex(SdJwt::parse(secured)?.jwt.as_bytes()));
+    }
+    if mdoc::is_mdoc(secured) {
+        return Ok(crypto::hash_to_hex(&mdoc::mso_bytes(secured)?));
+    }
+    Ok(crypto::hash_to_hex(secured.as_bytes()))
+}
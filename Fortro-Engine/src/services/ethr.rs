@@ -0,0 +1,61 @@
+use crate::blockchain::{DidRegistryChange, EthereumClient};
+use crate::error::AppError;
+use ethers::types::{RecoveryMessage, Signature, H256};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Request to authorize or relay a DID Registry change of a did:ethr identity
+#[derive(Debug, Deserialize)]
+pub struct EthrChangeRequest {
+    pub did: String,
+    pub change: DidRegistryChange,
+    /// 65-byte hex `r || s || v` signature of the change hash by the identity owner
+    pub signature: Option<String>,
+}
+
+/// did:ethr anchoring service; relays owner-signed DID Registry changes so holders need no gas
+pub struct EthrDidService {
+    blockchain: Arc<EthereumClient>,
+}
+
+impl EthrDidService {
+    /// Create a new did:ethr service
+    pub fn new(blockchain: Arc<EthereumClient>) -> Self {
+        Self { blockchain }
+    }
+
+    /// Get the hash the identity owner must sign to authorize a change
+    pub async fn change_hash(&self, did: &str, change: &DidRegistryChange) -> Result<H256, AppError> {
+        let (_, identity) = self.blockchain.did_ethr_identity(did).await?;
+        self.blockchain.did_change_hash(identity, change).await
+    }
+
+    /// Submit an owner-signed change to the DID Registry, paid for by the engine's wallet
+    pub async fn submit_signed_change(&self, did: &str, change: &DidRegistryChange, signature: &str) -> Result<H256, AppError> {
+        let (_, identity) = self.blockchain.did_ethr_identity(did).await?;
+
+        let signature: Signature = signature.trim_start_matches("0x").parse()
+            .map_err(|e| AppError::ValidationError(format!("Invalid signature: {}", e)))?;
+
+        // Check the signer before spending gas on a transaction the contract would reject
+        let hash = self.blockchain.did_change_hash(identity, change).await?;
+        let signer = signature.recover(RecoveryMessage::Hash(hash))
+            .map_err(|e| AppError::ValidationError(format!("Invalid signature: {}", e)))?;
+        let owner = self.blockchain.identity_owner(identity).await?;
+        if signer != owner {
+            return Err(AppError::AuthError(format!("Change is not signed by the owner of {}", did)));
+        }
+
+        match change {
+            DidRegistryChange::ChangeOwner { new_owner } => {
+                self.blockchain.change_owner_signed(identity, &signature, *new_owner).await
+            }
+            DidRegistryChange::AddDelegate { delegate_type, delegate, validity } => {
+                self.blockchain.add_delegate_signed(identity, &signature, delegate_type, *delegate, *validity).await
+            }
+            DidRegistryChange::SetAttribute { name, value, validity } => {
+                self.blockchain.set_attribute_signed(identity, &signature, name, value, *validity).await
+            }
+        }
+    }
+}
@@ -1,11 +1,13 @@
+use crate::config::DidWebConfig;
 use crate::db::Database;
 use crate::error::AppError;
-use crate::models::{CredentialOffer, CredentialRequest, CredentialRequestStatus};
+use crate::models::{CredentialOffer, CredentialRequest, CredentialRequestStatus, IssuerKey, UserRole};
+use crate::services::auth::AuthService;
+use crate::services::key::KeyService;
 pub use crate::services::credential::{CredentialService, IssueCredentialRequest};
 pub use crate::services::schema::{CreateSchemaRequest, SchemaService};
-use crate::utils::qr;
+use crate::utils::{did, qr};
 use chrono::{DateTime, Duration, Utc};
-use futures::TryStreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
@@ -19,7 +21,10 @@ pub struct CreateIssuerRequest {
     pub description: Option<String>,
     pub website: Option<String>,
     pub logo_url: Option<String>,
-    pub public_key: String,
+    /// DID method of the new issuer: `alyra` (default) or `web`
+    pub did_method: Option<String>,
+    /// Path segment of a did:web issuer, `did:web:<domain>:issuers:<slug>`; omit to claim the root DID
+    pub web_slug: Option<String>,
 }
 
 /// Create credential template request
@@ -35,8 +40,11 @@ pub struct CreateCredentialTemplateRequest {
 /// Issuer service
 pub struct IssuerService {
     db: Arc<Database>,
+    auth_service: AuthService,
+    key_service: KeyService,
     credential_service: CredentialService,
     schema_service: SchemaService,
+    did_web: DidWebConfig,
 }
 
 /// Process credential request
@@ -70,19 +78,42 @@ pub struct CredentialOfferResponse {
 
 impl IssuerService {
     /// Create a new issuer service
-    pub fn new(db: Arc<Database>, credential_service: CredentialService, schema_service: SchemaService) -> Self {
+    pub fn new(
+        db: Arc<Database>,
+        auth_service: AuthService,
+        key_service: KeyService,
+        credential_service: CredentialService,
+        schema_service: SchemaService,
+        did_web: DidWebConfig,
+    ) -> Self {
         Self {
             db,
+            auth_service,
+            key_service,
             credential_service,
             schema_service,
+            did_web,
         }
     }
 
-    /// Create a new issuer
-    pub async fn create_issuer(&self, request: CreateIssuerRequest) -> Result<HashMap<String, Value>, AppError> {
+    /// Create a new issuer controlled by the given DID
+    pub async fn create_issuer(&self, controller_did: &str, request: CreateIssuerRequest) -> Result<HashMap<String, Value>, AppError> {
+        // Generate the issuer's signing key; a did:alyra DID is derived from it
+        let key = match request.did_method.as_deref().unwrap_or("alyra") {
+            "alyra" => self.key_service.generate_issuer_key().await?,
+            "web" => {
+                let did = self.web_issuer_did(controller_did, request.web_slug.as_deref())?;
+                if self.db.count_documents("issuers", bson::doc! { "id": &did }).await? > 0 {
+                    return Err(AppError::ValidationError(format!("Issuer {} already exists", did)));
+                }
+                self.key_service.generate_issuer_key_for(&did).await?
+            }
+            other => return Err(AppError::ValidationError(format!("Unsupported issuer DID method: {}", other))),
+        };
+
         // Create a new issuer document
         let now = Utc::now();
-        let issuer_id = format!("did:fortro:{}", Uuid::new_v4());
+        let issuer_id = key.did;
 
         let issuer = json!({
             "id": issuer_id,
@@ -90,7 +121,9 @@ impl IssuerService {
             "description": request.description,
             "website": request.website,
             "logo_url": request.logo_url,
-            "public_key": request.public_key,
+            "public_key": key.public_key_base58,
+            "key_id": key.key_id,
+            "controller_did": controller_did,
             "created_at": now,
             "updated_at": now,
         });
@@ -100,6 +133,9 @@ impl IssuerService {
             .insert_one("issuers", &issuer)
             .await?;
 
+        // The controller can now act as an issuer
+        self.auth_service.grant_role(controller_did, UserRole::Issuer).await?;
+
         // Convert to HashMap for easier manipulation
         let issuer_map = serde_json::from_value::<HashMap<String, Value>>(issuer)
             .map_err(|e| AppError::ValidationError(format!("Failed to convert issuer to map: {}", e)))?;
@@ -107,6 +143,27 @@ impl IssuerService {
         Ok(issuer_map)
     }
 
+    /// DID of a did:web issuer hosted on the engine's domain
+    fn web_issuer_did(&self, controller_did: &str, slug: Option<&str>) -> Result<String, AppError> {
+        let domain = self.did_web.domain.as_deref()
+            .ok_or_else(|| AppError::ValidationError("did:web issuers require DID_WEB_DOMAIN to be configured".to_string()))?;
+
+        match slug {
+            Some(slug) => {
+                let valid = !slug.is_empty()
+                    && slug.len() <= 64
+                    && slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+                if !valid {
+                    return Err(AppError::ValidationError("web_slug may only contain lowercase letters, digits and '-'".to_string()));
+                }
+                Ok(did::did_web(domain, &["issuers", slug]))
+            }
+            // The root DID names the whole domain, so only its configured controller may claim it
+            None if self.did_web.root_controller.as_deref() == Some(controller_did) => Ok(did::did_web(domain, &[])),
+            None => Err(AppError::AccessDeniedError("Only the configured root controller may claim the domain's root did:web".to_string())),
+        }
+    }
+
     /// Get an issuer by DID
     pub async fn get_issuer(&self, did: &str) -> Result<HashMap<String, Value>, AppError> {
         let issuer = self.db
@@ -117,6 +174,19 @@ impl IssuerService {
         Ok(issuer)
     }
 
+    /// Check whether an issuer DID is the caller's own DID or an issuer it controls
+    pub async fn is_controlled_by(&self, issuer_did: &str, controller_did: &str) -> Result<bool, AppError> {
+        if issuer_did == controller_did {
+            return Ok(true);
+        }
+
+        let count = self.db
+            .count_documents("issuers", bson::doc! { "id": issuer_did, "controller_did": controller_did })
+            .await?;
+
+        Ok(count > 0)
+    }
+
     /// Update an issuer
     pub async fn update_issuer(&self, did: &str, updates: HashMap<String, Value>) -> Result<HashMap<String, Value>, AppError> {
 
@@ -125,8 +195,8 @@ impl IssuerService {
 
         // Add each field from the updates
         for (key, value) in updates {
-            // Skip the id field
-            if key == "id" {
+            // Skip the identity, controller and key fields
+            if matches!(key.as_str(), "id" | "controller_did" | "public_key" | "key_id") {
                 continue;
             }
 
@@ -154,6 +224,43 @@ impl IssuerService {
         self.get_issuer(did).await
     }
 
+    /// Rotate an issuer's signing key; new credentials are signed with the new key
+    pub async fn rotate_issuer_key(&self, did: &str) -> Result<Value, AppError> {
+        // Verify that the issuer exists
+        let _issuer = self.get_issuer(did).await?;
+
+        let key = self.key_service.rotate_issuer_key(did).await?;
+
+        // Re-sign the status lists, which are only checked against current keys when not anchored
+        self.credential_service.republish_status_lists(did).await?;
+
+        // Publish the new key on the issuer profile
+        self.db
+            .update_one(
+                "issuers",
+                bson::doc! { "id": did },
+                bson::doc! { "$set": {
+                    "public_key": &key.public_key_base58,
+                    "key_id": &key.key_id,
+                    "updated_at": bson::to_bson(&Utc::now())
+                        .map_err(|e| AppError::ValidationError(format!("Failed to convert date to BSON: {}", e)))?,
+                } },
+            )
+            .await?;
+
+        Ok(key_summary(&key))
+    }
+
+    /// List an issuer's current and retired signing keys, newest first
+    pub async fn list_issuer_keys(&self, did: &str) -> Result<Vec<Value>, AppError> {
+        let keys = self.key_service.key_history(did).await?;
+        if keys.is_empty() {
+            return Err(AppError::NotFoundError(format!("No signing keys found for issuer {}", did)));
+        }
+
+        Ok(keys.iter().map(key_summary).collect())
+    }
+
     /// Create a credential template
     pub async fn create_credential_template(&self, issuer_did: &str, request: CreateCredentialTemplateRequest) -> Result<HashMap<String, Value>, AppError> {
         // Verify that the issuer exists
@@ -357,10 +464,8 @@ impl IssuerService {
             reason: None,
         };
 
-        let issuer_private_key = "dummy_private_key";
-
         // Process the request
-        self.process_credential_request(issuer_did, issuer_private_key, process_request).await
+        self.process_credential_request(issuer_did, process_request).await
     }
 
     /// Reject a credential request
@@ -391,17 +496,14 @@ impl IssuerService {
             reason,
         };
 
-        let issuer_private_key = "dummy_private_key";
-
         // Process the request
-        self.process_credential_request(issuer_did, issuer_private_key, process_request).await
+        self.process_credential_request(issuer_did, process_request).await
     }
 
     /// Process a credential request
     pub async fn process_credential_request(
         &self,
         issuer_did: &str,
-        issuer_private_key: &str,
         request: ProcessCredentialRequestRequest,
     ) -> Result<CredentialRequest, AppError> {
         // Get the credential request
@@ -442,11 +544,16 @@ impl IssuerService {
                 subject_did: credential_request.user_did.clone(),
                 attributes: credential_request.request_data.clone(),
                 expiration_date: Some(Utc::now() + Duration::days(365)), // Default to 1 year
+                vc_version: None,
+                cryptosuite: None,
+                selective_disclosure: false,
+                bbs: false,
+                link_secret_commitment: credential_request.link_secret_commitment.clone(),
             };
 
             let credential_response = self
                 .credential_service
-                .issue_credential_with_key(issuer_did, issuer_private_key, issue_request)
+                .issue_credential(issuer_did, issue_request)
                 .await?;
 
             // Update the request with the credential ID
@@ -523,16 +630,7 @@ impl IssuerService {
             "status": "pending"
         };
 
-        let count = self
-            .db
-            .credential_requests()
-            .count_documents(filter)
-            .await
-            .map_err(|e| {
-                AppError::DatabaseError(format!("Failed to count pending requests: {}", e))
-            })?;
-
-        Ok(count)
+        self.db.count_documents("credential_requests", filter).await
     }
 
     /// Get recent credential requests for an issuer
@@ -547,19 +645,9 @@ impl IssuerService {
             .limit(limit)
             .build();
 
-        let cursor = self
-            .db
-            .credential_requests()
-            .find(filter)
-            .await
-            .map_err(|e| {
-                AppError::DatabaseError(format!("Failed to get recent requests: {}", e))
-            })?;
-
-        cursor
-            .try_collect()
+        self.db
+            .find_many_with_options("credential_requests", filter, options)
             .await
-            .map_err(|e| AppError::DatabaseError(format!("Failed to collect requests: {}", e)))
     }
 
     /// Get statistics for an issuer
@@ -567,58 +655,32 @@ impl IssuerService {
         // Count total requests
         let total_requests = self
             .db
-            .credential_requests()
-            .count_documents(bson::doc! { "issuer_did": issuer_did })
-            .await
-            .map_err(|e| {
-                AppError::DatabaseError(format!("Failed to count total requests: {}", e))
-            })?;
+            .count_documents("credential_requests", bson::doc! { "issuer_did": issuer_did })
+            .await?;
 
         // Count pending requests
         let pending_requests = self
             .db
-            .credential_requests()
-            .count_documents(
-                bson::doc! { "issuer_did": issuer_did, "status": "pending" },
-            )
-            .await
-            .map_err(|e| {
-                AppError::DatabaseError(format!("Failed to count pending requests: {}", e))
-            })?;
+            .count_documents("credential_requests", bson::doc! { "issuer_did": issuer_did, "status": "pending" })
+            .await?;
 
         // Count approved requests
         let approved_requests = self
             .db
-            .credential_requests()
-            .count_documents(
-                bson::doc! { "issuer_did": issuer_did, "status": "approved" },
-            )
-            .await
-            .map_err(|e| {
-                AppError::DatabaseError(format!("Failed to count approved requests: {}", e))
-            })?;
+            .count_documents("credential_requests", bson::doc! { "issuer_did": issuer_did, "status": "approved" })
+            .await?;
 
         // Count rejected requests
         let rejected_requests = self
             .db
-            .credential_requests()
-            .count_documents(
-                bson::doc! { "issuer_did": issuer_did, "status": "rejected" },
-            )
-            .await
-            .map_err(|e| {
-                AppError::DatabaseError(format!("Failed to count rejected requests: {}", e))
-            })?;
+            .count_documents("credential_requests", bson::doc! { "issuer_did": issuer_did, "status": "rejected" })
+            .await?;
 
         // Count issued credentials
         let issued_credentials = self
             .db
-            .credentials()
-            .count_documents(bson::doc! { "issuer_did": issuer_did })
-            .await
-            .map_err(|e| {
-                AppError::DatabaseError(format!("Failed to count issued credentials: {}", e))
-            })?;
+            .count_documents("credentials", bson::doc! { "issuer_did": issuer_did })
+            .await?;
 
         let mut statistics = HashMap::new();
         statistics.insert("total_requests".to_string(), json!(total_requests));
@@ -630,3 +692,15 @@ impl IssuerService {
         Ok(statistics)
     }
 }
+
+/// Public view of an issuer key, without its key store handle
+fn key_summary(key: &IssuerKey) -> Value {
+    json!({
+        "key_id": key.key_id,
+        "version": key.version,
+        "algorithm": key.algorithm,
+        "public_key": key.public_key_base58,
+        "created_at": key.created_at,
+        "retired_at": key.retired_at.and_then(|ts| DateTime::<Utc>::from_timestamp(ts, 0)),
+    })
+}
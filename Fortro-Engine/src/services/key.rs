@@ -0,0 +1,247 @@
+use crate::db::Database;
+use crate::error::AppError;
+use crate::keystore::KeyStore;
+use crate::models::IssuerKey;
+use crate::utils::{bbs, crypto, did};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::options::FindOptions;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const ISSUER_KEYS: &str = "issuer_keys";
+
+const DILITHIUM2: &str = "Dilithium2";
+
+/// Issuer key management service
+pub struct KeyService {
+    db: Arc<Database>,
+    keystore: Arc<dyn KeyStore>,
+}
+
+impl KeyService {
+    /// Create a new key service
+    pub fn new(db: Arc<Database>, keystore: Arc<dyn KeyStore>) -> Self {
+        Self { db, keystore }
+    }
+
+    /// The key store holding the private keys
+    pub fn keystore(&self) -> &dyn KeyStore {
+        self.keystore.as_ref()
+    }
+
+    /// Generate a Dilithium key pair in the key store and derive its did:alyra DID
+    pub async fn generate_issuer_key(&self) -> Result<IssuerKey, AppError> {
+        let key_handle = Uuid::new_v4().to_string();
+        let did_key_pair = did::generate_did_in_store(self.keystore(), &key_handle).await?;
+
+        let key = IssuerKey {
+            key_id: key_id(&did_key_pair.did, 1),
+            did: did_key_pair.did,
+            algorithm: DILITHIUM2.to_string(),
+            public_key_base58: did_key_pair.public_key_base58,
+            key_handle,
+            version: 1,
+            created_at: Utc::now(),
+            retired_at: None,
+        };
+
+        self.db.insert_one(ISSUER_KEYS, &key).await?;
+
+        tracing::info!("Generated signing key {}", key.key_id);
+        Ok(key)
+    }
+
+    /// Generate the first signing key of an issuer whose DID is not derived from its key, such as did:web
+    pub async fn generate_issuer_key_for(&self, did: &str) -> Result<IssuerKey, AppError> {
+        if self.manages(did).await? {
+            return Err(AppError::ValidationError(format!("Issuer {} already has signing keys", did)));
+        }
+
+        let key_handle = Uuid::new_v4().to_string();
+        let public_key = self.keystore.generate_key(&key_handle).await?;
+
+        let key = IssuerKey {
+            did: did.to_string(),
+            key_id: key_id(did, 1),
+            algorithm: DILITHIUM2.to_string(),
+            public_key_base58: bs58::encode(&public_key).into_string(),
+            key_handle,
+            version: 1,
+            created_at: Utc::now(),
+            retired_at: None,
+        };
+
+        self.db.insert_one(ISSUER_KEYS, &key).await?;
+
+        tracing::info!("Generated signing key {}", key.key_id);
+        Ok(key)
+    }
+
+    /// Replace the current signing key of an issuer DID with a fresh one.
+    ///
+    /// The DID itself does not change. The previous key stays in the history
+    /// so credentials it signed before now keep verifying.
+    pub async fn rotate_issuer_key(&self, did: &str) -> Result<IssuerKey, AppError> {
+        let current = self.signing_key(did).await?;
+        let now = Utc::now();
+
+        // Step 1: Create the new key pair in the key store
+        let key_handle = Uuid::new_v4().to_string();
+        let public_key = self.keystore.generate_key(&key_handle).await?;
+
+        // Step 2: Record the new key as the next version first, so the issuer always has
+        // an active key; this fails if a concurrent rotation got there first
+        let version = current.version + 1;
+        let key = IssuerKey {
+            did: did.to_string(),
+            key_id: key_id(did, version),
+            algorithm: DILITHIUM2.to_string(),
+            public_key_base58: bs58::encode(&public_key).into_string(),
+            key_handle,
+            version,
+            created_at: now,
+            retired_at: None,
+        };
+        let inserted = self.db.insert_one_if_absent(ISSUER_KEYS, doc! { "key_id": &key.key_id }, &key).await?;
+        if !inserted {
+            return Err(AppError::ValidationError(format!("Key {} was already rotated", current.key_id)));
+        }
+
+        // Step 3: Retire the previous key; until then the newer key is already the signing key
+        self.db.update_one(
+            ISSUER_KEYS,
+            doc! { "key_id": &current.key_id, "retired_at": Bson::Null },
+            doc! { "$set": { "retired_at": now.timestamp() } },
+        ).await?;
+
+        tracing::info!("Rotated signing key {} to {}", current.key_id, key.key_id);
+        Ok(key)
+    }
+
+    /// Get the BBS key of an issuer DID, generating it on first use.
+    ///
+    /// BBS keys sign unlinkable credentials next to the issuer's Dilithium
+    /// keys and are not rotated with them.
+    pub async fn bbs_key(&self, did: &str) -> Result<IssuerKey, AppError> {
+        if let Some(key) = self.db.find_one::<IssuerKey>(ISSUER_KEYS, doc! { "did": did, "algorithm": bbs::ALGORITHM }).await? {
+            return Ok(key);
+        }
+        if !self.manages(did).await? {
+            return Err(AppError::NotFoundError(format!("No signing key is managed for issuer {}", did)));
+        }
+
+        let key_handle = Uuid::new_v4().to_string();
+        let public_key = self.keystore.generate_bbs_key(&key_handle).await?;
+
+        let key = IssuerKey {
+            did: did.to_string(),
+            key_id: format!("{}#bbs-keys-1", did),
+            algorithm: bbs::ALGORITHM.to_string(),
+            public_key_base58: bs58::encode(&public_key).into_string(),
+            key_handle,
+            version: 1,
+            created_at: Utc::now(),
+            retired_at: None,
+        };
+        self.db.insert_one(ISSUER_KEYS, &key).await?;
+
+        tracing::info!("Generated BBS key {}", key.key_id);
+        Ok(key)
+    }
+
+    /// Move issuer keys stored in the database by earlier versions into the key store.
+    ///
+    /// Those records hold the private key encrypted with `KEY_ENCRYPTION_SECRET`
+    /// in `encrypted_private_key` instead of a `key_handle`. Each one is imported
+    /// under a new handle and the encrypted copy is removed from the database.
+    pub async fn migrate_legacy_keys(&self, encryption_secret: Option<&str>) -> Result<usize, AppError> {
+        let legacy_keys: Vec<Document> = self.db.find_many(
+            ISSUER_KEYS,
+            doc! { "encrypted_private_key": { "$exists": true } },
+        ).await?;
+        if legacy_keys.is_empty() {
+            return Ok(0);
+        }
+        let secret = encryption_secret.ok_or_else(|| AppError::ConfigError(format!(
+            "KEY_ENCRYPTION_SECRET must be set to migrate {} issuer keys stored in the database",
+            legacy_keys.len()
+        )))?;
+
+        for legacy_key in &legacy_keys {
+            let field = |name: &str| legacy_key.get_str(name)
+                .map_err(|_| AppError::DatabaseError(format!("Stored issuer key has no {}", name)));
+            let key_id = field("key_id")?;
+
+            // Step 1: Decrypt the private key with the secret it was stored under
+            let encrypted = general_purpose::STANDARD.decode(field("encrypted_private_key")?)
+                .map_err(|e| AppError::InternalError(format!("Corrupt signing key {}: {}", key_id, e)))?;
+            let private_key = crypto::decrypt_with_password(&encrypted, secret)
+                .map_err(|_| AppError::ConfigError(format!(
+                    "Failed to decrypt signing key {}; check KEY_ENCRYPTION_SECRET",
+                    key_id
+                )))?;
+            let public_key = bs58::decode(field("public_key_base58")?)
+                .into_vec()
+                .map_err(|e| AppError::InternalError(format!("Corrupt public key {}: {}", key_id, e)))?;
+
+            // Step 2: Import the key pair into the key store
+            let key_handle = Uuid::new_v4().to_string();
+            self.keystore.import_key(&key_handle, &public_key, &private_key).await?;
+
+            // Step 3: Point the record at the key store and drop the encrypted copy
+            self.db.update_one(
+                ISSUER_KEYS,
+                doc! { "key_id": key_id, "encrypted_private_key": { "$exists": true } },
+                doc! {
+                    "$set": { "key_handle": &key_handle, "version": 1 },
+                    "$unset": { "encrypted_private_key": "" },
+                },
+            ).await?;
+
+            tracing::info!("Migrated signing key {} to the key store", key_id);
+        }
+
+        Ok(legacy_keys.len())
+    }
+
+    /// Get the current Dilithium key record for an issuer DID
+    pub async fn get_issuer_key(&self, did: &str) -> Result<Option<IssuerKey>, AppError> {
+        // The newest version wins while a rotation has not retired the previous key yet
+        let options = FindOptions::builder().sort(doc! { "version": -1 }).limit(1).build();
+        let keys: Vec<IssuerKey> = self.db.find_many_with_options(
+            ISSUER_KEYS,
+            doc! { "did": did, "algorithm": DILITHIUM2, "retired_at": Bson::Null },
+            options,
+        ).await?;
+        Ok(keys.into_iter().next())
+    }
+
+    /// Get every key an issuer DID has used, newest first
+    pub async fn key_history(&self, did: &str) -> Result<Vec<IssuerKey>, AppError> {
+        let options = FindOptions::builder().sort(doc! { "version": -1 }).build();
+        self.db.find_many_with_options(ISSUER_KEYS, doc! { "did": did }, options).await
+    }
+
+    /// Get a key record, current or retired, by its key ID
+    pub async fn find_key(&self, key_id: &str) -> Result<Option<IssuerKey>, AppError> {
+        self.db.find_one::<IssuerKey>(ISSUER_KEYS, doc! { "key_id": key_id }).await
+    }
+
+    /// Whether the engine manages keys for a DID
+    pub async fn manages(&self, did: &str) -> Result<bool, AppError> {
+        Ok(self.db.count_documents(ISSUER_KEYS, doc! { "did": did }).await? > 0)
+    }
+
+    /// Get the signing key for an issuer DID, failing if the engine does not manage one
+    pub async fn signing_key(&self, did: &str) -> Result<IssuerKey, AppError> {
+        self.get_issuer_key(did).await?
+            .ok_or_else(|| AppError::NotFoundError(format!("No signing key is managed for issuer {}", did)))
+    }
+}
+
+/// Verification method ID of a DID's `version`th key
+fn key_id(did: &str, version: u32) -> String {
+    format!("{}#pq-keys-{}", did, version)
+}
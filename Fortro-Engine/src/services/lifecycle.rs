@@ -0,0 +1,82 @@
+use crate::blockchain::EthereumClient;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::{DidDeactivation, DidState};
+use chrono::Utc;
+use mongodb::bson::doc;
+use serde::Deserialize;
+use std::sync::Arc;
+
+const DID_DEACTIVATIONS: &str = "did_deactivations";
+
+/// Deactivate DID request
+#[derive(Debug, Default, Deserialize)]
+pub struct DeactivateDidRequest {
+    pub reason: Option<String>,
+    /// Also record the deactivation on chain
+    #[serde(default)]
+    pub anchor: bool,
+}
+
+/// DID lifecycle service
+#[derive(Clone)]
+pub struct DidLifecycleService {
+    db: Arc<Database>,
+    blockchain: Arc<EthereumClient>,
+}
+
+impl DidLifecycleService {
+    /// Create a new DID lifecycle service
+    pub fn new(db: Arc<Database>, blockchain: Arc<EthereumClient>) -> Self {
+        Self { db, blockchain }
+    }
+
+    /// Get the deactivation record of a DID, if it was deactivated
+    pub async fn deactivation(&self, did: &str) -> Result<Option<DidDeactivation>, AppError> {
+        self.db.find_one::<DidDeactivation>(DID_DEACTIVATIONS, doc! { "did": did }).await
+    }
+
+    /// Get the lifecycle state of a DID
+    pub async fn state(&self, did: &str) -> Result<DidState, AppError> {
+        Ok(match self.deactivation(did).await? {
+            Some(_) => DidState::Deactivated,
+            None => DidState::Active,
+        })
+    }
+
+    /// Fail if a DID has been deactivated
+    pub async fn ensure_active(&self, did: &str) -> Result<(), AppError> {
+        if self.state(did).await? == DidState::Deactivated {
+            return Err(AppError::AccessDeniedError(format!("DID {} is deactivated", did)));
+        }
+        Ok(())
+    }
+
+    /// Permanently deactivate a DID on behalf of `actor_did`, who must be entitled to it
+    pub async fn deactivate(&self, did: &str, actor_did: &str, request: DeactivateDidRequest) -> Result<DidDeactivation, AppError> {
+        if self.deactivation(did).await?.is_some() {
+            return Err(AppError::ValidationError(format!("DID {} is already deactivated", did)));
+        }
+
+        // Step 1: Anchor first, so a failed transaction leaves the DID untouched
+        let anchor_tx_hash = if request.anchor {
+            let tx_hash = self.blockchain.anchor_did_deactivation(did).await?;
+            Some(format!("{:?}", tx_hash))
+        } else {
+            None
+        };
+
+        // Step 2: Record the deactivation; from here on the DID is refused everywhere
+        let record = DidDeactivation {
+            did: did.to_string(),
+            deactivated_by: actor_did.to_string(),
+            reason: request.reason,
+            deactivated_at: Utc::now(),
+            anchor_tx_hash,
+        };
+        self.db.insert_one(DID_DEACTIVATIONS, &record).await?;
+
+        tracing::info!("Deactivated DID {} on behalf of {}", did, actor_did);
+        Ok(record)
+    }
+}
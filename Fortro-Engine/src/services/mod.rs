@@ -1,25 +1,38 @@
 pub(crate) mod auth;
 mod credential;
+pub(crate) mod ethr;
 pub(crate) mod issuer;
+pub(crate) mod key;
+pub(crate) mod lifecycle;
 mod presentation;
 mod qr;
 mod schema;
+pub(crate) mod session;
+pub(crate) mod status_list;
 mod user;
 pub(crate) mod verifier;
 pub(crate) mod wallet;
 
 use crate::blockchain::EthereumClient;
+use crate::config::Config;
 use crate::db::Database;
 use crate::ipfs::IpfsClient;
+use crate::keystore::KeyStore;
+use crate::resolver::{AlyraResolver, DidResolver, DidResolvers, DidWebClient, EthrResolver, KeyResolver, WebResolver};
 use std::sync::Arc;
 
 // Re-export service modules
 pub use auth::AuthService;
-pub use credential::CredentialService;
+pub use credential::{CredentialService, RevokeCredentialRequest};
+pub use ethr::EthrDidService;
 pub use issuer::IssuerService;
+pub use key::KeyService;
+pub use lifecycle::DidLifecycleService;
 pub use presentation::PresentationService;
 pub use qr::QrService;
 pub use schema::SchemaService;
+pub use session::{SessionService, SessionSettings};
+pub use status_list::{StatusListCache, StatusListService};
 pub use user::UserService;
 pub use verifier::VerifierService;
 pub use wallet::WalletService;
@@ -27,24 +40,45 @@ pub use wallet::WalletService;
 /// Application state shared across services
 #[derive(Clone)]
 pub struct AppState {
+    pub config: Arc<Config>,
     pub db: Arc<Database>,
     pub ipfs: Arc<IpfsClient>,
     pub blockchain: Arc<EthereumClient>,
+    pub keystore: Arc<dyn KeyStore>,
+    pub did_web: Arc<DidWebClient>,
+    pub status_lists: Arc<StatusListCache>,
 }
 
 impl AppState {
     /// Create a new application state
-    pub fn new(db: Database, ipfs: IpfsClient, blockchain: EthereumClient) -> Self {
+    pub fn new(
+        config: Config,
+        db: Database,
+        ipfs: IpfsClient,
+        blockchain: EthereumClient,
+        keystore: Arc<dyn KeyStore>,
+        did_web: DidWebClient,
+        status_lists: StatusListCache,
+    ) -> Self {
         Self {
+            config: Arc::new(config),
             db: Arc::new(db),
             ipfs: Arc::new(ipfs),
             blockchain: Arc::new(blockchain),
+            keystore,
+            did_web: Arc::new(did_web),
+            status_lists: Arc::new(status_lists),
         }
     }
 
     /// Get the auth service
     pub fn auth_service(&self) -> AuthService {
-        AuthService::new(self.db.clone())
+        AuthService::new(self.db.clone(), self.session_service(), self.lifecycle_service())
+    }
+
+    /// Get the session token service
+    pub fn session_service(&self) -> SessionService {
+        SessionService::new(self.db.clone(), SessionSettings::from_config(&self.config))
     }
 
     /// Get the user service
@@ -58,12 +92,68 @@ impl AppState {
             self.db.clone(),
             self.ipfs.clone(),
             self.blockchain.clone(),
+            self.key_service(),
+            self.did_resolver(),
+            self.lifecycle_service(),
+            self.status_list_service(),
+        )
+    }
+
+    /// Get the status list service
+    pub fn status_list_service(&self) -> StatusListService {
+        StatusListService::new(
+            self.db.clone(),
+            self.ipfs.clone(),
+            self.blockchain.clone(),
+            self.key_service(),
+            self.did_resolver(),
+            self.status_lists.clone(),
+            self.config.status_list.clone(),
+        )
+    }
+
+    /// Get the DID resolver for all supported DID methods
+    pub fn did_resolver(&self) -> Arc<dyn DidResolver> {
+        Arc::new(
+            DidResolvers::new()
+                .with_method("alyra", AlyraResolver::new(self.db.clone(), self.key_service()))
+                .with_method("key", KeyResolver)
+                .with_method("ethr", EthrResolver::new(self.blockchain.clone()))
+                .with_method("web", WebResolver::new(
+                    self.db.clone(),
+                    self.key_service(),
+                    self.config.did_web.domain.clone(),
+                    self.did_web.clone(),
+                ))
+                .with_lifecycle(self.lifecycle_service()),
         )
     }
 
+    /// Get the DID lifecycle service
+    pub fn lifecycle_service(&self) -> DidLifecycleService {
+        DidLifecycleService::new(self.db.clone(), self.blockchain.clone())
+    }
+
+    /// Get the did:ethr anchoring service
+    pub fn ethr_did_service(&self) -> EthrDidService {
+        EthrDidService::new(self.blockchain.clone())
+    }
+
+    /// Get the issuer key service
+    pub fn key_service(&self) -> KeyService {
+        KeyService::new(self.db.clone(), self.keystore.clone())
+    }
+
     /// Get the issuer service
     pub fn issuer_service(&self) -> IssuerService {
-        IssuerService::new(self.db.clone(), self.credential_service(), self.schema_service())
+        IssuerService::new(
+            self.db.clone(),
+            self.auth_service(),
+            self.key_service(),
+            self.credential_service(),
+            self.schema_service(),
+            self.config.did_web.clone(),
+        )
     }
 
     /// Get the presentation service
@@ -71,6 +161,8 @@ impl AppState {
         PresentationService::new(
             self.db.clone(),
             self.credential_service(),
+            self.did_resolver(),
+            self.lifecycle_service(),
         )
     }
 
@@ -1,8 +1,12 @@
 use crate::db::Database;
 use crate::error::AppError;
-use crate::models::{Credential, CredentialRequirement, Presentation, PresentationRequest, PresentationStatus};
+use crate::models::{Credential, CredentialRequirement, PredicateType, Presentation, PresentationRequest, PresentationStatus};
+use crate::resolver::DidResolver;
 use crate::services::credential::CredentialService;
-use crate::utils::{crypto, did, jwt, qr, zk_proofs};
+use crate::services::lifecycle::DidLifecycleService;
+use crate::utils::bbs_credential;
+use crate::utils::sd_jwt::{self, SdJwt};
+use crate::utils::{crypto, data_integrity, did, jcs, jwt, link_secret, mdoc, qr, zk_proofs};
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -13,6 +17,8 @@ use std::sync::Arc;
 pub struct PresentationService {
     db: Arc<Database>,
     credential_service: CredentialService,
+    resolver: Arc<dyn DidResolver>,
+    lifecycle: DidLifecycleService,
 }
 
 /// Create presentation request
@@ -33,12 +39,20 @@ pub struct SubmitPresentationRequest {
     pub credential_ids: Vec<String>,
     pub disclosed_attributes: HashMap<String, Vec<String>>,
     pub predicate_proofs: Vec<zk_proofs::PredicateProof>,
+    /// Proof that the presented credentials bound to a link secret share the prover's,
+    /// made by their wallet for the request's verifier and nonce
+    #[serde(default)]
+    pub same_holder_proof: Option<link_secret::SameHolderProof>,
 }
 
 /// Verify presentation request
 #[derive(Debug, Deserialize)]
 pub struct VerifyPresentationRequest {
     pub presentation_jwt: String,
+    /// Verifier checking the presentation, who must have made the answered request
+    pub verifier_did: String,
+    /// ID of the answered presentation request, the nonce the presentation must carry
+    pub presentation_request_id: String,
 }
 
 /// Presentation request response
@@ -69,10 +83,17 @@ pub struct PresentationVerificationResult {
 
 impl PresentationService {
     /// Create a new presentation service
-    pub fn new(db: Arc<Database>, credential_service: CredentialService) -> Self {
+    pub fn new(
+        db: Arc<Database>,
+        credential_service: CredentialService,
+        resolver: Arc<dyn DidResolver>,
+        lifecycle: DidLifecycleService,
+    ) -> Self {
         Self {
             db,
             credential_service,
+            resolver,
+            lifecycle,
         }
     }
 
@@ -81,6 +102,25 @@ impl PresentationService {
         &self,
         request: CreatePresentationRequestRequest,
     ) -> Result<PresentationRequestResponse, AppError> {
+        // Predicates must be provable: comparisons with a number or date, or sets of values
+        let today = Utc::now().date_naive();
+        for predicate in request.required_credentials.iter().filter_map(|r| r.predicate.as_ref()) {
+            match predicate.predicate_type {
+                PredicateType::In | PredicateType::NotIn => {
+                    zk_proofs::predicate_set(predicate)?;
+                }
+                PredicateType::NotEqual => {
+                    return Err(AppError::ValidationError(format!(
+                        "Predicate on {} cannot be proven: use not_in instead of !=",
+                        predicate.attribute
+                    )));
+                }
+                _ => {
+                    zk_proofs::predicate_value(predicate, today)?;
+                }
+            }
+        }
+
         // Create a new presentation request
         let presentation_request = PresentationRequest::new(
             request.verifier_did.clone(),
@@ -91,6 +131,9 @@ impl PresentationService {
             request.expires_at,
         );
 
+        // Keep the request, so submissions and verifications can look it up by its ID, the nonce
+        self.db.insert_one("presentation_requests", &presentation_request).await?;
+
         // Create a QR code for the request
         let qr_content = qr::create_presentation_request_qr(&presentation_request)?;
         let qr_code_data = qr_content.to_json_string()?;
@@ -108,6 +151,9 @@ impl PresentationService {
         prover_private_key: &str,
         request: SubmitPresentationRequest,
     ) -> Result<PresentationResponse, AppError> {
+        // A deactivated DID can no longer present
+        self.lifecycle.ensure_active(prover_did).await?;
+
         // Get the presentation request
         let presentation_request = self
             .db
@@ -132,6 +178,17 @@ impl PresentationService {
             }
         }
 
+        if presentation_request.consumed_at.is_some() {
+            return Err(AppError::ValidationError(
+                "Presentation request was already answered".to_string(),
+            ));
+        }
+
+        // Key binding JWTs and the presentation JWT are signed with the prover's DID key
+        let private_key = bs58::decode(prover_private_key)
+            .into_vec()
+            .map_err(|e| AppError::ValidationError(format!("Invalid prover private key: {}", e)))?;
+
         // Get the credentials
         let mut credentials = Vec::new();
         let mut credential_jwts = Vec::new();
@@ -156,40 +213,220 @@ impl PresentationService {
                 ));
             }
 
-            // Create selective disclosure for this credential
-            let disclosed_attrs = request
-                .disclosed_attributes
-                .get(credential_id)
-                .cloned()
-                .unwrap_or_default();
+            let disclosed_attrs = request.disclosed_attributes.get(credential_id);
+
+            if SdJwt::is_sd_jwt(&credential.jwt) {
+                // Reveal only the requested attributes, bound to this verifier and request
+                let disclosed = self
+                    .credential_service
+                    .create_selective_disclosure(credential_id, disclosed_attrs.map(Vec::as_slice).unwrap_or_default())
+                    .await?;
+                let presented = sd_jwt::add_key_binding(
+                    &disclosed,
+                    prover_did,
+                    &presentation_request.verifier_did,
+                    &presentation_request.id,
+                    &private_key,
+                )?;
+
+                presentation_data.insert(credential_id.clone(), json!(disclosed.disclosed_claims()?));
+                credential_jwts.push(json!(presented));
+            } else if mdoc::is_mdoc(&credential.jwt) {
+                // Present the chosen data elements, else those the verifier requested for this document type
+                let doc_type = mdoc::doc_type(&credential.jwt)?;
+                let element_names: Vec<String> = match disclosed_attrs.filter(|attrs| !attrs.is_empty()) {
+                    Some(attrs) => attrs.clone(),
+                    None => presentation_request.required_credentials.iter()
+                        .filter_map(|requirement| requirement.mdoc.as_ref())
+                        .filter(|items| items.doc_type == doc_type)
+                        .flat_map(|items| items.name_spaces.values().flat_map(|elements| elements.keys().cloned()))
+                        .collect(),
+                };
+                if element_names.is_empty() {
+                    return Err(AppError::ValidationError(format!(
+                        "No data elements of mdoc {} were requested or disclosed",
+                        credential_id
+                    )));
+                }
 
-            let disclosed_data = self
-                .credential_service
-                .create_selective_disclosure(credential_id, &disclosed_attrs)
-                .await?;
+                let device_response = mdoc::create_device_response(
+                    &credential.jwt,
+                    &element_names,
+                    prover_did,
+                    &presentation_request.verifier_did,
+                    &presentation_request.id,
+                    &private_key,
+                )?;
+
+                let disclosed: HashMap<&String, &Value> = credential.credential_data.iter()
+                    .filter(|(name, _)| element_names.contains(name))
+                    .collect();
+                presentation_data.insert(credential_id.clone(), json!(disclosed));
+                credential_jwts.push(json!(device_response));
+            } else if bbs_credential::parse(&credential.jwt).is_some() {
+                // Disclose the chosen attributes, else those the verifier requires of this credential type,
+                // and the subject DID so the verifier can bind the proof to the prover
+                let mut disclosed_names: Vec<String> = match disclosed_attrs.filter(|attrs| !attrs.is_empty()) {
+                    Some(attrs) => attrs.clone(),
+                    None => presentation_request.required_credentials.iter()
+                        .filter(|requirement| requirement.credential_type == credential.credential_type)
+                        .flat_map(|requirement| requirement.required_attributes.iter().cloned())
+                        .collect(),
+                };
+                if !disclosed_names.iter().any(|name| name == "id") {
+                    disclosed_names.push("id".to_string());
+                }
+
+                let derived = self
+                    .credential_service
+                    .derive_bbs_proof(
+                        credential_id,
+                        &disclosed_names,
+                        Some(&presentation_request.verifier_did),
+                        Some(&presentation_request.id),
+                    )
+                    .await?;
+
+                let disclosed: HashMap<&String, &Value> = credential.credential_data.iter()
+                    .filter(|(name, _)| disclosed_names.contains(name))
+                    .collect();
+                presentation_data.insert(credential_id.clone(), json!(disclosed));
+                credential_jwts.push(derived);
+            } else {
+                // Other credentials can only be presented whole
+                if disclosed_attrs.is_some_and(|attrs| !attrs.is_empty()) {
+                    return Err(AppError::ValidationError(format!(
+                        "Credential {} is not an SD-JWT VC, mdoc or BBS credential and cannot be disclosed selectively",
+                        credential_id
+                    )));
+                }
+
+                presentation_data.insert(credential_id.clone(), json!(credential.credential_data));
+                if data_integrity::is_secured_document(&credential.jwt) {
+                    credential_jwts.push(serde_json::from_str(&credential.jwt)?);
+                } else {
+                    credential_jwts.push(json!(credential.jwt));
+                }
+            }
 
-            // Add to presentation data
-            presentation_data.insert(credential_id.clone(), json!(disclosed_data));
-            
             credentials.push(credential.clone());
-            credential_jwts.push(credential.jwt.clone());
+        }
+
+        // Prove the request's predicates over the presented credentials, unless the prover brought a proof;
+        // comparisons are collected to be proven together
+        let mut predicate_proofs = request.predicate_proofs.clone();
+        let mut comparisons = Vec::new();
+        let today = Utc::now().date_naive();
+        for requirement in &presentation_request.required_credentials {
+            let Some(predicate) = &requirement.predicate else {
+                continue;
+            };
+            if predicate_proofs.iter().any(|proof| proof.attribute_name == predicate.attribute) {
+                continue;
+            }
+
+            let credential = credentials.iter()
+                .find(|credential| {
+                    credential.credential_type == requirement.credential_type
+                        && requirement.issuer_did.as_ref().is_none_or(|issuer| *issuer == credential.issuer_did)
+                        && credential.commitment_blindings.contains_key(&predicate.attribute)
+                })
+                .ok_or_else(|| AppError::ValidationError(format!(
+                    "No presented {} credential has a signed commitment to {}",
+                    requirement.credential_type, predicate.attribute
+                )))?;
+            let predicate_type = predicate.predicate_type.as_str();
+            if zk_proofs::is_set_predicate(predicate_type) {
+                let proof = self
+                    .credential_service
+                    .create_set_membership_proof(
+                        &credential.id,
+                        &predicate.attribute,
+                        predicate_type,
+                        &zk_proofs::predicate_set(predicate)?,
+                    )
+                    .await?;
+                predicate_proofs.push(proof);
+            } else {
+                comparisons.push((
+                    credential.id.as_str(),
+                    predicate.attribute.as_str(),
+                    predicate_type,
+                    zk_proofs::predicate_value(predicate, today)?,
+                ));
+            }
+        }
+
+        // A single comparison gets its own range proof, several share one aggregated range proof
+        let mut proofs = zk_proofs::PresentationProofs::default();
+        match comparisons.as_slice() {
+            [] => {}
+            [(credential_id, attribute_name, predicate_type, predicate_value)] => {
+                let proof = self
+                    .credential_service
+                    .create_predicate_proof(credential_id, attribute_name, predicate_type, *predicate_value)
+                    .await?;
+                predicate_proofs.push(proof);
+            }
+            _ => {
+                let proof = self.credential_service.create_aggregated_predicate_proof(&comparisons).await?;
+                proofs.aggregated_predicate_proofs.push(proof);
+            }
+        }
+        proofs.predicate_proofs = predicate_proofs;
+
+        // Every credential must be issued to the prover. Credentials bound to a link secret cannot be
+        // mixed with unbound ones, and need the wallet's proof that they share the prover's;
+        // only the wallet knows the secret
+        let mut link_secret_commitments = Vec::new();
+        for credential in &credentials {
+            let credential_data = crate::services::credential::extract_credential(&credential.jwt)?;
+            if credential_data["credentialSubject"]["id"].as_str() != Some(prover_did) {
+                return Err(AppError::ValidationError(format!(
+                    "Credential {} was not issued to {}",
+                    credential.id, prover_did
+                )));
+            }
+            if let Some(commitment) = credential_data[link_secret::LINK_SECRET_PROPERTY].as_str() {
+                link_secret_commitments.push(commitment.to_string());
+            }
+        }
+        if !link_secret_commitments.is_empty() {
+            if link_secret_commitments.len() != credentials.len() {
+                return Err(AppError::ValidationError(
+                    "Credentials bound to a link secret cannot be presented with unbound credentials".to_string(),
+                ));
+            }
+            let proof = request.same_holder_proof.ok_or_else(|| AppError::ValidationError(
+                "Presented credentials are bound to a link secret and need a same-holder proof from the wallet".to_string(),
+            ))?;
+            link_secret::verify_same_holder_proof(
+                &proof,
+                &link_secret_commitments,
+                &presentation_request.verifier_did,
+                &presentation_request.id,
+            )
+            .map_err(|e| AppError::ValidationError(e.to_string()))?;
+            proofs.same_holder_proof = Some(proof);
         }
 
         // Add predicate proofs to presentation data
-        for proof in &request.predicate_proofs {
+        let aggregated = proofs.aggregated_predicate_proofs.iter().flat_map(|proof| &proof.predicates);
+        for proof in proofs.predicate_proofs.iter().chain(aggregated) {
             presentation_data.insert(
                 format!("predicate_{}", proof.attribute_name),
                 json!(proof),
             );
         }
 
-        // Create a presentation JWT
-        let jwt = jwt::create_pq_presentation_jwt(
+        // Create a presentation JWT signed with the prover's DID key, answering the request's nonce
+        let jwt = jwt::create_presentation_jwt(
             prover_did,
             Some(&presentation_request.verifier_did),
+            Some(&presentation_request.id),
             &credential_jwts,
-            prover_private_key.as_ref(),
-            "dummy_public_key".as_bytes(), // Using dummy public key for demonstration
+            &proofs,
+            &private_key,
             Some(3600), // Default to 1 hour
         )?;
 
@@ -220,6 +457,32 @@ impl PresentationService {
         let mut errors = Vec::new();
         let mut is_valid = true;
         let mut credential_subjects = Vec::new();
+        let mut presented_credentials: Vec<PresentedCredential> = Vec::new();
+        let mut link_secret_commitments = Vec::new();
+        let mut proven = Vec::new();
+
+        // The verifier and nonce come from the verifier's own request, never from the presentation
+        let presentation_request = self.db
+            .find_one::<PresentationRequest>(
+                "presentation_requests",
+                mongodb::bson::doc! { "id": &request.presentation_request_id },
+            )
+            .await?
+            .filter(|presentation_request| presentation_request.verifier_did == request.verifier_did)
+            .ok_or_else(|| AppError::NotFoundError(format!(
+                "Presentation request with ID {} not found",
+                request.presentation_request_id
+            )))?;
+        let verifier_did = presentation_request.verifier_did.clone();
+        let nonce = presentation_request.id.as_str();
+        if presentation_request.consumed_at.is_some() {
+            errors.push("Presentation request was already answered".to_string());
+            is_valid = false;
+        }
+        if presentation_request.expires_at.is_some_and(|expires_at| expires_at < Utc::now()) {
+            errors.push("Presentation request is expired".to_string());
+            is_valid = false;
+        }
 
         // Extract the presentation from the JWT
         let presentation_data = match jwt::extract_presentation(&request.presentation_jwt) {
@@ -230,7 +493,7 @@ impl PresentationService {
                     is_valid: false,
                     errors,
                     prover_did: "".to_string(),
-                    verifier_did: "".to_string(),
+                    verifier_did,
                     presentation_type: "".to_string(),
                     created_at: Utc::now(),
                     credential_subjects: Vec::new(),
@@ -239,14 +502,6 @@ impl PresentationService {
         };
 
         // Extract required fields
-        let prover_did = presentation_data["holder"]
-            .as_str()
-            .unwrap_or("")
-            .to_string();
-        let verifier_did = jwt::decode_jwt_unverified(&request.presentation_jwt)?
-            .1
-            .aud
-            .unwrap_or_default();
         let presentation_type = presentation_data["type"]
             .as_array()
             .and_then(|types| types.get(1))
@@ -256,22 +511,44 @@ impl PresentationService {
         
         let created_at = Utc::now(); // JWT doesn't include creation time in the presentation itself
 
-        // Verify the JWT signature
-        match jwt::verify_pq_jwt(&request.presentation_jwt) {
-            Ok(_) => {}
+        // Verify the JWT signature; the prover is the DID that signed it, which must be the holder,
+        // and it must answer this verifier's request
+        let prover_did = match jwt::verify_did_jwt(&request.presentation_jwt, self.resolver.as_ref()).await {
+            Ok((_, claims)) => {
+                if presentation_data["holder"].as_str() != Some(claims.iss.as_str()) {
+                    errors.push("Presentation holder is not the DID that signed it".to_string());
+                    is_valid = false;
+                }
+                if claims.aud.as_deref() != Some(verifier_did.as_str()) {
+                    errors.push(format!("Presentation is not addressed to {}", verifier_did));
+                    is_valid = false;
+                }
+                if claims.additional_claims.get("nonce").and_then(|v| v.as_str()) != Some(nonce) {
+                    errors.push("Presentation does not answer this request's nonce".to_string());
+                    is_valid = false;
+                }
+                claims.iss
+            }
             Err(e) => {
                 errors.push(format!("JWT signature verification failed: {}", e));
                 is_valid = false;
+                String::new()
             }
-        }
+        };
 
         // Verify each credential in the presentation
         if let Some(credentials) = presentation_data["verifiableCredential"].as_array() {
             for credential_jwt in credentials {
-                if let Some(jwt_str) = credential_jwt.as_str() {
+                // JWT credentials are embedded as strings, Data Integrity credentials as objects
+                let secured = match credential_jwt {
+                    Value::Object(_) => Some(jcs::canonicalize(credential_jwt)?),
+                    other => other.as_str().map(str::to_string),
+                };
+                if let Some(jwt_str) = secured.as_deref() {
                     // Verify the credential
                     let verify_request = crate::services::credential::VerifyCredentialRequest {
                         credential_jwt: jwt_str.to_string(),
+                        credential: None,
                     };
                     
                     match self.credential_service.verify_credential(verify_request).await {
@@ -280,9 +557,80 @@ impl PresentationService {
                                 errors.push(format!("Credential verification failed: {:?}", result.errors));
                                 is_valid = false;
                             }
+
+                            // Every credential must be issued to the prover
+                            if result.subject_did != prover_did {
+                                errors.push(format!("Credential subject {} is not the presentation holder", result.subject_did));
+                                is_valid = false;
+                            }
+
+                            // Failures to read the registration time already showed up in the result
+                            let signed_at = self.credential_service.registered_at(&result.issuer_did, jwt_str).await
+                                .ok()
+                                .flatten();
+
+                            // SD-JWTs must be bound to the prover and addressed to the verifier
+                            if SdJwt::is_sd_jwt(jwt_str) {
+                                match sd_jwt::verify(jwt_str, self.resolver.as_ref(), signed_at, Some((&verifier_did, nonce))).await {
+                                    Ok(verified) if verified.holder_did.as_deref() == Some(prover_did.as_str()) => {}
+                                    Ok(_) => {
+                                        errors.push("SD-JWT is not bound to the presentation holder".to_string());
+                                        is_valid = false;
+                                    }
+                                    Err(e) => {
+                                        errors.push(format!("SD-JWT key binding check failed: {}", e));
+                                        is_valid = false;
+                                    }
+                                }
+                            }
+
+                            // mdocs must carry a device signature by the prover over this verifier and nonce
+                            if mdoc::is_mdoc(jwt_str) {
+                                match mdoc::verify(jwt_str, self.resolver.as_ref(), signed_at, Some((&verifier_did, nonce))).await {
+                                    Ok(verified) if verified.holder_did == prover_did => {}
+                                    Ok(_) => {
+                                        errors.push("mdoc device key does not belong to the presentation holder".to_string());
+                                        is_valid = false;
+                                    }
+                                    Err(e) => {
+                                        errors.push(format!("mdoc device authentication failed: {}", e));
+                                        is_valid = false;
+                                    }
+                                }
+                            }
                             
-                            // Extract credential subject
-                            let credential_data = jwt::extract_credential(jwt_str)?;
+                            // BBS proofs must be derived for this verifier and nonce and disclose the prover as subject
+                            if credential_jwt.is_object() && bbs_credential::is_bbs_document(credential_jwt) {
+                                match bbs_credential::verify(credential_jwt, self.resolver.as_ref(), Some((&verifier_did, nonce))).await {
+                                    Ok(_) if credential_jwt["credentialSubject"]["id"].as_str() == Some(prover_did.as_str()) => {}
+                                    Ok(_) => {
+                                        errors.push("BBS credential subject is not the presentation holder".to_string());
+                                        is_valid = false;
+                                    }
+                                    Err(e) => {
+                                        errors.push(format!("BBS presentation check failed: {}", e));
+                                        is_valid = false;
+                                    }
+                                }
+                            }
+
+                            // Extract the credential's types, signed commitments and subject
+                            let credential_data = crate::services::credential::extract_credential(jwt_str)?;
+                            presented_credentials.push(PresentedCredential {
+                                is_valid: result.is_valid,
+                                types: credential_data["type"].as_array()
+                                    .map(|types| types.iter().filter_map(|t| t.as_str().map(str::to_string)).collect())
+                                    .unwrap_or_default(),
+                                issuer_did: result.issuer_did.clone(),
+                                commitments: credential_data[zk_proofs::COMMITMENTS_PROPERTY].as_object()
+                                    .map(|commitments| commitments.iter()
+                                        .filter_map(|(name, commitment)| Some((name.clone(), commitment.as_str()?.to_string())))
+                                        .collect())
+                                    .unwrap_or_default(),
+                            });
+                            if let Some(commitment) = credential_data[link_secret::LINK_SECRET_PROPERTY].as_str() {
+                                link_secret_commitments.push(commitment.to_string());
+                            }
                             if let Some(subject) = credential_data["credentialSubject"].as_object() {
                                 let mut subject_map = HashMap::new();
                                 for (key, value) in subject {
@@ -302,26 +650,162 @@ impl PresentationService {
             }
         }
 
-        // Verify predicate proofs if any
-        if let Some(predicates) = presentation_data["predicateProofs"].as_array() {
-            for predicate in predicates {
-                if let Ok(proof) = serde_json::from_value::<zk_proofs::PredicateProof>(predicate.clone()) {
-                    match zk_proofs::verify_predicate_proof(&proof) {
-                        Ok(valid) => {
-                            if !valid {
-                                errors.push(format!("Predicate proof verification failed for attribute {}", proof.attribute_name));
-                                is_valid = false;
-                            }
-                        }
-                        Err(e) => {
-                            errors.push(format!("Failed to verify predicate proof: {}", e));
-                            is_valid = false;
-                        }
-                    }
+        // Verify predicate proofs against the commitments signed into the presented credentials
+        let proofs = serde_json::from_value::<zk_proofs::PresentationProofs>(presentation_data.clone())
+            .unwrap_or_else(|e| {
+                errors.push(format!("Invalid predicate proofs: {}", e));
+                is_valid = false;
+                zk_proofs::PresentationProofs::default()
+            });
+        let signed_commitment = |proof: &zk_proofs::PredicateProof| {
+            presented_credentials.iter()
+                .find(|credential| credential.signs(proof))
+                .map(|credential| credential.commitments[&proof.attribute_name].as_str())
+        };
+
+        let mut predicate_proofs = Vec::new();
+        for proof in &proofs.predicate_proofs {
+            match signed_commitment(proof) {
+                Some(commitment) => predicate_proofs.push((proof, commitment)),
+                None => {
+                    errors.push(format!(
+                        "No presented credential signs the commitment of the predicate proof for attribute {}",
+                        proof.attribute_name
+                    ));
+                    is_valid = false;
+                }
+            }
+        }
+        let mut aggregated_proofs = Vec::new();
+        for proof in &proofs.aggregated_predicate_proofs {
+            match proof.predicates.iter().map(signed_commitment).collect::<Option<Vec<&str>>>() {
+                Some(commitments) => aggregated_proofs.push((proof, commitments)),
+                None => {
+                    errors.push("No presented credential signs a commitment of the aggregated predicate proof".to_string());
+                    is_valid = false;
                 }
             }
         }
 
+        // All range proofs are checked in one batch; only a failed batch is checked proof by proof
+        let range_proofs: Vec<(&zk_proofs::PredicateProof, &str)> = predicate_proofs.iter()
+            .filter(|(proof, _)| !zk_proofs::is_set_predicate(&proof.predicate_type))
+            .copied()
+            .collect();
+        let batch_verified = zk_proofs::verify_predicate_proofs_batch(&range_proofs, &aggregated_proofs).unwrap_or(false);
+
+        for (proof, commitment) in predicate_proofs {
+            let verified = if batch_verified && !zk_proofs::is_set_predicate(&proof.predicate_type) {
+                Ok(true)
+            } else {
+                zk_proofs::verify_predicate_proof(proof, commitment)
+            };
+            match verified {
+                Ok(true) => proven.push(proof.clone()),
+                Ok(false) => {
+                    errors.push(format!("Predicate proof verification failed for attribute {}", proof.attribute_name));
+                    is_valid = false;
+                }
+                Err(e) => {
+                    errors.push(format!("Failed to verify predicate proof: {}", e));
+                    is_valid = false;
+                }
+            }
+        }
+
+        for (proof, commitments) in aggregated_proofs {
+            let verified = if batch_verified {
+                Ok(true)
+            } else {
+                zk_proofs::verify_aggregated_predicate_proof(proof, &commitments)
+            };
+            match verified {
+                Ok(true) => proven.extend(proof.predicates.iter().cloned()),
+                Ok(false) => {
+                    errors.push("Aggregated predicate proof verification failed".to_string());
+                    is_valid = false;
+                }
+                Err(e) => {
+                    errors.push(format!("Failed to verify aggregated predicate proof: {}", e));
+                    is_valid = false;
+                }
+            }
+        }
+
+        // Credentials bound to link secrets must all be bound, and proven to share the prover's
+        if !link_secret_commitments.is_empty() {
+            if link_secret_commitments.len() != presented_credentials.len() {
+                errors.push("Presentation mixes credentials bound to a link secret with unbound credentials".to_string());
+                is_valid = false;
+            }
+            let verified = match &proofs.same_holder_proof {
+                Some(proof) => {
+                    link_secret::verify_same_holder_proof(proof, &link_secret_commitments, &verifier_did, nonce)
+                }
+                None => Err(AppError::SsiError(
+                    "Presentation has no proof that its credentials share one holder".to_string(),
+                )),
+            };
+            if let Err(e) = verified {
+                errors.push(format!("Failed to verify holder binding: {}", e));
+                is_valid = false;
+            }
+        }
+
+        // Every requirement of the answered request must be met by a valid credential of its type, from its issuer
+        let meeting = |requirement: &CredentialRequirement| {
+            presented_credentials.iter()
+                .filter(|credential| credential.is_valid && meets_requirement(requirement, &credential.types, &credential.issuer_did))
+                .collect::<Vec<_>>()
+        };
+        for requirement in &presentation_request.required_credentials {
+            if meeting(requirement).is_empty() {
+                errors.push(match &requirement.issuer_did {
+                    Some(issuer_did) => format!("No valid {} credential from {} is presented", requirement.credential_type, issuer_did),
+                    None => format!("No valid {} credential is presented", requirement.credential_type),
+                });
+                is_valid = false;
+            }
+        }
+
+        // The predicates of the answered request must be proven over a commitment signed into a credential
+        // meeting the same requirement, relative dates as of today
+        let today = Utc::now().date_naive();
+        for requirement in &presentation_request.required_credentials {
+            let Some(predicate) = &requirement.predicate else {
+                continue;
+            };
+            let credentials = meeting(requirement);
+            let mut satisfied = false;
+            for proof in proven.iter().filter(|proof| credentials.iter().any(|credential| credential.signs(proof))) {
+                satisfied |= zk_proofs::satisfies(proof, predicate, today)?;
+            }
+            if !satisfied {
+                errors.push(format!(
+                    "Predicate {} {} is not proven",
+                    predicate.attribute,
+                    predicate.predicate_type.as_str()
+                ));
+                is_valid = false;
+            }
+        }
+
+        // Consume the request, so a valid presentation cannot be replayed; only one
+        // concurrent verification can win this update
+        if is_valid {
+            let consumed = self.db
+                .update_one(
+                    "presentation_requests",
+                    mongodb::bson::doc! { "id": &presentation_request.id, "consumed_at": mongodb::bson::Bson::Null },
+                    mongodb::bson::doc! { "$set": { "consumed_at": mongodb::bson::to_bson(&Utc::now())? } },
+                )
+                .await?;
+            if !consumed {
+                errors.push("Presentation request was already answered".to_string());
+                is_valid = false;
+            }
+        }
+
         Ok(PresentationVerificationResult {
             is_valid,
             errors,
@@ -390,4 +874,77 @@ impl PresentationService {
 
         Ok(true)
     }
-}
\ No newline at end of file
+}
+
+/// Presented credential, as checked against the request's requirements
+struct PresentedCredential {
+    is_valid: bool,
+    types: Vec<String>,
+    issuer_did: String,
+    /// Commitments signed into the credential, by attribute name
+    commitments: HashMap<String, String>,
+}
+
+impl PresentedCredential {
+    /// Whether the credential signs the commitment a predicate proof is made over
+    fn signs(&self, proof: &zk_proofs::PredicateProof) -> bool {
+        self.commitments.get(&proof.attribute_name) == Some(&proof.commitment)
+    }
+}
+
+/// Whether a credential of the given `type` list and issuer meets a requirement.
+///
+/// Issuers put the requested type into `type` or `vct` as a type name; mdocs carry their document type.
+fn meets_requirement(requirement: &CredentialRequirement, types: &[String], issuer_did: &str) -> bool {
+    let type_name = jwt::credential_types(&requirement.credential_type).pop().unwrap_or_default();
+    let doc_type = requirement.mdoc.as_ref().map(|items| items.doc_type.as_str());
+    let type_matches = types.iter().any(|t| {
+        *t == type_name || *t == requirement.credential_type || Some(t.as_str()) == doc_type
+    });
+
+    type_matches && requirement.issuer_did.as_ref().is_none_or(|issuer| issuer == issuer_did)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MdocItemsRequest;
+
+    const ISSUER: &str = "did:alyra:issuer";
+
+    fn requirement(credential_type: &str, issuer_did: Option<&str>) -> CredentialRequirement {
+        CredentialRequirement {
+            credential_type: credential_type.to_string(),
+            issuer_did: issuer_did.map(str::to_string),
+            required_attributes: Vec::new(),
+            predicate: None,
+            mdoc: None,
+        }
+    }
+
+    fn types(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn matches_credentials_of_the_required_type_and_issuer() {
+        let v2 = types(&["VerifiableCredential", "UniversityDegree"]);
+        let v1 = types(&["VerifiableCredential", "PostQuantumCredential", "UniversityDegree"]);
+
+        assert!(meets_requirement(&requirement("university degree", Some(ISSUER)), &v2, ISSUER));
+        assert!(meets_requirement(&requirement("UniversityDegree", None), &v1, "did:alyra:other"));
+
+        let mut mdl = requirement("mDL", None);
+        mdl.mdoc = Some(MdocItemsRequest { doc_type: "org.iso.18013.5.1.mDL".to_string(), name_spaces: HashMap::new() });
+        assert!(meets_requirement(&mdl, &types(&["VerifiableCredential", "org.iso.18013.5.1.mDL"]), ISSUER));
+    }
+
+    #[test]
+    fn rejects_credentials_of_another_type_or_issuer() {
+        let degree = types(&["VerifiableCredential", "UniversityDegree"]);
+
+        assert!(!meets_requirement(&requirement("DriverLicense", None), &degree, ISSUER));
+        assert!(!meets_requirement(&requirement("UniversityDegree", Some(ISSUER)), &degree, "did:alyra:other"));
+        assert!(!meets_requirement(&requirement("UniversityDegree", None), &types(&["VerifiableCredential", "PostQuantumCredential"]), ISSUER));
+    }
+}
@@ -70,6 +70,7 @@ impl QrService {
             expires_at: Some(chrono::Utc::now() + chrono::Duration::hours(24)),
             callback_url: None,
             required_credentials: vec![],
+            consumed_at: None,
         };
 
         // Create a QR code for the request
@@ -144,6 +145,7 @@ impl QrService {
             expires_at: Some(chrono::Utc::now() + chrono::Duration::hours(24)),
             callback_url: None,
             required_credentials: vec![],
+            consumed_at: None,
         };
 
         // Create a QR code for the request
@@ -1,8 +1,8 @@
 use crate::blockchain::EthereumClient;
 use crate::db::Database;
 use crate::error::AppError;
-use crate::models::{AttributeDataType, Schema, SchemaAttribute};
-use crate::utils::crypto;
+use crate::models::{AttributeDataType, MdocProfile, Schema, SchemaAttribute, VcVersion};
+use crate::utils::{crypto, zk_proofs};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value};
@@ -21,6 +21,11 @@ pub struct CreateSchemaRequest {
     pub name: String,
     pub version: String,
     pub attributes: Vec<SchemaAttributeRequest>,
+    #[serde(default)]
+    pub vc_version: VcVersion,
+    /// Issue credentials of this schema as ISO 18013-5 mdocs
+    #[serde(default)]
+    pub mdoc: Option<MdocProfile>,
 }
 
 /// Schema attribute request
@@ -87,6 +92,8 @@ impl SchemaService {
             version: request.version,
             issuer_did: issuer_did.to_string(),
             attributes,
+            vc_version: request.vc_version,
+            mdoc: request.mdoc,
             created_at: now,
             updated_at: now,
         };
@@ -186,9 +193,9 @@ impl SchemaService {
                             errors.push(format!("Attribute {} must be a date string", name));
                             is_valid = false;
                         } else if let Some(date_str) = value.as_str() {
-                            if chrono::DateTime::parse_from_rfc3339(date_str).is_err() {
+                            if zk_proofs::parse_date(date_str).is_none() {
                                 errors.push(format!(
-                                    "Attribute {} must be a valid RFC3339 date",
+                                    "Attribute {} must be a valid RFC3339 or YYYY-MM-DD date",
                                     name
                                 ));
                                 is_valid = false;
@@ -262,6 +269,8 @@ impl SchemaService {
             version: request.version,
             issuer_did: issuer_did.to_string(),
             attributes,
+            vc_version: request.vc_version,
+            mdoc: request.mdoc,
             created_at: existing_schema.created_at,
             updated_at: now,
         };
@@ -0,0 +1,217 @@
+use crate::config::Config;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::{RevokedToken, User};
+use crate::utils::jwt::{self, HmacAlgorithm, JwtClaims};
+use chrono::{Duration, Utc};
+use mongodb::bson::doc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const ISSUER: &str = "ssi-wallet";
+const KEY_ID: &str = "session";
+const REVOKED_TOKENS: &str = "revoked_tokens";
+
+/// Session token settings taken from the configuration
+#[derive(Debug, Clone)]
+pub struct SessionSettings {
+    secret: Vec<u8>,
+    algorithm: HmacAlgorithm,
+    access_ttl: i64,
+    refresh_ttl: i64,
+}
+
+impl SessionSettings {
+    /// Build the settings from `JWT_SECRET`, `JWT_ALGORITHM`, `JWT_EXPIRATION` and `JWT_REFRESH_EXPIRATION`
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            secret: config.jwt_secret.as_bytes().to_vec(),
+            algorithm: config.jwt_algorithm,
+            access_ttl: config.jwt_expiration as i64,
+            refresh_ttl: config.jwt_refresh_expiration as i64,
+        }
+    }
+}
+
+/// Kind of session token, carried in the `token_use` claim
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TokenUse {
+    #[serde(rename = "access")]
+    Access,
+    #[serde(rename = "refresh")]
+    Refresh,
+}
+
+/// Access and refresh token pair for a session
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub refresh_expires_in: i64,
+}
+
+/// Verified session token claims
+#[derive(Debug, Clone)]
+pub struct SessionClaims {
+    pub did: String,
+    pub session_id: String,
+    pub token_id: String,
+    pub expires_at: i64,
+}
+
+/// Session token service
+pub struct SessionService {
+    db: Arc<Database>,
+    settings: SessionSettings,
+}
+
+impl SessionService {
+    /// Create a new session token service
+    pub fn new(db: Arc<Database>, settings: SessionSettings) -> Self {
+        Self { db, settings }
+    }
+
+    /// Start a new session for a user
+    pub fn create_session(&self, user: &User) -> Result<SessionTokens, AppError> {
+        self.issue_tokens(user, &Uuid::new_v4().to_string())
+    }
+
+    /// Issue a new token pair for an existing session
+    pub fn resume_session(&self, user: &User, session_id: &str) -> Result<SessionTokens, AppError> {
+        self.issue_tokens(user, session_id)
+    }
+
+    /// Verify a session token of the expected kind and check it has not been revoked
+    pub async fn verify(&self, token: &str, expected: TokenUse) -> Result<SessionClaims, AppError> {
+        let session = self.decode(token, expected)?;
+
+        if self.is_revoked(&session).await? {
+            return Err(AppError::AuthError("Token has been revoked".to_string()));
+        }
+
+        Ok(session)
+    }
+
+    /// Spend a refresh token, which is single use, and return its session
+    pub async fn rotate(&self, refresh_token: &str) -> Result<SessionClaims, AppError> {
+        let session = self.decode(refresh_token, TokenUse::Refresh)?;
+
+        let session_revoked = self.db
+            .count_documents(REVOKED_TOKENS, doc! { "jti": &session.session_id })
+            .await? > 0;
+        if session_revoked {
+            return Err(AppError::AuthError("Token has been revoked".to_string()));
+        }
+
+        // Spending the token records its jti; of several concurrent refreshes only one can.
+        // Replaying a spent refresh token means it leaked, so end the whole session.
+        if !self.revoke(&session.did, &session.token_id, session.expires_at).await? {
+            self.revoke(&session.did, &session.session_id, self.session_expiry()).await?;
+            return Err(AppError::AuthError("Refresh token has already been used".to_string()));
+        }
+
+        Ok(session)
+    }
+
+    /// End a session: the access token and every token sharing its session ID stop working
+    pub async fn end_session(&self, session: &SessionClaims) -> Result<(), AppError> {
+        self.revoke(&session.did, &session.token_id, session.expires_at).await?;
+        self.revoke(&session.did, &session.session_id, self.session_expiry()).await?;
+        Ok(())
+    }
+
+    /// Record a token or session ID as revoked; returns false if it already was
+    pub async fn revoke(&self, did: &str, jti: &str, expires_at: i64) -> Result<bool, AppError> {
+        // Drop records for tokens that have expired on their own
+        self.db
+            .delete_many(REVOKED_TOKENS, doc! { "expires_at": { "$lt": Utc::now().timestamp() } })
+            .await?;
+
+        let record = RevokedToken {
+            jti: jti.to_string(),
+            did: did.to_string(),
+            expires_at,
+            revoked_at: Utc::now(),
+        };
+        self.db.insert_one_if_absent(REVOKED_TOKENS, doc! { "jti": jti }, &record).await
+    }
+
+    async fn is_revoked(&self, session: &SessionClaims) -> Result<bool, AppError> {
+        let filter = doc! { "jti": { "$in": [&session.token_id, &session.session_id] } };
+        Ok(self.db.count_documents(REVOKED_TOKENS, filter).await? > 0)
+    }
+
+    fn decode(&self, token: &str, expected: TokenUse) -> Result<SessionClaims, AppError> {
+        let (_, claims) = jwt::verify_hmac_jwt(token, &self.settings.secret, self.settings.algorithm)?;
+
+        if claims.iss != ISSUER {
+            return Err(AppError::AuthError(format!("Unexpected token issuer {}", claims.iss)));
+        }
+
+        let token_use: TokenUse = claims.additional_claims.get("token_use")
+            .cloned()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .ok_or_else(|| AppError::AuthError("Token missing token_use".to_string()))?;
+        if token_use != expected {
+            return Err(AppError::AuthError("Wrong kind of session token".to_string()));
+        }
+
+        let did = claims.sub.ok_or_else(|| AppError::AuthError("Token missing subject".to_string()))?;
+        let session_id = claims.additional_claims.get("sid")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| AppError::AuthError("Token missing session ID".to_string()))?
+            .to_string();
+
+        Ok(SessionClaims {
+            did,
+            session_id,
+            token_id: claims.jti,
+            expires_at: claims.exp.unwrap_or_else(|| self.session_expiry()),
+        })
+    }
+
+    fn issue_tokens(&self, user: &User, session_id: &str) -> Result<SessionTokens, AppError> {
+        let mut access_claims = self.claims(&user.did, session_id, TokenUse::Access, self.settings.access_ttl);
+        access_claims.additional_claims.insert("name".to_string(), json!(user.name));
+        access_claims.additional_claims.insert("email".to_string(), json!(user.email));
+
+        let refresh_claims = self.claims(&user.did, session_id, TokenUse::Refresh, self.settings.refresh_ttl);
+
+        Ok(SessionTokens {
+            access_token: jwt::create_hmac_jwt(&access_claims, KEY_ID, &self.settings.secret, self.settings.algorithm)?,
+            refresh_token: jwt::create_hmac_jwt(&refresh_claims, KEY_ID, &self.settings.secret, self.settings.algorithm)?,
+            token_type: "Bearer".to_string(),
+            expires_in: self.settings.access_ttl,
+            refresh_expires_in: self.settings.refresh_ttl,
+        })
+    }
+
+    fn claims(&self, did: &str, session_id: &str, token_use: TokenUse, ttl: i64) -> JwtClaims {
+        let now = Utc::now();
+
+        let mut claims = JwtClaims {
+            iss: ISSUER.to_string(),
+            sub: Some(did.to_string()),
+            aud: None,
+            exp: Some((now + Duration::seconds(ttl)).timestamp()),
+            nbf: Some(now.timestamp()),
+            iat: now.timestamp(),
+            jti: Uuid::new_v4().to_string(),
+            additional_claims: HashMap::new(),
+        };
+        claims.additional_claims.insert("sid".to_string(), json!(session_id));
+        claims.additional_claims.insert("token_use".to_string(), json!(token_use));
+
+        claims
+    }
+
+    /// Latest expiry of any token in a session started now
+    fn session_expiry(&self) -> i64 {
+        (Utc::now() + Duration::seconds(self.settings.refresh_ttl)).timestamp()
+    }
+}
@@ -0,0 +1,424 @@
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::blockchain::EthereumClient;
+use crate::config::StatusListConfig;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::ipfs::IpfsClient;
+use crate::models::{Credential, StatusList, StatusListEntry, StatusPurpose};
+use crate::resolver::DidResolver;
+use crate::services::key::KeyService;
+use crate::utils::jwt::{self, JwtClaims, JwtHeader};
+use crate::utils::{crypto, status_list};
+
+/// Collection of the issuers' status lists
+const STATUS_LISTS: &str = "status_lists";
+
+/// Path under which the engine serves status list credentials
+pub const STATUS_LIST_PATH: &str = "/api/status-lists";
+
+/// Attempts at a status list update before giving up on concurrent ones
+const MAX_ATTEMPTS: usize = 5;
+
+/// Largest status list credential accepted from a remote host
+const MAX_CREDENTIAL_BYTES: usize = 4 * 1024 * 1024;
+
+/// Timeout of a remote status list request
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Status list credential whose signature checked out
+#[derive(Debug)]
+pub struct VerifiedStatusList {
+    pub issuer_did: String,
+    /// Key that signed the list, and when, if known from its on-chain anchor
+    pub key_id: String,
+    pub signed_at: Option<DateTime<Utc>>,
+    pub purpose: StatusPurpose,
+    pub bits: Vec<u8>,
+}
+
+/// Fetches and caches the status lists credentials are checked against.
+///
+/// Shared across requests so cached lists outlive a single service.
+pub struct StatusListCache {
+    http: reqwest::Client,
+    ttl: Duration,
+    cache: Mutex<HashMap<String, (Instant, Arc<VerifiedStatusList>)>>,
+}
+
+impl StatusListCache {
+    pub fn new(config: &StatusListConfig) -> Result<Self, AppError> {
+        let http = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .map_err(|e| AppError::ConfigError(format!("Failed to build status list HTTP client: {}", e)))?;
+
+        Ok(Self {
+            http,
+            ttl: Duration::from_secs(config.cache_ttl),
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Cached list at a URL, if still fresh
+    fn cached(&self, url: &str) -> Option<Arc<VerifiedStatusList>> {
+        let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.get(url)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < self.ttl)
+            .map(|(_, list)| list.clone())
+    }
+
+    fn insert(&self, url: &str, list: Arc<VerifiedStatusList>) {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < self.ttl);
+        cache.insert(url.to_string(), (Instant::now(), list));
+    }
+
+    /// Drop a list that was just republished
+    fn invalidate(&self, url: &str) {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).remove(url);
+    }
+
+    /// Download a status list credential JWT, bare or enveloped
+    async fn download(&self, url: &str) -> Result<String, AppError> {
+        let mut response = self.http.get(url)
+            .header(reqwest::header::ACCEPT, "application/vc+jwt, application/vc+ld+json, application/json")
+            .send()
+            .await
+            .map_err(|e| AppError::SsiError(format!("Failed to fetch {}: {}", url, e)))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::SsiError(format!("Fetching {} returned {}", url, response.status())));
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await
+            .map_err(|e| AppError::SsiError(format!("Failed to read {}: {}", url, e)))?
+        {
+            body.extend_from_slice(&chunk);
+            if body.len() > MAX_CREDENTIAL_BYTES {
+                return Err(AppError::SsiError(format!("Status list at {} is too large", url)));
+            }
+        }
+
+        let body = String::from_utf8(body)
+            .map_err(|_| AppError::SsiError(format!("Status list at {} is not text", url)))?;
+        match serde_json::from_str::<Value>(&body) {
+            Ok(envelope) => Ok(status_list::unwrap_envelope(&envelope)?.to_string()),
+            Err(_) => Ok(body.trim().to_string()),
+        }
+    }
+}
+
+/// Status list service: issuers' Bitstring Status Lists, their publication and their checking
+pub struct StatusListService {
+    db: Arc<Database>,
+    ipfs: Arc<IpfsClient>,
+    blockchain: Arc<EthereumClient>,
+    key_service: KeyService,
+    resolver: Arc<dyn DidResolver>,
+    cache: Arc<StatusListCache>,
+    config: StatusListConfig,
+}
+
+impl StatusListService {
+    /// Create a new status list service
+    pub fn new(
+        db: Arc<Database>,
+        ipfs: Arc<IpfsClient>,
+        blockchain: Arc<EthereumClient>,
+        key_service: KeyService,
+        resolver: Arc<dyn DidResolver>,
+        cache: Arc<StatusListCache>,
+        config: StatusListConfig,
+    ) -> Self {
+        Self {
+            db,
+            ipfs,
+            blockchain,
+            key_service,
+            resolver,
+            cache,
+            config,
+        }
+    }
+
+    /// Reserve a bit for a new credential in each of the issuer's status lists
+    pub async fn allocate(&self, issuer_did: &str) -> Result<Vec<StatusListEntry>, AppError> {
+        let mut entries = Vec::new();
+        for purpose in [StatusPurpose::Revocation, StatusPurpose::Suspension] {
+            entries.push(self.allocate_in(issuer_did, purpose).await?);
+        }
+        Ok(entries)
+    }
+
+    /// Reserve the next free index of the issuer's list for a purpose, starting a new list when it is full
+    async fn allocate_in(&self, issuer_did: &str, purpose: StatusPurpose) -> Result<StatusListEntry, AppError> {
+        let filter = mongodb::bson::doc! {
+            "issuer_did": issuer_did,
+            "purpose": purpose.as_str(),
+            "next_index": { "$lt": status_list::LIST_SIZE as i64 },
+        };
+
+        for _ in 0..MAX_ATTEMPTS {
+            let list = match self.db.find_one::<StatusList>(STATUS_LISTS, filter.clone()).await? {
+                Some(list) => list,
+                None => self.create_list(issuer_did, purpose).await?,
+            };
+
+            // Another issuance may have taken the index meanwhile
+            let reserved = self.db.update_one(
+                STATUS_LISTS,
+                mongodb::bson::doc! { "id": &list.id, "next_index": list.next_index },
+                mongodb::bson::doc! { "$inc": { "next_index": 1 } },
+            ).await?;
+            if reserved {
+                return Ok(StatusListEntry { list_id: list.id, purpose, index: list.next_index });
+            }
+        }
+
+        Err(AppError::DatabaseError(format!("Could not reserve a {} status list index", purpose.as_str())))
+    }
+
+    /// Create and publish an empty status list
+    async fn create_list(&self, issuer_did: &str, purpose: StatusPurpose) -> Result<StatusList, AppError> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let list = StatusList {
+            url: self.list_url(&id),
+            id,
+            issuer_did: issuer_did.to_string(),
+            purpose,
+            encoded_list: status_list::empty()?,
+            next_index: 0,
+            version: 0,
+            credential: None,
+            ipfs_cid: None,
+            anchor_tx: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.db.insert_one(STATUS_LISTS, &list).await?;
+
+        self.publish(list).await
+    }
+
+    /// `credentialStatus` of a credential holding the given entries
+    pub async fn credential_status(&self, entries: &[StatusListEntry]) -> Result<Value, AppError> {
+        let mut statuses = Vec::new();
+        for entry in entries {
+            let list = self.find_list(&entry.list_id).await?;
+            statuses.push(status_list::entry(&list.url, entry.purpose, entry.index));
+        }
+        Ok(json!(statuses))
+    }
+
+    /// Set or clear a credential's bit in its list for a purpose and republish the list.
+    ///
+    /// Returns false for credentials issued without status list entries.
+    pub async fn set_status(&self, credential: &Credential, purpose: StatusPurpose, value: bool) -> Result<bool, AppError> {
+        let Some(entry) = credential.status_entries.iter().find(|entry| entry.purpose == purpose) else {
+            return Ok(false);
+        };
+
+        for _ in 0..MAX_ATTEMPTS {
+            let mut list = self.find_list(&entry.list_id).await?;
+            let mut bits = status_list::decode(&list.encoded_list)?;
+            if status_list::bit(&bits, entry.index as usize)? == value {
+                return Ok(true);
+            }
+            status_list::set_bit(&mut bits, entry.index as usize, value)?;
+            let encoded_list = status_list::encode(&bits)?;
+
+            // Apply the change only to the version it was made on, so concurrent changes are not lost
+            let updated = self.db.update_one(
+                STATUS_LISTS,
+                mongodb::bson::doc! { "id": &list.id, "version": list.version },
+                mongodb::bson::doc! {
+                    "$set": { "encoded_list": &encoded_list, "updated_at": mongodb::bson::to_bson(&Utc::now())? },
+                    "$inc": { "version": 1 },
+                },
+            ).await?;
+            if updated {
+                list.encoded_list = encoded_list;
+                list.version += 1;
+                self.publish(list).await?;
+                return Ok(true);
+            }
+        }
+
+        Err(AppError::DatabaseError(format!("Could not update status list {}", entry.list_id)))
+    }
+
+    /// Sign a list with the issuer's key and publish it to IPFS, anchoring its hash on chain when configured
+    async fn publish(&self, mut list: StatusList) -> Result<StatusList, AppError> {
+        // Step 1: Sign the list as a VCDM 2.0 credential JWT
+        let issuer_key = self.key_service.signing_key(&list.issuer_did).await?;
+        let now = Utc::now();
+        let document = status_list::list_credential(&list.issuer_did, &list.url, list.purpose, &list.encoded_list, now);
+        let header = JwtHeader {
+            alg: "Dilithium".to_string(),
+            typ: "vc+jwt".to_string(),
+            kid: issuer_key.key_id.clone(),
+        };
+        let claims = JwtClaims {
+            iss: list.issuer_did.clone(),
+            sub: None,
+            aud: None,
+            exp: None,
+            nbf: Some(now.timestamp()),
+            iat: now.timestamp(),
+            jti: list.url.clone(),
+            additional_claims: match document {
+                Value::Object(document) => document.into_iter().collect(),
+                _ => HashMap::new(),
+            },
+        };
+        let credential = jwt::create_pq_jwt_with_store(&header, &claims, self.key_service.keystore(), &issuer_key.key_handle).await?;
+
+        // Step 2: Publish it, enveloped, to IPFS
+        let cid = self.ipfs.upload_json(&status_list::envelope(&credential)).await?;
+
+        // Step 3: Anchor its hash in the SSIRegistry, with the CID as metadata
+        let anchor_tx = if self.config.anchor {
+            let tx_hash = self.blockchain
+                .register_credential(&list.issuer_did, &crypto::hash_to_hex(credential.as_bytes()), &cid)
+                .await?;
+            Some(format!("{:?}", tx_hash))
+        } else {
+            None
+        };
+
+        // A newer version is published by whoever made it
+        self.db.update_one(
+            STATUS_LISTS,
+            mongodb::bson::doc! { "id": &list.id, "version": list.version },
+            mongodb::bson::doc! { "$set": {
+                "credential": credential.as_str(),
+                "ipfs_cid": cid.as_str(),
+                "anchor_tx": anchor_tx.clone(),
+                "updated_at": mongodb::bson::to_bson(&now)?,
+            } },
+        ).await?;
+        self.cache.invalidate(&list.url);
+
+        tracing::info!("Published {} status list {} of {} as {}", list.purpose.as_str(), list.id, list.issuer_did, cid);
+        list.credential = Some(credential);
+        list.ipfs_cid = Some(cid);
+        list.anchor_tx = anchor_tx;
+        Ok(list)
+    }
+
+    /// Sign every published list of an issuer again, with its current key
+    pub async fn republish(&self, issuer_did: &str) -> Result<(), AppError> {
+        let lists: Vec<StatusList> = self.db.find_many(
+            STATUS_LISTS,
+            mongodb::bson::doc! { "issuer_did": issuer_did, "credential": { "$ne": mongodb::bson::Bson::Null } },
+        ).await?;
+        for list in lists {
+            self.publish(list).await?;
+        }
+        Ok(())
+    }
+
+    /// Latest signed credential of a status list
+    pub async fn published(&self, list_id: &str) -> Result<String, AppError> {
+        self.find_list(list_id).await?
+            .credential
+            .ok_or_else(|| AppError::NotFoundError(format!("Status list {} is not published yet", list_id)))
+    }
+
+    /// Fetch the status list credential at a URL and verify its signature, from the cache while fresh.
+    ///
+    /// The engine's own lists are read back from IPFS; others are fetched from their URL.
+    pub async fn fetch(&self, url: &str) -> Result<Arc<VerifiedStatusList>, AppError> {
+        if let Some(list) = self.cache.cached(url) {
+            return Ok(list);
+        }
+
+        let credential = match self.own_list_id(url) {
+            Some(list_id) => {
+                let cid = self.find_list(list_id).await?.ipfs_cid
+                    .ok_or_else(|| AppError::NotFoundError(format!("Status list {} is not published yet", list_id)))?;
+                let envelope: Value = self.ipfs.get_json(&cid).await?;
+                status_list::unwrap_envelope(&envelope)?.to_string()
+            }
+            None => self.cache.download(url).await?,
+        };
+
+        let list = Arc::new(self.verify(url, &credential).await?);
+        self.cache.insert(url, list.clone());
+        Ok(list)
+    }
+
+    /// Verify a status list credential fetched from a URL
+    async fn verify(&self, url: &str, credential: &str) -> Result<VerifiedStatusList, AppError> {
+        // Step 1: Lists anchored by this deployment must be registered on chain, which
+        // also gives the time they were signed at
+        let signed_at = if self.config.anchor {
+            let (_, claims) = jwt::decode_jwt_unverified(credential)?;
+            let registered_at = self.blockchain
+                .credential_registered_at(&claims.iss, &crypto::hash_to_hex(credential.as_bytes()))
+                .await?;
+            if registered_at.is_none() {
+                return Err(AppError::SsiError(format!("Status list at {} is not anchored on chain", url)));
+            }
+            registered_at
+        } else {
+            None
+        };
+
+        // Step 2: Check the signature and that the credential is the list at this URL
+        let (header, claims) = jwt::verify_did_jwt_signed_at(credential, self.resolver.as_ref(), signed_at).await?;
+        let document = jwt::extract_credential(credential)?;
+        if document["id"] != url {
+            return Err(AppError::SsiError(format!("Status list credential is not the one at {}", url)));
+        }
+        if !document["type"].as_array().is_some_and(|types| types.iter().any(|t| t == status_list::CREDENTIAL_TYPE)) {
+            return Err(AppError::SsiError(format!("{} is not a {}", url, status_list::CREDENTIAL_TYPE)));
+        }
+        if document["issuer"] != claims.iss.as_str() {
+            return Err(AppError::SsiError("Status list issuer does not match the JWT issuer".to_string()));
+        }
+
+        // Step 3: Decode the bitstring
+        let subject = &document["credentialSubject"];
+        if subject["type"] != status_list::LIST_TYPE {
+            return Err(AppError::SsiError(format!("{} has no {} subject", url, status_list::LIST_TYPE)));
+        }
+        let purpose = serde_json::from_value(subject["statusPurpose"].clone())
+            .map_err(|_| AppError::SsiError(format!("Unsupported status purpose of {}", url)))?;
+        let bits = status_list::decode(subject["encodedList"].as_str().unwrap_or_default())?;
+
+        Ok(VerifiedStatusList {
+            issuer_did: claims.iss,
+            key_id: header.kid,
+            signed_at,
+            purpose,
+            bits,
+        })
+    }
+
+    async fn find_list(&self, list_id: &str) -> Result<StatusList, AppError> {
+        self.db.find_one::<StatusList>(STATUS_LISTS, mongodb::bson::doc! { "id": list_id }).await?
+            .ok_or_else(|| AppError::NotFoundError(format!("Status list {} not found", list_id)))
+    }
+
+    /// URL the engine serves a status list at
+    fn list_url(&self, list_id: &str) -> String {
+        format!("{}{}/{}", self.config.base_url, STATUS_LIST_PATH, list_id)
+    }
+
+    /// ID of a list the engine serves, from its URL
+    fn own_list_id<'a>(&self, url: &'a str) -> Option<&'a str> {
+        url.strip_prefix(self.config.base_url.as_str())?
+            .strip_prefix(STATUS_LIST_PATH)?
+            .strip_prefix('/')
+            .filter(|list_id| !list_id.is_empty() && !list_id.contains('/'))
+    }
+}
@@ -122,9 +122,6 @@ impl UserService {
     /// Count the number of users
     pub async fn count_users(&self) -> Result<u64, AppError> {
         let filter = mongodb::bson::doc! {};
-        let count = self.db.users().count_documents(filter).await
-            .map_err(|e| AppError::DatabaseError(format!("Failed to count users: {}", e)))?;
-        
-        Ok(count)
+        self.db.count_documents("users", filter).await
     }
 }
\ No newline at end of file
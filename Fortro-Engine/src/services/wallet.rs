@@ -1,10 +1,11 @@
 use crate::db::Database;
 use crate::error::AppError;
-use crate::models::{ConsentRecord, Credential, Presentation, User, AccessLevel, ExpirationPolicy};
+use crate::models::{ConsentRecord, Credential, Presentation, RevocationRecord, User, AccessLevel, ExpirationPolicy};
 use crate::services::credential::CredentialService;
 use crate::services::presentation::PresentationService;
-use crate::utils::{crypto, did, jwt, qr};
+use crate::utils::{crypto, did, qr};
 use chrono::{DateTime, Utc};
+use pqc_kyber::KYBER_PUBLICKEYBYTES;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
@@ -72,6 +73,9 @@ pub struct CredentialSummary {
     pub issuance_date: DateTime<Utc>,
     pub expiration_date: Option<DateTime<Utc>>,
     pub status: String,
+    /// Who revoked the credential, when and why
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revocation: Option<RevocationRecord>,
 }
 
 /// Wallet statistics
@@ -81,6 +85,7 @@ pub struct WalletStatistics {
     pub active_credentials: usize,
     pub expired_credentials: usize,
     pub revoked_credentials: usize,
+    pub suspended_credentials: usize,
     pub total_presentations: usize,
     pub active_consents: usize,
 }
@@ -121,6 +126,25 @@ impl WalletService {
         })
     }
 
+    /// Publish the holder's Kyber768 public key as the key agreement key of their DID document
+    pub async fn set_key_agreement_key(&self, did: &str, public_key: &str) -> Result<String, AppError> {
+        if !did.starts_with("did:alyra:") {
+            return Err(AppError::ValidationError("Only did:alyra DIDs can publish a key agreement key".to_string()));
+        }
+        let key_bytes = bs58::decode(public_key).into_vec()
+            .ok()
+            .filter(|key| key.len() == KYBER_PUBLICKEYBYTES)
+            .ok_or_else(|| AppError::ValidationError("Key agreement key must be a base58 Kyber768 public key".to_string()))?;
+
+        let mut user = self.db.find_user_by_did(did).await?
+            .ok_or_else(|| AppError::NotFoundError(format!("Wallet with DID {} not found", did)))?;
+        user.kyber_public_key = Some(bs58::encode(&key_bytes).into_string());
+        user.updated_at = Utc::now();
+        self.db.update_user(&user).await?;
+
+        Ok(format!("{}#kem-keys-1", did))
+    }
+
     /// Get wallet by DID
     pub async fn get_wallet(&self, did: &str) -> Result<WalletResponse, AppError> {
         let user = self.db.find_user_by_did(did).await?
@@ -139,16 +163,19 @@ impl WalletService {
     pub async fn get_credential_summaries(&self, did: &str) -> Result<Vec<CredentialSummary>, AppError> {
         let credentials = self.credential_service.get_credentials_by_owner(did).await?;
 
-        let summaries = credentials.into_iter().map(|cred| {
-            CredentialSummary {
+        let mut summaries = Vec::new();
+        for cred in credentials {
+            let revocation = self.credential_service.credential_revocation(&cred).await?;
+            summaries.push(CredentialSummary {
                 id: cred.id,
                 issuer_did: cred.issuer_did,
                 credential_type: cred.credential_type,
                 issuance_date: cred.created_at,
                 expiration_date: cred.expires_at,
                 status: format!("{:?}", cred.status),
-            }
-        }).collect();
+                revocation,
+            });
+        }
 
         Ok(summaries)
     }
@@ -162,6 +189,7 @@ impl WalletService {
         // Verify the credential
         let verify_request = crate::services::credential::VerifyCredentialRequest {
             credential_jwt: request.credential_jwt.clone(),
+            credential: None,
         };
 
         let verification_result = self.credential_service.verify_credential(verify_request).await?;
@@ -181,7 +209,7 @@ impl WalletService {
         }
 
         // Extract credential data
-        let credential_data = jwt::extract_credential(&request.credential_jwt)?;
+        let credential_data = crate::services::credential::extract_credential(&request.credential_jwt)?;
 
         // Create a credential object
         let mut credential = Credential::new(
@@ -189,14 +217,7 @@ impl WalletService {
             owner_did.to_string(),
             verification_result.credential_type.clone(),
             "".to_string(), // Schema ID not available from JWT
-            credential_data["credentialSubject"]["claims"]
-                .as_object()
-                .map(|obj| {
-                    obj.iter()
-                        .map(|(k, v)| (k.clone(), v.clone()))
-                        .collect::<HashMap<String, Value>>()
-                })
-                .unwrap_or_default(),
+            credential_attributes(&credential_data["credentialSubject"]),
             request.credential_jwt.clone(),
         );
 
@@ -234,6 +255,7 @@ impl WalletService {
             credential_ids: request.credential_ids.clone(),
             disclosed_attributes: request.disclosed_attributes.clone(),
             predicate_proofs: Vec::new(), // No predicate proofs for direct sharing
+            same_holder_proof: None,
         };
 
         let presentation_response = self.presentation_service
@@ -311,6 +333,9 @@ impl WalletService {
         let revoked_credentials = credentials.iter()
             .filter(|c| c.status == crate::models::CredentialStatus::Revoked)
             .count();
+        let suspended_credentials = credentials.iter()
+            .filter(|c| c.status == crate::models::CredentialStatus::Suspended)
+            .count();
 
         // Get presentations
         let presentations = self.presentation_service.get_presentations_by_prover(did).await?;
@@ -327,6 +352,7 @@ impl WalletService {
             active_credentials,
             expired_credentials,
             revoked_credentials,
+            suspended_credentials,
             total_presentations,
             active_consents,
         })
@@ -478,3 +504,14 @@ impl WalletService {
         })
     }
 }
+
+/// Attributes of a credential subject: nested under `claims` in VCDM 1.1, the
+/// subject's own properties besides `id` in VCDM 2.0
+fn credential_attributes(subject: &Value) -> HashMap<String, Value> {
+    match subject["claims"].as_object() {
+        Some(claims) => claims.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        None => subject.as_object()
+            .map(|obj| obj.iter().filter(|(k, _)| *k != "id").map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default(),
+    }
+}
@@ -0,0 +1,636 @@
+use bls12_381_plus::{
+    multi_miller_loop, ExpandMsg, ExpandMsgXmd, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt, Scalar,
+};
+use rand::{rngs::OsRng, RngCore};
+use sha2_09::Sha256;
+
+use crate::error::AppError;
+
+/// Key algorithm name of BBS issuer keys
+pub const ALGORITHM: &str = "Bls12381G2";
+
+/// API ID of the BBS signature interface of the BLS12-381-SHA-256 ciphersuite of
+/// draft-irtf-cfrg-bbs-signatures, which prefixes every seed and domain separation tag
+const API_ID: &[u8] = b"BBS_BLS12381G1_XMD:SHA-256_SSWU_RO_H2G_HM2S_";
+
+/// Multicodec prefix of a BLS12-381 G2 public key
+const BLS12381_G2_MULTICODEC: [u8; 2] = [0xeb, 0x01];
+
+/// Length of the uniform bytes hashed to a scalar
+const EXPAND_LEN: usize = 48;
+
+const SCALAR_LENGTH: usize = 32;
+const G1_LENGTH: usize = 48;
+const G2_LENGTH: usize = 96;
+
+/// Generate a secret key from at least 32 bytes of key material, as the draft's `KeyGen`
+pub fn key_gen(key_material: &[u8], key_info: &[u8]) -> Result<Vec<u8>, AppError> {
+    if key_material.len() < 32 {
+        return Err(AppError::ValidationError("BBS key material must be at least 32 bytes".to_string()));
+    }
+    if key_info.len() > u16::MAX as usize {
+        return Err(AppError::ValidationError("BBS key info is too long".to_string()));
+    }
+
+    let derive_input = [key_material, &(key_info.len() as u16).to_be_bytes(), key_info].concat();
+    let sk = hash_to_scalar(&derive_input, &dst(b"KEYGEN_DST_"));
+    if sk == Scalar::zero() {
+        return Err(AppError::InternalError("Derived BBS secret key is zero".to_string()));
+    }
+
+    Ok(scalar_to_octets(&sk).to_vec())
+}
+
+/// Generate a secret key from fresh randomness
+pub fn generate_secret_key() -> Result<Vec<u8>, AppError> {
+    let mut key_material = [0u8; 32];
+    OsRng.fill_bytes(&mut key_material);
+    key_gen(&key_material, &[])
+}
+
+/// Compressed G2 public key of a secret key
+pub fn sk_to_pk(secret_key: &[u8]) -> Result<Vec<u8>, AppError> {
+    let sk = octets_to_secret_key(secret_key)?;
+    Ok(G2Affine::from(G2Projective::generator() * sk).to_compressed().to_vec())
+}
+
+/// Sign messages and a header, which is disclosed in every proof.
+///
+/// The signature is `A || e`: a compressed G1 point and a scalar.
+pub fn sign(secret_key: &[u8], public_key: &[u8], header: &[u8], messages: &[Vec<u8>]) -> Result<Vec<u8>, AppError> {
+    let sk = octets_to_secret_key(secret_key)?;
+    let messages = messages_to_scalars(messages);
+    let generators = Generators::create(messages.len() + 1);
+
+    // Step 1: Bind the public key, generators and header
+    let domain = calculate_domain(public_key, &generators, header)?;
+
+    // Step 2: Derive e deterministically from the key and the signed content
+    let mut e_input = scalar_to_octets(&sk).to_vec();
+    for message in &messages {
+        e_input.extend_from_slice(&scalar_to_octets(message));
+    }
+    e_input.extend_from_slice(&scalar_to_octets(&domain));
+    let e = hash_to_scalar(&e_input, &dst(b"H2S_"));
+
+    // Step 3: A = B * 1/(SK + e)
+    let b = generators.commitment(&domain, messages.iter().enumerate());
+    let inverse = Option::<Scalar>::from((sk + e).invert())
+        .ok_or_else(|| AppError::InternalError("BBS signing failed".to_string()))?;
+    let a = G1Affine::from(b * inverse);
+
+    Ok([&a.to_compressed()[..], &scalar_to_octets(&e)].concat())
+}
+
+/// Verify a signature over all messages and the header
+pub fn verify(public_key: &[u8], signature: &[u8], header: &[u8], messages: &[Vec<u8>]) -> Result<bool, AppError> {
+    let w = octets_to_public_key(public_key)?;
+    let (a, e) = match octets_to_signature(signature) {
+        Ok(signature) => signature,
+        Err(_) => return Ok(false),
+    };
+    let messages = messages_to_scalars(messages);
+    let generators = Generators::create(messages.len() + 1);
+
+    let domain = calculate_domain(public_key, &generators, header)?;
+    let b = generators.commitment(&domain, messages.iter().enumerate());
+
+    // e(A, W + BP2 * e) * e(B, -BP2) == 1
+    let w_e = G2Affine::from(G2Projective::from(w) + G2Projective::generator() * e);
+    Ok(pairing_product_is_identity(&a, &w_e, &G1Affine::from(b)))
+}
+
+/// Derive a zero-knowledge proof of a signature that reveals only the messages at `disclosed_indexes`.
+///
+/// Every proof is freshly randomized, so two proofs of one signature cannot
+/// be linked. The presentation header `ph` binds the proof to one verifier
+/// and nonce.
+pub fn proof_gen(
+    public_key: &[u8],
+    signature: &[u8],
+    header: &[u8],
+    ph: &[u8],
+    messages: &[Vec<u8>],
+    disclosed_indexes: &[usize],
+) -> Result<Vec<u8>, AppError> {
+    proof_gen_with_scalars(public_key, signature, header, ph, messages, disclosed_indexes, calculate_random_scalars)
+}
+
+/// `CoreProofGen`, drawing its blinding factors from `random_scalars(count)`
+fn proof_gen_with_scalars(
+    public_key: &[u8],
+    signature: &[u8],
+    header: &[u8],
+    ph: &[u8],
+    messages: &[Vec<u8>],
+    disclosed_indexes: &[usize],
+    random_scalars: fn(usize) -> Vec<Scalar>,
+) -> Result<Vec<u8>, AppError> {
+    let (a, e) = octets_to_signature(signature)?;
+    let scalars = messages_to_scalars(messages);
+    let generators = Generators::create(scalars.len() + 1);
+
+    let mut disclosed: Vec<usize> = disclosed_indexes.to_vec();
+    disclosed.sort_unstable();
+    disclosed.dedup();
+    if disclosed.last().is_some_and(|&index| index >= scalars.len()) {
+        return Err(AppError::ValidationError("Disclosed message index is out of range".to_string()));
+    }
+    let undisclosed: Vec<usize> = (0..scalars.len()).filter(|i| !disclosed.contains(i)).collect();
+
+    // Step 1: Randomize the signature
+    let mut blinding = random_scalars(5 + undisclosed.len());
+    let m_tilde = blinding.split_off(5);
+    let [r1, r2, e_tilde, r1_tilde, r3_tilde] = <[Scalar; 5]>::try_from(blinding)
+        .map_err(|_| AppError::InternalError("BBS proof generation failed".to_string()))?;
+
+    let domain = calculate_domain(public_key, &generators, header)?;
+    let b = generators.commitment(&domain, scalars.iter().enumerate());
+    let d = b * r2;
+    let a_bar = G1Projective::from(a) * (r1 * r2);
+    let b_bar = d * r1 - a_bar * e;
+
+    // Step 2: Commit to the blinding factors
+    let t1 = a_bar * e_tilde + d * r1_tilde;
+    let mut t2 = d * r3_tilde;
+    for (index, m) in undisclosed.iter().zip(&m_tilde) {
+        t2 += generators.h[*index] * m;
+    }
+
+    let init = ProofInit {
+        a_bar: a_bar.into(),
+        b_bar: b_bar.into(),
+        d: d.into(),
+        t1: t1.into(),
+        t2: t2.into(),
+        domain,
+    };
+    let disclosed_messages: Vec<(usize, Scalar)> = disclosed.iter().map(|&i| (i, scalars[i])).collect();
+    let challenge = calculate_challenge(&init, &disclosed_messages, ph);
+
+    // Step 3: Answer the challenge
+    let r3 = Option::<Scalar>::from(r2.invert())
+        .ok_or_else(|| AppError::InternalError("BBS proof generation failed".to_string()))?;
+
+    let mut proof = Vec::new();
+    for point in [&init.a_bar, &init.b_bar, &init.d] {
+        proof.extend_from_slice(&point.to_compressed());
+    }
+    for scalar in [e_tilde + e * challenge, r1_tilde - r1 * challenge, r3_tilde - r3 * challenge] {
+        proof.extend_from_slice(&scalar_to_octets(&scalar));
+    }
+    for (index, m) in undisclosed.iter().zip(&m_tilde) {
+        proof.extend_from_slice(&scalar_to_octets(&(m + scalars[*index] * challenge)));
+    }
+    proof.extend_from_slice(&scalar_to_octets(&challenge));
+
+    Ok(proof)
+}
+
+/// Verify a proof against the disclosed messages, each given with its index among all signed messages
+pub fn proof_verify(
+    public_key: &[u8],
+    proof: &[u8],
+    header: &[u8],
+    ph: &[u8],
+    disclosed_messages: &[(usize, Vec<u8>)],
+) -> Result<bool, AppError> {
+    let w = octets_to_public_key(public_key)?;
+    let proof = match Proof::from_octets(proof) {
+        Ok(proof) => proof,
+        Err(_) => return Ok(false),
+    };
+
+    // Step 1: Place the disclosed messages among all signed messages
+    let count = disclosed_messages.len() + proof.m_hat.len();
+    if disclosed_messages.windows(2).any(|pair| pair[0].0 >= pair[1].0)
+        || disclosed_messages.last().is_some_and(|(index, _)| *index >= count)
+    {
+        return Ok(false);
+    }
+    let disclosed: Vec<(usize, Scalar)> = disclosed_messages.iter()
+        .map(|(index, message)| (*index, message_to_scalar(message)))
+        .collect();
+    let undisclosed: Vec<usize> = (0..count).filter(|i| !disclosed.iter().any(|(index, _)| index == i)).collect();
+    let generators = Generators::create(count + 1);
+
+    // Step 2: Recompute the commitments from the proof's responses
+    let domain = calculate_domain(public_key, &generators, header)?;
+    let a_bar = G1Projective::from(proof.a_bar);
+    let b_bar = G1Projective::from(proof.b_bar);
+    let d = G1Projective::from(proof.d);
+
+    let t1 = b_bar * proof.challenge + a_bar * proof.e_hat + d * proof.r1_hat;
+    let bv = generators.commitment(&domain, disclosed.iter().map(|(index, m)| (*index, m)));
+    let mut t2 = bv * proof.challenge + d * proof.r3_hat;
+    for (index, m_hat) in undisclosed.iter().zip(&proof.m_hat) {
+        t2 += generators.h[*index] * m_hat;
+    }
+
+    let init = ProofInit {
+        a_bar: proof.a_bar,
+        b_bar: proof.b_bar,
+        d: proof.d,
+        t1: t1.into(),
+        t2: t2.into(),
+        domain,
+    };
+    if calculate_challenge(&init, &disclosed, ph) != proof.challenge {
+        return Ok(false);
+    }
+
+    // Step 3: e(Abar, W) * e(Bbar, -BP2) == 1
+    Ok(pairing_product_is_identity(&proof.a_bar, &w, &proof.b_bar))
+}
+
+/// Encode a public key as a multicodec-prefixed, base58btc multibase Multikey
+pub fn public_key_to_multikey(public_key: &[u8]) -> String {
+    format!("z{}", bs58::encode([&BLS12381_G2_MULTICODEC[..], public_key].concat()).into_string())
+}
+
+/// Decode a BLS12-381 G2 Multikey
+pub fn public_key_from_multikey(multibase: &str) -> Result<Vec<u8>, AppError> {
+    let data = multibase.strip_prefix('z')
+        .and_then(|encoded| bs58::decode(encoded).into_vec().ok())
+        .ok_or_else(|| AppError::SsiError(format!("Invalid multibase key: {}", multibase)))?;
+
+    match data.split_at_checked(2) {
+        Some((prefix, public_key)) if prefix == BLS12381_G2_MULTICODEC => {
+            octets_to_public_key(public_key)?;
+            Ok(public_key.to_vec())
+        }
+        _ => Err(AppError::SsiError(format!("{} is not a BLS12-381 G2 key", multibase))),
+    }
+}
+
+/// Q_1 and the message generators H_1..H_L of a ciphersuite
+struct Generators {
+    p1: G1Projective,
+    q1: G1Projective,
+    h: Vec<G1Projective>,
+}
+
+impl Generators {
+    /// `create_generators(count)`, with the fixed base point P1 of the ciphersuite
+    fn create(count: usize) -> Self {
+        let p1 = create_generators(1, b"BP_MESSAGE_GENERATOR_SEED")[0];
+        let mut generators = create_generators(count, b"MESSAGE_GENERATOR_SEED");
+        let q1 = generators.remove(0);
+        Self { p1, q1, h: generators }
+    }
+
+    /// `B = P1 + Q_1 * domain + H_i * msg_i + ...` over the given messages
+    fn commitment<'a>(&self, domain: &Scalar, messages: impl Iterator<Item = (usize, &'a Scalar)>) -> G1Projective {
+        let mut b = self.p1 + self.q1 * domain;
+        for (index, message) in messages {
+            b += self.h[index] * message;
+        }
+        b
+    }
+}
+
+/// Values the challenge of a proof is computed from
+struct ProofInit {
+    a_bar: G1Affine,
+    b_bar: G1Affine,
+    d: G1Affine,
+    t1: G1Affine,
+    t2: G1Affine,
+    domain: Scalar,
+}
+
+/// Decoded proof: `(Abar, Bbar, D, e^, r1^, r3^, (m^_j1, ..., m^_jU), c)`
+struct Proof {
+    a_bar: G1Affine,
+    b_bar: G1Affine,
+    d: G1Affine,
+    e_hat: Scalar,
+    r1_hat: Scalar,
+    r3_hat: Scalar,
+    m_hat: Vec<Scalar>,
+    challenge: Scalar,
+}
+
+impl Proof {
+    fn from_octets(octets: &[u8]) -> Result<Self, AppError> {
+        let points_length = 3 * G1_LENGTH;
+        if octets.len() < points_length + 4 * SCALAR_LENGTH || !(octets.len() - points_length).is_multiple_of(SCALAR_LENGTH) {
+            return Err(AppError::SsiError("Invalid BBS proof length".to_string()));
+        }
+
+        let (points, scalars) = octets.split_at(points_length);
+        let points = points.chunks(G1_LENGTH).map(octets_to_g1).collect::<Result<Vec<_>, _>>()?;
+        let mut scalars = scalars.chunks(SCALAR_LENGTH).map(octets_to_scalar).collect::<Result<Vec<_>, _>>()?;
+
+        if bool::from(points[0].is_identity()) {
+            return Err(AppError::SsiError("Invalid BBS proof".to_string()));
+        }
+
+        let challenge = scalars.pop().unwrap_or_default();
+        let m_hat = scalars.split_off(3);
+        Ok(Self {
+            a_bar: points[0],
+            b_bar: points[1],
+            d: points[2],
+            e_hat: scalars[0],
+            r1_hat: scalars[1],
+            r3_hat: scalars[2],
+            m_hat,
+            challenge,
+        })
+    }
+}
+
+/// `create_generators`: points hashed to G1 from a chain of expanded seeds
+fn create_generators(count: usize, seed: &[u8]) -> Vec<G1Projective> {
+    let seed_dst = dst(b"SIG_GENERATOR_SEED_");
+    let generator_dst = dst(b"SIG_GENERATOR_DST_");
+
+    let mut v = expand_message(&[API_ID, seed].concat(), &seed_dst);
+    (1..=count as u64)
+        .map(|i| {
+            v = expand_message(&[&v[..], &i.to_be_bytes()].concat(), &seed_dst);
+            G1Projective::hash::<ExpandMsgXmd<Sha256>>(&v, &generator_dst)
+        })
+        .collect()
+}
+
+/// `calculate_domain`: hash of the public key, the generators and the header
+fn calculate_domain(public_key: &[u8], generators: &Generators, header: &[u8]) -> Result<Scalar, AppError> {
+    let mut input = public_key.to_vec();
+    input.extend_from_slice(&(generators.h.len() as u64).to_be_bytes());
+    input.extend_from_slice(&G1Affine::from(generators.q1).to_compressed());
+    for h in &generators.h {
+        input.extend_from_slice(&G1Affine::from(h).to_compressed());
+    }
+    input.extend_from_slice(API_ID);
+    input.extend_from_slice(&(header.len() as u64).to_be_bytes());
+    input.extend_from_slice(header);
+
+    Ok(hash_to_scalar(&input, &dst(b"H2S_")))
+}
+
+/// `ProofChallengeCalculate`
+fn calculate_challenge(init: &ProofInit, disclosed: &[(usize, Scalar)], ph: &[u8]) -> Scalar {
+    let mut input = (disclosed.len() as u64).to_be_bytes().to_vec();
+    for (index, message) in disclosed {
+        input.extend_from_slice(&(*index as u64).to_be_bytes());
+        input.extend_from_slice(&scalar_to_octets(message));
+    }
+    for point in [&init.a_bar, &init.b_bar, &init.d, &init.t1, &init.t2] {
+        input.extend_from_slice(&point.to_compressed());
+    }
+    input.extend_from_slice(&scalar_to_octets(&init.domain));
+    input.extend_from_slice(&(ph.len() as u64).to_be_bytes());
+    input.extend_from_slice(ph);
+
+    hash_to_scalar(&input, &dst(b"H2S_"))
+}
+
+fn pairing_product_is_identity(a: &G1Affine, w: &G2Affine, b: &G1Affine) -> bool {
+    let w = G2Prepared::from(*w);
+    let minus_bp2 = G2Prepared::from(-G2Affine::generator());
+    multi_miller_loop(&[(a, &w), (b, &minus_bp2)]).final_exponentiation() == Gt::identity()
+}
+
+fn messages_to_scalars(messages: &[Vec<u8>]) -> Vec<Scalar> {
+    messages.iter().map(|message| message_to_scalar(message)).collect()
+}
+
+fn message_to_scalar(message: &[u8]) -> Scalar {
+    hash_to_scalar(message, &dst(b"MAP_MSG_TO_SCALAR_AS_HASH_"))
+}
+
+fn hash_to_scalar(message: &[u8], dst: &[u8]) -> Scalar {
+    let uniform_bytes: [u8; EXPAND_LEN] = expand_message(message, dst);
+    Scalar::from_okm(&uniform_bytes)
+}
+
+fn expand_message(message: &[u8], dst: &[u8]) -> [u8; EXPAND_LEN] {
+    let mut uniform_bytes = [0u8; EXPAND_LEN];
+    ExpandMsgXmd::<Sha256>::expand_message(message, dst, &mut uniform_bytes);
+    uniform_bytes
+}
+
+/// `calculate_random_scalars`
+fn calculate_random_scalars(count: usize) -> Vec<Scalar> {
+    (0..count)
+        .map(|_| {
+            let mut bytes = [0u8; EXPAND_LEN];
+            OsRng.fill_bytes(&mut bytes);
+            Scalar::from_okm(&bytes)
+        })
+        .collect()
+}
+
+/// Domain separation tag of the ciphersuite's API
+fn dst(suffix: &[u8]) -> Vec<u8> {
+    [API_ID, suffix].concat()
+}
+
+/// Big-endian encoding, as the draft's `I2OSP(scalar, 32)`
+fn scalar_to_octets(scalar: &Scalar) -> [u8; SCALAR_LENGTH] {
+    let mut octets = scalar.to_bytes();
+    octets.reverse();
+    octets
+}
+
+fn octets_to_scalar(octets: &[u8]) -> Result<Scalar, AppError> {
+    let mut bytes: [u8; SCALAR_LENGTH] = octets.try_into()
+        .map_err(|_| AppError::SsiError("Invalid BBS scalar length".to_string()))?;
+    bytes.reverse();
+    Option::from(Scalar::from_bytes(&bytes))
+        .ok_or_else(|| AppError::SsiError("BBS scalar is not reduced".to_string()))
+}
+
+fn octets_to_secret_key(octets: &[u8]) -> Result<Scalar, AppError> {
+    octets_to_scalar(octets)
+        .ok()
+        .filter(|sk| *sk != Scalar::zero())
+        .ok_or_else(|| AppError::ValidationError("Invalid BBS secret key".to_string()))
+}
+
+fn octets_to_g1(octets: &[u8]) -> Result<G1Affine, AppError> {
+    let bytes: &[u8; G1_LENGTH] = octets.try_into()
+        .map_err(|_| AppError::SsiError("Invalid G1 point length".to_string()))?;
+    Option::from(G1Affine::from_compressed(bytes))
+        .ok_or_else(|| AppError::SsiError("Invalid G1 point".to_string()))
+}
+
+fn octets_to_public_key(octets: &[u8]) -> Result<G2Affine, AppError> {
+    let bytes: &[u8; G2_LENGTH] = octets.try_into()
+        .map_err(|_| AppError::SsiError("Invalid BBS public key length".to_string()))?;
+    Option::<G2Affine>::from(G2Affine::from_compressed(bytes))
+        .filter(|w| !bool::from(w.is_identity()))
+        .ok_or_else(|| AppError::SsiError("Invalid BBS public key".to_string()))
+}
+
+fn octets_to_signature(octets: &[u8]) -> Result<(G1Affine, Scalar), AppError> {
+    if octets.len() != G1_LENGTH + SCALAR_LENGTH {
+        return Err(AppError::SsiError("Invalid BBS signature length".to_string()));
+    }
+    let (a, e) = octets.split_at(G1_LENGTH);
+    let a = octets_to_g1(a)?;
+    let e = octets_to_scalar(e)?;
+    if bool::from(a.is_identity()) || e == Scalar::zero() {
+        return Err(AppError::SsiError("Invalid BBS signature".to_string()));
+    }
+    Ok((a, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2_09::Digest;
+
+    // Fixtures of the BLS12-381-SHA-256 ciphersuite of draft-irtf-cfrg-bbs-signatures
+    const KEY_MATERIAL: &str = "746869732d49532d6a7573742d616e2d546573742d494b4d2d746f2d67656e65726174652d246528724074232d6b6579";
+    const KEY_INFO: &str = "746869732d49532d736f6d652d6b65792d6d657461646174612d746f2d62652d757365642d696e2d746573742d6b65792d67656e";
+    const SECRET_KEY: &str = "60e55110f76883a13d030b2f6bd11883422d5abde717569fc0731f51237169fc";
+    const PUBLIC_KEY: &str = "a820f230f6ae38503b86c70dc50b61c58a77e45c39ab25c0652bbaa8fa136f2851bd4781c9dcde39fc9d1d52c9e60268061e7d7632171d91aa8d460acee0e96f1e7c4cfb12d3ff9ab5d5dc91c277db75c845d649ef3c4f63aebc364cd55ded0c";
+    const HEADER: &str = "11223344556677889900aabbccddeeff";
+    const PRESENTATION_HEADER: &str = "bed231d880675ed101ead304512e043ade9958dd0241ea70b4b3957fba941501";
+    const MESSAGES: [&str; 10] = [
+        "9872ad089e452c7b6e283dfac2a80d58e8d0ff71cc4d5e310a1debdda4a45f02",
+        "c344136d9ab02da4dd5908bbba913ae6f58c2cc844b802a6f811f5fb075f9b80",
+        "7372e9daa5ed31e6cd5c825eac1b855e84476a1d94932aa348e07b73",
+        "77fe97eb97a1ebe2e81e4e3597a3ee740a66e9ef2412472c",
+        "496694774c5604ab1b2544eababcf0f53278ff50",
+        "515ae153e22aae04ad16f759e07237b4",
+        "d183ddc6e2665aa4e2f088af",
+        "ac55fb33a75909ed",
+        "96012096",
+        "",
+    ];
+
+    const SINGLE_MESSAGE_SIGNATURE: &str = "84773160b824e194073a57493dac1a20b667af70cd2352d8af241c77658da5253aa8458317cca0eae615690d55b1f27164657dcafee1d5c1973947aa70e2cfbb4c892340be5969920d0916067b4565a0";
+    const MULTI_MESSAGE_SIGNATURE: &str = "8339b285a4acd89dec7777c09543a43e3cc60684b0a6f8ab335da4825c96e1463e28f8c5f4fd0641d19cec5920d3a8ff4bedb6c9691454597bbd298288abed3632078557b2ace7d44caed846e1a0a1e8";
+
+    const SINGLE_MESSAGE_PROOF: &str = "94916292a7a6bade28456c601d3af33fcf39278d6594b467e128a3f83686a104ef2b2fcf72df0215eeaf69262ffe8194a19fab31a82ddbe06908985abc4c9825788b8a1610942d12b7f5debbea8985296361206dbace7af0cc834c80f33e0aadaeea5597befbb651827b5eed5a66f1a959bb46cfd5ca1a817a14475960f69b32c54db7587b5ee3ab665fbd37b506830a49f21d592f5e634f47cee05a025a2f8f94e73a6c15f02301d1178a92873b6e8634bafe4983c3e15a663d64080678dbf29417519b78af042be2b3e1c4d08b8d520ffab008cbaaca5671a15b22c239b38e940cfeaa5e72104576a9ec4a6fad78c532381aeaa6fb56409cef56ee5c140d455feeb04426193c57086c9b6d397d9418";
+    const ALL_DISCLOSED_PROOF: &str = "b1f468aec2001c4f54cb56f707c6222a43e5803a25b2253e67b2210ab2ef9eab52db2d4b379935c4823281eaf767fd37b08ce80dc65de8f9769d27099ae649ad4c9b4bd2cc23edcba52073a298087d2495e6d57aaae051ef741adf1cbce65c64a73c8c97264177a76c4a03341956d2ae45ed3438ce598d5cda4f1bf9507fecef47855480b7b30b5e4052c92a4360110c67327365763f5aa9fb85ddcbc2975449b8c03db1216ca66b310f07d0ccf12ab460cdc6003b677fed36d0a23d0818a9d4d098d44f749e91008cf50e8567ef936704c8277b7710f41ab7e6e16408ab520edc290f9801349aee7b7b4e318e6a76e028e1dea911e2e7baec6a6a174da1a22362717fbae1cd961d7bf4adce1d31c2ab";
+    const HALF_DISCLOSED_PROOF: &str = "a2ed608e8e12ed21abc2bf154e462d744a367c7f1f969bdbf784a2a134c7db2d340394223a5397a3011b1c340ebc415199462ba6f31106d8a6da8b513b37a47afe93c9b3474d0d7a354b2edc1b88818b063332df774c141f7a07c48fe50d452f897739228c88afc797916dca01e8f03bd9c5375c7a7c59996e514bb952a436afd24457658acbaba5ddac2e693ac481356918cd38025d86b28650e909defe9604a7259f44386b861608be742af7775a2e71a6070e5836f5f54dc43c60096834a5b6da295bf8f081f72b7cdf7f3b4347fb3ff19edaa9e74055c8ba46dbcb7594fb2b06633bb5324192eb9be91be0d33e453b4d3127459de59a5e2193c900816f049a02cb9127dac894418105fa1641d5a206ec9c42177af9316f433417441478276ca0303da8f941bf2e0222a43251cf5c2bf6eac1961890aa740534e519c1767e1223392a3a286b0f4d91f7f25217a7862b8fcc1810cdcfddde2a01c80fcc90b632585fec12dc4ae8fea1918e9ddeb9414623a457e88f53f545841f9d5dcb1f8e160d1560770aa79d65e2eca8edeaecb73fb7e995608b820c4a64de6313a370ba05dc25ed7c1d185192084963652f2870341bdaa4b1a37f8c06348f38a4f80c5a2650a21d59f09e8305dcd3fc3ac30e2a";
+
+    /// Seed of the draft's mocked random scalars
+    const MOCKED_SCALARS_SEED: &str = "332e313431353932363533353839373933323338343632363433333833323739";
+
+    fn decode(value: &str) -> Vec<u8> {
+        hex::decode(value).unwrap()
+    }
+
+    fn messages() -> Vec<Vec<u8>> {
+        MESSAGES.iter().map(|message| decode(message)).collect()
+    }
+
+    /// `expand_message_xmd` of RFC 9380 without the 288-byte bound of `ExpandMsgXmd`
+    fn expand_message_xmd(message: &[u8], dst: &[u8], length: usize) -> Vec<u8> {
+        let dst_prime = [dst, &[dst.len() as u8]].concat();
+        let b_0 = Sha256::new()
+            .chain([0u8; 64])
+            .chain(message)
+            .chain((length as u16).to_be_bytes())
+            .chain([0u8])
+            .chain(&dst_prime)
+            .finalize();
+
+        let mut b_i = Sha256::new().chain(b_0).chain([1u8]).chain(&dst_prime).finalize();
+        let mut uniform_bytes = b_i.to_vec();
+        for i in 2..=length.div_ceil(32) {
+            let xored: Vec<u8> = b_0.iter().zip(&b_i).map(|(x, y)| x ^ y).collect();
+            b_i = Sha256::new().chain(xored).chain([i as u8]).chain(&dst_prime).finalize();
+            uniform_bytes.extend_from_slice(&b_i);
+        }
+        uniform_bytes.truncate(length);
+        uniform_bytes
+    }
+
+    /// The draft's `mocked_calculate_random_scalars`
+    fn mocked_random_scalars(count: usize) -> Vec<Scalar> {
+        let dst = dst(b"MOCK_RANDOM_SCALARS_DST_");
+        expand_message_xmd(&decode(MOCKED_SCALARS_SEED), &dst, count * EXPAND_LEN)
+            .chunks(EXPAND_LEN)
+            .map(|chunk| Scalar::from_okm(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn derives_the_fixture_key_pair() {
+        let secret_key = key_gen(&decode(KEY_MATERIAL), &decode(KEY_INFO)).unwrap();
+        assert_eq!(hex::encode(&secret_key), SECRET_KEY);
+        assert_eq!(hex::encode(sk_to_pk(&secret_key).unwrap()), PUBLIC_KEY);
+    }
+
+    #[test]
+    fn creates_the_fixture_generators() {
+        let generators = Generators::create(3);
+        let encode = |point: &G1Projective| hex::encode(G1Affine::from(point).to_compressed());
+
+        assert_eq!(encode(&generators.p1), "a8ce256102840821a3e94ea9025e4662b205762f9776b3a766c872b948f1fd225e7c59698588e70d11406d161b4e28c9");
+        assert_eq!(encode(&generators.q1), "a9ec65b70a7fbe40c874c9eb041c2cb0a7af36ccec1bea48fa2ba4c2eb67ef7f9ecb17ed27d38d27cdeddff44c8137be");
+        assert_eq!(encode(&generators.h[0]), "98cd5313283aaf5db1b3ba8611fe6070d19e605de4078c38df36019fbaad0bd28dd090fd24ed27f7f4d22d5ff5dea7d4");
+        assert_eq!(encode(&generators.h[1]), "a31fbe20c5c135bcaa8d9fc4e4ac665cc6db0226f35e737507e803044093f37697a9d452490a970eea6f9ad6c3dcaa3a");
+    }
+
+    #[test]
+    fn mocks_the_fixture_random_scalars() {
+        let scalars = mocked_random_scalars(10);
+        assert_eq!(hex::encode(scalar_to_octets(&scalars[0])), "04f8e2518993c4383957ad14eb13a023c4ad0c67d01ec86eeb902e732ed6df3f");
+        assert_eq!(hex::encode(scalar_to_octets(&scalars[1])), "5d87c1ba64c320ad601d227a1b74188a41a100325cecf00223729863966392b1");
+        assert_eq!(hex::encode(scalar_to_octets(&scalars[9])), "485e2adab17b76f5334c95bf36c03ccf91cef77dcfcdc6b8a69e2090b3156663");
+    }
+
+    #[test]
+    fn signs_and_verifies_the_fixture_messages() {
+        let (secret_key, public_key, header) = (decode(SECRET_KEY), decode(PUBLIC_KEY), decode(HEADER));
+        let messages = messages();
+
+        for (signed, expected) in [(&messages[..1], SINGLE_MESSAGE_SIGNATURE), (&messages[..], MULTI_MESSAGE_SIGNATURE)] {
+            let signature = sign(&secret_key, &public_key, &header, signed).unwrap();
+            assert_eq!(hex::encode(&signature), expected);
+            assert!(verify(&public_key, &signature, &header, signed).unwrap());
+        }
+
+        // A modified message, a reordering or another header breaks the signature
+        let signature = decode(MULTI_MESSAGE_SIGNATURE);
+        let mut modified = messages.clone();
+        modified[0] = b"Modified message".to_vec();
+        assert!(!verify(&public_key, &signature, &header, &modified).unwrap());
+
+        let mut reordered = messages.clone();
+        reordered.swap(0, 1);
+        assert!(!verify(&public_key, &signature, &header, &reordered).unwrap());
+        assert!(!verify(&public_key, &signature, b"", &messages).unwrap());
+    }
+
+    #[test]
+    fn generates_and_verifies_the_fixture_proofs() {
+        let (public_key, header, ph) = (decode(PUBLIC_KEY), decode(HEADER), decode(PRESENTATION_HEADER));
+        let messages = messages();
+
+        let cases = [
+            (&messages[..1], SINGLE_MESSAGE_SIGNATURE, vec![0], SINGLE_MESSAGE_PROOF),
+            (&messages[..], MULTI_MESSAGE_SIGNATURE, (0..10).collect(), ALL_DISCLOSED_PROOF),
+            (&messages[..], MULTI_MESSAGE_SIGNATURE, vec![0, 2, 4, 6], HALF_DISCLOSED_PROOF),
+        ];
+        for (signed, signature, disclosed_indexes, expected) in cases {
+            let signature = decode(signature);
+            let proof = proof_gen_with_scalars(&public_key, &signature, &header, &ph, signed, &disclosed_indexes, mocked_random_scalars).unwrap();
+            assert_eq!(hex::encode(&proof), expected);
+
+            let disclosed: Vec<(usize, Vec<u8>)> = disclosed_indexes.iter().map(|&i| (i, signed[i].clone())).collect();
+            assert!(proof_verify(&public_key, &proof, &header, &ph, &disclosed).unwrap());
+
+            // The proof is bound to its presentation header and disclosed messages
+            assert!(!proof_verify(&public_key, &proof, &header, b"", &disclosed).unwrap());
+            let mut modified = disclosed.clone();
+            modified[0].1 = b"Modified message".to_vec();
+            assert!(!proof_verify(&public_key, &proof, &header, &ph, &modified).unwrap());
+        }
+
+        // Fresh randomness still yields a valid, but different proof
+        let signature = decode(MULTI_MESSAGE_SIGNATURE);
+        let proof = proof_gen(&public_key, &signature, &header, &ph, &messages, &[0, 2, 4, 6]).unwrap();
+        assert_ne!(hex::encode(&proof), HALF_DISCLOSED_PROOF);
+        let disclosed: Vec<(usize, Vec<u8>)> = [0, 2, 4, 6].iter().map(|&i| (i, messages[i].clone())).collect();
+        assert!(proof_verify(&public_key, &proof, &header, &ph, &disclosed).unwrap());
+    }
+}
@@ -0,0 +1,294 @@
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+
+use crate::error::AppError;
+use crate::keystore::KeyStore;
+use crate::resolver::{split_did_url, DidResolver};
+use crate::utils::data_integrity::{self, ASSERTION_METHOD, DATA_INTEGRITY_PROOF};
+use crate::utils::{bbs, jcs, jwt};
+
+/// Data Integrity cryptosuite of BBS credentials: JCS canonicalization, one BBS message per subject attribute
+pub const CRYPTOSUITE: &str = "bbs-jcs-2024";
+
+/// Proof of a BBS credential whose signature or derived proof checked out
+#[derive(Debug, Clone)]
+pub struct VerifiedBbsProof {
+    pub verification_method: String,
+    /// Whether the proof was derived by the holder rather than signed by the issuer
+    pub derived: bool,
+}
+
+/// Whether a secured document carries a BBS signature or derived proof
+pub fn is_bbs_document(document: &Value) -> bool {
+    document["proof"]["cryptosuite"].as_str() == Some(CRYPTOSUITE)
+}
+
+/// Parse a secured credential if it is a BBS credential
+pub fn parse(secured: &str) -> Option<Value> {
+    if !data_integrity::is_secured_document(secured) {
+        return None;
+    }
+    serde_json::from_str(secured).ok().filter(is_bbs_document)
+}
+
+/// Create a VCDM 2.0 credential signed with an issuer BBS key held in a key store.
+///
+/// Each `credentialSubject` entry is signed as its own message, so the holder
+/// can later disclose any subset of them. The credential carries no ID and a
+/// day-precision `validFrom`, which would otherwise single it out.
+pub async fn create_credential_with_store(
+    issuer_did: &str,
+    key_id: &str,
+    draft: &jwt::CredentialDraft,
+    store: &dyn KeyStore,
+    kid: &str,
+) -> Result<Value, AppError> {
+    // Step 1: Build the credential without its correlating fields
+    let now = Utc::now();
+    let mut document = jwt::credential_document(issuer_did, draft, now)?;
+    let credential = document.as_object_mut()
+        .ok_or_else(|| AppError::InternalError("Credential is not a JSON object".to_string()))?;
+    credential.remove("id");
+    let issued_on = now.date_naive().and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    credential.insert("validFrom".to_string(), json!(issued_on.to_rfc3339()));
+
+    // Step 2: Sign the header and the subject entries
+    let mut proof = json!({
+        "type": DATA_INTEGRITY_PROOF,
+        "cryptosuite": CRYPTOSUITE,
+        "verificationMethod": key_id,
+        "proofPurpose": ASSERTION_METHOD,
+    });
+    let header = header(&document, &proof)?;
+    let messages: Vec<Vec<u8>> = subject_messages(&document)?.into_iter().map(|(_, message)| message).collect();
+    let signature = store.bbs_sign(kid, &header, &messages).await?;
+
+    // Step 3: Embed the signature as the base proof
+    proof["proofValue"] = json!(encode_proof_value(&signature));
+    document["proof"] = proof;
+
+    Ok(document)
+}
+
+/// Derive a proof from a BBS credential that discloses only the named subject entries.
+///
+/// The other entries are left out of the derived credential, and every
+/// derived proof is freshly randomized, so verifiers cannot link two
+/// presentations of one credential through the proof. `verifier` and `nonce`
+/// bind the proof to one presentation.
+pub async fn derive_proof(
+    document: &Value,
+    disclosed: &[String],
+    verifier: Option<&str>,
+    nonce: Option<&str>,
+    resolver: &dyn DidResolver,
+) -> Result<Value, AppError> {
+    // Step 1: Take the issuer's signature from the base proof
+    let (unsecured, proof) = split_proof(document)?;
+    if proof.contains_key("disclosedIndexes") {
+        return Err(AppError::ValidationError("Proofs can only be derived from a base BBS proof".to_string()));
+    }
+    let signature = decode_proof_value(&proof)?;
+    let proof_config = proof_config(&proof)?;
+    let (_, public_key) = resolve_key(&proof_config, resolver).await?;
+
+    // Step 2: Find the messages to disclose
+    let messages = subject_messages(&unsecured)?;
+    if let Some(name) = disclosed.iter().find(|name| !messages.iter().any(|(entry, _)| entry == *name)) {
+        return Err(AppError::ValidationError(format!("Attribute {} not found in credential", name)));
+    }
+    let disclosed_indexes: Vec<usize> = messages.iter()
+        .enumerate()
+        .filter(|(_, (name, _))| disclosed.contains(name))
+        .map(|(index, _)| index)
+        .collect();
+
+    // Step 3: Prove knowledge of the signature over the hidden messages
+    let header = header(&unsecured, &proof_config)?;
+    let ph = presentation_header(verifier, nonce)?;
+    let all_messages: Vec<Vec<u8>> = messages.into_iter().map(|(_, message)| message).collect();
+    let bbs_proof = bbs::proof_gen(&public_key, &signature, &header, &ph, &all_messages, &disclosed_indexes)?;
+
+    // Step 4: Keep only the disclosed entries in the derived credential
+    let mut derived = unsecured;
+    if let Some(subject) = derived["credentialSubject"].as_object_mut() {
+        subject.retain(|name, _| disclosed.contains(name));
+    }
+
+    let mut derived_proof = proof_config;
+    derived_proof["proofValue"] = json!(encode_proof_value(&bbs_proof));
+    derived_proof["disclosedIndexes"] = json!(disclosed_indexes);
+    if let Some(nonce) = nonce {
+        derived_proof["challenge"] = json!(nonce);
+    }
+    if let Some(verifier) = verifier {
+        derived_proof["domain"] = json!(verifier);
+    }
+    derived["proof"] = derived_proof;
+
+    Ok(derived)
+}
+
+/// Verify the base or derived proof of a BBS credential.
+///
+/// With a `(verifier, nonce)` session, only a derived proof made for that
+/// verifier and nonce is accepted.
+pub async fn verify(
+    document: &Value,
+    resolver: &dyn DidResolver,
+    session: Option<(&str, &str)>,
+) -> Result<VerifiedBbsProof, AppError> {
+    // Step 1: Split the document and resolve the issuer's BBS key
+    let (unsecured, proof) = split_proof(document)?;
+    let proof_value = decode_proof_value(&proof)?;
+    let proof_config = proof_config(&proof)?;
+    let (verification_method, public_key) = resolve_key(&proof_config, resolver).await?;
+
+    let header = header(&unsecured, &proof_config)?;
+    let messages = subject_messages(&unsecured)?;
+
+    // Step 2: Check a base proof against every message
+    let disclosed_indexes = match proof.get("disclosedIndexes") {
+        Some(indexes) => indexes,
+        None => {
+            if session.is_some() {
+                return Err(AppError::SsiError("Presented BBS credentials must carry a derived proof".to_string()));
+            }
+            let messages: Vec<Vec<u8>> = messages.into_iter().map(|(_, message)| message).collect();
+            if !bbs::verify(&public_key, &proof_value, &header, &messages)? {
+                return Err(AppError::SsiError("BBS signature verification failed".to_string()));
+            }
+            return Ok(VerifiedBbsProof { verification_method, derived: false });
+        }
+    };
+
+    // Step 3: Check a derived proof against the disclosed messages and its presentation
+    let challenge = proof.get("challenge").and_then(|v| v.as_str());
+    let domain = proof.get("domain").and_then(|v| v.as_str());
+    if let Some((verifier, nonce)) = session {
+        if domain != Some(verifier) || challenge != Some(nonce) {
+            return Err(AppError::SsiError("BBS proof was not derived for this verifier and nonce".to_string()));
+        }
+    }
+
+    let disclosed_indexes: Vec<usize> = serde_json::from_value(disclosed_indexes.clone())
+        .map_err(|_| AppError::SsiError("Invalid disclosedIndexes".to_string()))?;
+    if disclosed_indexes.len() != messages.len() {
+        return Err(AppError::SsiError("disclosedIndexes does not match the disclosed attributes".to_string()));
+    }
+    let disclosed_messages: Vec<(usize, Vec<u8>)> = disclosed_indexes.into_iter()
+        .zip(messages.into_iter().map(|(_, message)| message))
+        .collect();
+
+    let ph = presentation_header(domain, challenge)?;
+    if !bbs::proof_verify(&public_key, &proof_value, &header, &ph, &disclosed_messages)? {
+        return Err(AppError::SsiError("BBS proof verification failed".to_string()));
+    }
+
+    Ok(VerifiedBbsProof { verification_method, derived: true })
+}
+
+/// Split a secured document into the unsecured document and its proof
+fn split_proof(document: &Value) -> Result<(Value, Map<String, Value>), AppError> {
+    let mut unsecured = document.as_object()
+        .ok_or_else(|| AppError::SsiError("Secured document is not a JSON object".to_string()))?
+        .clone();
+    match unsecured.remove("proof") {
+        Some(Value::Object(proof)) => Ok((Value::Object(unsecured), proof)),
+        Some(_) => Err(AppError::SsiError("Only a single proof object is supported".to_string())),
+        None => Err(AppError::SsiError("Document has no proof".to_string())),
+    }
+}
+
+/// Proof options covered by the BBS header, checked against the cryptosuite
+fn proof_config(proof: &Map<String, Value>) -> Result<Value, AppError> {
+    let field = |name: &str| -> Result<&str, AppError> {
+        proof.get(name).and_then(|v| v.as_str())
+            .ok_or_else(|| AppError::SsiError(format!("Proof has no {}", name)))
+    };
+
+    if field("type")? != DATA_INTEGRITY_PROOF || field("cryptosuite")? != CRYPTOSUITE {
+        return Err(AppError::SsiError(format!("Not a {} proof", CRYPTOSUITE)));
+    }
+    if field("proofPurpose")? != ASSERTION_METHOD {
+        return Err(AppError::SsiError(format!("Unsupported proof purpose {}", field("proofPurpose")?)));
+    }
+
+    Ok(json!({
+        "type": DATA_INTEGRITY_PROOF,
+        "cryptosuite": CRYPTOSUITE,
+        "verificationMethod": field("verificationMethod")?,
+        "proofPurpose": ASSERTION_METHOD,
+    }))
+}
+
+/// Resolve the BBS public key of a proof's verification method, which the DID must authorize for assertions
+async fn resolve_key(proof_config: &Value, resolver: &dyn DidResolver) -> Result<(String, Vec<u8>), AppError> {
+    let verification_method = proof_config["verificationMethod"].as_str().unwrap_or_default().to_string();
+    let (did, _) = split_did_url(&verification_method);
+    let did_document = resolver.resolve(did).await?;
+    if !did_document.assertion_method.contains(&verification_method) {
+        return Err(AppError::SsiError(format!("{} is not an assertion method of {}", verification_method, did)));
+    }
+    let public_key = did_document.verification_method(&verification_method)
+        .ok_or_else(|| AppError::SsiError(format!("Verification method {} not found", verification_method)))?
+        .bbs_public_key()?;
+
+    Ok((verification_method, public_key))
+}
+
+/// BBS header: the JCS form of the credential without its subject, with the proof options.
+/// It is disclosed with every derived proof.
+fn header(unsecured: &Value, proof_config: &Value) -> Result<Vec<u8>, AppError> {
+    let mut header = unsecured.clone();
+    if let Some(credential) = header.as_object_mut() {
+        credential.remove("credentialSubject");
+        credential.insert("proof".to_string(), proof_config.clone());
+    }
+    Ok(jcs::canonicalize(&header)?.into_bytes())
+}
+
+/// BBS messages: the JCS form of `{name: value}` for each subject entry, ordered by name
+fn subject_messages(unsecured: &Value) -> Result<Vec<(String, Vec<u8>)>, AppError> {
+    let subject = unsecured["credentialSubject"].as_object()
+        .ok_or_else(|| AppError::SsiError("credentialSubject is not a JSON object".to_string()))?;
+
+    let mut messages = subject.iter()
+        .map(|(name, value)| {
+            let entry = jcs::canonicalize(&json!({ name: value }))?;
+            Ok((name.clone(), entry.into_bytes()))
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+    messages.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(messages)
+}
+
+/// Presentation header binding a derived proof to a verifier and nonce
+fn presentation_header(verifier: Option<&str>, nonce: Option<&str>) -> Result<Vec<u8>, AppError> {
+    if verifier.is_none() && nonce.is_none() {
+        return Ok(Vec::new());
+    }
+    let mut ph = Map::new();
+    if let Some(nonce) = nonce {
+        ph.insert("challenge".to_string(), json!(nonce));
+    }
+    if let Some(verifier) = verifier {
+        ph.insert("domain".to_string(), json!(verifier));
+    }
+    Ok(jcs::canonicalize(&Value::Object(ph))?.into_bytes())
+}
+
+/// Multibase base64url encoding of a signature or proof
+fn encode_proof_value(bytes: &[u8]) -> String {
+    format!("u{}", general_purpose::URL_SAFE_NO_PAD.encode(bytes))
+}
+
+fn decode_proof_value(proof: &Map<String, Value>) -> Result<Vec<u8>, AppError> {
+    proof.get("proofValue")
+        .and_then(|v| v.as_str())
+        .and_then(|value| value.strip_prefix('u'))
+        .and_then(|encoded| general_purpose::URL_SAFE_NO_PAD.decode(encoded).ok())
+        .ok_or_else(|| AppError::SsiError("proofValue is not base64url multibase".to_string()))
+}
+
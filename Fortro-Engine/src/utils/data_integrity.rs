@@ -0,0 +1,282 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::error::AppError;
+use crate::resolver::{split_did_url, DidResolver};
+use crate::utils::did::KeyType;
+use crate::utils::jcs;
+
+/// Proof type of all Data Integrity cryptosuites
+pub const DATA_INTEGRITY_PROOF: &str = "DataIntegrityProof";
+
+/// JSON-LD context defining `DataIntegrityProof`, already part of the VCDM 2.0 context
+pub const DATA_INTEGRITY_CONTEXT: &str = "https://w3id.org/security/data-integrity/v2";
+
+/// Proof purpose of credential proofs
+pub const ASSERTION_METHOD: &str = "assertionMethod";
+
+/// Data Integrity cryptosuite; both canonicalize with JCS and hash with SHA-256
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cryptosuite {
+    /// Ed25519 signatures, as specified by the W3C EdDSA cryptosuites
+    #[serde(rename = "eddsa-jcs-2022")]
+    EddsaJcs2022,
+    /// Dilithium2 signatures with the `eddsa-jcs-2022` transformation and hashing
+    #[serde(rename = "dilithium2-jcs-2024")]
+    Dilithium2Jcs2024,
+}
+
+impl Cryptosuite {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cryptosuite::EddsaJcs2022 => "eddsa-jcs-2022",
+            Cryptosuite::Dilithium2Jcs2024 => "dilithium2-jcs-2024",
+        }
+    }
+
+    /// Type of the keys that sign with this cryptosuite
+    pub fn key_type(&self) -> KeyType {
+        match self {
+            Cryptosuite::EddsaJcs2022 => KeyType::Ed25519,
+            Cryptosuite::Dilithium2Jcs2024 => KeyType::Dilithium2,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "eddsa-jcs-2022" => Some(Cryptosuite::EddsaJcs2022),
+            "dilithium2-jcs-2024" => Some(Cryptosuite::Dilithium2Jcs2024),
+            _ => None,
+        }
+    }
+}
+
+/// Proof of a document whose signature checked out
+#[derive(Debug, Clone)]
+pub struct VerifiedProof {
+    pub verification_method: String,
+}
+
+/// Whether a secured credential is a JSON document rather than a JWT
+pub fn is_secured_document(secured: &str) -> bool {
+    secured.trim_start().starts_with('{')
+}
+
+/// Build the proof configuration of a new proof and the bytes to sign for it.
+///
+/// Sign the bytes with the key of `verification_method`, then pass the
+/// signature to `attach_proof`.
+pub fn prepare_proof(
+    document: &Value,
+    cryptosuite: Cryptosuite,
+    verification_method: &str,
+    created: DateTime<Utc>,
+) -> Result<(Value, Vec<u8>), AppError> {
+    if document.get("proof").is_some() {
+        return Err(AppError::ValidationError("Document already has a proof".to_string()));
+    }
+
+    let proof_config = json!({
+        "type": DATA_INTEGRITY_PROOF,
+        "cryptosuite": cryptosuite.as_str(),
+        "created": created.to_rfc3339_opts(SecondsFormat::Secs, true),
+        "verificationMethod": verification_method,
+        "proofPurpose": ASSERTION_METHOD,
+    });
+
+    let hash_data = hash_data(document, &proof_config)?;
+    Ok((proof_config, hash_data))
+}
+
+/// Embed a proof, made from a proof configuration and its signature, into a document
+pub fn attach_proof(mut document: Value, mut proof_config: Value, signature: &[u8]) -> Result<Value, AppError> {
+    proof_config["proofValue"] = json!(format!("z{}", bs58::encode(signature).into_string()));
+
+    document.as_object_mut()
+        .ok_or_else(|| AppError::ValidationError("Only JSON objects can be secured".to_string()))?
+        .insert("proof".to_string(), proof_config);
+
+    Ok(document)
+}
+
+/// Verify the embedded proof of a document against the DID document of its verification method.
+///
+/// Only single `assertionMethod` proofs of the supported cryptosuites are accepted.
+/// A rotated-out key is accepted only if it was still valid at the trusted
+/// `signed_at`; the proof's own `created` is not trusted for that.
+pub async fn verify_proof(
+    document: &Value,
+    resolver: &dyn DidResolver,
+    signed_at: Option<DateTime<Utc>>,
+) -> Result<VerifiedProof, AppError> {
+    // Step 1: Split the document into the unsecured document and the proof
+    let mut unsecured = document.as_object()
+        .ok_or_else(|| AppError::SsiError("Secured document is not a JSON object".to_string()))?
+        .clone();
+    let mut proof = match unsecured.remove("proof") {
+        Some(Value::Object(proof)) => proof,
+        Some(_) => return Err(AppError::SsiError("Only a single proof object is supported".to_string())),
+        None => return Err(AppError::SsiError("Document has no proof".to_string())),
+    };
+    let mut unsecured = Value::Object(unsecured);
+
+    // Step 2: Check the proof options
+    let field = |name: &str| -> Result<String, AppError> {
+        proof.get(name).and_then(|v| v.as_str()).map(str::to_string)
+            .ok_or_else(|| AppError::SsiError(format!("Proof has no {}", name)))
+    };
+
+    if field("type")? != DATA_INTEGRITY_PROOF {
+        return Err(AppError::SsiError(format!("Unsupported proof type {}", field("type")?)));
+    }
+    let cryptosuite = Cryptosuite::from_name(&field("cryptosuite")?)
+        .ok_or_else(|| AppError::SsiError(format!("Unsupported cryptosuite {}", field("cryptosuite").unwrap_or_default())))?;
+    if field("proofPurpose")? != ASSERTION_METHOD {
+        return Err(AppError::SsiError(format!("Unsupported proof purpose {}", field("proofPurpose")?)));
+    }
+    let verification_method = field("verificationMethod")?;
+    DateTime::parse_from_rfc3339(&field("created")?)
+        .map_err(|e| AppError::SsiError(format!("Invalid proof creation time: {}", e)))?;
+
+    let proof_value = field("proofValue")?;
+    let signature = proof_value.strip_prefix('z')
+        .and_then(|encoded| bs58::decode(encoded).into_vec().ok())
+        .ok_or_else(|| AppError::SsiError("proofValue is not base58btc multibase".to_string()))?;
+    proof.remove("proofValue");
+
+    // A proof context must be a prefix of the document context
+    if let Some(proof_context) = proof.get("@context") {
+        let contexts = |value: &Value| match value {
+            Value::Array(items) => items.clone(),
+            other => vec![other.clone()],
+        };
+        let document_context = unsecured.get("@context").map(contexts).unwrap_or_default();
+        if !document_context.starts_with(&contexts(proof_context)) {
+            return Err(AppError::SsiError("Proof context does not match the document context".to_string()));
+        }
+        unsecured["@context"] = proof_context.clone();
+    }
+
+    // Step 3: Resolve the key, which the DID must authorize for assertions
+    let (did, _) = split_did_url(&verification_method);
+    let did_document = resolver.resolve(did).await?;
+    if !did_document.assertion_method.contains(&verification_method) {
+        return Err(AppError::SsiError(format!("{} is not an assertion method of {}", verification_method, did)));
+    }
+    let method = did_document.verification_method(&verification_method)
+        .ok_or_else(|| AppError::SsiError(format!("Verification method {} not found", verification_method)))?;
+    let public_key = method.public_key()?;
+    if public_key.key_type != cryptosuite.key_type() {
+        return Err(AppError::SsiError(format!("Key {} cannot verify {} proofs", verification_method, cryptosuite.as_str())));
+    }
+
+    // Step 4: Verify the signature over the hashed proof configuration and document
+    let hash_data = hash_data(&unsecured, &Value::Object(proof))?;
+    if !public_key.verify(&hash_data, &signature)? {
+        return Err(AppError::SsiError("Proof signature verification failed".to_string()));
+    }
+
+    // Reject proofs made after the key was rotated out
+    if let Some(expires) = method.expires {
+        if signed_at.unwrap_or_else(Utc::now) > expires {
+            return Err(AppError::SsiError(format!("Proof was created after key {} expired", verification_method)));
+        }
+    }
+
+    Ok(VerifiedProof { verification_method })
+}
+
+/// Canonicalize and hash the proof configuration and the unsecured document.
+///
+/// The proof configuration takes the document's `@context`, so the signature
+/// also covers the context.
+fn hash_data(unsecured: &Value, proof_config: &Value) -> Result<Vec<u8>, AppError> {
+    let mut proof_config = proof_config.clone();
+    if let Some(context) = unsecured.get("@context") {
+        proof_config["@context"] = context.clone();
+    }
+
+    let mut hash_data = Sha256::digest(jcs::canonicalize(&proof_config)?.as_bytes()).to_vec();
+    hash_data.extend_from_slice(&Sha256::digest(jcs::canonicalize(unsecured)?.as_bytes()));
+    Ok(hash_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::KeyResolver;
+    use crate::utils::did;
+
+    // Test vector of the eddsa-jcs-2022 cryptosuite, W3C Data Integrity EdDSA Cryptosuites v1.0
+    const PUBLIC_KEY: &str = "z6MkrJVnaZkeFzdQyMZu1cgjg7k1pZZ6pvBQ7XJPt4swbTQ2";
+    const SECRET_KEY: &str = "z3u2en7t5LR2WtQH5PfFqMqwVHBeXouLzo6haApm8XHqvjxq";
+    const CANONICAL_DOCUMENT: &str = r#"{"@context":["https://www.w3.org/ns/credentials/v2","https://www.w3.org/ns/credentials/examples/v2"],"credentialSubject":{"alumniOf":"The School of Examples","id":"did:example:abcdefgh"},"description":"A minimum viable example of an Alumni Credential.","id":"urn:uuid:58172aac-d8ba-11ed-83dd-0b3aef56cc33","issuer":"https://vc.example/issuers/5678","name":"Alumni Credential","type":["VerifiableCredential","AlumniCredential"],"validFrom":"2023-01-01T00:00:00Z"}"#;
+    const PROOF_CONFIG_HASH: &str = "66ab154f5c2890a140cb8388a22a160454f80575f6eae09e5a097cabe539a1db";
+    const DOCUMENT_HASH: &str = "59b7cb6251b8991add1ce0bc83107e3db9dbbab5bd2c28f687db1a03abc92f19";
+    const SIGNATURE: &str = "407cd12654b33d718ecbb99179a1506daaa849450bf3fc523cce3e1c96f8b80351da3f253d725c6f00b07c9e5448d50b3ef78012b9ab54255116d069c6dd2808";
+    const PROOF_VALUE: &str = "z2HnFSSPPBzR36zdDgK8PbEHeXbR56YF24jwMpt3R1eHXQzJDMWS93FCzpvJpwTWd3GAVFuUfjoJdcnTMuVor51aX";
+
+    fn credential() -> Value {
+        json!({
+            "@context": [
+                "https://www.w3.org/ns/credentials/v2",
+                "https://www.w3.org/ns/credentials/examples/v2"
+            ],
+            "id": "urn:uuid:58172aac-d8ba-11ed-83dd-0b3aef56cc33",
+            "type": ["VerifiableCredential", "AlumniCredential"],
+            "name": "Alumni Credential",
+            "description": "A minimum viable example of an Alumni Credential.",
+            "issuer": "https://vc.example/issuers/5678",
+            "validFrom": "2023-01-01T00:00:00Z",
+            "credentialSubject": {
+                "id": "did:example:abcdefgh",
+                "alumniOf": "The School of Examples"
+            }
+        })
+    }
+
+    /// Ed25519 seed of the multibase secret key, after its multicodec prefix
+    fn secret_key() -> Vec<u8> {
+        let decoded = bs58::decode(&SECRET_KEY[1..]).into_vec().unwrap();
+        assert_eq!(decoded[..2], [0x80, 0x26]);
+        decoded[2..].to_vec()
+    }
+
+    #[tokio::test]
+    async fn matches_the_eddsa_jcs_2022_test_vector() {
+        let document = credential();
+        let verification_method = format!("did:key:{}#{}", PUBLIC_KEY, PUBLIC_KEY);
+        let created = "2023-02-24T23:36:38Z".parse().unwrap();
+
+        assert_eq!(jcs::canonicalize(&document).unwrap(), CANONICAL_DOCUMENT);
+
+        let (proof_config, hash_data) =
+            prepare_proof(&document, Cryptosuite::EddsaJcs2022, &verification_method, created).unwrap();
+        assert_eq!(hex::encode(&hash_data), format!("{}{}", PROOF_CONFIG_HASH, DOCUMENT_HASH));
+
+        let signature = did::sign_with_key(KeyType::Ed25519, &hash_data, &secret_key()).unwrap();
+        assert_eq!(hex::encode(&signature), SIGNATURE);
+
+        let secured = attach_proof(document, proof_config, &signature).unwrap();
+        assert_eq!(secured["proof"]["proofValue"], PROOF_VALUE);
+
+        let verified = verify_proof(&secured, &KeyResolver, None).await.unwrap();
+        assert_eq!(verified.verification_method, verification_method);
+    }
+
+    #[tokio::test]
+    async fn rejects_a_tampered_document() {
+        let document = credential();
+        let verification_method = format!("did:key:{}#{}", PUBLIC_KEY, PUBLIC_KEY);
+        let (proof_config, hash_data) =
+            prepare_proof(&document, Cryptosuite::EddsaJcs2022, &verification_method, Utc::now()).unwrap();
+        let signature = did::sign_with_key(KeyType::Ed25519, &hash_data, &secret_key()).unwrap();
+
+        let mut secured = attach_proof(document, proof_config, &signature).unwrap();
+        secured["credentialSubject"]["alumniOf"] = json!("Another School");
+
+        assert!(verify_proof(&secured, &KeyResolver, None).await.is_err());
+    }
+}
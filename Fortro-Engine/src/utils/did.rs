@@ -1,7 +1,150 @@
 use serde::{Deserialize, Serialize};
 use crate::error::AppError;
+use crate::keystore::KeyStore;
 use crate::utils::crypto::{generate_dilithium_keypair, dilithium_sign, dilithium_verify};
 use crystals_dilithium::dilithium2::PUBLICKEYBYTES;
+use did_key::{Ed25519KeyPair, Fingerprint, KeyMaterial, P256KeyPair};
+use std::convert::TryFrom;
+
+/// Multicodec prefix of an Ed25519 public key
+const ED25519_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+/// Multicodec prefix of a compressed P-256 public key
+const P256_MULTICODEC: [u8; 2] = [0x80, 0x24];
+
+/// Signature algorithm of a DID key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum KeyType {
+    /// Post-quantum key of a did:alyra DID
+    #[default]
+    #[serde(rename = "dilithium2")]
+    Dilithium2,
+    #[serde(rename = "ed25519")]
+    Ed25519,
+    #[serde(rename = "p256")]
+    P256,
+}
+
+impl KeyType {
+    /// JOSE `alg` value of JWTs signed with this key type
+    pub fn jwt_alg(&self) -> &'static str {
+        match self {
+            KeyType::Dilithium2 => "Dilithium",
+            KeyType::Ed25519 => "EdDSA",
+            KeyType::P256 => "ES256",
+        }
+    }
+}
+
+/// Public key of a DID verification method
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublicKey {
+    pub key_type: KeyType,
+    pub bytes: Vec<u8>,
+}
+
+impl PublicKey {
+    /// Decode a multicodec-prefixed, base58btc multibase key (`z…`), as used by did:key and Multikey
+    pub fn from_multikey(multibase: &str) -> Result<Self, AppError> {
+        let data = multibase.strip_prefix('z')
+            .and_then(|encoded| bs58::decode(encoded).into_vec().ok())
+            .ok_or_else(|| AppError::SsiError(format!("Invalid multibase key: {}", multibase)))?;
+
+        let key = match data.split_at_checked(2) {
+            Some((prefix, bytes)) if prefix == ED25519_MULTICODEC => PublicKey { key_type: KeyType::Ed25519, bytes: bytes.to_vec() },
+            Some((prefix, bytes)) if prefix == P256_MULTICODEC => PublicKey { key_type: KeyType::P256, bytes: bytes.to_vec() },
+            _ => return Err(AppError::SsiError(format!("Unsupported key type in {}", multibase))),
+        };
+
+        key.validate()?;
+        Ok(key)
+    }
+
+    /// Encode as a multicodec-prefixed, base58btc multibase key
+    pub fn to_multikey(&self) -> Result<String, AppError> {
+        let prefix = match self.key_type {
+            KeyType::Ed25519 => ED25519_MULTICODEC,
+            KeyType::P256 => P256_MULTICODEC,
+            KeyType::Dilithium2 => {
+                return Err(AppError::SsiError("Dilithium2 keys have no multicodec".to_string()))
+            }
+        };
+
+        Ok(format!("z{}", bs58::encode([&prefix[..], &self.bytes].concat()).into_string()))
+    }
+
+    /// Decode an Ed25519 (`OKP`) or P-256 (`EC`) public JWK
+    pub fn from_jwk(jwk: &serde_json::Value) -> Result<Self, AppError> {
+        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+
+        let member = |name: &str| -> Result<Vec<u8>, AppError> {
+            jwk.get(name)
+                .and_then(|v| v.as_str())
+                .and_then(|v| URL_SAFE_NO_PAD.decode(v).ok())
+                .ok_or_else(|| AppError::SsiError(format!("JWK is missing a valid '{}' member", name)))
+        };
+        let kty = jwk.get("kty").and_then(|v| v.as_str()).unwrap_or_default();
+        let crv = jwk.get("crv").and_then(|v| v.as_str()).unwrap_or_default();
+
+        let key = match (kty, crv) {
+            ("OKP", "Ed25519") => PublicKey { key_type: KeyType::Ed25519, bytes: member("x")? },
+            ("EC", "P-256") => {
+                // Store the compressed point, as Multikey does
+                let uncompressed = [&[0x04][..], &member("x")?, &member("y")?].concat();
+                let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&uncompressed)
+                    .map_err(|e| AppError::SsiError(format!("Invalid P-256 JWK: {}", e)))?;
+                PublicKey { key_type: KeyType::P256, bytes: key.to_encoded_point(true).as_bytes().to_vec() }
+            }
+            _ => return Err(AppError::SsiError(format!("Unsupported JWK key type {} {}", kty, crv))),
+        };
+
+        key.validate()?;
+        Ok(key)
+    }
+
+    /// Check that the bytes are a valid key of their type
+    fn validate(&self) -> Result<(), AppError> {
+        let valid = match self.key_type {
+            KeyType::Dilithium2 => self.bytes.len() == PUBLICKEYBYTES,
+            KeyType::Ed25519 => ed25519_dalek::PublicKey::from_bytes(&self.bytes).is_ok(),
+            KeyType::P256 => p256::ecdsa::VerifyingKey::from_sec1_bytes(&self.bytes).is_ok(),
+        };
+
+        if !valid {
+            return Err(AppError::SsiError(format!("Invalid {:?} public key", self.key_type)));
+        }
+        Ok(())
+    }
+
+    /// Verify a signature over `data`
+    pub fn verify(&self, data: &[u8], signature: &[u8]) -> Result<bool, AppError> {
+        match self.key_type {
+            KeyType::Dilithium2 => pq_verify(data, signature, &self.bytes),
+            KeyType::Ed25519 => {
+                use ed25519_dalek::Verifier;
+
+                let key = ed25519_dalek::PublicKey::from_bytes(&self.bytes)
+                    .map_err(|e| AppError::SsiError(format!("Invalid Ed25519 public key: {}", e)))?;
+                let signature = match ed25519_dalek::Signature::try_from(signature) {
+                    Ok(signature) => signature,
+                    Err(_) => return Ok(false),
+                };
+                Ok(key.verify(data, &signature).is_ok())
+            }
+            KeyType::P256 => {
+                use p256::ecdsa::signature::Verifier;
+
+                let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&self.bytes)
+                    .map_err(|e| AppError::SsiError(format!("Invalid P-256 public key: {}", e)))?;
+                let signature = match p256::ecdsa::Signature::try_from(signature) {
+                    Ok(signature) => signature,
+                    Err(_) => return Ok(false),
+                };
+                Ok(key.verify(data, &signature).is_ok())
+            }
+        }
+    }
+}
 
 /// DID key pair containing both public and private keys
 #[derive(Clone, Serialize, Deserialize)]
@@ -28,6 +171,119 @@ pub fn generate_did() -> Result<DidKeyPair, AppError> {
     })
 }
 
+/// Generate a did:alyra DID whose private key is created and kept inside a key store
+pub async fn generate_did_in_store(store: &dyn KeyStore, kid: &str) -> Result<DidKeyPair, AppError> {
+    let public_key = store.generate_key(kid).await?;
+
+    let public_key_base58 = bs58::encode(&public_key).into_string();
+    let did = format!("did:alyra:{}", &public_key_base58);
+
+    Ok(DidKeyPair {
+        did,
+        public_key_base58,
+        private_key_base58: None,
+    })
+}
+
+/// Generate a new did:key DID with an Ed25519 or P-256 key pair
+pub fn generate_did_key(key_type: KeyType) -> Result<DidKeyPair, AppError> {
+    let key_pair = match key_type {
+        KeyType::Ed25519 => did_key::generate::<Ed25519KeyPair>(None),
+        KeyType::P256 => did_key::generate::<P256KeyPair>(None),
+        KeyType::Dilithium2 => return generate_did(),
+    };
+
+    Ok(DidKeyPair {
+        did: format!("did:key:{}", key_pair.fingerprint()),
+        public_key_base58: bs58::encode(key_pair.public_key_bytes()).into_string(),
+        private_key_base58: Some(bs58::encode(key_pair.private_key_bytes()).into_string()),
+    })
+}
+
+/// Create a did:key DID from an existing Ed25519 or P-256 secret key
+pub fn did_key_from_private_key(key_type: KeyType, private_key_base58: &str) -> Result<DidKeyPair, AppError> {
+    let private_key = bs58::decode(private_key_base58)
+        .into_vec()
+        .map_err(|e| AppError::SsiError(format!("Failed to decode private key: {}", e)))?;
+
+    // Check the secret first: the did-key crate panics on invalid key material
+    let key_pair = match key_type {
+        KeyType::Ed25519 => {
+            ed25519_dalek::SecretKey::from_bytes(&private_key)
+                .map_err(|e| AppError::SsiError(format!("Invalid Ed25519 private key: {}", e)))?;
+            did_key::from_existing_key::<Ed25519KeyPair>(&[], Some(&private_key))
+        }
+        KeyType::P256 => {
+            p256::ecdsa::SigningKey::from_bytes(&private_key)
+                .map_err(|e| AppError::SsiError(format!("Invalid P-256 private key: {}", e)))?;
+            did_key::from_existing_key::<P256KeyPair>(&[], Some(&private_key))
+        }
+        KeyType::Dilithium2 => return did_from_private_key(private_key_base58),
+    };
+
+    Ok(DidKeyPair {
+        did: format!("did:key:{}", key_pair.fingerprint()),
+        public_key_base58: bs58::encode(key_pair.public_key_bytes()).into_string(),
+        private_key_base58: Some(private_key_base58.to_string()),
+    })
+}
+
+/// Decode the public key a did:key DID is made of
+pub fn did_key_public_key(did: &str) -> Result<PublicKey, AppError> {
+    let fingerprint = did.strip_prefix("did:key:")
+        .ok_or_else(|| AppError::SsiError(format!("Not a did:key DID: {}", did)))?;
+
+    PublicKey::from_multikey(fingerprint)
+}
+
+/// Key type of the keys behind a DID
+pub fn key_type_of(did: &str) -> Result<KeyType, AppError> {
+    if did.starts_with("did:alyra:") {
+        return Ok(KeyType::Dilithium2);
+    }
+    Ok(did_key_public_key(did)?.key_type)
+}
+
+/// Verification method ID of a DID's own key, as used in the JWT `kid` header
+pub fn default_key_id(did: &str) -> Result<String, AppError> {
+    match key_type_of(did)? {
+        KeyType::Dilithium2 => Ok(format!("{}#pq-keys-1", did)),
+        // did:key names its key after the fingerprint
+        _ => Ok(format!("{}#{}", did, did.trim_start_matches("did:key:"))),
+    }
+}
+
+/// Sign data with a private key of the given type
+pub fn sign_with_key(key_type: KeyType, data: &[u8], private_key: &[u8]) -> Result<Vec<u8>, AppError> {
+    match key_type {
+        KeyType::Dilithium2 => pq_sign(data, private_key),
+        KeyType::Ed25519 => {
+            let secret = ed25519_dalek::SecretKey::from_bytes(private_key)
+                .map_err(|e| AppError::SsiError(format!("Invalid Ed25519 private key: {}", e)))?;
+            let public = ed25519_dalek::PublicKey::from(&secret);
+            Ok(ed25519_dalek::ExpandedSecretKey::from(&secret).sign(data, &public).to_bytes().to_vec())
+        }
+        KeyType::P256 => {
+            use p256::ecdsa::signature::Signer;
+
+            let key = p256::ecdsa::SigningKey::from_bytes(private_key)
+                .map_err(|e| AppError::SsiError(format!("Invalid P-256 private key: {}", e)))?;
+            let signature: p256::ecdsa::Signature = key.sign(data);
+            Ok(signature.as_ref().to_vec())
+        }
+    }
+}
+
+/// Verify a signature made by the key behind a DID.
+///
+/// did:key DIDs carry their key; for did:alyra the registered Dilithium key is used.
+pub fn verify_did_signature(did: &str, public_key_base58: &str, data: &[u8], signature: &[u8]) -> Result<bool, AppError> {
+    if did.starts_with("did:key:") {
+        return did_key_public_key(did)?.verify(data, signature);
+    }
+    verify(data, signature, public_key_base58)
+}
+
 /// Create a DID key pair from an existing private key (Dilithium secret key bytes)
 pub fn did_from_private_key(private_key_base58: &str) -> Result<DidKeyPair, AppError> {
     // Decode the private key from base58
@@ -73,8 +329,93 @@ pub fn validate_did(did: &str) -> bool {
         return false;
     }
 
-    // For now, accept only did:alyra method
+    // Accept did:alyra and well-formed did:key, did:web and did:ethr DIDs
     did.starts_with("did:alyra:")
+        || did_key_public_key(did).is_ok()
+        || did_web_url(did, false).is_ok()
+        || parse_did_ethr(did).is_ok()
+}
+
+/// Chain ID and identity address of a `did:ethr:[<network>:]<address>` DID.
+///
+/// Without a network the DID is on Ethereum mainnet, as the method requires.
+pub fn parse_did_ethr(did: &str) -> Result<(u64, ethers::types::Address), AppError> {
+    let invalid = || AppError::ValidationError(format!("Invalid did:ethr DID: {}", did));
+
+    let id = did.strip_prefix("did:ethr:").ok_or_else(invalid)?;
+    let (network, identifier) = match id.rsplit_once(':') {
+        Some((network, identifier)) => (network, identifier),
+        None => ("mainnet", id),
+    };
+
+    let chain_id = match network {
+        "mainnet" => 1,
+        "sepolia" => 11155111,
+        "base" => 8453,
+        "base-sepolia" => 84532,
+        hex_id => hex_id.strip_prefix("0x")
+            .and_then(|hex_id| u64::from_str_radix(hex_id, 16).ok())
+            .ok_or_else(|| AppError::ValidationError(format!("Unknown did:ethr network: {}", network)))?,
+    };
+
+    // Only address identifiers are supported, not public keys
+    if identifier.len() != 42 || !identifier.starts_with("0x") {
+        return Err(invalid());
+    }
+    let address = identifier.parse().map_err(|_| invalid())?;
+
+    Ok((chain_id, address))
+}
+
+/// Build a did:web DID from a host (with optional port) and path segments
+pub fn did_web(domain: &str, path: &[&str]) -> String {
+    let mut did = format!("did:web:{}", domain.replace(':', "%3A"));
+    for segment in path {
+        did.push(':');
+        did.push_str(segment);
+    }
+    did
+}
+
+/// URL of the DID document of a did:web DID
+pub fn did_web_url(did: &str, allow_http: bool) -> Result<String, AppError> {
+    let invalid = || AppError::ValidationError(format!("Invalid did:web DID: {}", did));
+
+    let id = did.strip_prefix("did:web:").ok_or_else(invalid)?;
+    let mut parts = id.split(':');
+
+    // The host may carry a percent-encoded port
+    let host = parts.next().unwrap_or("").replace("%3A", ":").replace("%3a", ":");
+    let (name, port) = match host.split_once(':') {
+        Some((name, port)) => (name, Some(port)),
+        None => (host.as_str(), None),
+    };
+    let name_ok = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+        && !name.starts_with('.') && !name.starts_with('-');
+    let port_ok = port.is_none_or(|p| !p.is_empty() && p.parse::<u16>().is_ok());
+    if !name_ok || !port_ok {
+        return Err(invalid());
+    }
+
+    let segments: Vec<&str> = parts.collect();
+    for segment in &segments {
+        let segment_ok = !segment.is_empty()
+            && *segment != "." && *segment != ".."
+            && segment.chars().all(|c| c.is_ascii_alphanumeric() || "._~-".contains(c));
+        if !segment_ok {
+            return Err(invalid());
+        }
+    }
+
+    let scheme = if allow_http { "http" } else { "https" };
+    let path = if segments.is_empty() {
+        "/.well-known/did.json".to_string()
+    } else {
+        format!("/{}/did.json", segments.join("/"))
+    };
+
+    Ok(format!("{}://{}{}", scheme, host, path))
 }
 
 /// Sign data using a DID's private key (Dilithium)
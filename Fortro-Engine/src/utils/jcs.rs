@@ -0,0 +1,226 @@
+use serde_json::Value;
+
+use crate::error::AppError;
+
+/// Serialize JSON in the JSON Canonicalization Scheme (RFC 8785).
+///
+/// Object members are sorted by the UTF-16 code units of their names, there is
+/// no whitespace, and numbers are written the way ECMAScript prints them.
+pub fn canonicalize(value: &Value) -> Result<String, AppError> {
+    let mut out = String::new();
+    write_value(value, &mut out)?;
+    Ok(out)
+}
+
+fn write_value(value: &Value, out: &mut String) -> Result<(), AppError> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&number(n)?),
+        Value::String(s) => write_string(s, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out)?;
+            }
+            out.push(']');
+        }
+        Value::Object(members) => {
+            let mut names: Vec<&String> = members.keys().collect();
+            names.sort_by(|a, b| a.encode_utf16().cmp(b.encode_utf16()));
+
+            out.push('{');
+            for (i, name) in names.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(name, out);
+                out.push(':');
+                write_value(&members[name], out)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+/// Write a string with only the escapes JSON requires
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{0c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Format a number like ECMAScript's `Number.prototype.toString`
+fn number(n: &serde_json::Number) -> Result<String, AppError> {
+    if let Some(i) = n.as_i64() {
+        return Ok(i.to_string());
+    }
+    if let Some(u) = n.as_u64() {
+        return Ok(u.to_string());
+    }
+
+    let x = n.as_f64()
+        .filter(|x| x.is_finite())
+        .ok_or_else(|| AppError::ValidationError(format!("Number {} cannot be canonicalized", n)))?;
+    if x == 0.0 {
+        return Ok("0".to_string());
+    }
+
+    // Shortest round-trip digits and decimal exponent, e.g. `1.25e-7`
+    let scientific = format!("{:e}", x.abs());
+    let (mantissa, exponent) = scientific.split_once('e')
+        .ok_or_else(|| AppError::InternalError(format!("Unexpected float format {}", scientific)))?;
+    let mut digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let exponent: i32 = exponent.parse()
+        .map_err(|_| AppError::InternalError(format!("Unexpected float format {}", scientific)))?;
+
+    // When x lies halfway between two shortest digit strings, ECMAScript takes the even one
+    let exact = format!("{:.1100e}", x.abs());
+    if let Some((exact_mantissa, exact_exponent)) = exact.split_once('e') {
+        let exact_digits: String = exact_mantissa.chars().filter(|c| *c != '.').collect();
+        let (prefix, rest) = exact_digits.split_at(digits.len());
+        if exact_exponent == exponent.to_string() && rest.trim_end_matches('0') == "5" {
+            let below = prefix.to_string();
+            let above = increment(prefix);
+            let even = |candidate: &str| candidate.ends_with(['0', '2', '4', '6', '8']);
+            if digits == above && even(&below) {
+                digits = below;
+            } else if digits == below && even(&above) {
+                digits = above;
+            }
+        }
+    }
+
+    let k = digits.len() as i32;
+    let n = exponent + 1;
+    let sign = if x < 0.0 { "-" } else { "" };
+
+    let formatted = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat((-n) as usize), digits)
+    } else {
+        let exponent_sign = if n - 1 < 0 { "-" } else { "+" };
+        let mantissa = if k == 1 { digits.clone() } else { format!("{}.{}", &digits[..1], &digits[1..]) };
+        format!("{}e{}{}", mantissa, exponent_sign, (n - 1).abs())
+    };
+
+    Ok(format!("{}{}", sign, formatted))
+}
+
+/// Add one to a string of decimal digits that are not all nines
+fn increment(digits: &str) -> String {
+    let mut bytes = digits.as_bytes().to_vec();
+    for digit in bytes.iter_mut().rev() {
+        if *digit == b'9' {
+            *digit = b'0';
+        } else {
+            *digit += 1;
+            break;
+        }
+    }
+    String::from_utf8(bytes).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Canonical form of an IEEE 754 double, given by its bits
+    fn canonical_number(bits: u64) -> String {
+        let number = serde_json::Number::from_f64(f64::from_bits(bits)).unwrap();
+        canonicalize(&Value::Number(number)).unwrap()
+    }
+
+    #[test]
+    fn formats_numbers_like_ecmascript() {
+        // RFC 8785, appendix B; NaN and Infinity cannot be JSON numbers at all
+        let vectors = [
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+        ];
+        for (bits, expected) in vectors {
+            assert_eq!(canonical_number(bits), expected, "bits {:016x}", bits);
+        }
+    }
+
+    #[test]
+    fn sorts_members_by_utf16_code_units() {
+        // RFC 8785, section 3.2.3
+        let value = json!({
+            "\u{20ac}": "Euro Sign",
+            "\r": "Carriage Return",
+            "\u{fb33}": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\u{1f600}": "Emoji: Grinning Face",
+            "\u{80}": "Control",
+            "\u{f6}": "Latin Small Letter O With Diaeresis",
+        });
+
+        assert_eq!(
+            canonicalize(&value).unwrap(),
+            "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",\
+             \"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",\
+             \"\u{1f600}\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+        );
+    }
+
+    #[test]
+    fn canonicalizes_the_rfc_example() {
+        // RFC 8785, section 3.2.2
+        let value: Value = serde_json::from_str(
+            r#"{
+                "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            canonicalize(&value).unwrap(),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+}
@@ -1,9 +1,15 @@
 use crate::error::AppError;
-use crate::utils::did::{sign, pq_sign, pq_verify};
+use crate::keystore::KeyStore;
+use crate::models::VcVersion;
+use crate::resolver::{split_did_url, DidResolver};
+use crate::utils::did::{default_key_id, key_type_of, sign_with_key, KeyType};
+use crate::utils::{link_secret, zk_proofs};
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use base64::{Engine as _, engine::general_purpose};
+use hmac::{digest::KeyInit, Hmac, Mac};
+use sha2::{Sha256, Sha512};
 use std::collections::HashMap;
 
 /// JWT header
@@ -28,47 +34,168 @@ pub struct JwtClaims {
     pub additional_claims: HashMap<String, Value>,
 }
 
-/// Create a generic JWT
-pub fn create_jwt(
-    header: &JwtHeader,
+/// HMAC algorithm used for session tokens
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum HmacAlgorithm {
+    HS256,
+    HS512,
+}
+
+impl HmacAlgorithm {
+    /// JOSE `alg` header value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HmacAlgorithm::HS256 => "HS256",
+            HmacAlgorithm::HS512 => "HS512",
+        }
+    }
+
+    fn mac(&self, secret: &[u8], input: &[u8]) -> Result<Vec<u8>, AppError> {
+        Ok(match self {
+            HmacAlgorithm::HS256 => keyed_mac::<Hmac<Sha256>>(secret, input)?.finalize().into_bytes().to_vec(),
+            HmacAlgorithm::HS512 => keyed_mac::<Hmac<Sha512>>(secret, input)?.finalize().into_bytes().to_vec(),
+        })
+    }
+
+    fn verify(&self, secret: &[u8], input: &[u8], tag: &[u8]) -> Result<bool, AppError> {
+        // verify_slice compares in constant time
+        Ok(match self {
+            HmacAlgorithm::HS256 => keyed_mac::<Hmac<Sha256>>(secret, input)?.verify_slice(tag).is_ok(),
+            HmacAlgorithm::HS512 => keyed_mac::<Hmac<Sha512>>(secret, input)?.verify_slice(tag).is_ok(),
+        })
+    }
+}
+
+/// Key an HMAC with the secret and feed it the input
+fn keyed_mac<M: Mac + KeyInit>(secret: &[u8], input: &[u8]) -> Result<M, AppError> {
+    let mut mac = <M as Mac>::new_from_slice(secret)
+        .map_err(|e| AppError::InternalError(format!("Invalid HMAC key: {}", e)))?;
+    mac.update(input);
+    Ok(mac)
+}
+
+impl std::str::FromStr for HmacAlgorithm {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "HS256" => Ok(HmacAlgorithm::HS256),
+            "HS512" => Ok(HmacAlgorithm::HS512),
+            other => Err(AppError::ConfigError(format!(
+                "JWT_ALGORITHM must be 'HS256' or 'HS512', got '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Create a JWT signed with an HMAC shared secret
+pub fn create_hmac_jwt(
     claims: &JwtClaims,
-    private_key_base58: &str,
+    kid: &str,
+    secret: &[u8],
+    algorithm: HmacAlgorithm,
 ) -> Result<String, AppError> {
+    let header = JwtHeader {
+        alg: algorithm.as_str().to_string(),
+        typ: "JWT".to_string(),
+        kid: kid.to_string(),
+    };
+
     // Encode header
-    let header_json = serde_json::to_string(header)
-        .map_err(|e| AppError::SsiError(format!("Failed to serialize JWT header: {}", e)))?;
+    let header_json = serde_json::to_string(&header)
+        .map_err(|e| AppError::InternalError(format!("Failed to serialize JWT header: {}", e)))?;
     let header_base64 = general_purpose::URL_SAFE_NO_PAD.encode(header_json.as_bytes());
-    
+
     // Encode claims
     let claims_json = serde_json::to_string(claims)
-        .map_err(|e| AppError::SsiError(format!("Failed to serialize JWT claims: {}", e)))?;
+        .map_err(|e| AppError::InternalError(format!("Failed to serialize JWT claims: {}", e)))?;
     let claims_base64 = general_purpose::URL_SAFE_NO_PAD.encode(claims_json.as_bytes());
-    
-    // Create signature input
+
+    // MAC the signing input
     let signature_input = format!("{}.{}", header_base64, claims_base64);
-    
-    // Sign the input
-    let signature = sign(signature_input.as_bytes(), private_key_base58)?;
-    let signature_base64 = general_purpose::URL_SAFE_NO_PAD.encode(&signature);
-    
-    // Combine to form the JWT
-    let jwt = format!("{}.{}.{}", header_base64, claims_base64, signature_base64);
-    
-    Ok(jwt)
+    let signature = algorithm.mac(secret, signature_input.as_bytes())?;
+    let signature_base64 = general_purpose::URL_SAFE_NO_PAD.encode(signature);
+
+    Ok(format!("{}.{}.{}", header_base64, claims_base64, signature_base64))
 }
 
-/// Extract a verifiable credential from a JWT
+/// Verify a JWT signed with an HMAC shared secret, including its time claims
+pub fn verify_hmac_jwt(
+    jwt: &str,
+    secret: &[u8],
+    algorithm: HmacAlgorithm,
+) -> Result<(JwtHeader, JwtClaims), AppError> {
+    let parts: Vec<&str> = jwt.split('.').collect();
+    if parts.len() != 3 {
+        return Err(AppError::AuthError("Invalid token format".to_string()));
+    }
+
+    // Decode header and pin the algorithm to the configured one
+    let header_json = general_purpose::URL_SAFE_NO_PAD.decode(parts[0])
+        .map_err(|e| AppError::AuthError(format!("Failed to decode token header: {}", e)))?;
+    let header: JwtHeader = serde_json::from_slice(&header_json)
+        .map_err(|e| AppError::AuthError(format!("Failed to parse token header: {}", e)))?;
+
+    if header.alg != algorithm.as_str() {
+        return Err(AppError::AuthError(format!(
+            "Token algorithm {} is not accepted, expected {}",
+            header.alg,
+            algorithm.as_str()
+        )));
+    }
+
+    // Verify the MAC before trusting any claim
+    let signature = general_purpose::URL_SAFE_NO_PAD.decode(parts[2])
+        .map_err(|e| AppError::AuthError(format!("Failed to decode token signature: {}", e)))?;
+    let signature_input = format!("{}.{}", parts[0], parts[1]);
+
+    if !algorithm.verify(secret, signature_input.as_bytes(), &signature)? {
+        return Err(AppError::AuthError("Token signature verification failed".to_string()));
+    }
+
+    // Decode claims
+    let claims_json = general_purpose::URL_SAFE_NO_PAD.decode(parts[1])
+        .map_err(|e| AppError::AuthError(format!("Failed to decode token claims: {}", e)))?;
+    let claims: JwtClaims = serde_json::from_slice(&claims_json)
+        .map_err(|e| AppError::AuthError(format!("Failed to parse token claims: {}", e)))?;
+
+    let now = Utc::now().timestamp();
+
+    // Session tokens must expire
+    match claims.exp {
+        Some(exp) if exp >= now => {}
+        Some(_) => return Err(AppError::AuthError("Token is expired".to_string())),
+        None => return Err(AppError::AuthError("Token has no expiration".to_string())),
+    }
+
+    if let Some(nbf) = claims.nbf {
+        if nbf > now {
+            return Err(AppError::AuthError("Token is not yet valid".to_string()));
+        }
+    }
+
+    Ok((header, claims))
+}
+
+/// Extract a verifiable credential from a JWT without verifying it; see `verify_did_jwt`
 pub fn extract_credential(jwt: &str) -> Result<Value, AppError> {
-    let (_, claims) = verify_pq_jwt(jwt)?;
+    let (_, mut claims) = decode_jwt_unverified(jwt)?;
 
-    claims.additional_claims.get("vc")
-        .cloned()
-        .ok_or_else(|| AppError::SsiError("JWT does not contain a verifiable credential".to_string()))
+    // VCDM 1.1 wraps the credential in `vc`; in VCDM 2.0 the payload is the credential
+    if let Some(credential) = claims.additional_claims.remove("vc") {
+        return Ok(credential);
+    }
+    if claims.additional_claims.contains_key("@context") {
+        return Ok(Value::Object(claims.additional_claims.into_iter().collect()));
+    }
+
+    Err(AppError::SsiError("JWT does not contain a verifiable credential".to_string()))
 }
 
-/// Extract a verifiable presentation from a JWT
+/// Extract a verifiable presentation from a JWT without verifying it; see `verify_did_jwt`
 pub fn extract_presentation(jwt: &str) -> Result<Value, AppError> {
-    let (_, claims) = verify_pq_jwt(jwt)?;
+    let (_, claims) = decode_jwt_unverified(jwt)?;
     
     claims.additional_claims.get("vp")
         .cloned()
@@ -101,125 +228,309 @@ pub fn decode_jwt_unverified(jwt: &str) -> Result<(JwtHeader, JwtClaims), AppErr
     Ok((header, claims))
 }
 
-/// Create a JWT using post-quantum Dilithium signatures
-pub fn create_pq_jwt(
-    header: &JwtHeader,
-    claims: &JwtClaims,
-    private_key: &[u8],
-) -> Result<String, AppError> {
+/// Encode the header and claims into the JWT signing input
+fn signing_input(header: &JwtHeader, claims: &JwtClaims) -> Result<String, AppError> {
     // Encode header
     let header_json = serde_json::to_string(header)
         .map_err(|e| AppError::SsiError(format!("Failed to serialize JWT header: {}", e)))?;
     let header_base64 = general_purpose::URL_SAFE_NO_PAD.encode(header_json.as_bytes());
-    
+
     // Encode claims
     let claims_json = serde_json::to_string(claims)
         .map_err(|e| AppError::SsiError(format!("Failed to serialize JWT claims: {}", e)))?;
     let claims_base64 = general_purpose::URL_SAFE_NO_PAD.encode(claims_json.as_bytes());
-    
-    // Create signature input
-    let signature_input = format!("{}.{}", header_base64, claims_base64);
-    
-    // Sign the input using Dilithium
-    let signature = pq_sign(signature_input.as_bytes(), private_key)?;
+
+    Ok(format!("{}.{}", header_base64, claims_base64))
+}
+
+/// Create a JWT signed with a DID key: Dilithium, EdDSA or ES256 depending on `key_type`
+pub fn create_did_jwt(
+    header: &JwtHeader,
+    claims: &JwtClaims,
+    key_type: KeyType,
+    private_key: &[u8],
+) -> Result<String, AppError> {
+    if header.alg != key_type.jwt_alg() {
+        return Err(AppError::SsiError(format!("JWT algorithm {} does not match the signing key", header.alg)));
+    }
+
+    let signature_input = signing_input(header, claims)?;
+
+    let signature = sign_with_key(key_type, signature_input.as_bytes(), private_key)?;
     let signature_base64 = general_purpose::URL_SAFE_NO_PAD.encode(&signature);
-    
-    // Combine to form the JWT
-    let jwt = format!("{}.{}.{}", header_base64, claims_base64, signature_base64);
-    
-    Ok(jwt)
+
+    Ok(format!("{}.{}", signature_input, signature_base64))
+}
+
+/// Create a JWT signed by a Dilithium key held in a key store
+pub async fn create_pq_jwt_with_store(
+    header: &JwtHeader,
+    claims: &JwtClaims,
+    store: &dyn KeyStore,
+    kid: &str,
+) -> Result<String, AppError> {
+    let signature_input = signing_input(header, claims)?;
+
+    // The private key stays inside the store
+    let signature = store.sign(kid, signature_input.as_bytes()).await?;
+    let signature_base64 = general_purpose::URL_SAFE_NO_PAD.encode(&signature);
+
+    Ok(format!("{}.{}", signature_input, signature_base64))
+}
+
+/// Context of VCDM 2.0 credentials
+const VC_V2_CONTEXT: &str = "https://www.w3.org/ns/credentials/v2";
+
+/// JWT `typ` of a VCDM 2.0 credential secured with JOSE
+const VC_JWT_TYPE: &str = "vc+jwt";
+
+/// Contents of a credential to be signed
+#[derive(Debug, Clone)]
+pub struct CredentialDraft {
+    pub id: String,
+    pub version: VcVersion,
+    pub credential_type: String,
+    pub subject_did: String,
+    pub attributes: HashMap<String, Value>,
+    /// `credentialSchema` entry; VCDM 2.0 only
+    pub schema: Option<Value>,
+    /// `credentialStatus` entry or entries
+    pub status: Option<Value>,
+    pub expiration_seconds: Option<i64>,
+    /// Commitments to attributes, signed along with the credential
+    pub commitments: Option<Value>,
+    /// Blinded commitment to the holder's link secret, signed along with the credential
+    pub link_secret_commitment: Option<String>,
 }
 
-/// Create a credential JWT using post-quantum Dilithium signatures
-pub fn create_pq_credential_jwt(
+/// Create a credential JWT signed by an issuer key held in a key store
+pub async fn create_pq_credential_jwt_with_store(
     issuer_did: &str,
-    subject_did: &str,
-    credential_data: Value,
-    private_key: &[u8],
-    public_key: &[u8],
-    expiration_seconds: Option<i64>,
+    key_id: &str,
+    draft: CredentialDraft,
+    store: &dyn KeyStore,
+    kid: &str,
 ) -> Result<String, AppError> {
+    let (header, claims) = match draft.version {
+        VcVersion::V1_1 => {
+            let public_key = store.public_key(kid).await?;
+            pq_credential_parts(issuer_did, key_id, draft, &public_key)?
+        }
+        VcVersion::V2_0 => pq_credential_v2_parts(issuer_did, key_id, draft)?,
+    };
+
+    create_pq_jwt_with_store(&header, &claims, store, kid).await
+}
+
+/// Build the unsecured credential of a draft in its data model version
+pub fn credential_document(issuer_did: &str, draft: &CredentialDraft, issued_at: DateTime<Utc>) -> Result<Value, AppError> {
+    let expires_at = match draft.expiration_seconds {
+        Some(secs) => {
+            let exp = (issued_at + Duration::seconds(secs)).timestamp();
+            Some(DateTime::<Utc>::from_timestamp(exp, 0)
+                .ok_or_else(|| AppError::ValidationError("Invalid expiration date".to_string()))?)
+        }
+        None => None,
+    };
+
+    if draft.version == VcVersion::V1_1 {
+        // The credential's own type follows the engine's, so verifiers can match it to their requests
+        let mut types = vec!["VerifiableCredential".to_string(), "PostQuantumCredential".to_string()];
+        types.extend(credential_types(&draft.credential_type).into_iter().skip(1));
+
+        let mut credential = json!({
+            "@context": [
+                "https://www.w3.org/2018/credentials/v1",
+                "https://www.w3.org/2018/credentials/examples/v1"
+            ],
+            "type": types,
+            "id": draft.id,
+            "issuer": issuer_did,
+            "issuanceDate": issued_at.to_rfc3339(),
+            "expirationDate": expires_at.map(|date| date.to_rfc3339()),
+            "credentialSubject": {
+                "id": draft.subject_did,
+                "claims": draft.attributes
+            }
+        });
+        if let Some(status) = &draft.status {
+            credential["credentialStatus"] = status.clone();
+        }
+        if let Some(commitments) = &draft.commitments {
+            credential[zk_proofs::COMMITMENTS_PROPERTY] = commitments.clone();
+        }
+        if let Some(commitment) = &draft.link_secret_commitment {
+            credential[link_secret::LINK_SECRET_PROPERTY] = json!(commitment);
+        }
+        return Ok(credential);
+    }
+
+    if draft.attributes.contains_key("id") {
+        return Err(AppError::ValidationError("id is reserved for the credential subject's DID".to_string()));
+    }
+
+    // Attributes are properties of the subject itself
+    let mut subject = serde_json::Map::new();
+    subject.insert("id".to_string(), json!(draft.subject_did));
+    subject.extend(draft.attributes.clone());
+
+    let mut credential = serde_json::Map::new();
+    credential.insert("@context".to_string(), json!([VC_V2_CONTEXT]));
+    credential.insert("id".to_string(), json!(format!("urn:uuid:{}", draft.id)));
+    credential.insert("type".to_string(), json!(credential_types(&draft.credential_type)));
+    credential.insert("issuer".to_string(), json!(issuer_did));
+    credential.insert("validFrom".to_string(), json!(issued_at.to_rfc3339()));
+    if let Some(expires_at) = expires_at {
+        credential.insert("validUntil".to_string(), json!(expires_at.to_rfc3339()));
+    }
+    credential.insert("credentialSubject".to_string(), Value::Object(subject));
+    if let Some(schema) = &draft.schema {
+        credential.insert("credentialSchema".to_string(), schema.clone());
+    }
+    if let Some(status) = &draft.status {
+        credential.insert("credentialStatus".to_string(), status.clone());
+    }
+    if let Some(commitments) = &draft.commitments {
+        credential.insert(zk_proofs::COMMITMENTS_PROPERTY.to_string(), commitments.clone());
+    }
+    if let Some(commitment) = &draft.link_secret_commitment {
+        credential.insert(link_secret::LINK_SECRET_PROPERTY.to_string(), json!(commitment));
+    }
+
+    Ok(Value::Object(credential))
+}
+
+/// Build the header and claims of a post-quantum VCDM 1.1 credential JWT
+fn pq_credential_parts(
+    issuer_did: &str,
+    key_id: &str,
+    draft: CredentialDraft,
+    public_key: &[u8],
+) -> Result<(JwtHeader, JwtClaims), AppError> {
     let now = Utc::now();
-    let exp = expiration_seconds.map(|secs| (now + Duration::seconds(secs)).timestamp());
-    
-    // For PQ, we'll use a different key ID format to indicate it's using Dilithium
-    let key_id = format!("{}#pq-keys-1", issuer_did);
-    
+    let exp = draft.expiration_seconds.map(|secs| (now + Duration::seconds(secs)).timestamp());
+
     let header = JwtHeader {
         alg: "Dilithium".to_string(), // Indicate we're using Dilithium instead of EdDSA
         typ: "JWT".to_string(),
-        kid: key_id,
+        kid: key_id.to_string(),
     };
     
-    let credential_id = uuid::Uuid::new_v4().to_string();
-    
-    let credential = json!({
-        "@context": [
-            "https://www.w3.org/2018/credentials/v1",
-            "https://www.w3.org/2018/credentials/examples/v1"
-        ],
-        "type": ["VerifiableCredential", "PostQuantumCredential"],
-        "id": credential_id,
-        "issuer": issuer_did,
-        "issuanceDate": now.to_rfc3339(),
-        "expirationDate": exp.map(|ts| DateTime::<Utc>::from_timestamp(ts, 0).unwrap().to_rfc3339()),
-        "credentialSubject": {
-            "id": subject_did,
-            "claims": credential_data
-        }
-    });
+    let credential = credential_document(issuer_did, &draft, now)?;
     
     let mut claims = JwtClaims {
         iss: issuer_did.to_string(),
-        sub: Some(subject_did.to_string()),
+        sub: Some(draft.subject_did),
         aud: None,
         exp,
         nbf: Some(now.timestamp()),
         iat: now.timestamp(),
-        jti: credential_id,
+        jti: draft.id,
         additional_claims: HashMap::new(),
     };
     
     claims.additional_claims.insert("vc".to_string(), credential);
     // Store the public key in the JWT for verification
     claims.additional_claims.insert("pqk".to_string(), json!(hex::encode(public_key)));
-    
-    create_pq_jwt(&header, &claims, private_key)
+
+    Ok((header, claims))
+}
+
+/// Build the header and claims of a post-quantum VCDM 2.0 credential JWT.
+///
+/// Following VC-JOSE-COSE, the credential itself is the JWT payload, next to
+/// the registered claims, instead of being wrapped in a `vc` claim.
+fn pq_credential_v2_parts(issuer_did: &str, key_id: &str, draft: CredentialDraft) -> Result<(JwtHeader, JwtClaims), AppError> {
+    let now = Utc::now();
+    let exp = draft.expiration_seconds.map(|secs| (now + Duration::seconds(secs)).timestamp());
+
+    let header = JwtHeader {
+        alg: "Dilithium".to_string(),
+        typ: VC_JWT_TYPE.to_string(),
+        kid: key_id.to_string(),
+    };
+
+    let credential = match credential_document(issuer_did, &draft, now)? {
+        Value::Object(credential) => credential.into_iter().collect(),
+        _ => HashMap::new(),
+    };
+
+    let claims = JwtClaims {
+        iss: issuer_did.to_string(),
+        sub: Some(draft.subject_did),
+        aud: None,
+        exp,
+        nbf: Some(now.timestamp()),
+        iat: now.timestamp(),
+        jti: draft.id,
+        additional_claims: credential,
+    };
+
+    Ok((header, claims))
+}
+
+/// Credential `type` array for a credential type name, e.g. `university_degree` gives
+/// `["VerifiableCredential", "UniversityDegree"]`
+pub fn credential_types(credential_type: &str) -> Vec<String> {
+    let name: String = credential_type
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect();
+
+    let mut types = vec!["VerifiableCredential".to_string()];
+    if !name.is_empty() && name != "VerifiableCredential" {
+        types.push(name);
+    }
+    types
 }
 
-/// Create a presentation JWT using post-quantum Dilithium signatures
-pub fn create_pq_presentation_jwt(
+/// Create a presentation JWT signed with the holder's DID key, carrying the verifier's nonce if one was given
+/// and the holder's predicate proofs over the presented credentials
+pub fn create_presentation_jwt(
     holder_did: &str,
     verifier_did: Option<&str>,
-    credential_jwt_list: &[String],
+    nonce: Option<&str>,
+    credential_jwt_list: &[Value],
+    predicate_proofs: &zk_proofs::PresentationProofs,
     private_key: &[u8],
-    public_key: &[u8],
     expiration_seconds: Option<i64>,
 ) -> Result<String, AppError> {
     let now = Utc::now();
     let exp = expiration_seconds.map(|secs| (now + Duration::seconds(secs)).timestamp());
-    
-    // For PQ, we'll use a different key ID format to indicate it's using Dilithium
-    let key_id = format!("{}#pq-keys-1", holder_did);
-    
+
+    // The algorithm follows from the holder's DID method
+    let key_type = key_type_of(holder_did)?;
+
     let header = JwtHeader {
-        alg: "Dilithium".to_string(), // Indicate we're using Dilithium instead of EdDSA
+        alg: key_type.jwt_alg().to_string(),
         typ: "JWT".to_string(),
-        kid: key_id,
+        kid: default_key_id(holder_did)?,
     };
     
     let presentation_id = uuid::Uuid::new_v4().to_string();
+
+    let mut presentation_types = vec!["VerifiablePresentation"];
+    if key_type == KeyType::Dilithium2 {
+        presentation_types.push("PostQuantumPresentation");
+    }
     
-    let presentation = json!({
+    let mut presentation = json!({
         "@context": [
             "https://www.w3.org/2018/credentials/v1",
             "https://www.w3.org/2018/credentials/examples/v1"
         ],
-        "type": ["VerifiablePresentation", "PostQuantumPresentation"],
+        "type": presentation_types,
         "id": presentation_id,
         "holder": holder_did,
         "verifiableCredential": credential_jwt_list
     });
+    if let (Some(presentation), Value::Object(proofs)) = (presentation.as_object_mut(), json!(predicate_proofs)) {
+        presentation.extend(proofs);
+    }
     
     let mut claims = JwtClaims {
         iss: holder_did.to_string(),
@@ -233,60 +544,70 @@ pub fn create_pq_presentation_jwt(
     };
     
     claims.additional_claims.insert("vp".to_string(), presentation);
-    // Store the public key in the JWT for verification
-    claims.additional_claims.insert("pqk".to_string(), json!(hex::encode(public_key)));
+    if let Some(nonce) = nonce {
+        claims.additional_claims.insert("nonce".to_string(), json!(nonce));
+    }
     
-    create_pq_jwt(&header, &claims, private_key)
+    create_did_jwt(&header, &claims, key_type, private_key)
 }
 
-/// Verify a JWT that was signed using post-quantum Dilithium
-pub fn verify_pq_jwt(jwt: &str) -> Result<(JwtHeader, JwtClaims), AppError> {
-    // Split the JWT into parts
-    let parts: Vec<&str> = jwt.split('.').collect();
-    if parts.len() != 3 {
-        return Err(AppError::SsiError("Invalid JWT format".to_string()));
+/// Verify a JWT signed by a DID: Dilithium for did:alyra, EdDSA or ES256 for did:key.
+///
+/// The signing key is the verification method named by `kid`, looked up in
+/// the DID document of the JWT issuer; the `pqk` claim is not trusted.
+/// Rotated-out keys are rejected; see `verify_did_jwt_signed_at`.
+pub async fn verify_did_jwt(jwt: &str, resolver: &dyn DidResolver) -> Result<(JwtHeader, JwtClaims), AppError> {
+    verify_did_jwt_signed_at(jwt, resolver, None).await
+}
+
+/// Verify a JWT signed by a DID, accepting a rotated-out key if it was still valid at `signed_at`.
+///
+/// `signed_at` must come from a trusted source, such as the on-chain
+/// registration of a credential, never from the token's own `iat`. Without
+/// it the key must still be valid now.
+pub async fn verify_did_jwt_signed_at(
+    jwt: &str,
+    resolver: &dyn DidResolver,
+    signed_at: Option<DateTime<Utc>>,
+) -> Result<(JwtHeader, JwtClaims), AppError> {
+    let (header, claims) = decode_jwt_unverified(jwt)?;
+
+    // The key must belong to the DID that issued the token
+    let (key_did, _) = split_did_url(&header.kid);
+    if key_did != claims.iss {
+        return Err(AppError::SsiError(format!("JWT key {} does not belong to issuer {}", header.kid, claims.iss)));
     }
-    
-    let header_base64 = parts[0];
-    let claims_base64 = parts[1];
-    let signature_base64 = parts[2];
-    
-    // Decode header
-    let header_json = general_purpose::URL_SAFE_NO_PAD.decode(header_base64)
-        .map_err(|e| AppError::SsiError(format!("Failed to decode JWT header: {}", e)))?;
-    let header: JwtHeader = serde_json::from_slice(&header_json)
-        .map_err(|e| AppError::SsiError(format!("Failed to parse JWT header: {}", e)))?;
-    
-    // Check if this is a post-quantum JWT
-    if header.alg != "Dilithium" {
-        return Err(AppError::SsiError("JWT is not signed with Dilithium".to_string()));
+
+    // Resolve the signing key from the issuer's DID document
+    let document = resolver.resolve(key_did).await?;
+    let method = document.signing_key(&header.kid)
+        .ok_or_else(|| AppError::SsiError(format!("Key {} is not a signing key of {}", header.kid, key_did)))?;
+    let public_key = method.public_key()?;
+
+    // The algorithm is fixed by the key, never chosen by the token
+    if header.alg != public_key.key_type.jwt_alg() {
+        return Err(AppError::SsiError(format!("JWT algorithm {} does not match key {}", header.alg, header.kid)));
     }
-    
-    // Decode claims
-    let claims_json = general_purpose::URL_SAFE_NO_PAD.decode(claims_base64)
-        .map_err(|e| AppError::SsiError(format!("Failed to decode JWT claims: {}", e)))?;
-    let claims: JwtClaims = serde_json::from_slice(&claims_json)
-        .map_err(|e| AppError::SsiError(format!("Failed to parse JWT claims: {}", e)))?;
-    
-    // Get the public key from the claims
-    let public_key_hex = claims.additional_claims.get("pqk")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| AppError::SsiError("JWT does not contain a post-quantum public key".to_string()))?;
-    
-    let public_key = hex::decode(public_key_hex)
-        .map_err(|e| AppError::SsiError(format!("Failed to decode public key: {}", e)))?;
-    
+
     // Verify the signature
-    let signature_input = format!("{}.{}", header_base64, claims_base64);
+    let (signature_input, signature_base64) = jwt.rsplit_once('.')
+        .ok_or_else(|| AppError::SsiError("Invalid JWT format".to_string()))?;
     let signature = general_purpose::URL_SAFE_NO_PAD.decode(signature_base64)
         .map_err(|e| AppError::SsiError(format!("Failed to decode JWT signature: {}", e)))?;
-    
-    let is_valid = pq_verify(signature_input.as_bytes(), &signature, &public_key)?;
-    
+
+    let is_valid = public_key.verify(signature_input.as_bytes(), &signature)?;
+
     if !is_valid {
         return Err(AppError::SsiError("JWT signature verification failed".to_string()));
     }
-    
+
+    // Reject signatures made after the key was rotated out
+    if let Some(expires) = method.expires {
+        if signed_at.unwrap_or_else(Utc::now) > expires {
+            return Err(AppError::SsiError(format!("JWT was signed after key {} expired", header.kid)));
+        }
+    }
+
     // Check if the token is expired
     if let Some(exp) = claims.exp {
         let now = Utc::now().timestamp();
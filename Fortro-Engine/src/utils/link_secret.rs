@@ -0,0 +1,220 @@
+use bulletproofs::PedersenGens;
+use curve25519_dalek_ng::scalar::Scalar;
+use merlin::Transcript;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::models::LinkSecretCommitment;
+use crate::utils::zk_proofs::{challenge_scalar, decode_commitment};
+
+/// Credential property holding the issuer-signed commitment to the holder's link secret
+pub const LINK_SECRET_PROPERTY: &str = "linkSecretCommitment";
+
+/// Proof, made by the holder's wallet, that the link secret commitments of a presentation's credentials hide one secret.
+///
+/// A Schnorr proof of knowledge of the secret and every blinding, with one
+/// response for the secret shared by all commitments. It is bound to the
+/// verifier and nonce of the presentation, so it cannot be replayed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SameHolderProof {
+    /// Signed commitments, in the order of the credentials presenting them
+    pub commitments: Vec<String>,
+    pub proof: Vec<u8>,
+}
+
+/// Verify that the holder who sent a link secret commitment can open it, for a credential
+/// of `issuer_did` to `subject_did`
+pub fn verify_commitment(
+    commitment: &LinkSecretCommitment,
+    issuer_did: &str,
+    subject_did: &str,
+) -> Result<(), AppError> {
+    let invalid = || AppError::ValidationError("Invalid proof of knowledge of the link secret commitment".to_string());
+    let point = decode_commitment(&commitment.commitment)
+        .map_err(|_| AppError::ValidationError("Invalid link secret commitment".to_string()))?;
+
+    let scalars = decode_scalars(&commitment.proof).ok_or_else(invalid)?;
+    let [challenge, secret_response, blinding_response] = scalars[..] else {
+        return Err(invalid());
+    };
+
+    // Recompute the announcement from the responses; the challenge must match it
+    let announcement = PedersenGens::default().commit(secret_response, blinding_response) - point * challenge;
+    let mut transcript = commitment_transcript(&commitment.commitment, issuer_did, subject_did);
+    transcript.append_message(b"announcement", announcement.compress().as_bytes());
+    if challenge_scalar(&mut transcript) != challenge {
+        return Err(invalid());
+    }
+
+    Ok(())
+}
+
+/// Verify a same-holder proof over the signed commitments of a presentation, for its verifier and nonce
+pub fn verify_same_holder_proof(
+    proof: &SameHolderProof,
+    signed_commitments: &[String],
+    verifier: &str,
+    nonce: &str,
+) -> Result<bool, AppError> {
+    if proof.commitments != signed_commitments {
+        return Err(AppError::SsiError(
+            "Same-holder proof does not cover the link secret commitments of the presented credentials".to_string(),
+        ));
+    }
+    let invalid = || AppError::SsiError("Invalid same-holder proof".to_string());
+
+    let scalars = decode_scalars(&proof.proof).ok_or_else(invalid)?;
+    if scalars.len() != 2 + proof.commitments.len() {
+        return Err(invalid());
+    }
+    let (challenge, secret_response) = (scalars[0], scalars[1]);
+
+    // Recompute the announcements from the responses; the challenge must match them
+    let pc_gens = PedersenGens::default();
+    let mut transcript = same_holder_transcript(&proof.commitments, verifier, nonce);
+    for (commitment, blinding_response) in proof.commitments.iter().zip(&scalars[2..]) {
+        let announcement = pc_gens.commit(secret_response, *blinding_response) - decode_commitment(commitment)? * challenge;
+        transcript.append_message(b"announcement", announcement.compress().as_bytes());
+    }
+    if challenge_scalar(&mut transcript) != challenge {
+        return Err(AppError::SsiError(
+            "Presented credentials are not bound to the same holder".to_string(),
+        ));
+    }
+
+    Ok(true)
+}
+
+/// Transcript binding a same-holder proof to its commitments and presentation session
+fn same_holder_transcript(commitments: &[String], verifier: &str, nonce: &str) -> Transcript {
+    let mut transcript = Transcript::new(b"fortro-same-holder-proof");
+    transcript.append_message(b"verifier", verifier.as_bytes());
+    transcript.append_message(b"nonce", nonce.as_bytes());
+    transcript.append_u64(b"commitments", commitments.len() as u64);
+    for commitment in commitments {
+        transcript.append_message(b"commitment", commitment.as_bytes());
+    }
+    transcript
+}
+
+/// Transcript binding a link secret commitment to the credential request it is sent with
+fn commitment_transcript(commitment: &str, issuer_did: &str, subject_did: &str) -> Transcript {
+    let mut transcript = Transcript::new(b"fortro-link-secret-commitment");
+    transcript.append_message(b"issuer", issuer_did.as_bytes());
+    transcript.append_message(b"subject", subject_did.as_bytes());
+    transcript.append_message(b"commitment", commitment.as_bytes());
+    transcript
+}
+
+fn decode_scalars(bytes: &[u8]) -> Option<Vec<Scalar>> {
+    bytes.chunks(32)
+        .map(|chunk| <[u8; 32]>::try_from(chunk).ok().and_then(Scalar::from_canonical_bytes))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose, Engine as _};
+    use curve25519_dalek_ng::ristretto::RistrettoPoint;
+    use rand::thread_rng;
+
+    const ISSUER: &str = "did:alyra:issuer";
+    const SUBJECT: &str = "did:alyra:holder";
+    const VERIFIER: &str = "did:alyra:verifier";
+    const NONCE: &str = "request-1";
+
+    fn encode_point(point: RistrettoPoint) -> String {
+        general_purpose::URL_SAFE_NO_PAD.encode(point.compress().as_bytes())
+    }
+
+    fn to_bytes(scalars: &[Scalar]) -> Vec<u8> {
+        scalars.iter().flat_map(|scalar| scalar.to_bytes()).collect()
+    }
+
+    /// What a wallet sends along a credential request: a fresh commitment to its secret, with its blinding
+    fn commit(secret: Scalar, issuer_did: &str, subject_did: &str) -> (LinkSecretCommitment, Scalar) {
+        let mut rng = thread_rng();
+        let pc_gens = PedersenGens::default();
+        let blinding = Scalar::random(&mut rng);
+        let commitment = encode_point(pc_gens.commit(secret, blinding));
+
+        let nonces = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let mut transcript = commitment_transcript(&commitment, issuer_did, subject_did);
+        transcript.append_message(b"announcement", pc_gens.commit(nonces.0, nonces.1).compress().as_bytes());
+        let challenge = challenge_scalar(&mut transcript);
+
+        let proof = to_bytes(&[challenge, nonces.0 + challenge * secret, nonces.1 + challenge * blinding]);
+        (LinkSecretCommitment { commitment, proof }, blinding)
+    }
+
+    /// A wallet's proof that commitments, given with their blindings, hide its secret
+    fn prove_same_holder(secret: Scalar, openings: &[(String, Scalar)], verifier: &str, nonce: &str) -> SameHolderProof {
+        let mut rng = thread_rng();
+        let pc_gens = PedersenGens::default();
+        let commitments: Vec<String> = openings.iter().map(|(commitment, _)| commitment.clone()).collect();
+        let mut transcript = same_holder_transcript(&commitments, verifier, nonce);
+
+        let secret_nonce = Scalar::random(&mut rng);
+        let blinding_nonces: Vec<Scalar> = openings.iter().map(|_| Scalar::random(&mut rng)).collect();
+        for blinding_nonce in &blinding_nonces {
+            let announcement = pc_gens.commit(secret_nonce, *blinding_nonce);
+            transcript.append_message(b"announcement", announcement.compress().as_bytes());
+        }
+
+        let challenge = challenge_scalar(&mut transcript);
+        let scalars: Vec<Scalar> = [challenge, secret_nonce + challenge * secret].into_iter()
+            .chain(openings.iter().zip(&blinding_nonces).map(|((_, blinding), nonce)| nonce + challenge * blinding))
+            .collect();
+        SameHolderProof { commitments, proof: to_bytes(&scalars) }
+    }
+
+    #[test]
+    fn accepts_commitments_only_for_their_issuer_and_subject() {
+        let secret = Scalar::random(&mut thread_rng());
+        let (commitment, _) = commit(secret, ISSUER, SUBJECT);
+        assert!(verify_commitment(&commitment, ISSUER, SUBJECT).is_ok());
+        assert!(verify_commitment(&commitment, "did:alyra:other", SUBJECT).is_err());
+        assert!(verify_commitment(&commitment, ISSUER, "did:alyra:other").is_err());
+
+        // A proof made for another commitment does not open this one
+        let (other, _) = commit(secret, ISSUER, SUBJECT);
+        let swapped = LinkSecretCommitment { commitment: other.commitment, proof: commitment.proof.clone() };
+        assert!(verify_commitment(&swapped, ISSUER, SUBJECT).is_err());
+
+        let truncated = LinkSecretCommitment { proof: commitment.proof[..64].to_vec(), ..commitment };
+        assert!(verify_commitment(&truncated, ISSUER, SUBJECT).is_err());
+    }
+
+    #[test]
+    fn proves_credentials_share_one_holder() {
+        let secret = Scalar::random(&mut thread_rng());
+        let openings: Vec<(String, Scalar)> = ["did:alyra:university", "did:alyra:employer"].iter()
+            .map(|issuer| commit(secret, issuer, SUBJECT))
+            .map(|(commitment, blinding)| (commitment.commitment, blinding))
+            .collect();
+        let commitments: Vec<String> = openings.iter().map(|(commitment, _)| commitment.clone()).collect();
+        assert_ne!(commitments[0], commitments[1]);
+
+        let proof = prove_same_holder(secret, &openings, VERIFIER, NONCE);
+        assert!(verify_same_holder_proof(&proof, &commitments, VERIFIER, NONCE).is_ok());
+
+        // The proof answers one verifier and nonce, over the credentials in their order
+        assert!(verify_same_holder_proof(&proof, &commitments, "did:alyra:other", NONCE).is_err());
+        assert!(verify_same_holder_proof(&proof, &commitments, VERIFIER, "request-2").is_err());
+        let reversed: Vec<String> = commitments.iter().rev().cloned().collect();
+        assert!(verify_same_holder_proof(&proof, &reversed, VERIFIER, NONCE).is_err());
+    }
+
+    #[test]
+    fn rejects_credentials_of_different_holders() {
+        let (secret, other_secret) = (Scalar::random(&mut thread_rng()), Scalar::random(&mut thread_rng()));
+        let (mine, my_blinding) = commit(secret, ISSUER, SUBJECT);
+        let (theirs, their_blinding) = commit(other_secret, ISSUER, "did:alyra:other");
+        let openings = [(mine.commitment, my_blinding), (theirs.commitment, their_blinding)];
+        let commitments: Vec<String> = openings.iter().map(|(commitment, _)| commitment.clone()).collect();
+
+        let proof = prove_same_holder(secret, &openings, VERIFIER, NONCE);
+        assert!(verify_same_holder_proof(&proof, &commitments, VERIFIER, NONCE).is_err());
+    }
+}
@@ -0,0 +1,520 @@
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use ciborium::value::Value as Cbor;
+use coset::{iana, Algorithm, AsCborValue, CoseKey, CoseSign1, CoseSign1Builder, Header, KeyType as CoseKeyType, Label};
+use rand::{rngs::OsRng, RngCore};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::error::AppError;
+use crate::keystore::KeyStore;
+use crate::resolver::{split_did_url, DidResolver};
+use crate::utils::did::{did_key_public_key, sign_with_key, KeyType, PublicKey};
+
+/// Private-use COSE algorithm of Dilithium2 issuer signatures, which have no registered value
+const DILITHIUM2_ALG: i64 = -65537;
+
+/// CBOR tag of embedded CBOR (`#6.24(bstr)`)
+const ENCODED_CBOR_TAG: u64 = 24;
+
+/// CBOR tag of a `tdate` text date
+const TDATE_TAG: u64 = 0;
+
+/// Validity of credentials issued without an expiration date; the MSO requires `validUntil`
+const DEFAULT_VALIDITY_DAYS: i64 = 365;
+
+/// Document type and data elements of an mdoc to be signed
+#[derive(Debug, Clone)]
+pub struct MdocDraft {
+    pub doc_type: String,
+    pub namespace: String,
+    pub elements: HashMap<String, Value>,
+    /// did:key DID whose key becomes the device key
+    pub holder_did: String,
+    pub valid_until: Option<DateTime<Utc>>,
+}
+
+/// Issuer-signed mdoc whose MSO signature, digests and device authentication checked out
+#[derive(Debug)]
+pub struct VerifiedMdoc {
+    /// DID URL of the issuer key that signed the MSO
+    pub issuer_key_id: String,
+    /// did:key DID of the device key
+    pub holder_did: String,
+}
+
+/// Whether a secured credential is a base64url CBOR mdoc rather than a JWT, SD-JWT or JSON document
+pub fn is_mdoc(secured: &str) -> bool {
+    !secured.is_empty()
+        && !secured.contains(['.', '~', '{'])
+        && general_purpose::URL_SAFE_NO_PAD.decode(secured).is_ok()
+}
+
+/// Create the `IssuerSigned` structure of an mdoc, with the MSO signed by an issuer key held in a key store.
+///
+/// Each data element gets its own salted digest in the MSO, so the holder can
+/// later present any subset of them.
+pub async fn create_issuer_signed_with_store(
+    key_id: &str,
+    draft: &MdocDraft,
+    store: &dyn KeyStore,
+    kid: &str,
+) -> Result<String, AppError> {
+    let device_key = did_key_public_key(&draft.holder_did).map_err(|_| {
+        AppError::ValidationError("mdocs can only be bound to did:key holders".to_string())
+    })?;
+
+    // Step 1: Wrap each element in an IssuerSignedItem and digest it
+    let mut names: Vec<&String> = draft.elements.keys().collect();
+    names.sort();
+
+    let mut items = Vec::new();
+    let mut digests = Vec::new();
+    for (digest_id, name) in names.into_iter().enumerate() {
+        let mut random = [0u8; 16];
+        OsRng.fill_bytes(&mut random);
+
+        let item = Cbor::Map(vec![
+            (text("digestID"), Cbor::from(digest_id as u64)),
+            (text("random"), Cbor::Bytes(random.to_vec())),
+            (text("elementIdentifier"), text(name)),
+            (text("elementValue"), to_cbor(&draft.elements[name])?),
+        ]);
+        let item_bytes = encoded_cbor(&item)?;
+        digests.push((Cbor::from(digest_id as u64), Cbor::Bytes(Sha256::digest(encode(&item_bytes)?).to_vec())));
+        items.push(item_bytes);
+    }
+
+    // Step 2: Build the mobile security object
+    let now = Utc::now();
+    let valid_until = draft.valid_until.unwrap_or(now + Duration::days(DEFAULT_VALIDITY_DAYS));
+    let mso = Cbor::Map(vec![
+        (text("version"), text("1.0")),
+        (text("digestAlgorithm"), text("SHA-256")),
+        (text("valueDigests"), Cbor::Map(vec![(text(&draft.namespace), Cbor::Map(digests))])),
+        (text("deviceKeyInfo"), Cbor::Map(vec![(text("deviceKey"), cose_key(&device_key)?.to_cbor_value().map_err(cose_error)?)])),
+        (text("docType"), text(&draft.doc_type)),
+        (text("validityInfo"), Cbor::Map(vec![
+            (text("signed"), tdate(now)),
+            (text("validFrom"), tdate(now)),
+            (text("validUntil"), tdate(valid_until)),
+        ])),
+    ]);
+
+    // Step 3: Sign the MSO; the private key stays inside the store
+    let protected = Header {
+        alg: Some(cose_algorithm(KeyType::Dilithium2)?),
+        key_id: key_id.as_bytes().to_vec(),
+        ..Default::default()
+    };
+    let mut issuer_auth = CoseSign1Builder::new()
+        .protected(protected)
+        .payload(encode(&encoded_cbor(&mso)?)?)
+        .build();
+    issuer_auth.signature = store.sign(kid, &issuer_auth.tbs_data(&[])).await?;
+
+    let issuer_signed = Cbor::Map(vec![
+        (text("nameSpaces"), Cbor::Map(vec![(text(&draft.namespace), Cbor::Array(items))])),
+        (text("issuerAuth"), issuer_auth.to_cbor_value().map_err(cose_error)?),
+    ]);
+
+    Ok(general_purpose::URL_SAFE_NO_PAD.encode(encode(&issuer_signed)?))
+}
+
+/// Create a `DeviceResponse` presenting some data elements of an mdoc to one verifier.
+///
+/// The device signature covers a session transcript naming the verifier and the nonce.
+pub fn create_device_response(
+    issuer_signed: &str,
+    element_names: &[String],
+    holder_did: &str,
+    verifier_did: &str,
+    nonce: &str,
+    private_key: &[u8],
+) -> Result<String, AppError> {
+    let issuer_signed = decode(issuer_signed)?;
+    let issuer_auth = CoseSign1::from_cbor_value(field(&issuer_signed, "issuerAuth")?.clone()).map_err(cose_error)?;
+    let doc_type = mso_of(&issuer_auth)?.1;
+
+    // Step 1: Keep only the requested elements
+    let mut name_spaces = Vec::new();
+    let mut found = Vec::new();
+    for (namespace, items) in map_entries(field(&issuer_signed, "nameSpaces")?)? {
+        let mut kept = Vec::new();
+        for item_bytes in items.as_array().into_iter().flatten() {
+            let item = decode_encoded_cbor(item_bytes)?;
+            let name = field(&item, "elementIdentifier")?.as_text().unwrap_or_default().to_string();
+            if element_names.contains(&name) {
+                kept.push(item_bytes.clone());
+                found.push(name);
+            }
+        }
+        name_spaces.push((namespace.clone(), Cbor::Array(kept)));
+    }
+    if let Some(missing) = element_names.iter().find(|name| !found.contains(name)) {
+        return Err(AppError::ValidationError(format!("Attribute {} not found in credential", missing)));
+    }
+
+    // Step 2: Sign the device authentication structure with the device key
+    let key_type = did_key_public_key(holder_did)?.key_type;
+    let device_name_spaces = encoded_cbor(&Cbor::Map(Vec::new()))?;
+    let device_authentication = device_authentication(&doc_type, &device_name_spaces, verifier_did, nonce)?;
+
+    let mut device_signature = CoseSign1Builder::new()
+        .protected(Header { alg: Some(cose_algorithm(key_type)?), ..Default::default() })
+        .build();
+    device_signature.signature = sign_with_key(key_type, &device_signature.tbs_detached_data(&device_authentication, &[]), private_key)?;
+
+    let document = Cbor::Map(vec![
+        (text("docType"), text(&doc_type)),
+        (text("issuerSigned"), Cbor::Map(vec![
+            (text("nameSpaces"), Cbor::Map(name_spaces)),
+            (text("issuerAuth"), issuer_auth.to_cbor_value().map_err(cose_error)?),
+        ])),
+        (text("deviceSigned"), Cbor::Map(vec![
+            (text("nameSpaces"), device_name_spaces),
+            (text("deviceAuth"), Cbor::Map(vec![(text("deviceSignature"), device_signature.to_cbor_value().map_err(cose_error)?)])),
+        ])),
+    ]);
+
+    let device_response = Cbor::Map(vec![
+        (text("version"), text("1.0")),
+        (text("documents"), Cbor::Array(vec![document])),
+        (text("status"), Cbor::from(0u64)),
+    ]);
+
+    Ok(general_purpose::URL_SAFE_NO_PAD.encode(encode(&device_response)?))
+}
+
+/// Verify an mdoc, given as `IssuerSigned` or as a single-document `DeviceResponse`.
+///
+/// With a verifier and nonce, a `DeviceResponse` whose device signature covers them is required.
+/// `signed_at` is the trusted issuance time the issuer key is checked at; the MSO's own
+/// `signed` time is not trusted for that.
+pub async fn verify(
+    secured: &str,
+    resolver: &dyn DidResolver,
+    signed_at: Option<DateTime<Utc>>,
+    session: Option<(&str, &str)>,
+) -> Result<VerifiedMdoc, AppError> {
+    let (issuer_signed, device_signed, doc_type) = split_document(&decode(secured)?)?;
+
+    // Step 1: Verify the issuer signature over the MSO
+    let issuer_auth = CoseSign1::from_cbor_value(field(&issuer_signed, "issuerAuth")?.clone()).map_err(cose_error)?;
+    let issuer_key_id = String::from_utf8(issuer_auth.protected.header.key_id.clone())
+        .map_err(|_| AppError::SsiError("mdoc issuer key ID is not a DID URL".to_string()))?;
+    let (did, _) = split_did_url(&issuer_key_id);
+    let document = resolver.resolve(did).await?;
+    let method = document.signing_key(&issuer_key_id)
+        .ok_or_else(|| AppError::SsiError(format!("Key {} is not a signing key of {}", issuer_key_id, did)))?;
+    let issuer_key = method.public_key()?;
+
+    if issuer_auth.protected.header.alg != Some(cose_algorithm(issuer_key.key_type)?) {
+        return Err(AppError::SsiError(format!("mdoc algorithm does not match key {}", issuer_key_id)));
+    }
+    if !issuer_key.verify(&issuer_auth.tbs_data(&[]), &issuer_auth.signature)? {
+        return Err(AppError::SsiError("mdoc issuer signature verification failed".to_string()));
+    }
+
+    // Step 2: Check the MSO itself
+    let (mso, mso_doc_type) = mso_of(&issuer_auth)?;
+    if doc_type.as_ref().is_some_and(|doc_type| *doc_type != mso_doc_type) {
+        return Err(AppError::SsiError("Document type does not match the MSO".to_string()));
+    }
+    if field(&mso, "digestAlgorithm")?.as_text() != Some("SHA-256") {
+        return Err(AppError::SsiError("Unsupported mdoc digest algorithm".to_string()));
+    }
+
+    let validity = field(&mso, "validityInfo")?;
+    date_field(validity, "signed")?;
+    let valid_from = date_field(validity, "validFrom")?;
+    let valid_until = date_field(validity, "validUntil")?;
+    if let Some(expires) = method.expires {
+        if signed_at.unwrap_or_else(Utc::now) > expires {
+            return Err(AppError::SsiError(format!("mdoc was signed after key {} expired", issuer_key_id)));
+        }
+    }
+    let now = Utc::now();
+    if now < valid_from {
+        return Err(AppError::SsiError("mdoc is not yet valid".to_string()));
+    }
+    if now > valid_until {
+        return Err(AppError::SsiError("mdoc is expired".to_string()));
+    }
+
+    // Step 3: Every element must match its digest
+    issuer_signed_elements(&issuer_signed, &mso)?;
+
+    // Step 4: Check the device signature against the device key in the MSO
+    let device_key = device_key_of(&mso)?;
+    match (session, device_signed) {
+        (Some((verifier_did, nonce)), Some(device_signed)) => {
+            let device_signature = field(field(&device_signed, "deviceAuth")?, "deviceSignature")?;
+            let device_signature = CoseSign1::from_cbor_value(device_signature.clone()).map_err(cose_error)?;
+            if device_signature.protected.header.alg != Some(cose_algorithm(device_key.key_type)?) {
+                return Err(AppError::SsiError("Device signature algorithm does not match the device key".to_string()));
+            }
+
+            let device_authentication = device_authentication(&mso_doc_type, field(&device_signed, "nameSpaces")?, verifier_did, nonce)?;
+            if !device_key.verify(&device_signature.tbs_detached_data(&device_authentication, &[]), &device_signature.signature)? {
+                return Err(AppError::SsiError("mdoc device signature verification failed".to_string()));
+            }
+        }
+        (Some(_), None) => return Err(AppError::SsiError("mdoc has no device authentication".to_string())),
+        (None, _) => {}
+    }
+
+    Ok(VerifiedMdoc {
+        issuer_key_id,
+        holder_did: format!("did:key:{}", device_key.to_multikey()?),
+    })
+}
+
+/// Read an mdoc without verifying it, in the shape of a VCDM 2.0 credential.
+///
+/// `credentialSubject` holds the did:key DID of the device key and the data elements of all namespaces.
+pub fn extract_credential(secured: &str) -> Result<Value, AppError> {
+    let (issuer_signed, _, _) = split_document(&decode(secured)?)?;
+    let issuer_auth = CoseSign1::from_cbor_value(field(&issuer_signed, "issuerAuth")?.clone()).map_err(cose_error)?;
+    let (mso, doc_type) = mso_of(&issuer_auth)?;
+    let validity = field(&mso, "validityInfo")?;
+
+    let issuer_key_id = String::from_utf8_lossy(&issuer_auth.protected.header.key_id).into_owned();
+    let (issuer_did, _) = split_did_url(&issuer_key_id);
+
+    let mut subject = Map::new();
+    subject.insert("id".to_string(), json!(format!("did:key:{}", device_key_of(&mso)?.to_multikey()?)));
+    subject.extend(issuer_signed_elements(&issuer_signed, &mso)?);
+
+    Ok(json!({
+        "type": ["VerifiableCredential", doc_type],
+        "issuer": issuer_did,
+        "validFrom": date_field(validity, "validFrom")?.to_rfc3339(),
+        "validUntil": date_field(validity, "validUntil")?.to_rfc3339(),
+        "credentialSubject": subject,
+    }))
+}
+
+/// Document type of an mdoc, read from its MSO without verifying it
+pub fn doc_type(secured: &str) -> Result<String, AppError> {
+    let (issuer_signed, _, _) = split_document(&decode(secured)?)?;
+    let issuer_auth = CoseSign1::from_cbor_value(field(&issuer_signed, "issuerAuth")?.clone()).map_err(cose_error)?;
+    Ok(mso_of(&issuer_auth)?.1)
+}
+
+/// Issuer-signed MSO bytes of an mdoc, which every presentation of it shares
+pub fn mso_bytes(secured: &str) -> Result<Vec<u8>, AppError> {
+    let (issuer_signed, _, _) = split_document(&decode(secured)?)?;
+    let issuer_auth = CoseSign1::from_cbor_value(field(&issuer_signed, "issuerAuth")?.clone()).map_err(cose_error)?;
+    issuer_auth.payload
+        .ok_or_else(|| AppError::SsiError("mdoc issuerAuth has no payload".to_string()))
+}
+
+/// Split an `IssuerSigned` or `DeviceResponse` into the issuer-signed part, the device-signed part and the document type
+fn split_document(mdoc: &Cbor) -> Result<(Cbor, Option<Cbor>, Option<String>), AppError> {
+    let documents = match get(mdoc, "documents") {
+        Some(documents) => documents,
+        None => return Ok((mdoc.clone(), None, None)),
+    };
+
+    let document = match documents.as_array().map(Vec::as_slice) {
+        Some([document]) => document,
+        _ => return Err(AppError::SsiError("DeviceResponse must hold exactly one document".to_string())),
+    };
+    let doc_type = field(document, "docType")?.as_text().map(str::to_string);
+
+    Ok((field(document, "issuerSigned")?.clone(), get(document, "deviceSigned").cloned(), doc_type))
+}
+
+/// Decoded MSO of an `issuerAuth` and its document type
+fn mso_of(issuer_auth: &CoseSign1) -> Result<(Cbor, String), AppError> {
+    let payload = issuer_auth.payload.as_ref()
+        .ok_or_else(|| AppError::SsiError("mdoc issuerAuth has no payload".to_string()))?;
+    let mso = decode_encoded_cbor(&decode_bytes(payload)?)?;
+    let doc_type = field(&mso, "docType")?.as_text()
+        .ok_or_else(|| AppError::SsiError("MSO docType is not text".to_string()))?
+        .to_string();
+    Ok((mso, doc_type))
+}
+
+/// Data elements of an `IssuerSigned`, each checked against its digest in the MSO
+fn issuer_signed_elements(issuer_signed: &Cbor, mso: &Cbor) -> Result<HashMap<String, Value>, AppError> {
+    let value_digests = field(mso, "valueDigests")?;
+    let mut elements = HashMap::new();
+
+    for (namespace, items) in map_entries(field(issuer_signed, "nameSpaces")?)? {
+        let digests = namespace.as_text().and_then(|namespace| get(value_digests, namespace))
+            .ok_or_else(|| AppError::SsiError("Namespace has no digests in the MSO".to_string()))?;
+
+        for item_bytes in items.as_array().into_iter().flatten() {
+            let item = decode_encoded_cbor(item_bytes)?;
+            let digest_id = field(&item, "digestID")?;
+            let expected = map_entries(digests)?.iter()
+                .find(|(id, _)| id == digest_id)
+                .and_then(|(_, digest)| digest.as_bytes().cloned());
+            if expected.as_deref() != Some(Sha256::digest(encode(item_bytes)?).as_slice()) {
+                return Err(AppError::SsiError("mdoc data element does not match its digest".to_string()));
+            }
+
+            let name = field(&item, "elementIdentifier")?.as_text()
+                .ok_or_else(|| AppError::SsiError("elementIdentifier is not text".to_string()))?
+                .to_string();
+            let value = field(&item, "elementValue")?.deserialized::<Value>()
+                .map_err(|e| AppError::SsiError(format!("Unsupported mdoc element value: {}", e)))?;
+            if elements.insert(name.clone(), value).is_some() {
+                return Err(AppError::SsiError(format!("{} is disclosed twice", name)));
+            }
+        }
+    }
+
+    Ok(elements)
+}
+
+/// `DeviceAuthenticationBytes` the device signature covers, with a session transcript binding the verifier and nonce
+fn device_authentication(doc_type: &str, device_name_spaces: &Cbor, verifier_did: &str, nonce: &str) -> Result<Vec<u8>, AppError> {
+    let handover = Cbor::Array(vec![text("FortroHandover"), text(verifier_did), text(nonce)]);
+    let session_transcript = Cbor::Array(vec![Cbor::Null, Cbor::Null, handover]);
+
+    let device_authentication = Cbor::Array(vec![
+        text("DeviceAuthentication"),
+        session_transcript,
+        text(doc_type),
+        device_name_spaces.clone(),
+    ]);
+    encode(&encoded_cbor(&device_authentication)?)
+}
+
+/// COSE algorithm of signatures made with a key type
+fn cose_algorithm(key_type: KeyType) -> Result<Algorithm, AppError> {
+    Ok(match key_type {
+        KeyType::Dilithium2 => Algorithm::PrivateUse(DILITHIUM2_ALG),
+        KeyType::Ed25519 => Algorithm::Assigned(iana::Algorithm::EdDSA),
+        KeyType::P256 => Algorithm::Assigned(iana::Algorithm::ES256),
+    })
+}
+
+/// COSE_Key of a device key: OKP for Ed25519, EC2 with both coordinates for P-256
+fn cose_key(key: &PublicKey) -> Result<CoseKey, AppError> {
+    let (kty, params) = match key.key_type {
+        KeyType::Ed25519 => (iana::KeyType::OKP, vec![
+            (Label::Int(iana::OkpKeyParameter::Crv as i64), Cbor::from(iana::EllipticCurve::Ed25519 as i64)),
+            (Label::Int(iana::OkpKeyParameter::X as i64), Cbor::Bytes(key.bytes.clone())),
+        ]),
+        KeyType::P256 => {
+            let point = p256::ecdsa::VerifyingKey::from_sec1_bytes(&key.bytes)
+                .map_err(|e| AppError::SsiError(format!("Invalid P-256 public key: {}", e)))?
+                .to_encoded_point(false);
+            let (x, y) = match (point.x(), point.y()) {
+                (Some(x), Some(y)) => (x.to_vec(), y.to_vec()),
+                _ => return Err(AppError::SsiError("Invalid P-256 public key".to_string())),
+            };
+            (iana::KeyType::EC2, vec![
+                (Label::Int(iana::Ec2KeyParameter::Crv as i64), Cbor::from(iana::EllipticCurve::P_256 as i64)),
+                (Label::Int(iana::Ec2KeyParameter::X as i64), Cbor::Bytes(x)),
+                (Label::Int(iana::Ec2KeyParameter::Y as i64), Cbor::Bytes(y)),
+            ])
+        }
+        KeyType::Dilithium2 => {
+            return Err(AppError::ValidationError("Dilithium2 keys cannot be mdoc device keys".to_string()))
+        }
+    };
+
+    Ok(CoseKey { kty: CoseKeyType::Assigned(kty), params, ..Default::default() })
+}
+
+/// Device key of an MSO
+fn device_key_of(mso: &Cbor) -> Result<PublicKey, AppError> {
+    let device_key = field(field(mso, "deviceKeyInfo")?, "deviceKey")?;
+    let device_key = CoseKey::from_cbor_value(device_key.clone()).map_err(cose_error)?;
+    let param = |label: i64| {
+        device_key.params.iter()
+            .find(|(l, _)| *l == Label::Int(label))
+            .and_then(|(_, value)| value.as_bytes().cloned())
+            .ok_or_else(|| AppError::SsiError("Device key is missing a coordinate".to_string()))
+    };
+
+    let key = if device_key.kty == CoseKeyType::Assigned(iana::KeyType::OKP) {
+        PublicKey { key_type: KeyType::Ed25519, bytes: param(iana::OkpKeyParameter::X as i64)? }
+    } else if device_key.kty == CoseKeyType::Assigned(iana::KeyType::EC2) {
+        let uncompressed = [&[0x04][..], &param(iana::Ec2KeyParameter::X as i64)?, &param(iana::Ec2KeyParameter::Y as i64)?].concat();
+        let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&uncompressed)
+            .map_err(|e| AppError::SsiError(format!("Invalid P-256 device key: {}", e)))?;
+        PublicKey { key_type: KeyType::P256, bytes: key.to_encoded_point(true).as_bytes().to_vec() }
+    } else {
+        return Err(AppError::SsiError("Unsupported device key type".to_string()));
+    };
+
+    // Round-trip through Multikey to validate the key
+    PublicKey::from_multikey(&key.to_multikey()?)
+}
+
+fn text(value: &str) -> Cbor {
+    Cbor::Text(value.to_string())
+}
+
+fn tdate(date: DateTime<Utc>) -> Cbor {
+    Cbor::Tag(TDATE_TAG, Box::new(text(&date.to_rfc3339_opts(SecondsFormat::Secs, true))))
+}
+
+fn date_field(map: &Cbor, name: &str) -> Result<DateTime<Utc>, AppError> {
+    let date = match field(map, name)? {
+        Cbor::Tag(TDATE_TAG, date) => date.as_text(),
+        _ => None,
+    };
+    date.and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        .map(|date| date.with_timezone(&Utc))
+        .ok_or_else(|| AppError::SsiError(format!("{} is not a tdate", name)))
+}
+
+fn to_cbor(value: &Value) -> Result<Cbor, AppError> {
+    Cbor::serialized(value).map_err(|e| AppError::ValidationError(format!("Cannot encode {} as CBOR: {}", value, e)))
+}
+
+/// Wrap a CBOR value as `#6.24(bstr .cbor value)`
+fn encoded_cbor(value: &Cbor) -> Result<Cbor, AppError> {
+    Ok(Cbor::Tag(ENCODED_CBOR_TAG, Box::new(Cbor::Bytes(encode(value)?))))
+}
+
+fn decode_encoded_cbor(value: &Cbor) -> Result<Cbor, AppError> {
+    match value {
+        Cbor::Tag(ENCODED_CBOR_TAG, bytes) => match bytes.as_ref() {
+            Cbor::Bytes(bytes) => decode_bytes(bytes),
+            _ => Err(AppError::SsiError("Embedded CBOR is not a byte string".to_string())),
+        },
+        _ => Err(AppError::SsiError("Expected embedded CBOR".to_string())),
+    }
+}
+
+fn encode(value: &Cbor) -> Result<Vec<u8>, AppError> {
+    let mut bytes = Vec::new();
+    ciborium::ser::into_writer(value, &mut bytes)
+        .map_err(|e| AppError::InternalError(format!("Failed to encode CBOR: {}", e)))?;
+    Ok(bytes)
+}
+
+fn decode(secured: &str) -> Result<Cbor, AppError> {
+    let bytes = general_purpose::URL_SAFE_NO_PAD.decode(secured)
+        .map_err(|e| AppError::SsiError(format!("Failed to decode mdoc: {}", e)))?;
+    decode_bytes(&bytes)
+}
+
+fn decode_bytes(bytes: &[u8]) -> Result<Cbor, AppError> {
+    ciborium::de::from_reader(bytes).map_err(|e| AppError::SsiError(format!("Failed to parse CBOR: {}", e)))
+}
+
+fn get<'a>(map: &'a Cbor, name: &str) -> Option<&'a Cbor> {
+    map.as_map()?.iter().find(|(key, _)| key.as_text() == Some(name)).map(|(_, value)| value)
+}
+
+fn field<'a>(map: &'a Cbor, name: &str) -> Result<&'a Cbor, AppError> {
+    get(map, name).ok_or_else(|| AppError::SsiError(format!("mdoc is missing {}", name)))
+}
+
+fn map_entries(map: &Cbor) -> Result<&Vec<(Cbor, Cbor)>, AppError> {
+    map.as_map().ok_or_else(|| AppError::SsiError("Expected a CBOR map".to_string()))
+}
+
+fn cose_error(e: coset::CoseError) -> AppError {
+    AppError::SsiError(format!("Invalid COSE structure: {}", e))
+}
@@ -1,5 +1,14 @@
+pub mod bbs;
+pub mod bbs_credential;
 pub mod crypto;
+pub mod data_integrity;
 pub mod did;
+pub mod jcs;
 pub mod jwt;
+pub mod link_secret;
+pub mod mdoc;
 pub mod qr;
+pub mod range_proof_batch;
+pub mod sd_jwt;
+pub mod status_list;
 pub mod zk_proofs;
\ No newline at end of file
@@ -0,0 +1,309 @@
+use bulletproofs::PedersenGens;
+use curve25519_dalek_ng::{
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+    traits::{IsIdentity, VartimeMultiscalarMul},
+};
+use merlin::Transcript;
+use rand::thread_rng;
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake256,
+};
+
+/// A Bulletproofs range proof to check in a batch, with the transcript it was
+/// proven over and the value commitments it proves, padding included
+pub struct RangeStatement {
+    pub proof: Vec<u8>,
+    pub transcript: Transcript,
+    pub commitments: Vec<CompressedRistretto>,
+}
+
+/// Range proof of `bulletproofs` 4.0, decoded from its `to_bytes` layout
+struct DecodedProof {
+    a: CompressedRistretto,
+    s: CompressedRistretto,
+    t_1: CompressedRistretto,
+    t_2: CompressedRistretto,
+    t_x: Scalar,
+    t_x_blinding: Scalar,
+    e_blinding: Scalar,
+    l_vec: Vec<CompressedRistretto>,
+    r_vec: Vec<CompressedRistretto>,
+    ipp_a: Scalar,
+    ipp_b: Scalar,
+}
+
+/// Verify `n`-bit range proofs together, as one multiscalar multiplication.
+///
+/// Each proof's verification equation, as checked by `RangeProof::verify_multiple`,
+/// is a sum of multiples of points that must be the identity. The equations are
+/// weighted by random scalars and added up, so the generators they share are
+/// multiplied once. A batch that contains an invalid proof passes only with
+/// negligible probability, but does not tell which proof is invalid.
+///
+/// The transcript labels, generators and proof layout are those of `bulletproofs`
+/// 4.0.0, which Cargo.toml pins; an upgrade of the crate must re-check them.
+pub fn verify_batch(statements: Vec<RangeStatement>, n: usize) -> bool {
+    let party_capacity = statements.iter().map(|statement| statement.commitments.len()).max().unwrap_or(0);
+    let pc_gens = PedersenGens::default();
+    let mut rng = thread_rng();
+
+    // Scalars of the points shared by every proof: B, B_blinding and each party's G and H
+    let mut b_scalar = Scalar::zero();
+    let mut b_blinding_scalar = Scalar::zero();
+    let mut g_scalars = vec![vec![Scalar::zero(); n]; party_capacity];
+    let mut h_scalars = vec![vec![Scalar::zero(); n]; party_capacity];
+
+    // Scalars and points of each proof's own points
+    let mut scalars = Vec::new();
+    let mut points = Vec::new();
+
+    for mut statement in statements {
+        let Some(proof) = decode_proof(&statement.proof) else {
+            return false;
+        };
+        let m = statement.commitments.len();
+        if m == 0 || !m.is_power_of_two() || proof.l_vec.len() >= 32 || n * m != 1 << proof.l_vec.len() {
+            return false;
+        }
+
+        // Step 1: Replay the proof's transcript to recompute its challenges
+        let transcript = &mut statement.transcript;
+        transcript.append_message(b"dom-sep", b"rangeproof v1");
+        transcript.append_u64(b"n", n as u64);
+        transcript.append_u64(b"m", m as u64);
+        for commitment in &statement.commitments {
+            transcript.append_message(b"V", commitment.as_bytes());
+        }
+        if !append_point(transcript, b"A", &proof.a) || !append_point(transcript, b"S", &proof.s) {
+            return false;
+        }
+        let y = challenge(transcript, b"y");
+        let z = challenge(transcript, b"z");
+        if !append_point(transcript, b"T_1", &proof.t_1) || !append_point(transcript, b"T_2", &proof.t_2) {
+            return false;
+        }
+        let x = challenge(transcript, b"x");
+        transcript.append_message(b"t_x", proof.t_x.as_bytes());
+        transcript.append_message(b"t_x_blinding", proof.t_x_blinding.as_bytes());
+        transcript.append_message(b"e_blinding", proof.e_blinding.as_bytes());
+        let w = challenge(transcript, b"w");
+
+        transcript.append_message(b"dom-sep", b"ipp v1");
+        transcript.append_u64(b"n", (n * m) as u64);
+        let mut u = Vec::new();
+        for (l, r) in proof.l_vec.iter().zip(&proof.r_vec) {
+            if !append_point(transcript, b"L", l) || !append_point(transcript, b"R", r) {
+                return false;
+            }
+            u.push(challenge(transcript, b"u"));
+        }
+
+        // Step 2: Inner product verification scalars u², u⁻² and s
+        let mut u_inv = u.clone();
+        let all_inv = Scalar::batch_invert(&mut u_inv);
+        let u_sq: Vec<Scalar> = u.iter().map(|u| u * u).collect();
+        let u_inv_sq: Vec<Scalar> = u_inv.iter().map(|u| u * u).collect();
+        let lg_n = u.len();
+        let mut s = vec![all_inv];
+        for i in 1..n * m {
+            let lg_i = (usize::BITS - 1 - i.leading_zeros()) as usize;
+            s.push(s[i - (1 << lg_i)] * u_sq[(lg_n - 1) - lg_i]);
+        }
+
+        // Step 3: Add the proof's equation, weighted by a random scalar.
+        // c batches the proof's polynomial check with its inner product check.
+        let weight = Scalar::random(&mut rng);
+        let c = Scalar::random(&mut rng);
+        let zz = z * z;
+        let powers_of_2 = powers(Scalar::from(2u64), n);
+        let powers_of_z = powers(z, m);
+        let y_inv = y.invert();
+
+        b_blinding_scalar += weight * (-proof.e_blinding - c * proof.t_x_blinding);
+        b_scalar += weight * (w * (proof.t_x - proof.ipp_a * proof.ipp_b) + c * (delta(n, m, &y, &z) - proof.t_x));
+
+        let mut y_inv_power = Scalar::one();
+        for i in 0..n * m {
+            let (party, bit) = (i / n, i % n);
+            let z_and_2 = powers_of_z[party] * powers_of_2[bit];
+            g_scalars[party][bit] += weight * (-z - proof.ipp_a * s[i]);
+            h_scalars[party][bit] += weight * (z + y_inv_power * (zz * z_and_2 - proof.ipp_b * s[n * m - 1 - i]));
+            y_inv_power *= y_inv;
+        }
+
+        scalars.extend([weight, weight * x, weight * c * x, weight * c * x * x]);
+        points.extend([proof.a, proof.s, proof.t_1, proof.t_2]);
+        scalars.extend(u_sq.iter().chain(&u_inv_sq).map(|scalar| weight * scalar));
+        points.extend(proof.l_vec.iter().chain(&proof.r_vec));
+        scalars.extend(powers_of_z.iter().map(|z_power| weight * c * zz * z_power));
+        points.extend(statement.commitments);
+    }
+
+    // Step 4: One multiscalar multiplication over every proof
+    let mut points: Vec<Option<RistrettoPoint>> = points.iter().map(CompressedRistretto::decompress).collect();
+    points.extend([Some(pc_gens.B), Some(pc_gens.B_blinding)]);
+    scalars.extend([b_scalar, b_blinding_scalar]);
+    for ((g, h), (g_scalars, h_scalars)) in generators(n, party_capacity).into_iter().zip(g_scalars.into_iter().zip(h_scalars)) {
+        points.extend(g.into_iter().chain(h).map(Some));
+        scalars.extend(g_scalars.into_iter().chain(h_scalars));
+    }
+
+    // The multiscalar multiplication needs iterators of exactly known length
+    let check = RistrettoPoint::optional_multiscalar_mul(scalars, points);
+    check.is_some_and(|point| point.is_identity())
+}
+
+fn decode_proof(bytes: &[u8]) -> Option<DecodedProof> {
+    if !bytes.len().is_multiple_of(32) || bytes.len() < 9 * 32 || !(bytes.len() / 32 - 9).is_multiple_of(2) {
+        return None;
+    }
+    let chunks: Vec<[u8; 32]> = bytes.chunks(32).map(|chunk| chunk.try_into().ok()).collect::<Option<_>>()?;
+    let scalar = |chunk: &[u8; 32]| Scalar::from_canonical_bytes(*chunk);
+
+    let lg_n = (chunks.len() - 9) / 2;
+    let (l_vec, r_vec) = chunks[7..7 + 2 * lg_n].chunks(2)
+        .map(|pair| (CompressedRistretto(pair[0]), CompressedRistretto(pair[1])))
+        .unzip();
+
+    Some(DecodedProof {
+        a: CompressedRistretto(chunks[0]),
+        s: CompressedRistretto(chunks[1]),
+        t_1: CompressedRistretto(chunks[2]),
+        t_2: CompressedRistretto(chunks[3]),
+        t_x: scalar(&chunks[4])?,
+        t_x_blinding: scalar(&chunks[5])?,
+        e_blinding: scalar(&chunks[6])?,
+        l_vec,
+        r_vec,
+        ipp_a: scalar(&chunks[7 + 2 * lg_n])?,
+        ipp_b: scalar(&chunks[8 + 2 * lg_n])?,
+    })
+}
+
+/// The G and H generators of each party, as `BulletproofGens` derives them
+fn generators(n: usize, party_capacity: usize) -> Vec<(Vec<RistrettoPoint>, Vec<RistrettoPoint>)> {
+    let chain = |kind: u8, party: usize| {
+        let mut label = vec![kind];
+        label.extend_from_slice(&(party as u32).to_le_bytes());
+        let mut shake = Shake256::default();
+        shake.update(b"GeneratorsChain");
+        shake.update(&label);
+        let mut reader = shake.finalize_xof();
+        (0..n)
+            .map(|_| {
+                let mut uniform_bytes = [0u8; 64];
+                reader.read(&mut uniform_bytes);
+                RistrettoPoint::from_uniform_bytes(&uniform_bytes)
+            })
+            .collect()
+    };
+    (0..party_capacity).map(|party| (chain(b'G', party), chain(b'H', party))).collect()
+}
+
+/// Append a point that must not be the identity
+fn append_point(transcript: &mut Transcript, label: &'static [u8], point: &CompressedRistretto) -> bool {
+    if point.is_identity() {
+        return false;
+    }
+    transcript.append_message(label, point.as_bytes());
+    true
+}
+
+fn challenge(transcript: &mut Transcript, label: &'static [u8]) -> Scalar {
+    let mut bytes = [0u8; 64];
+    transcript.challenge_bytes(label, &mut bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+/// `1, x, x², ..., x^(count - 1)`
+fn powers(x: Scalar, count: usize) -> Vec<Scalar> {
+    std::iter::successors(Some(Scalar::one()), |power| Some(power * x)).take(count).collect()
+}
+
+/// `δ(y, z) = (z - z²)·<1, y^(nm)> - z³·<1, 2^n>·<1, z^m>`
+fn delta(n: usize, m: usize, y: &Scalar, z: &Scalar) -> Scalar {
+    let sum_y: Scalar = powers(*y, n * m).iter().sum();
+    let sum_2: Scalar = powers(Scalar::from(2u64), n).iter().sum();
+    let sum_z: Scalar = powers(*z, m).iter().sum();
+    (z - z * z) * sum_y - z * z * z * sum_2 * sum_z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bulletproofs::{BulletproofGens, RangeProof};
+
+    const BITS: usize = 64;
+
+    /// A proof of `values` made by `bulletproofs` itself
+    fn statement(label: &'static [u8], values: &[u64]) -> RangeStatement {
+        let mut rng = thread_rng();
+        let blindings: Vec<Scalar> = values.iter().map(|_| Scalar::random(&mut rng)).collect();
+        let bp_gens = BulletproofGens::new(BITS, values.len());
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &bp_gens,
+            &PedersenGens::default(),
+            &mut Transcript::new(label),
+            values,
+            &blindings,
+            BITS,
+        )
+        .unwrap();
+
+        RangeStatement {
+            proof: proof.to_bytes(),
+            transcript: Transcript::new(label),
+            commitments,
+        }
+    }
+
+    fn statements() -> Vec<RangeStatement> {
+        vec![
+            statement(b"single", &[18]),
+            statement(b"pair", &[0, u64::MAX]),
+            statement(b"aggregated", &[1, 2, 3, 4]),
+        ]
+    }
+
+    #[test]
+    fn accepts_proofs_of_different_sizes_together() {
+        assert!(verify_batch(statements(), BITS));
+        assert!(verify_batch(vec![statement(b"single", &[7])], BITS));
+        assert!(verify_batch(Vec::new(), BITS));
+    }
+
+    #[test]
+    fn rejects_a_batch_with_one_invalid_proof() {
+        // Another commitment
+        let mut batch = statements();
+        batch[1].commitments.swap(0, 1);
+        assert!(!verify_batch(batch, BITS));
+
+        // Another transcript
+        let mut batch = statements();
+        batch[2].transcript = Transcript::new(b"other");
+        assert!(!verify_batch(batch, BITS));
+
+        // A modified scalar
+        let mut batch = statements();
+        batch[0].proof[4 * 32] ^= 1;
+        assert!(!verify_batch(batch, BITS));
+
+        // A truncated proof
+        let mut batch = statements();
+        let length = batch[2].proof.len();
+        batch[2].proof.truncate(length - 64);
+        assert!(!verify_batch(batch, BITS));
+    }
+
+    #[test]
+    fn rejects_values_out_of_range() {
+        // A proof of 2^64 - 1 is not a proof of 2^64 once its commitment is shifted by 1
+        let mut shifted = statement(b"single", &[u64::MAX]);
+        let commitment = shifted.commitments[0].decompress().unwrap() + PedersenGens::default().B;
+        shifted.commitments[0] = commitment.compress();
+        assert!(!verify_batch(vec![statement(b"pair", &[1, 2]), shifted], BITS));
+    }
+}
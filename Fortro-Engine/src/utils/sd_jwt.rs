@@ -0,0 +1,385 @@
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Duration, Utc};
+use rand::{rngs::OsRng, RngCore};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::error::AppError;
+use crate::keystore::KeyStore;
+use crate::resolver::{split_did_url, DidResolver};
+use crate::utils::did::{default_key_id, key_type_of};
+use crate::utils::jwt::{self, CredentialDraft, JwtClaims, JwtHeader};
+use crate::utils::{link_secret, zk_proofs};
+
+/// JWT `typ` of the issuer-signed part of an SD-JWT VC
+pub const SD_JWT_VC_TYPE: &str = "dc+sd-jwt";
+
+/// JWT `typ` of a key binding JWT
+pub const KB_JWT_TYPE: &str = "kb+jwt";
+
+/// Hash algorithm of the disclosure digests
+const SD_ALG: &str = "sha-256";
+
+/// Separator between the parts of an SD-JWT
+const SEPARATOR: char = '~';
+
+/// Claims the issuer always discloses, which disclosures may not set
+const RESERVED_CLAIMS: [&str; 15] = [
+    "iss", "sub", "aud", "exp", "nbf", "iat", "jti", "vct", "cnf", "status", "_sd", "_sd_alg",
+    "credentialStatus", zk_proofs::COMMITMENTS_PROPERTY, link_secret::LINK_SECRET_PROPERTY,
+];
+
+/// How long a key binding JWT is accepted after it was signed, the lifetime of the presentations carrying it
+const KEY_BINDING_MAX_AGE_SECONDS: i64 = 3600;
+
+/// Salted `[salt, name, value]` array revealing one claim
+#[derive(Debug, Clone)]
+pub struct Disclosure {
+    pub encoded: String,
+    pub name: String,
+    pub value: Value,
+}
+
+impl Disclosure {
+    fn new(name: &str, value: &Value) -> Result<Self, AppError> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+
+        let array = json!([general_purpose::URL_SAFE_NO_PAD.encode(salt), name, value]);
+        let encoded = general_purpose::URL_SAFE_NO_PAD.encode(serde_json::to_string(&array)?);
+
+        Ok(Self { encoded, name: name.to_string(), value: value.clone() })
+    }
+
+    fn parse(encoded: &str) -> Result<Self, AppError> {
+        let decoded = general_purpose::URL_SAFE_NO_PAD.decode(encoded)
+            .map_err(|e| AppError::SsiError(format!("Failed to decode disclosure: {}", e)))?;
+        let array: Vec<Value> = serde_json::from_slice(&decoded)
+            .map_err(|e| AppError::SsiError(format!("Failed to parse disclosure: {}", e)))?;
+
+        match array.as_slice() {
+            [Value::String(_), Value::String(name), value] => Ok(Self {
+                encoded: encoded.to_string(),
+                name: name.clone(),
+                value: value.clone(),
+            }),
+            _ => Err(AppError::SsiError("Disclosure is not a [salt, name, value] array".to_string())),
+        }
+    }
+
+    /// Digest listed in the `_sd` claim of the issuer-signed JWT
+    pub fn digest(&self) -> String {
+        general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(self.encoded.as_bytes()))
+    }
+}
+
+/// SD-JWT: the issuer-signed JWT, the disclosures revealed, and an optional key binding JWT
+#[derive(Debug, Clone)]
+pub struct SdJwt {
+    pub jwt: String,
+    pub disclosures: Vec<Disclosure>,
+    pub key_binding: Option<String>,
+}
+
+impl SdJwt {
+    /// Whether a secured credential is an SD-JWT rather than a plain JWT
+    pub fn is_sd_jwt(secured: &str) -> bool {
+        secured.contains(SEPARATOR)
+    }
+
+    /// Parse `<jwt>~<disclosure>~…~[<kb-jwt>]`
+    pub fn parse(serialized: &str) -> Result<Self, AppError> {
+        let mut parts: Vec<&str> = serialized.split(SEPARATOR).collect();
+        if parts.len() < 2 {
+            return Err(AppError::SsiError("Invalid SD-JWT format".to_string()));
+        }
+
+        let key_binding = parts.pop().filter(|kb| !kb.is_empty()).map(str::to_string);
+        let jwt = parts.remove(0).to_string();
+        let disclosures = parts.into_iter().map(Disclosure::parse).collect::<Result<_, _>>()?;
+
+        Ok(Self { jwt, disclosures, key_binding })
+    }
+
+    /// Serialize in the compact `~`-separated form
+    pub fn serialize(&self) -> String {
+        format!("{}{}", self.without_key_binding(), self.key_binding.as_deref().unwrap_or(""))
+    }
+
+    /// The SD-JWT up to and including the `~` before the key binding JWT
+    fn without_key_binding(&self) -> String {
+        let mut serialized = format!("{}{}", self.jwt, SEPARATOR);
+        for disclosure in &self.disclosures {
+            serialized.push_str(&disclosure.encoded);
+            serialized.push(SEPARATOR);
+        }
+        serialized
+    }
+
+    /// Keep only the disclosures of the named claims, dropping any key binding
+    pub fn select(&self, names: &[String]) -> Result<Self, AppError> {
+        let mut disclosures = Vec::new();
+        for name in names {
+            if disclosures.iter().any(|d: &Disclosure| d.name == *name) {
+                continue;
+            }
+            let disclosure = self.disclosures.iter().find(|d| d.name == *name)
+                .ok_or_else(|| AppError::ValidationError(format!("Attribute {} not found in credential", name)))?;
+            disclosures.push(disclosure.clone());
+        }
+
+        Ok(Self { jwt: self.jwt.clone(), disclosures, key_binding: None })
+    }
+
+    /// Claims revealed by the disclosures whose digests the issuer signed
+    pub fn disclosed_claims(&self) -> Result<HashMap<String, Value>, AppError> {
+        let (_, claims) = jwt::decode_jwt_unverified(&self.jwt)?;
+        disclosed_claims(&claims, &self.disclosures)
+    }
+
+    /// `sd_hash` a key binding JWT must carry for this SD-JWT
+    fn sd_hash(&self) -> String {
+        general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(self.without_key_binding().as_bytes()))
+    }
+}
+
+/// SD-JWT VC whose issuer signature, disclosures and key binding checked out
+#[derive(Debug)]
+pub struct VerifiedSdJwt {
+    pub header: JwtHeader,
+    /// DID of the holder whose key binding JWT was verified
+    pub holder_did: Option<String>,
+}
+
+/// Create an SD-JWT VC signed by an issuer key held in a key store, with every attribute as a disclosure.
+///
+/// The credential is bound to the subject's DID key through `cnf`.
+pub async fn create_sd_jwt_vc_with_store(
+    issuer_did: &str,
+    key_id: &str,
+    draft: &CredentialDraft,
+    store: &dyn KeyStore,
+    kid: &str,
+) -> Result<String, AppError> {
+    let holder_key = default_key_id(&draft.subject_did).map_err(|_| {
+        AppError::ValidationError("SD-JWT VCs can only be bound to did:alyra or did:key holders".to_string())
+    })?;
+
+    // Step 1: Turn each attribute into a salted disclosure
+    let mut disclosures = Vec::new();
+    for (name, value) in &draft.attributes {
+        if RESERVED_CLAIMS.contains(&name.as_str()) || name == "..." {
+            return Err(AppError::ValidationError(format!("{} cannot be a selectively disclosable attribute", name)));
+        }
+        disclosures.push(Disclosure::new(name, value)?);
+    }
+
+    // Sorted digests do not reveal the attribute order
+    let mut digests: Vec<String> = disclosures.iter().map(Disclosure::digest).collect();
+    digests.sort();
+
+    // Step 2: Sign the digests with the always-disclosed claims
+    let now = Utc::now();
+    let header = JwtHeader {
+        alg: "Dilithium".to_string(),
+        typ: SD_JWT_VC_TYPE.to_string(),
+        kid: key_id.to_string(),
+    };
+
+    let vct = jwt::credential_types(&draft.credential_type).pop().unwrap_or_default();
+    let mut claims = JwtClaims {
+        iss: issuer_did.to_string(),
+        sub: Some(draft.subject_did.clone()),
+        aud: None,
+        exp: draft.expiration_seconds.map(|secs| (now + Duration::seconds(secs)).timestamp()),
+        nbf: Some(now.timestamp()),
+        iat: now.timestamp(),
+        jti: draft.id.clone(),
+        additional_claims: HashMap::new(),
+    };
+    claims.additional_claims.insert("vct".to_string(), json!(vct));
+    claims.additional_claims.insert("_sd".to_string(), json!(digests));
+    claims.additional_claims.insert("_sd_alg".to_string(), json!(SD_ALG));
+    claims.additional_claims.insert("cnf".to_string(), json!({ "kid": holder_key }));
+    if let Some(status) = &draft.status {
+        claims.additional_claims.insert("credentialStatus".to_string(), status.clone());
+    }
+    if let Some(commitments) = &draft.commitments {
+        claims.additional_claims.insert(zk_proofs::COMMITMENTS_PROPERTY.to_string(), commitments.clone());
+    }
+    if let Some(commitment) = &draft.link_secret_commitment {
+        claims.additional_claims.insert(link_secret::LINK_SECRET_PROPERTY.to_string(), json!(commitment));
+    }
+
+    let jwt = jwt::create_pq_jwt_with_store(&header, &claims, store, kid).await?;
+
+    Ok(SdJwt { jwt, disclosures, key_binding: None }.serialize())
+}
+
+/// Append a key binding JWT, signed with the holder's DID key, for one verifier and nonce
+pub fn add_key_binding(
+    sd_jwt: &SdJwt,
+    holder_did: &str,
+    audience: &str,
+    nonce: &str,
+    private_key: &[u8],
+) -> Result<String, AppError> {
+    let key_type = key_type_of(holder_did)?;
+    let header = JwtHeader {
+        alg: key_type.jwt_alg().to_string(),
+        typ: KB_JWT_TYPE.to_string(),
+        kid: default_key_id(holder_did)?,
+    };
+
+    let now = Utc::now().timestamp();
+    let mut claims = JwtClaims {
+        iss: holder_did.to_string(),
+        sub: None,
+        aud: Some(audience.to_string()),
+        exp: None,
+        nbf: None,
+        iat: now,
+        jti: uuid::Uuid::new_v4().to_string(),
+        additional_claims: HashMap::new(),
+    };
+    claims.additional_claims.insert("nonce".to_string(), json!(nonce));
+    claims.additional_claims.insert("sd_hash".to_string(), json!(sd_jwt.sd_hash()));
+
+    let key_binding = jwt::create_did_jwt(&header, &claims, key_type, private_key)?;
+
+    Ok(SdJwt { key_binding: Some(key_binding), ..sd_jwt.clone() }.serialize())
+}
+
+/// Verify an SD-JWT VC: the issuer signature, every disclosure, and the key binding JWT if present.
+///
+/// With a verifier and nonce, a key binding JWT for that verifier and nonce is required.
+/// `signed_at` is the trusted issuance time the issuer key is checked at.
+pub async fn verify(
+    serialized: &str,
+    resolver: &dyn DidResolver,
+    signed_at: Option<DateTime<Utc>>,
+    session: Option<(&str, &str)>,
+) -> Result<VerifiedSdJwt, AppError> {
+    let sd_jwt = SdJwt::parse(serialized)?;
+
+    // Step 1: Verify the issuer-signed JWT
+    let (header, claims) = jwt::verify_did_jwt_signed_at(&sd_jwt.jwt, resolver, signed_at).await?;
+    if header.typ != SD_JWT_VC_TYPE {
+        return Err(AppError::SsiError(format!("Unexpected SD-JWT type {}", header.typ)));
+    }
+    if claims.additional_claims.get("_sd_alg").and_then(|v| v.as_str()) != Some(SD_ALG) {
+        return Err(AppError::SsiError("Unsupported SD-JWT digest algorithm".to_string()));
+    }
+
+    // Step 2: Every disclosure must match a digest the issuer signed
+    if disclosed_claims(&claims, &sd_jwt.disclosures)?.len() != sd_jwt.disclosures.len() {
+        return Err(AppError::SsiError("Disclosure does not match any signed digest".to_string()));
+    }
+
+    // Step 3: Check the holder's key binding
+    let holder_did = match (&sd_jwt.key_binding, session) {
+        (Some(key_binding), _) => Some(verify_key_binding(&sd_jwt, key_binding, &claims, resolver, session).await?),
+        (None, Some(_)) => return Err(AppError::SsiError("SD-JWT has no key binding JWT".to_string())),
+        (None, None) => None,
+    };
+
+    Ok(VerifiedSdJwt { header, holder_did })
+}
+
+/// Verify a key binding JWT against the `cnf` key of the credential, returning the holder's DID
+async fn verify_key_binding(
+    sd_jwt: &SdJwt,
+    key_binding: &str,
+    claims: &JwtClaims,
+    resolver: &dyn DidResolver,
+    session: Option<(&str, &str)>,
+) -> Result<String, AppError> {
+    let (header, kb_claims) = jwt::verify_did_jwt(key_binding, resolver).await?;
+    if header.typ != KB_JWT_TYPE {
+        return Err(AppError::SsiError(format!("Unexpected key binding JWT type {}", header.typ)));
+    }
+
+    // The key must be the one the issuer bound the credential to
+    let bound_key = claims.additional_claims.get("cnf").and_then(|cnf| cnf["kid"].as_str());
+    if bound_key != Some(header.kid.as_str()) {
+        return Err(AppError::SsiError(format!("Key binding JWT is not signed with the credential's holder key {}", header.kid)));
+    }
+
+    if kb_claims.additional_claims.get("sd_hash").and_then(|v| v.as_str()) != Some(sd_jwt.sd_hash().as_str()) {
+        return Err(AppError::SsiError("Key binding JWT does not cover the presented disclosures".to_string()));
+    }
+    let kb_nonce = kb_claims.additional_claims.get("nonce").and_then(|v| v.as_str());
+    if kb_nonce.is_none() {
+        return Err(AppError::SsiError("Key binding JWT has no nonce".to_string()));
+    }
+    if let Some((audience, nonce)) = session {
+        if kb_claims.aud.as_deref() != Some(audience) {
+            return Err(AppError::SsiError(format!("Key binding JWT is not intended for {}", audience)));
+        }
+        if kb_nonce != Some(nonce) {
+            return Err(AppError::SsiError("Key binding JWT does not answer the request's nonce".to_string()));
+        }
+    }
+
+    let age = Utc::now().timestamp() - kb_claims.iat;
+    if !(-60..=KEY_BINDING_MAX_AGE_SECONDS).contains(&age) {
+        return Err(AppError::SsiError("Key binding JWT is too old".to_string()));
+    }
+
+    let (holder_did, _) = split_did_url(&header.kid);
+    Ok(holder_did.to_string())
+}
+
+/// Read an SD-JWT VC without verifying it, in the shape of a VCDM 2.0 credential.
+///
+/// `credentialSubject` holds the subject DID and the disclosed claims.
+pub fn extract_credential(serialized: &str) -> Result<Value, AppError> {
+    let sd_jwt = SdJwt::parse(serialized)?;
+    let (_, claims) = jwt::decode_jwt_unverified(&sd_jwt.jwt)?;
+
+    let mut subject = Map::new();
+    if let Some(sub) = &claims.sub {
+        subject.insert("id".to_string(), json!(sub));
+    }
+    subject.extend(disclosed_claims(&claims, &sd_jwt.disclosures)?);
+
+    let date = |timestamp: i64| DateTime::<Utc>::from_timestamp(timestamp, 0).map(|date| date.to_rfc3339());
+
+    let mut credential = json!({
+        "type": ["VerifiableCredential", claims.additional_claims.get("vct").cloned().unwrap_or(Value::Null)],
+        "issuer": claims.iss,
+        "validFrom": date(claims.iat),
+        "validUntil": claims.exp.and_then(date),
+        "credentialSubject": subject,
+    });
+    for property in ["credentialStatus", zk_proofs::COMMITMENTS_PROPERTY, link_secret::LINK_SECRET_PROPERTY] {
+        if let Some(value) = claims.additional_claims.get(property) {
+            credential[property] = value.clone();
+        }
+    }
+    Ok(credential)
+}
+
+/// Claims of the disclosures whose digests are in `_sd`, rejecting duplicates and reserved names
+fn disclosed_claims(claims: &JwtClaims, disclosures: &[Disclosure]) -> Result<HashMap<String, Value>, AppError> {
+    let digests: Vec<&str> = claims.additional_claims.get("_sd")
+        .and_then(|v| v.as_array())
+        .map(|digests| digests.iter().filter_map(|d| d.as_str()).collect())
+        .unwrap_or_default();
+
+    let mut disclosed = HashMap::new();
+    for disclosure in disclosures {
+        if !digests.contains(&disclosure.digest().as_str()) {
+            continue;
+        }
+        if RESERVED_CLAIMS.contains(&disclosure.name.as_str()) {
+            return Err(AppError::SsiError(format!("Disclosure cannot set {}", disclosure.name)));
+        }
+        if disclosed.insert(disclosure.name.clone(), disclosure.value.clone()).is_some() {
+            return Err(AppError::SsiError(format!("{} is disclosed twice", disclosure.name)));
+        }
+    }
+
+    Ok(disclosed)
+}
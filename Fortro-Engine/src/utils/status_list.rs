@@ -0,0 +1,165 @@
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+
+use crate::error::AppError;
+use crate::models::StatusPurpose;
+
+/// `credentialStatus` type of an entry in a Bitstring Status List
+pub const ENTRY_TYPE: &str = "BitstringStatusListEntry";
+
+/// `credentialSubject` type of a status list credential
+pub const LIST_TYPE: &str = "BitstringStatusList";
+
+/// Credential type of a status list credential
+pub const CREDENTIAL_TYPE: &str = "BitstringStatusListCredential";
+
+/// Bits in a status list, the 16 KiB minimum, so a credential's status hides among 131,072 others
+pub const LIST_SIZE: usize = 131_072;
+
+/// Largest decompressed bitstring accepted from a status list credential
+const MAX_LIST_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Multibase prefix of base64url without padding
+const MULTIBASE_BASE64URL: char = 'u';
+
+/// Context of VCDM 2.0 credentials, which defines the status list terms
+const VC_V2_CONTEXT: &str = "https://www.w3.org/ns/credentials/v2";
+
+/// Status list entry found in a credential's `credentialStatus`
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub purpose: StatusPurpose,
+    pub index: usize,
+    /// URL of the status list credential
+    pub list_url: String,
+}
+
+/// Encoded list with every bit cleared
+pub fn empty() -> Result<String, AppError> {
+    encode(&vec![0u8; LIST_SIZE / 8])
+}
+
+/// GZIP-compress a bitstring and encode it as multibase base64url
+pub fn encode(bits: &[u8]) -> Result<String, AppError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bits)
+        .and_then(|_| encoder.finish())
+        .map(|compressed| format!("{}{}", MULTIBASE_BASE64URL, general_purpose::URL_SAFE_NO_PAD.encode(compressed)))
+        .map_err(|e| AppError::SsiError(format!("Failed to compress status list: {}", e)))
+}
+
+/// Decode an `encodedList`, rejecting lists shorter than the minimum size
+pub fn decode(encoded: &str) -> Result<Vec<u8>, AppError> {
+    let encoded = encoded.strip_prefix(MULTIBASE_BASE64URL).unwrap_or(encoded);
+    let compressed = general_purpose::URL_SAFE_NO_PAD.decode(encoded)
+        .map_err(|e| AppError::SsiError(format!("Invalid status list encoding: {}", e)))?;
+
+    let mut bits = Vec::new();
+    GzDecoder::new(compressed.as_slice())
+        .take(MAX_LIST_BYTES + 1)
+        .read_to_end(&mut bits)
+        .map_err(|e| AppError::SsiError(format!("Failed to decompress status list: {}", e)))?;
+
+    if bits.len() as u64 > MAX_LIST_BYTES {
+        return Err(AppError::SsiError("Status list is too large".to_string()));
+    }
+    if bits.len() < LIST_SIZE / 8 {
+        return Err(AppError::SsiError(format!("Status list has fewer than {} bits", LIST_SIZE)));
+    }
+    Ok(bits)
+}
+
+/// Bit at an index; index 0 is the most significant bit of the first byte
+pub fn bit(bits: &[u8], index: usize) -> Result<bool, AppError> {
+    let byte = bits.get(index / 8)
+        .ok_or_else(|| AppError::SsiError(format!("Status list index {} is out of range", index)))?;
+    Ok(byte & (0x80 >> (index % 8)) != 0)
+}
+
+/// Set or clear the bit at an index
+pub fn set_bit(bits: &mut [u8], index: usize, value: bool) -> Result<(), AppError> {
+    let byte = bits.get_mut(index / 8)
+        .ok_or_else(|| AppError::SsiError(format!("Status list index {} is out of range", index)))?;
+    if value {
+        *byte |= 0x80 >> (index % 8);
+    } else {
+        *byte &= !(0x80 >> (index % 8));
+    }
+    Ok(())
+}
+
+/// `credentialStatus` entry pointing at a bit of a status list
+pub fn entry(list_url: &str, purpose: StatusPurpose, index: i64) -> Value {
+    json!({
+        "id": format!("{}#{}", list_url, index),
+        "type": ENTRY_TYPE,
+        "statusPurpose": purpose.as_str(),
+        "statusListIndex": index.to_string(),
+        "statusListCredential": list_url,
+    })
+}
+
+/// Status list entries of a credential, skipping other status types and purposes
+pub fn entries(credential: &Value) -> Result<Vec<Entry>, AppError> {
+    let statuses = match &credential["credentialStatus"] {
+        Value::Array(statuses) => statuses.iter().collect(),
+        Value::Null => Vec::new(),
+        status => vec![status],
+    };
+
+    let mut entries = Vec::new();
+    for status in statuses.into_iter().filter(|status| status["type"] == ENTRY_TYPE) {
+        let Ok(purpose) = serde_json::from_value::<StatusPurpose>(status["statusPurpose"].clone()) else {
+            continue;
+        };
+        let index = status["statusListIndex"].as_str()
+            .and_then(|index| index.parse().ok())
+            .ok_or_else(|| AppError::SsiError("Invalid statusListIndex".to_string()))?;
+        let list_url = status["statusListCredential"].as_str()
+            .ok_or_else(|| AppError::SsiError("Status list entry has no statusListCredential".to_string()))?;
+        entries.push(Entry { purpose, index, list_url: list_url.to_string() });
+    }
+    Ok(entries)
+}
+
+/// Unsecured status list credential of an issuer
+pub fn list_credential(
+    issuer_did: &str,
+    list_url: &str,
+    purpose: StatusPurpose,
+    encoded_list: &str,
+    issued_at: DateTime<Utc>,
+) -> Value {
+    json!({
+        "@context": [VC_V2_CONTEXT],
+        "id": list_url,
+        "type": ["VerifiableCredential", CREDENTIAL_TYPE],
+        "issuer": issuer_did,
+        "validFrom": issued_at.to_rfc3339(),
+        "credentialSubject": {
+            "id": format!("{}#list", list_url),
+            "type": LIST_TYPE,
+            "statusPurpose": purpose.as_str(),
+            "encodedList": encoded_list,
+        },
+    })
+}
+
+/// `EnvelopedVerifiableCredential` carrying a credential JWT, the JSON form lists are published in
+pub fn envelope(jwt: &str) -> Value {
+    json!({
+        "@context": [VC_V2_CONTEXT],
+        "id": format!("data:application/vc+jwt,{}", jwt),
+        "type": "EnvelopedVerifiableCredential",
+    })
+}
+
+/// Credential JWT of an `EnvelopedVerifiableCredential`
+pub fn unwrap_envelope(envelope: &Value) -> Result<&str, AppError> {
+    envelope["id"].as_str()
+        .and_then(|id| id.strip_prefix("data:application/vc+jwt,"))
+        .ok_or_else(|| AppError::SsiError("Not an enveloped credential JWT".to_string()))
+}
@@ -1,302 +1,673 @@
+use base64::{engine::general_purpose, Engine as _};
 use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate};
 use curve25519_dalek_ng::{
-    ristretto::{CompressedRistretto},
+    ristretto::{CompressedRistretto, RistrettoPoint},
     scalar::Scalar,
+    traits::Identity,
 };
 use merlin::Transcript;
 use rand::thread_rng;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
+use serde_json::Value;
+use sha2_09::Sha512;
 use std::collections::HashMap;
 
 use crate::error::AppError;
+use crate::models::Predicate;
+use crate::utils::link_secret::SameHolderProof;
+use crate::utils::range_proof_batch::{self, RangeStatement};
 
-/// A range proof with its commitment
+/// Credential property holding the issuer-signed commitments, by attribute name
+pub const COMMITMENTS_PROPERTY: &str = "attributeCommitments";
+
+/// Bit size of the ranges proven, so attribute values are `u64`
+const RANGE_BITS: usize = 64;
+
+/// Largest allow- or deny-list a set predicate may use
+const MAX_SET_SIZE: usize = 256;
+
+/// Domain separator of the scalars committing to string attributes
+const STRING_SCALAR_DOMAIN: &[u8] = b"fortro-attribute-string:";
+
+/// Pedersen commitment to a numeric attribute, with the blinding factor that opens it
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RangeProofWithCommitment {
-    pub proof: Vec<u8>,
-    pub commitment: Vec<u8>,
-    pub attribute_name: String,
+pub struct AttributeCommitment {
+    /// Compressed Ristretto point, base64url
+    pub commitment: String,
+    /// Blinding scalar, base64url; only the holder may learn it
+    pub blinding: String,
 }
 
-/// A predicate proof for a credential attribute
+/// A predicate proof for a credential attribute.
+///
+/// Comparisons are range proofs against the issuer-signed commitment, shifted
+/// by the predicate value; `in` and `not_in` are set membership and
+/// non-membership proofs against it. Either only verifies for the commitment
+/// and predicate it was made for.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PredicateProof {
     pub attribute_name: String,
     pub predicate_type: String,
+    /// Value compared with, for comparison predicates
+    #[serde(default)]
     pub predicate_value: i64,
-    pub range_proof: RangeProofWithCommitment,
+    /// Allow- or deny-list, for `in` and `not_in` predicates
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub predicate_set: Vec<Value>,
+    /// Issuer-signed commitment to the attribute, as found in the credential
+    pub commitment: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub range_proof: Vec<u8>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub set_proof: Vec<u8>,
 }
 
-/// Create a range proof for a value
-pub fn create_range_proof(
-    value: u64,
-    attribute_name: &str,
-) -> Result<RangeProofWithCommitment, AppError> {
-    // Set up the generators
-    let pc_gens = PedersenGens::default();
-    let bp_gens = BulletproofGens::new(64, 1);
+/// Comparison predicates proven together with one aggregated range proof
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregatedPredicateProof {
+    /// The predicates proven, each without a proof of its own
+    pub predicates: Vec<PredicateProof>,
+    pub range_proof: Vec<u8>,
+}
 
-    // Create a random blinding factor
-    let mut rng = thread_rng();
-    let blinding = Scalar::random(&mut rng);
-
-    // Create a Pedersen commitment to the value
-    let commitment = pc_gens.commit(Scalar::from(value), blinding);
-
-    // Create a range proof for the value
-    let mut transcript = Transcript::new(b"range_proof");
-    let (proof, _) = RangeProof::prove_single(
-        &bp_gens,
-        &pc_gens,
-        &mut transcript,
-        value,
-        &blinding,
-        64,
-    )
-    .map_err(|e| AppError::SsiError(format!("Failed to create range proof: {}", e)))?;
-
-    Ok(RangeProofWithCommitment {
-        proof: proof.to_bytes(),
-        commitment: commitment.compress().to_bytes().to_vec(),
-        attribute_name: attribute_name.to_string(),
-    })
+/// A comparison predicate to prove over a committed attribute
+pub struct PredicateStatement<'a> {
+    pub attribute_name: &'a str,
+    pub attribute_value: u64,
+    pub commitment: &'a AttributeCommitment,
+    pub predicate_type: &'a str,
+    pub predicate_value: i64,
 }
 
-/// Verify a range proof
-pub fn verify_range_proof(proof_with_commitment: &RangeProofWithCommitment) -> Result<bool, AppError> {
-    // Set up the generators
-    let pc_gens = PedersenGens::default();
-    let bp_gens = BulletproofGens::new(64, 1);
+/// Zero-knowledge proofs carried by a presentation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresentationProofs {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub predicate_proofs: Vec<PredicateProof>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aggregated_predicate_proofs: Vec<AggregatedPredicateProof>,
+    /// Proof that the presented credentials are bound to one link secret
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub same_holder_proof: Option<SameHolderProof>,
+}
 
-    // Parse the proof and commitment
-    let proof = RangeProof::from_bytes(&proof_with_commitment.proof)
-        .map_err(|e| AppError::SsiError(format!("Failed to parse range proof: {}", e)))?;
+/// Read an attribute as the unsigned number predicates are proven over; dates are read as day counts
+pub fn attribute_number(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.parse::<u64>().ok()
+            .or_else(|| parse_date(s).and_then(|date| u64::try_from(day_count(date)).ok())),
+        _ => None,
+    }
+}
 
-    let commitment_bytes: [u8; 32] = proof_with_commitment.commitment.clone().try_into()
-        .map_err(|_| AppError::SsiError("Invalid commitment length".to_string()))?;
+/// Scalar an attribute is committed to: its number or day count, else the hash of its string
+pub fn attribute_scalar(value: &Value) -> Option<Scalar> {
+    if let Some(number) = attribute_number(value) {
+        return Some(Scalar::from(number));
+    }
+    value.as_str().map(|s| Scalar::hash_from_bytes::<Sha512>(&[STRING_SCALAR_DOMAIN, s.as_bytes()].concat()))
+}
 
-    let commitment = CompressedRistretto::from_slice(&commitment_bytes);
+/// Parse a date attribute: an RFC 3339 timestamp, taken at its own calendar date, or a `YYYY-MM-DD` date
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(value).map(|timestamp| timestamp.date_naive()).ok()
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok())
+}
 
-    let mut transcript = Transcript::new(b"range_proof");
-    proof.verify_single(&bp_gens, &pc_gens, &mut transcript, &commitment, 64)
-        .map_err(|e| AppError::SsiError(format!("Range proof verification failed: {}", e)))?;
+/// Day count of a date in the proleptic Gregorian calendar, with 0001-01-01 as day 1
+pub fn day_count(date: NaiveDate) -> i64 {
+    date.num_days_from_ce() as i64
+}
 
-    Ok(true)
+/// Number a predicate compares its attribute with, as of `today` for relative date predicates
+pub fn predicate_value(predicate: &Predicate, today: NaiveDate) -> Result<i64, AppError> {
+    let invalid = || AppError::ValidationError(format!(
+        "Predicate on {} must compare with a number, a date or a date relative to today",
+        predicate.attribute
+    ));
+
+    if let Some(offset) = &predicate.relative {
+        let months = offset.years as i64 * 12 + offset.months as i64;
+        let month_count = Months::new(u32::try_from(months.unsigned_abs()).map_err(|_| invalid())?);
+        let days = Days::new(offset.days.unsigned_abs());
+        let shifted = if months < 0 {
+            today.checked_sub_months(month_count)
+        } else {
+            today.checked_add_months(month_count)
+        };
+        let shifted = shifted.and_then(|date| {
+            if offset.days < 0 { date.checked_sub_days(days) } else { date.checked_add_days(days) }
+        });
+        return shifted.map(day_count).ok_or_else(invalid);
+    }
+
+    match &predicate.value {
+        Value::Number(n) => n.as_i64().ok_or_else(invalid),
+        Value::String(s) => parse_date(s).map(day_count).ok_or_else(invalid),
+        _ => Err(invalid()),
+    }
+}
+
+/// Allow- or deny-list of an `in` or `not_in` predicate
+pub fn predicate_set(predicate: &Predicate) -> Result<Vec<Value>, AppError> {
+    predicate.value.as_array()
+        .filter(|set| !set.is_empty())
+        .cloned()
+        .ok_or_else(|| AppError::ValidationError(format!(
+            "{} predicate on {} must list the values of its set",
+            predicate.predicate_type.as_str(), predicate.attribute
+        )))
+}
+
+/// Whether a predicate type is proven by set membership or non-membership
+pub fn is_set_predicate(predicate_type: &str) -> bool {
+    matches!(predicate_type, "in" | "not_in")
 }
 
-/// Create a predicate proof for a credential attribute
+/// Whether a proof proves a required predicate, or a stricter one of the same type.
+///
+/// Relative predicates are proven at submission and checked at verification,
+/// when the required value has moved on; a proof of a stricter value still holds.
+/// A shorter allow-list or a longer deny-list is stricter too.
+pub fn satisfies(proof: &PredicateProof, predicate: &Predicate, today: NaiveDate) -> Result<bool, AppError> {
+    let predicate_type = predicate.predicate_type.as_str();
+    if proof.attribute_name != predicate.attribute || proof.predicate_type != predicate_type {
+        return Ok(false);
+    }
+
+    if is_set_predicate(predicate_type) {
+        let required = set_scalars(&predicate_set(predicate)?)?;
+        let proven = set_scalars(&proof.predicate_set)?;
+        return Ok(match predicate_type {
+            "in" => proven.iter().all(|element| required.contains(element)),
+            _ => required.iter().all(|element| proven.contains(element)),
+        });
+    }
+
+    let required = predicate_value(predicate, today)?;
+    Ok(match predicate_type {
+        ">=" | ">" => proof.predicate_value >= required,
+        "<=" | "<" => proof.predicate_value <= required,
+        _ => proof.predicate_value == required,
+    })
+}
+
+/// Commit to every number, date or string attribute, for the issuer to sign into the credential
+pub fn commit_attributes(attributes: &HashMap<String, Value>) -> HashMap<String, AttributeCommitment> {
+    let pc_gens = PedersenGens::default();
+    let mut rng = thread_rng();
+
+    attributes.iter()
+        .filter_map(|(name, value)| attribute_scalar(value).map(|scalar| (name, scalar)))
+        .map(|(name, scalar)| {
+            let blinding = Scalar::random(&mut rng);
+            let commitment = pc_gens.commit(scalar, blinding);
+            (name.clone(), AttributeCommitment {
+                commitment: general_purpose::URL_SAFE_NO_PAD.encode(commitment.compress().as_bytes()),
+                blinding: general_purpose::URL_SAFE_NO_PAD.encode(blinding.as_bytes()),
+            })
+        })
+        .collect()
+}
+
+/// Create a predicate proof for a credential attribute against its signed commitment
 pub fn create_predicate_proof(
     attribute_name: &str,
     attribute_value: u64,
+    commitment: &AttributeCommitment,
     predicate_type: &str,
     predicate_value: i64,
 ) -> Result<PredicateProof, AppError> {
-    match predicate_type {
-        ">=" => {
-            if (attribute_value as i64) < predicate_value {
-                return Err(AppError::ValidationError(format!(
-                    "Attribute value {} does not satisfy predicate {} {}",
-                    attribute_value, predicate_type, predicate_value
-                )));
-            }
+    // Step 1: Shift the committed value so the predicate holds iff every shifted value is in range
+    let (values, blindings) = shifted_openings(attribute_value, commitment, predicate_type, predicate_value)?;
 
-            let range_proof = create_range_proof(
-                (attribute_value as i64 - predicate_value) as u64,
-                attribute_name,
-            )?;
+    // Step 2: Prove the ranges with the attribute, predicate and commitment in the transcript
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(RANGE_BITS, values.len());
+    let mut transcript = predicate_transcript(attribute_name, predicate_type, predicate_value, &commitment.commitment);
+    let (proof, _) = RangeProof::prove_multiple(&bp_gens, &pc_gens, &mut transcript, &values, &blindings, RANGE_BITS)
+        .map_err(|e| AppError::SsiError(format!("Failed to create range proof: {}", e)))?;
 
-            Ok(PredicateProof {
-                attribute_name: attribute_name.to_string(),
-                predicate_type: predicate_type.to_string(),
-                predicate_value,
-                range_proof,
-            })
-        }
-        "<=" => {
-            if (attribute_value as i64) > predicate_value {
-                return Err(AppError::ValidationError(format!(
-                    "Attribute value {} does not satisfy predicate {} {}",
-                    attribute_value, predicate_type, predicate_value
-                )));
-            }
+    Ok(PredicateProof {
+        attribute_name: attribute_name.to_string(),
+        predicate_type: predicate_type.to_string(),
+        predicate_value,
+        predicate_set: Vec::new(),
+        commitment: commitment.commitment.clone(),
+        range_proof: proof.to_bytes(),
+        set_proof: Vec::new(),
+    })
+}
 
-            let range_proof = create_range_proof(
-                (predicate_value - attribute_value as i64) as u64,
-                attribute_name,
-            )?;
+/// Prove several comparison predicates with one aggregated range proof.
+///
+/// The shifted values of all predicates are proven together, padded with
+/// zeros to a power of two, so the proof grows logarithmically with their number.
+pub fn create_aggregated_predicate_proof(statements: &[PredicateStatement]) -> Result<AggregatedPredicateProof, AppError> {
+    if statements.is_empty() {
+        return Err(AppError::ValidationError("No predicates to prove".to_string()));
+    }
 
-            Ok(PredicateProof {
-                attribute_name: attribute_name.to_string(),
-                predicate_type: predicate_type.to_string(),
-                predicate_value,
-                range_proof,
-            })
-        }
-        ">" => {
-            if (attribute_value as i64) <= predicate_value {
-                return Err(AppError::ValidationError(format!(
-                    "Attribute value {} does not satisfy predicate {} {}",
-                    attribute_value, predicate_type, predicate_value
-                )));
-            }
+    // Step 1: Shift each committed value by its predicate
+    let mut values = Vec::new();
+    let mut blindings = Vec::new();
+    let mut predicates = Vec::new();
+    for statement in statements {
+        let (shifted, shifted_blindings) = shifted_openings(
+            statement.attribute_value,
+            statement.commitment,
+            statement.predicate_type,
+            statement.predicate_value,
+        )?;
+        values.extend(shifted);
+        blindings.extend(shifted_blindings);
+        predicates.push(PredicateProof {
+            attribute_name: statement.attribute_name.to_string(),
+            predicate_type: statement.predicate_type.to_string(),
+            predicate_value: statement.predicate_value,
+            predicate_set: Vec::new(),
+            commitment: statement.commitment.commitment.clone(),
+            range_proof: Vec::new(),
+            set_proof: Vec::new(),
+        });
+    }
 
-            let range_proof = create_range_proof(
-                (attribute_value as i64 - predicate_value - 1) as u64,
-                attribute_name,
-            )?;
+    // Step 2: Pad to the party capacity with commitments to zero under a zero blinding, the identity
+    let capacity = values.len().next_power_of_two();
+    values.resize(capacity, 0);
+    blindings.resize(capacity, Scalar::zero());
 
-            Ok(PredicateProof {
-                attribute_name: attribute_name.to_string(),
-                predicate_type: predicate_type.to_string(),
-                predicate_value,
-                range_proof,
-            })
-        }
-        "<" => {
-            if (attribute_value as i64) >= predicate_value {
-                return Err(AppError::ValidationError(format!(
-                    "Attribute value {} does not satisfy predicate {} {}",
-                    attribute_value, predicate_type, predicate_value
-                )));
-            }
+    // Step 3: Prove all ranges at once, with every predicate in the transcript
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(RANGE_BITS, capacity);
+    let mut transcript = aggregated_transcript(&predicates);
+    let (proof, _) = RangeProof::prove_multiple(&bp_gens, &pc_gens, &mut transcript, &values, &blindings, RANGE_BITS)
+        .map_err(|e| AppError::SsiError(format!("Failed to create aggregated range proof: {}", e)))?;
+
+    Ok(AggregatedPredicateProof {
+        predicates,
+        range_proof: proof.to_bytes(),
+    })
+}
 
-            let range_proof = create_range_proof(
-                (predicate_value - attribute_value as i64 - 1) as u64,
-                attribute_name,
-            )?;
+/// Prove that a committed attribute is (`in`) or is not (`not_in`) one of a set of values
+pub fn create_set_membership_proof(
+    attribute_name: &str,
+    attribute_value: &Value,
+    commitment: &AttributeCommitment,
+    predicate_type: &str,
+    set: &[Value],
+) -> Result<PredicateProof, AppError> {
+    let value = attribute_scalar(attribute_value).ok_or_else(|| {
+        AppError::ValidationError(format!("Attribute {} is not a number, date or string", attribute_name))
+    })?;
+    let blinding = decode_blinding(&commitment.blinding)?;
+    let elements = set_scalars(set)?;
+    let unsatisfied = || AppError::ValidationError(format!(
+        "Attribute {} does not satisfy its {} predicate",
+        attribute_name, predicate_type
+    ));
+
+    // Step 1: Each difference C - s·B commits to the attribute minus a set element
+    let pc_gens = PedersenGens::default();
+    let differences = set_differences(&pc_gens, decode_commitment(&commitment.commitment)?, &elements);
+    let mut transcript = set_transcript(attribute_name, predicate_type, &elements, &commitment.commitment);
+    let mut rng = thread_rng();
 
-            Ok(PredicateProof {
-                attribute_name: attribute_name.to_string(),
-                predicate_type: predicate_type.to_string(),
-                predicate_value,
-                range_proof,
-            })
-        }
-        "==" => {
-            if (attribute_value as i64) != predicate_value {
-                return Err(AppError::ValidationError(format!(
-                    "Attribute value {} does not satisfy predicate {} {}",
-                    attribute_value, predicate_type, predicate_value
-                )));
+    // Step 2: Prove one difference commits to zero, or that none does
+    let scalars = match predicate_type {
+        "in" => {
+            // One-of-many proof of the blinding of the zero difference, simulating the others
+            let index = elements.iter().position(|element| *element == value).ok_or_else(unsatisfied)?;
+            let mut challenges: Vec<Scalar> = (0..elements.len()).map(|_| Scalar::random(&mut rng)).collect();
+            let mut responses: Vec<Scalar> = (0..elements.len()).map(|_| Scalar::random(&mut rng)).collect();
+            let nonce = Scalar::random(&mut rng);
+            for (i, difference) in differences.iter().enumerate() {
+                let announcement = if i == index {
+                    pc_gens.B_blinding * nonce
+                } else {
+                    pc_gens.B_blinding * responses[i] - difference * challenges[i]
+                };
+                transcript.append_message(b"announcement", announcement.compress().as_bytes());
             }
 
-            // For equality, we just prove that the difference is 0
-            let range_proof = create_range_proof(0, attribute_name)?;
-
-            Ok(PredicateProof {
-                attribute_name: attribute_name.to_string(),
-                predicate_type: predicate_type.to_string(),
-                predicate_value,
-                range_proof,
-            })
+            let challenge = challenge_scalar(&mut transcript);
+            let simulated: Scalar = challenges.iter().enumerate()
+                .filter(|(i, _)| *i != index)
+                .map(|(_, c)| c)
+                .sum();
+            challenges[index] = challenge - simulated;
+            responses[index] = nonce + challenges[index] * blinding;
+            challenges.iter().zip(&responses).flat_map(|(c, z)| [*c, *z]).collect::<Vec<Scalar>>()
         }
-        "!=" => {
-            if (attribute_value as i64) == predicate_value {
-                return Err(AppError::ValidationError(format!(
-                    "Attribute value {} does not satisfy predicate {} {}",
-                    attribute_value, predicate_type, predicate_value
-                )));
+        "not_in" => {
+            // With a = value - s ≠ 0, B = a⁻¹·D - a⁻¹r·B̃; a zero difference has no such opening
+            let mut openings = Vec::new();
+            for (element, difference) in elements.iter().zip(&differences) {
+                let difference_value = value - element;
+                if difference_value == Scalar::zero() {
+                    return Err(unsatisfied());
+                }
+                let inverse = difference_value.invert();
+                let nonces = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+                let announcement = difference * nonces.0 + pc_gens.B_blinding * nonces.1;
+                transcript.append_message(b"announcement", announcement.compress().as_bytes());
+                openings.push((inverse, -inverse * blinding, nonces));
             }
 
-            // For inequality, we prove that the absolute difference is at least 1
-            let diff = if attribute_value as i64 > predicate_value {
-                (attribute_value as i64 - predicate_value) as u64
-            } else {
-                (predicate_value - attribute_value as i64) as u64
-            };
+            let challenge = challenge_scalar(&mut transcript);
+            std::iter::once(challenge)
+                .chain(openings.iter().flat_map(|(x, y, (w1, w2))| [w1 + challenge * x, w2 + challenge * y]))
+                .collect()
+        }
+        _ => return Err(AppError::ValidationError(format!("Unsupported set predicate type: {}", predicate_type))),
+    };
 
-            let range_proof = create_range_proof(diff, attribute_name)?;
+    Ok(PredicateProof {
+        attribute_name: attribute_name.to_string(),
+        predicate_type: predicate_type.to_string(),
+        predicate_value: 0,
+        predicate_set: set.to_vec(),
+        commitment: commitment.commitment.clone(),
+        range_proof: Vec::new(),
+        set_proof: scalars.iter().flat_map(|scalar| scalar.to_bytes()).collect(),
+    })
+}
 
-            Ok(PredicateProof {
-                attribute_name: attribute_name.to_string(),
-                predicate_type: predicate_type.to_string(),
-                predicate_value,
-                range_proof,
-            })
-        }
-        _ => Err(AppError::ValidationError(format!("Unsupported predicate type: {}", predicate_type))),
+/// Verify a predicate proof against the commitment the issuer signed for its attribute
+pub fn verify_predicate_proof(proof: &PredicateProof, signed_commitment: &str) -> Result<bool, AppError> {
+    if is_set_predicate(&proof.predicate_type) {
+        check_signed_commitment(proof, signed_commitment)?;
+        return verify_set_membership_proof(proof, &PedersenGens::default(), decode_commitment(&proof.commitment)?);
     }
+
+    let statement = range_statement(proof, signed_commitment)?;
+    verify_range_statement(statement, "Range proof verification failed")?;
+
+    Ok(true)
 }
 
-/// Verify a predicate proof
-pub fn verify_predicate_proof(proof: &PredicateProof) -> Result<bool, AppError> {
-    // Verify the range proof
-    verify_range_proof(&proof.range_proof)?;
+/// Verify an aggregated range proof against the commitments signed for its predicates, in order.
+///
+/// All predicates are checked in one verification, a single multiscalar
+/// multiplication over every shifted commitment.
+pub fn verify_aggregated_predicate_proof(
+    proof: &AggregatedPredicateProof,
+    signed_commitments: &[&str],
+) -> Result<bool, AppError> {
+    let statement = aggregated_range_statement(proof, signed_commitments)?;
+    verify_range_statement(statement, "Aggregated range proof verification failed")?;
 
     Ok(true)
 }
 
-/// Create a selective disclosure proof for a credential
-pub fn create_selective_disclosure(
-    credential_data: &HashMap<String, serde_json::Value>,
-    disclosed_attributes: &[String],
-) -> Result<HashMap<String, serde_json::Value>, AppError> {
-    let mut disclosed_data = HashMap::new();
+/// Verify the range proofs of comparison predicate proofs and of aggregated proofs in one batch.
+///
+/// Each proof is checked against the commitments signed for it, as by
+/// `verify_predicate_proof` and `verify_aggregated_predicate_proof`, but all
+/// their equations are combined into one multiscalar multiplication. A failed
+/// batch does not tell which proof is invalid. Set predicates have no range
+/// proof and are rejected.
+pub fn verify_predicate_proofs_batch(
+    predicate_proofs: &[(&PredicateProof, &str)],
+    aggregated_proofs: &[(&AggregatedPredicateProof, Vec<&str>)],
+) -> Result<bool, AppError> {
+    let mut statements = Vec::new();
+    for (proof, signed_commitment) in predicate_proofs {
+        statements.push(range_statement(proof, signed_commitment)?);
+    }
+    for (proof, signed_commitments) in aggregated_proofs {
+        statements.push(aggregated_range_statement(proof, signed_commitments)?);
+    }
 
-    for attr in disclosed_attributes {
-        if let Some(value) = credential_data.get(attr) {
-            disclosed_data.insert(attr.clone(), value.clone());
-        } else {
-            return Err(AppError::ValidationError(format!("Attribute {} not found in credential", attr)));
-        }
+    Ok(range_proof_batch::verify_batch(statements, RANGE_BITS))
+}
+
+/// The range proof of a comparison predicate, over the signed commitment shifted by the predicate
+fn range_statement(proof: &PredicateProof, signed_commitment: &str) -> Result<RangeStatement, AppError> {
+    check_signed_commitment(proof, signed_commitment)?;
+
+    // Shift the signed commitment by the predicate, as the prover shifted the value
+    let commitment = decode_commitment(&proof.commitment)?;
+    Ok(RangeStatement {
+        proof: proof.range_proof.clone(),
+        transcript: predicate_transcript(&proof.attribute_name, &proof.predicate_type, proof.predicate_value, &proof.commitment),
+        commitments: shifted_commitments(&PedersenGens::default(), commitment, &proof.predicate_type, proof.predicate_value)?,
+    })
+}
+
+/// The range proof of an aggregated proof, over all its shifted commitments padded to a power of two
+fn aggregated_range_statement(
+    proof: &AggregatedPredicateProof,
+    signed_commitments: &[&str],
+) -> Result<RangeStatement, AppError> {
+    if proof.predicates.is_empty() || proof.predicates.len() != signed_commitments.len() {
+        return Err(AppError::SsiError("Aggregated predicate proof does not match its commitments".to_string()));
     }
 
-    // Create a hash of the undisclosed attributes to prove knowledge of them
-    let mut hasher = Sha256::new();
+    // Shift each signed commitment by its predicate, as the prover shifted the values
+    let pc_gens = PedersenGens::default();
+    let mut shifted = Vec::new();
+    for (predicate, signed_commitment) in proof.predicates.iter().zip(signed_commitments) {
+        check_signed_commitment(predicate, signed_commitment)?;
+        let commitment = decode_commitment(&predicate.commitment)?;
+        shifted.extend(shifted_commitments(&pc_gens, commitment, &predicate.predicate_type, predicate.predicate_value)?);
+    }
+    shifted.resize(shifted.len().next_power_of_two(), RistrettoPoint::identity().compress());
 
-    for (key, value) in credential_data {
-        if !disclosed_attributes.contains(key) {
-            hasher.update(key.as_bytes());
-            hasher.update(value.to_string().as_bytes());
-        }
+    Ok(RangeStatement {
+        proof: proof.range_proof.clone(),
+        transcript: aggregated_transcript(&proof.predicates),
+        commitments: shifted,
+    })
+}
+
+/// Verify a single range statement with `bulletproofs` itself
+fn verify_range_statement(mut statement: RangeStatement, failure: &str) -> Result<(), AppError> {
+    let range_proof = RangeProof::from_bytes(&statement.proof)
+        .map_err(|e| AppError::SsiError(format!("Failed to parse range proof: {}", e)))?;
+
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(RANGE_BITS, statement.commitments.len());
+    range_proof.verify_multiple(&bp_gens, &pc_gens, &mut statement.transcript, &statement.commitments, RANGE_BITS)
+        .map_err(|e| AppError::SsiError(format!("{}: {}", failure, e)))
+}
+
+fn check_signed_commitment(proof: &PredicateProof, signed_commitment: &str) -> Result<(), AppError> {
+    if proof.commitment != signed_commitment {
+        return Err(AppError::SsiError(format!(
+            "Predicate proof for {} is not made against the signed commitment",
+            proof.attribute_name
+        )));
+    }
+    Ok(())
+}
+
+/// Values and blindings shifted by a comparison predicate, each in range iff the predicate holds
+fn shifted_openings(
+    attribute_value: u64,
+    commitment: &AttributeCommitment,
+    predicate_type: &str,
+    predicate_value: i64,
+) -> Result<(Vec<u64>, Vec<Scalar>), AppError> {
+    let blinding = decode_blinding(&commitment.blinding)?;
+    let unsatisfied = || AppError::ValidationError(format!(
+        "Attribute value {} does not satisfy predicate {} {}",
+        attribute_value, predicate_type, predicate_value
+    ));
+
+    let mut values = Vec::new();
+    let mut blindings = Vec::new();
+    for (sign, offset) in relations(predicate_type, predicate_value)? {
+        let shifted = sign * attribute_value as i128 + offset;
+        values.push(u64::try_from(shifted).map_err(|_| unsatisfied())?);
+        blindings.push(if sign < 0 { -blinding } else { blinding });
     }
+    Ok((values, blindings))
+}
 
-    let hash = hasher.finalize();
-    disclosed_data.insert("_undisclosed_hash".to_string(), serde_json::Value::String(hex::encode(hash)));
+/// A signed commitment shifted by a comparison predicate, matching `shifted_openings`
+fn shifted_commitments(
+    pc_gens: &PedersenGens,
+    commitment: RistrettoPoint,
+    predicate_type: &str,
+    predicate_value: i64,
+) -> Result<Vec<CompressedRistretto>, AppError> {
+    Ok(relations(predicate_type, predicate_value)?
+        .into_iter()
+        .map(|(sign, offset)| {
+            let signed = if sign < 0 { -commitment } else { commitment };
+            (signed + pc_gens.B * scalar_from_i128(offset)).compress()
+        })
+        .collect())
+}
 
-    Ok(disclosed_data)
+/// Transcript binding an aggregated range proof to all its predicates and commitments
+fn aggregated_transcript(predicates: &[PredicateProof]) -> Transcript {
+    let mut transcript = Transcript::new(b"fortro-aggregated-predicate-proof");
+    transcript.append_u64(b"predicates", predicates.len() as u64);
+    for predicate in predicates {
+        transcript.append_message(b"attribute", predicate.attribute_name.as_bytes());
+        transcript.append_message(b"predicate", predicate.predicate_type.as_bytes());
+        transcript.append_message(b"value", &predicate.predicate_value.to_le_bytes());
+        transcript.append_message(b"commitment", predicate.commitment.as_bytes());
+    }
+    transcript
 }
 
-/// Verify a selective disclosure proof against the original credential
-pub fn verify_selective_disclosure(
-    original_credential: &HashMap<String, serde_json::Value>,
-    disclosed_data: &HashMap<String, serde_json::Value>,
+/// Verify an `in` or `not_in` proof against the signed commitment
+fn verify_set_membership_proof(
+    proof: &PredicateProof,
+    pc_gens: &PedersenGens,
+    commitment: RistrettoPoint,
 ) -> Result<bool, AppError> {
-    // Check that all disclosed attributes match the original
-    for (key, value) in disclosed_data {
-        if key == "_undisclosed_hash" {
-            continue;
+    let elements = set_scalars(&proof.predicate_set)?;
+    let differences = set_differences(pc_gens, commitment, &elements);
+    let mut transcript = set_transcript(&proof.attribute_name, &proof.predicate_type, &elements, &proof.commitment);
+    let invalid = || AppError::SsiError(format!("Invalid {} proof for attribute {}", proof.predicate_type, proof.attribute_name));
+
+    let scalars = proof.set_proof.chunks(32)
+        .map(|chunk| <[u8; 32]>::try_from(chunk).ok().and_then(Scalar::from_canonical_bytes))
+        .collect::<Option<Vec<Scalar>>>()
+        .ok_or_else(invalid)?;
+
+    // Recompute the announcements from the responses; the challenge must match them
+    let valid = match proof.predicate_type.as_str() {
+        "in" => {
+            if scalars.len() != 2 * elements.len() {
+                return Err(invalid());
+            }
+            let mut challenges = Scalar::zero();
+            for (pair, difference) in scalars.chunks(2).zip(&differences) {
+                let announcement = pc_gens.B_blinding * pair[1] - difference * pair[0];
+                transcript.append_message(b"announcement", announcement.compress().as_bytes());
+                challenges += pair[0];
+            }
+            challenges == challenge_scalar(&mut transcript)
         }
-
-        if let Some(original_value) = original_credential.get(key) {
-            if value != original_value {
-                return Ok(false);
+        _ => {
+            if scalars.len() != 1 + 2 * elements.len() {
+                return Err(invalid());
             }
-        } else {
-            return Ok(false);
+            let challenge = scalars[0];
+            for (pair, difference) in scalars[1..].chunks(2).zip(&differences) {
+                let announcement = difference * pair[0] + pc_gens.B_blinding * pair[1] - pc_gens.B * challenge;
+                transcript.append_message(b"announcement", announcement.compress().as_bytes());
+            }
+            challenge == challenge_scalar(&mut transcript)
         }
+    };
+
+    if !valid {
+        return Err(AppError::SsiError(format!(
+            "{} proof verification failed for attribute {}",
+            proof.predicate_type, proof.attribute_name
+        )));
+    }
+    Ok(true)
+}
+
+/// Scalars of the elements of an allow- or deny-list
+fn set_scalars(set: &[Value]) -> Result<Vec<Scalar>, AppError> {
+    if set.is_empty() || set.len() > MAX_SET_SIZE {
+        return Err(AppError::ValidationError(format!(
+            "Set predicates must list between 1 and {} values",
+            MAX_SET_SIZE
+        )));
     }
+    set.iter()
+        .map(|element| attribute_scalar(element).ok_or_else(|| {
+            AppError::ValidationError(format!("Set value {} is not a number, date or string", element))
+        }))
+        .collect()
+}
 
-    // If there are undisclosed attributes, verify the hash
-    if let Some(hash_value) = disclosed_data.get("_undisclosed_hash") {
-        if let serde_json::Value::String(hash_hex) = hash_value {
-            let mut hasher = Sha256::new();
+/// Commitments to the attribute minus each set element
+fn set_differences(pc_gens: &PedersenGens, commitment: RistrettoPoint, elements: &[Scalar]) -> Vec<RistrettoPoint> {
+    elements.iter().map(|element| commitment - pc_gens.B * element).collect()
+}
 
-            for (key, value) in original_credential {
-                if !disclosed_data.contains_key(key) {
-                    hasher.update(key.as_bytes());
-                    hasher.update(value.to_string().as_bytes());
-                }
-            }
+/// Transcript binding a set proof to its attribute, predicate, set and commitment
+fn set_transcript(attribute_name: &str, predicate_type: &str, elements: &[Scalar], commitment: &str) -> Transcript {
+    let mut transcript = Transcript::new(b"fortro-set-predicate-proof");
+    transcript.append_message(b"attribute", attribute_name.as_bytes());
+    transcript.append_message(b"predicate", predicate_type.as_bytes());
+    for element in elements {
+        transcript.append_message(b"element", element.as_bytes());
+    }
+    transcript.append_message(b"commitment", commitment.as_bytes());
+    transcript
+}
 
-            let computed_hash = hasher.finalize();
-            let computed_hash_hex = hex::encode(computed_hash);
+pub(crate) fn challenge_scalar(transcript: &mut Transcript) -> Scalar {
+    let mut bytes = [0u8; 64];
+    transcript.challenge_bytes(b"challenge", &mut bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
 
-            return Ok(&computed_hash_hex == hash_hex);
-        }
+/// Shifts `(sign, offset)` such that the predicate holds iff `sign * value + offset`
+/// is in `[0, 2^64)` for every shift
+fn relations(predicate_type: &str, predicate_value: i64) -> Result<Vec<(i128, i128)>, AppError> {
+    let predicate_value = predicate_value as i128;
+    match predicate_type {
+        ">=" => Ok(vec![(1, -predicate_value)]),
+        ">" => Ok(vec![(1, -predicate_value - 1)]),
+        "<=" => Ok(vec![(-1, predicate_value)]),
+        "<" => Ok(vec![(-1, predicate_value - 1)]),
+        // Both differences are non-negative only when they are zero
+        "==" => Ok(vec![(1, -predicate_value), (-1, predicate_value)]),
+        _ => Err(AppError::ValidationError(format!("Unsupported predicate type: {}", predicate_type))),
     }
+}
+
+/// Transcript binding a range proof to its attribute, predicate and commitment
+fn predicate_transcript(attribute_name: &str, predicate_type: &str, predicate_value: i64, commitment: &str) -> Transcript {
+    let mut transcript = Transcript::new(b"fortro-predicate-proof");
+    transcript.append_message(b"attribute", attribute_name.as_bytes());
+    transcript.append_message(b"predicate", predicate_type.as_bytes());
+    transcript.append_message(b"value", &predicate_value.to_le_bytes());
+    transcript.append_message(b"commitment", commitment.as_bytes());
+    transcript
+}
+
+fn scalar_from_i128(value: i128) -> Scalar {
+    let magnitude = Scalar::from(value.unsigned_abs());
+    if value < 0 { -magnitude } else { magnitude }
+}
+
+pub(crate) fn decode_commitment(encoded: &str) -> Result<RistrettoPoint, AppError> {
+    general_purpose::URL_SAFE_NO_PAD.decode(encoded).ok()
+        .filter(|bytes| bytes.len() == 32)
+        .and_then(|bytes| CompressedRistretto::from_slice(&bytes).decompress())
+        .ok_or_else(|| AppError::SsiError("Invalid attribute commitment".to_string()))
+}
 
-    // If there's no hash, all attributes should be disclosed
-    Ok(disclosed_data.len() == original_credential.len() + 1) // +1 for the _undisclosed_hash field
+pub(crate) fn decode_blinding(encoded: &str) -> Result<Scalar, AppError> {
+    general_purpose::URL_SAFE_NO_PAD.decode(encoded).ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(Scalar::from_canonical_bytes)
+        .ok_or_else(|| AppError::ValidationError("Invalid commitment blinding factor".to_string()))
 }